@@ -0,0 +1,150 @@
+use crate::commands::add::{add_files, AddOptions};
+use crate::commands::init::{init_project, InitOptions};
+use crate::commands::pull::{pull_project, PullOptions};
+use crate::commands::push::{push_project, PushOptions};
+use crate::commands::status::{file_statuses, FileStatus};
+use crate::core::{Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::utils::output::{redirect, Sink};
+use crate::utils::project_name_in;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Where a project and the shade repo it syncs through live
+///
+/// The binary works this out from the current directory and the home
+/// directory; a library caller says so explicitly.
+pub struct Context {
+    pub paths: ShadePaths,
+    /// Root of the project's git repository
+    pub project_path: PathBuf,
+    pub project_name: String,
+    /// Where commands print their progress; stdout when `None`
+    pub output: Option<Sink>,
+}
+
+impl Context {
+    /// A project at `project_path` with git-shade's files under `root`
+    /// (normally `~/.local/git-shade`)
+    ///
+    /// The name defaults the same way as for `init`: `name` from
+    /// `.git-shade.toml`, else the directory name.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        project_path: impl Into<PathBuf>,
+        project_name: Option<String>,
+    ) -> Result<Self> {
+        let project_path = project_path.into();
+        let project_name = project_name_in(&project_path, project_name)?;
+        Ok(Self {
+            paths: ShadePaths::at(root.into()),
+            project_path,
+            project_name,
+            output: None,
+        })
+    }
+
+    /// Print command output to `writer` instead of stdout
+    pub fn with_output(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.output = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+}
+
+/// git-shade as a library: the main commands for one project
+///
+/// Commands report progress like the binary does, on stdout or the context's
+/// output; warnings still go to stderr. They only prompt (on the terminal)
+/// when stdin is a terminal, so set the options that answer for them (e.g.
+/// `InitOptions::no_pull`, `AddOptions::force`) to stay non-interactive.
+pub struct GitShade {
+    context: Context,
+}
+
+impl GitShade {
+    pub fn new(context: Context) -> Self {
+        Self { context }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Register the project (`git-shade init`); `options.name` defaults to the context's
+    pub fn init(&self, mut options: InitOptions) -> Result<()> {
+        options
+            .name
+            .get_or_insert_with(|| self.context.project_name.clone());
+        self.run(|| init_project(&self.context.paths, &self.context.project_path, options))
+    }
+
+    /// Track files, relative to the project root (`git-shade add`)
+    pub fn add(&self, files: Vec<PathBuf>, options: AddOptions) -> Result<()> {
+        self.run(|| {
+            add_files(
+                &self.context.paths,
+                &self.context.project_path,
+                &self.context.project_name,
+                files,
+                options,
+            )
+        })
+    }
+
+    /// Copy, commit and push local changes (`git-shade push`), returning whether
+    /// a commit was made
+    pub fn push(&self, options: &PushOptions) -> Result<bool> {
+        self.ensure_initialized()?;
+        self.run(|| {
+            push_project(
+                &self.context.paths,
+                &self.context.project_path,
+                &self.context.project_name,
+                options,
+            )
+        })
+    }
+
+    /// Pull and sync shade's files into the project (`git-shade pull`),
+    /// returning how many were copied
+    pub fn pull(&self, options: &PullOptions) -> Result<usize> {
+        self.ensure_initialized()?;
+        self.run(|| {
+            pull_project(
+                &self.context.paths,
+                &self.context.project_path,
+                &self.context.project_name,
+                options,
+            )
+        })
+    }
+
+    /// Every tracked file with its sync status, sorted by path (`git-shade status`)
+    pub fn status(&self) -> Result<Vec<(PathBuf, FileStatus)>> {
+        let config = self.ensure_initialized()?;
+        self.run(|| {
+            file_statuses(
+                &self.context.paths,
+                &config,
+                &self.context.project_path,
+                &self.context.project_name,
+            )
+        })
+    }
+
+    /// Run a command, printing to the context's output
+    fn run<T>(&self, command: impl FnOnce() -> Result<T>) -> Result<T> {
+        redirect(self.context.output.as_ref(), command)
+    }
+
+    fn ensure_initialized(&self) -> Result<Config> {
+        let config = Config::load(&self.context.paths.config)?;
+        if config.find_project(&self.context.project_name).is_none() {
+            return Err(ShadeError::NotInitialized {
+                project_name: self.context.project_name.clone(),
+            });
+        }
+        Ok(config)
+    }
+}
@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use git_shade::commands::config::ConfigAction;
+use git_shade::commands::stash::StashAction;
+use git_shade::core::ConflictStrategy;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -6,26 +10,242 @@ use std::path::PathBuf;
 #[command(about = "Sync git-excluded files across machines")]
 #[command(version)]
 pub struct Cli {
+    #[arg(
+        short,
+        long,
+        global = true,
+        help = "List every synced file and log each git command with its output"
+    )]
+    pub verbose: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "When to color output (auto: only on a terminal and without NO_COLOR)"
+    )]
+    pub color: ColorChoice,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output; `auto` honors `NO_COLOR` and needs stdout to be a terminal
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Initialize a project to use git-shade
     Init {
         #[arg(long, help = "Project name (default: current directory name)")]
         name: Option<String>,
+        #[arg(long, help = "Don't pull existing shade files", conflicts_with = "yes")]
+        no_pull: bool,
+        #[arg(short, long, help = "Pull existing shade files without asking")]
+        yes: bool,
+        #[arg(long, help = "Create the shade repo without asking if there is none")]
+        create_shade: bool,
+        #[arg(
+            long,
+            value_name = "URL",
+            help = "Remote for a newly created shade repo (implies --create-shade)"
+        )]
+        remote: Option<String>,
+        #[arg(
+            long,
+            help = "Show what init would create and offer to pull, without doing it"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Copy an existing folder of secrets into the project's shade dir"
+        )]
+        import_from: Option<PathBuf>,
+    },
+    /// Clone an existing shade repo onto this machine
+    Clone {
+        #[arg(help = "URL of the shade repo")]
+        url: String,
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Only fetch the last N commits (shallow clone)"
+        )]
+        depth: Option<u32>,
+        #[arg(long, help = "Only fetch the default branch")]
+        single_branch: bool,
+    },
+    /// Deregister a project and remove its shade files (inverse of init)
+    Uninit {
+        #[arg(long, help = "Project name (default: current directory name)")]
+        name: Option<String>,
+        #[arg(long, help = "Leave the project's entries in .git/info/exclude")]
+        keep_exclude: bool,
+        #[arg(long, help = "Leave the project's files in the shade repo")]
+        keep_shade: bool,
+        #[arg(short, long, help = "Don't ask for confirmation")]
+        yes: bool,
     },
     /// Add files or directories to shade
     Add {
         #[arg(help = "Files or directories to add")]
         files: Vec<PathBuf>,
+        #[arg(long, help = "Write directories as `dir/**` globs instead of `dir/`")]
+        dir_as_glob: bool,
+        #[arg(
+            long,
+            help = "Skip confirmations: add past the size limits and overwrite differing shade copies"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Skip matching paths inside added directories (repeatable)"
+        )]
+        exclude: Vec<String>,
+        #[arg(
+            long,
+            help = "Also add existing files matched by .gitignore and .git/info/exclude"
+        )]
+        from_gitignore: bool,
+        #[arg(long, help = "Project name, if it was initialized with init --name")]
+        name: Option<String>,
+        #[arg(
+            long,
+            visible_alias = "move",
+            conflicts_with_all = ["dir_as_glob", "exclude", "from_gitignore"],
+            help = "Move files into shade and leave a symlink in their place (Unix only)"
+        )]
+        symlink: bool,
+        #[arg(long, help = "Also read paths to add from stdin, one per line")]
+        stdin: bool,
+        #[arg(
+            long,
+            conflicts_with = "stdin",
+            help = "Like --stdin, but NUL-separated (e.g. find -print0)"
+        )]
+        stdin0: bool,
+        #[arg(
+            long = "as",
+            value_name = "PATH",
+            conflicts_with_all = ["dir_as_glob", "exclude", "from_gitignore", "symlink", "stdin", "stdin0"],
+            help = "Track one file from outside the project, stored as PATH in shade"
+        )]
+        as_path: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "as_path",
+            help = "Keep files already in the shade dir when they differ, copying them into the project"
+        )]
+        link_existing: bool,
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with = "as_path",
+            help = "Add the files of a template that exist (node, rails, python, docker, or from config.toml)"
+        )]
+        template: Option<String>,
+        #[arg(
+            long,
+            conflicts_with_all = ["as_path", "symlink", "link_existing"],
+            help = "Only track the paths (they may not exist yet); push copies them once they do"
+        )]
+        no_copy: bool,
+        #[arg(
+            long,
+            value_name = "MODE",
+            value_parser = parse_mode,
+            conflicts_with = "as_path",
+            help = "Octal mode (e.g. 600) the files get on every push and pull (no-op on Windows)"
+        )]
+        chmod: Option<u32>,
+        #[arg(
+            long,
+            visible_alias = "git-add",
+            conflicts_with_all = ["as_path", "no_copy"],
+            help = "Also git add the copies in the shade repo (staged, not committed)"
+        )]
+        stage: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["as_path", "no_copy"],
+            help = "Store the files as this OS's variants (projects/<project>/<os>/...)"
+        )]
+        per_os: bool,
+    },
+    /// Rename a tracked file or directory, keeping its shade history
+    Mv {
+        #[arg(help = "Tracked file or directory to rename")]
+        from: PathBuf,
+        #[arg(help = "New path")]
+        to: PathBuf,
+        #[arg(long, help = "Project name, if it was initialized with init --name")]
+        name: Option<String>,
     },
     /// Sync local changes to shade repo and push
     Push {
         #[arg(short, long, help = "Custom commit message")]
         message: Option<String>,
+        #[arg(long, help = "Fold changes into this project's last shade commit")]
+        amend: bool,
+        #[arg(
+            long,
+            help = "Overwrite remote commits you've fetched but not pulled (refused if there are newer ones)"
+        )]
+        force_with_lease: bool,
+        #[arg(long, help = "Show which files changed in the commit just pushed")]
+        stat: bool,
+        #[arg(
+            long,
+            help = "Don't sign the commit (overrides sign_commits and commit.gpgsign)"
+        )]
+        no_sign: bool,
+        #[arg(
+            long,
+            help = "Only copy files changed here since the last sync, skipping ones in sync"
+        )]
+        only_changed: bool,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Label this push with NAME instead of machine_name or the hostname"
+        )]
+        machine: Option<String>,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Then tag the shade repo as <project>/NAME (annotated) and push the tag"
+        )]
+        tag: Option<String>,
+        #[arg(long, help = "Registered project to push (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
     },
     /// Pull changes from shade repo to local project
     Pull {
@@ -33,9 +253,309 @@ pub enum Commands {
         force: bool,
         #[arg(long, help = "Show what would happen without executing")]
         dry_run: bool,
+        #[arg(
+            long,
+            visible_alias = "offline",
+            help = "Don't git pull; sync from the shade dir as it is"
+        )]
+        no_fetch: bool,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ConflictStrategy::Abort,
+            conflicts_with = "force",
+            help = "How to settle files changed on both sides"
+        )]
+        strategy: ConflictStrategy,
+        #[arg(
+            long,
+            conflicts_with_all = ["force", "strategy", "dry_run", "output", "push_after"],
+            help = "Write both versions of conflicting text files into the local file, git-style"
+        )]
+        merge_markers: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["no_fetch", "ff_only", "merge"],
+            help = "Rebase shade commits onto the remote's (overrides pull_strategy)"
+        )]
+        rebase: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["no_fetch", "merge"],
+            help = "Only fast-forward the shade repo, failing if it has diverged"
+        )]
+        ff_only: bool,
+        #[arg(
+            long,
+            conflicts_with = "no_fetch",
+            help = "Merge the remote into the shade repo (overrides pull_strategy)"
+        )]
+        merge: bool,
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "force", "dry_run", "strategy", "merge_markers", "rebase", "ff_only", "merge",
+                "output", "backup_all", "push_after", "prune",
+            ],
+            help = "Only add the shade files to .git/info/exclude; copy nothing"
+        )]
+        set_exclude_only: bool,
+        #[arg(long, help = "Registered project to pull (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Write pulled files into DIR instead of the project"
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Only pull files matching PATTERN (repeatable)"
+        )]
+        only: Vec<String>,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Don't pull files matching PATTERN (repeatable)"
+        )]
+        except: Vec<String>,
+        #[arg(
+            long,
+            conflicts_with = "output",
+            help = "Back up local files before the pull overwrites them"
+        )]
+        backup_all: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["output", "dry_run", "no_fetch"],
+            help = "Then push files this machine has and the remote doesn't"
+        )]
+        push_after: bool,
+        #[arg(
+            long,
+            conflicts_with = "output",
+            help = "Delete local copies of synced files that were removed from shade"
+        )]
+        prune: bool,
+        #[arg(
+            short,
+            long,
+            requires = "prune",
+            help = "Don't ask before --prune deletes files"
+        )]
+        yes: bool,
+    },
+    /// Push automatically whenever tracked files change (until Ctrl-C)
+    Watch {
+        #[arg(
+            long,
+            value_name = "MS",
+            default_value_t = 1000,
+            help = "Wait for changes to settle this long before pushing"
+        )]
+        debounce: u64,
+        #[arg(
+            long,
+            help = "Registered project to watch (default: current directory)"
+        )]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// Pull remote changes, then push local changes
+    Sync {
+        #[arg(long, help = "Overwrite local files without conflict checking")]
+        force: bool,
+        #[arg(short, long, help = "Custom commit message")]
+        message: Option<String>,
+        #[arg(long, help = "Registered project to sync (default: current directory)")]
+        project: Option<String>,
     },
     /// Show synchronization status of files
-    Status,
+    Status {
+        #[arg(long, help = "Registered project to show (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+        #[arg(
+            long,
+            help = "Fetch the shade remote and report commits behind/ahead (no merge)"
+        )]
+        fetch: bool,
+        #[arg(long, help = "Only list files that aren't in sync, grouped by state")]
+        compact: bool,
+        #[arg(
+            long,
+            conflicts_with = "compact",
+            help = "Say what push and pull would each do with every file"
+        )]
+        plan: bool,
+        #[arg(
+            long,
+            help = "Exit with 1 if files need a push or pull, 2 if any conflict"
+        )]
+        exit_code: bool,
+    },
+    /// Write this project's shade files to a .tar.gz for offline transfer
+    Export {
+        #[arg(help = "Archive to write (e.g. myapp-shade.tar.gz)")]
+        out: PathBuf,
+        #[arg(
+            long,
+            help = "Registered project to export (default: current directory)"
+        )]
+        project: Option<String>,
+    },
+    /// Unpack an archive from `export` into the shade repo and pull it
+    Import {
+        #[arg(help = "Archive written by git-shade export")]
+        archive: PathBuf,
+        #[arg(
+            long,
+            help = "Project to import into, even if the archive is for another one"
+        )]
+        project: Option<String>,
+    },
+    /// Read or change settings in config.toml (`git-shade env` shows where it is)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print version, paths, git and shade repo details for bug reports
+    Env,
+    /// Diagnose setup problems and suggest fixes
+    Doctor {
+        #[arg(long, help = "Reset sync trackers that fail validation")]
+        repair: bool,
+    },
+    /// Compact the shade repo (git gc) and report how much space it freed
+    Gc {
+        #[arg(
+            long,
+            value_name = "WHEN",
+            help = "Also expire reflog entries older than this (e.g. now, 2.weeks.ago)"
+        )]
+        expire: Option<String>,
+    },
+    /// Check that exclude patterns, shade files and local files agree
+    Verify {
+        #[arg(long, help = "Reconcile what's out of sync (asks before deleting)")]
+        fix: bool,
+        #[arg(short, long, help = "Delete orphaned shade files without asking")]
+        yes: bool,
+        #[arg(
+            long,
+            help = "Registered project to verify (default: current directory)"
+        )]
+        project: Option<String>,
+    },
+    /// Undo the project's last shade commit, and push the undo
+    Rollback {
+        #[arg(short, long, help = "Don't ask for confirmation")]
+        yes: bool,
+        #[arg(long, help = "Registered project (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// Stop syncing tracked files for now, without untracking them
+    Pause {
+        #[arg(required = true, help = "Tracked files or directories to pause")]
+        files: Vec<PathBuf>,
+        #[arg(long, help = "Registered project (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// Sync paused files again
+    Resume {
+        #[arg(required = true, help = "Paused files or directories")]
+        files: Vec<PathBuf>,
+        #[arg(long, help = "Registered project (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// Set local changes to tracked files aside (and put them back with `stash pop`)
+    Stash {
+        #[command(subcommand)]
+        action: Option<StashAction>,
+        #[arg(long, help = "List the project's stashes, newest first")]
+        list: bool,
+        #[arg(long, help = "Registered project (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// Show when and from which machine a shaded file changed
+    History {
+        #[arg(help = "Tracked file, relative to the project root")]
+        file: PathBuf,
+        #[arg(short, long, help = "Include the diff of each change")]
+        patch: bool,
+        #[arg(long, help = "Registered project (default: current directory)")]
+        project: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "project",
+            help = "Project name, if it was initialized with init --name"
+        )]
+        name: Option<String>,
+    },
+    /// List the projects initialized here, or with --remote the ones to set up
+    List {
+        #[arg(
+            long,
+            help = "List projects in the shade repo not initialized on this machine"
+        )]
+        remote: bool,
+    },
     /// Explain how git-shade works and show setup guide
     Guide,
+    /// Print a shell completion script (e.g. `git-shade completions zsh > _git-shade`)
+    #[command(hide = true)]
+    Completions {
+        #[arg(help = "Shell to generate completions for")]
+        shell: clap_complete::Shell,
+    },
+}
+
+/// Octal permission bits like `600`, `0600` or `0o600`
+fn parse_mode(value: &str) -> Result<u32, String> {
+    let digits = value.trim_start_matches("0o");
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| format!("'{}' isn't an octal mode like 600 or 644", value))
 }
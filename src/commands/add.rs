@@ -1,33 +1,205 @@
-use crate::core::{Config, ShadePaths};
+use crate::core::{
+    case_collisions, format_case_collision_warning, is_excluded, shown_config, template_files,
+    template_names, to_slash, Config, HashCache, Manifest, ShadeLayout, ShadePaths,
+};
 use crate::error::{Result, ShadeError};
-use crate::git::add_to_exclude;
+use crate::git::{add_to_exclude, find_ignored, read_patterns, run_git, Pattern};
+use crate::utils::output::outln;
 use crate::utils::{
-    copy_dir_preserve_structure, copy_file_preserve_structure, detect_project_name, verify_git_repo,
+    contained_path, copy_file_to, detect_project_name, format_size, move_to_shade,
+    relative_to_project, set_file_mode, set_mtime, unshare_file, verify_git_repo, ShadeStore,
 };
 use colored::Colorize;
-use std::path::PathBuf;
+use dialoguer::{Confirm, Select};
+use filetime::FileTime;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// How `git-shade add --stdin` splits the paths it reads
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StdinPaths {
+    /// One path per line (`--stdin`)
+    Lines,
+    /// NUL-separated, e.g. from `find -print0` (`--stdin0`)
+    Nul,
+}
+
+/// Flags for `git-shade add`
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    pub dir_as_glob: bool,
+    pub force: bool,
+    /// Subpaths to skip inside added directories, gitignore-style
+    pub exclude: Vec<String>,
+    /// Also add existing files matched by the project's ignore rules
+    pub from_gitignore: bool,
+    /// Project name, if it was initialized with `init --name`
+    pub name: Option<String>,
+    /// Move files into shade and leave symlinks to them behind
+    pub symlink: bool,
+    /// Also read paths from stdin
+    pub stdin: Option<StdinPaths>,
+    /// Track a single file from outside the project under this shade path
+    pub as_path: Option<PathBuf>,
+    /// Keep shade copies that differ from the local files, copying them into the project
+    pub link_existing: bool,
+    /// Also add the files of this named template (built-in or from config.toml)
+    pub template: Option<String>,
+    /// Only record the patterns, for files that may not exist yet
+    pub no_copy: bool,
+    /// Permission bits the files get on every push and pull, whatever the local mode
+    pub chmod: Option<u32>,
+    /// `git add` the copies in the shade repo, so they show as staged until push commits them
+    pub stage: bool,
+    /// Store the files as this OS's variants, turning on the per-OS layout
+    pub per_os: bool,
+}
+
+/// What to do with a file whose shade copy differs from the local one
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Existing {
+    /// Overwrite the shade copy with the local file
+    KeepLocal,
+    /// Copy the shade version into the project
+    KeepShade,
+    /// Leave both alone, recorded in the manifest: status, push and pull treat
+    /// the file as a conflict until pull settles it
+    Conflict,
+}
 
-pub fn run(files: Vec<PathBuf>) -> Result<()> {
+pub fn run(files: Vec<PathBuf>, options: AddOptions) -> Result<()> {
     // 1. Verify it's a git repo
     let project_path = verify_git_repo()?;
 
     // 2. Detect project name
-    let project_name = detect_project_name(None)?;
+    let project_name = detect_project_name(options.name.clone())?;
 
     // 3. Setup paths
     let paths = ShadePaths::new()?;
 
+    add_files(&paths, &project_path, &project_name, files, options)
+}
+
+/// Add files to the project at `project_path` (relative paths are taken from there)
+pub fn add_files(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    mut files: Vec<PathBuf>,
+    options: AddOptions,
+) -> Result<()> {
+    let AddOptions {
+        dir_as_glob,
+        force,
+        exclude,
+        from_gitignore,
+        name: _,
+        symlink,
+        stdin,
+        as_path,
+        link_existing,
+        template,
+        no_copy,
+        chmod,
+        stage,
+        per_os,
+    } = options;
+
+    if symlink && !cfg!(unix) {
+        return Err(anyhow::anyhow!("--symlink is only supported on Unix").into());
+    }
+
     // 4. Verify project is initialized
     let config = Config::load(&paths.config)?;
-    if config.find_project(&project_name).is_none() {
-        return Err(ShadeError::NotInitialized { project_name });
+    if config.find_project(project_name).is_none() {
+        return Err(ShadeError::NotInitialized {
+            project_name: project_name.to_string(),
+        });
     }
 
-    let project_shade_dir = paths.project_shade_dir(&project_name);
+    // New files are shared unless added as this OS's variants
+    let mut layout = ShadeLayout::new(paths, &config, project_name);
+    if per_os {
+        layout.enable()?;
+    }
+    let project_shade_dir = match layout.os_dir() {
+        Some(os_dir) if per_os => os_dir,
+        _ => layout.shared_dir().to_path_buf(),
+    };
 
-    // 5. Process each file/directory
-    let mut added_files = Vec::new();
-    let mut patterns_to_exclude = Vec::new();
+    if let Some(as_path) = as_path {
+        let [source] = files.as_slice() else {
+            return Err(anyhow::anyhow!("--as takes exactly one file").into());
+        };
+        return add_external(
+            paths,
+            project_path,
+            project_name,
+            &project_shade_dir,
+            source,
+            &as_path,
+        );
+    }
+
+    if let Some(name) = template {
+        let found = expand_template(&name, &config, project_path)?;
+        if found.is_empty() && files.is_empty() && !from_gitignore && stdin.is_none() {
+            outln!("{} Nothing to add", "→".blue());
+            return Ok(());
+        }
+        files.extend(found);
+    }
+
+    if from_gitignore {
+        let ignored = find_importable(project_path)?;
+        if ignored.is_empty() && files.is_empty() {
+            outln!(
+                "{} No existing files matched by .gitignore or .git/info/exclude",
+                "→".blue()
+            );
+            return Ok(());
+        }
+
+        if !ignored.is_empty() {
+            outln!("Importing from ignore rules:");
+            for path in &ignored {
+                outln!("  - {}", path.display());
+            }
+            outln!();
+        }
+        files.extend(ignored);
+    }
+
+    if let Some(format) = stdin {
+        let piped = read_stdin_paths(format)?;
+        let tracked_patterns = read_patterns(project_path)?;
+        let (tracked, new): (Vec<PathBuf>, Vec<PathBuf>) = piped.into_iter().partition(|path| {
+            relative_to_project(project_path, path)
+                .is_ok_and(|rel| tracked_patterns.iter().any(|pattern| pattern.covers(&rel)))
+        });
+
+        if !tracked.is_empty() {
+            outln!(
+                "{} Skipping {} path(s) from stdin that are already tracked",
+                "→".blue(),
+                tracked.len()
+            );
+        }
+        for path in new {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+
+        if files.is_empty() {
+            outln!("{} Nothing to add", "→".blue());
+            return Ok(());
+        }
+    }
+
+    // 5. Resolve each file/directory to a pattern
+    let mut patterns = Vec::new();
 
     for file_path in &files {
         let full_path = if file_path.is_absolute() {
@@ -36,61 +208,716 @@ pub fn run(files: Vec<PathBuf>) -> Result<()> {
             project_path.join(file_path)
         };
 
-        // Verify file exists
-        if !full_path.exists() {
+        // Verify file exists (with --no-copy it may come later)
+        let exists = full_path.exists();
+        if !exists && !no_copy {
             return Err(ShadeError::FileNotFound(file_path.clone()));
         }
 
-        // Get relative path from project root
-        let rel_path = full_path
-            .strip_prefix(&project_path)
-            .map_err(|_| anyhow::anyhow!("File is not inside project directory"))?;
+        // Get relative path from project root, after resolving `..` and symlinks
+        // (a path that doesn't exist yet can only be checked as written)
+        let rel_path = if exists {
+            contained_path(project_path, &full_path, &project_shade_dir)?
+        } else {
+            planned_path(project_path, &full_path)?
+        };
+        let rel_path = rel_path.as_path();
 
-        // Add to exclude patterns
-        let pattern = if full_path.is_dir() {
-            format!("{}/", rel_path.display())
+        if symlink && full_path.is_symlink() {
+            return Err(anyhow::anyhow!(
+                "{} is already a symlink; add it without --symlink",
+                file_path.display()
+            )
+            .into());
+        }
+        if symlink && full_path.is_dir() {
+            return Err(anyhow::anyhow!(
+                "--symlink only works with files, not directories: {}",
+                file_path.display()
+            )
+            .into());
+        }
+
+        // .git/info/exclude has one pattern per line and no way to escape a newline
+        if rel_path.to_string_lossy().contains('\n') {
+            return Err(anyhow::anyhow!(
+                "Can't add {:?}: .git/info/exclude can't hold a path with a newline",
+                rel_path
+            )
+            .into());
+        }
+
+        // A trailing slash marks a directory that doesn't exist yet
+        let is_dir = full_path.is_dir() || (!exists && file_path.to_string_lossy().ends_with('/'));
+        let pattern = if is_dir {
+            Pattern::Dir(rel_path.to_path_buf())
         } else {
-            rel_path.display().to_string()
+            Pattern::File(rel_path.to_path_buf())
         };
-        patterns_to_exclude.push(pattern);
+        patterns.push(pattern);
+    }
+
+    // Editor/OS junk is skipped like the --exclude patterns, but not recorded
+    let skip: Vec<String> = exclude
+        .iter()
+        .chain(&config.junk_patterns)
+        .cloned()
+        .collect();
+
+    if no_copy {
+        return register_without_copy(
+            paths,
+            project_path,
+            project_name,
+            &patterns,
+            &exclude,
+            dir_as_glob,
+            chmod,
+        );
+    }
+
+    // 6. Guard against accidentally adding something huge
+    if !force && !confirm_large_add(&patterns, &skip, project_path, &config)? {
+        outln!("{} Nothing added", "→".blue());
+        return Ok(());
+    }
+
+    // 7. Don't clobber shade copies that differ (e.g. pushed from another machine)
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+    // The copy a file would replace: this OS's variant if it has one
+    let existing_shade_file = |rel: &Path| match layout.os_dir() {
+        Some(os_dir) if per_os => os_dir.join(rel),
+        _ => layout.file(rel),
+    };
+    let differing = find_differing(
+        &patterns,
+        &skip,
+        project_path,
+        existing_shade_file,
+        &mut hashes,
+    )?;
+    let existing = choose_existing(differing, link_existing, force, symlink)?;
 
-        // Copy to shade
-        if full_path.is_dir() {
-            let copied =
-                copy_dir_preserve_structure(&full_path, &project_path, &project_shade_dir)?;
+    // Conflicts keep the shade version, which the copy below would overwrite
+    let mut conflicted = Vec::new();
+    for (rel, choice) in &existing {
+        let shade_file = existing_shade_file(rel);
+        match choice {
+            Existing::KeepLocal => {}
+            Existing::KeepShade => copy_file_to(&shade_file, &project_path.join(rel))?,
+            Existing::Conflict => conflicted.push((
+                rel.clone(),
+                fs::read(&shade_file)?,
+                FileTime::from_last_modification_time(&fs::metadata(&shade_file)?),
+            )),
+        }
+    }
+
+    // 8. Copy to shade
+    let mut store = ShadeStore::new(config.storage, &paths.projects)?;
+    let mut added_files = Vec::new();
+    let mut patterns_to_exclude = Vec::new();
+
+    for pattern in &patterns {
+        patterns_to_exclude.push(pattern.to_exclude_line(dir_as_glob));
+
+        let full_path = project_path.join(pattern.path());
+        if symlink {
+            let moved = move_to_shade(&full_path, project_path, &project_shade_dir)?;
+            added_files.push(moved);
+        } else if full_path.is_dir() {
+            let copied = store.store_dir(&full_path, project_path, &project_shade_dir, &skip)?;
             added_files.extend(copied);
         } else {
-            let copied =
-                copy_file_preserve_structure(&full_path, &project_path, &project_shade_dir)?;
+            let copied = store.store_file(&full_path, project_path, &project_shade_dir)?;
             added_files.push(copied);
         }
     }
 
-    // 6. Add to .git/info/exclude
-    add_to_exclude(&project_path, &patterns_to_exclude)?;
+    for (rel, contents, modified) in &conflicted {
+        let shade_file = existing_shade_file(rel);
+        // Replace rather than write through: it may be hardlinked to another file
+        fs::remove_file(&shade_file)?;
+        fs::write(&shade_file, contents)?;
+        set_mtime(&shade_file, *modified)?;
+        added_files.retain(|file| *file != shade_file);
+    }
+
+    // 9. Add to .git/info/exclude
+    add_to_exclude(project_path, &patterns_to_exclude)?;
+
+    // Record what was added in the manifest
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    if !exclude.is_empty() {
+        for pattern in patterns.iter().filter(|pattern| pattern.is_dir()) {
+            manifest.add_excludes(pattern.path(), &exclude);
+        }
+    }
+    for file in &added_files {
+        if let Ok(rel) = file.strip_prefix(&project_shade_dir) {
+            manifest.record(rel, file, &mut hashes)?;
+        }
+    }
+    // Kept both: status, push and pull treat it as a conflict until pull settles it
+    for (rel, _, _) in &conflicted {
+        manifest.mark_conflict(rel);
+    }
+    if let Some(mode) = chmod {
+        for pattern in &patterns {
+            manifest.set_chmod(pattern.path(), mode);
+        }
+        for file in &added_files {
+            unshare_file(file)?;
+            set_file_mode(file, mode)?;
+            if let Ok(rel) = file.strip_prefix(&project_shade_dir) {
+                let local = project_path.join(rel);
+                if local.is_file() {
+                    set_file_mode(&local, mode)?;
+                }
+            }
+        }
+    }
+    manifest.save(&manifest_path)?;
+    hashes.save(&hash_cache_path)?;
+
+    if stage {
+        stage_in_shade(&paths.projects, &added_files)?;
+    }
 
-    // 7. Print success message
-    println!("{} Added to .git/info/exclude:", "✓".green().bold());
+    // 10. Print success message
+    outln!("{} Added to .git/info/exclude:", "✓".green().bold());
     for pattern in &patterns_to_exclude {
-        println!("  - {}", pattern);
+        outln!("  - {}", pattern);
     }
-    println!();
+    outln!();
 
-    println!(
-        "{} Copied to {}:",
+    if !exclude.is_empty() && patterns.iter().any(|pattern| pattern.is_dir()) {
+        outln!("{} Skipping inside directories:", "✓".green().bold());
+        for pattern in &exclude {
+            outln!("  - {}", pattern);
+        }
+        outln!();
+    }
+
+    let verb = if symlink {
+        "Moved (and symlinked back) to"
+    } else {
+        "Copied to"
+    };
+    outln!(
+        "{} {} {}:",
         "✓".green().bold(),
+        verb,
         project_shade_dir.display()
     );
 
     // Show relative paths from shade dir
     for file in &added_files {
         if let Ok(rel) = file.strip_prefix(&project_shade_dir) {
-            println!("  - {}", rel.display());
+            outln!("  - {}", rel.display());
         }
     }
-    println!();
+    outln!();
+
+    if stage && !added_files.is_empty() {
+        outln!(
+            "{} Staged {} file(s) in the shade repo (push commits them)",
+            "✓".green().bold(),
+            added_files.len()
+        );
+        outln!();
+    }
+
+    if let Some(mode) = chmod {
+        print_chmod(mode);
+    }
+
+    print_existing(&existing);
+
+    // Paths differing only by case are separate files here, one file on macOS/Windows
+    let added_rels: Vec<PathBuf> = added_files
+        .iter()
+        .filter_map(|file| file.strip_prefix(&project_shade_dir).ok())
+        .map(Path::to_path_buf)
+        .collect();
+    let collisions: Vec<_> = case_collisions(&manifest.paths())
+        .into_iter()
+        .filter(|(first, second)| added_rels.contains(first) || added_rels.contains(second))
+        .collect();
+    if !collisions.is_empty() {
+        outln!("{}", format_case_collision_warning(&collisions));
+        outln!();
+    }
 
-    println!("Ready to push with: {}", "git-shade push".bold());
+    outln!("Ready to push with: {}", "git-shade push".bold());
 
     Ok(())
 }
+
+/// `add --stage`: `git add` the given shade files in the shade repo
+fn stage_in_shade(repo: &Path, files: &[PathBuf]) -> Result<()> {
+    let rels: Vec<String> = files
+        .iter()
+        .filter_map(|file| file.strip_prefix(repo).ok())
+        .map(to_slash)
+        .collect();
+    if rels.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec!["add", "--"];
+    args.extend(rels.iter().map(String::as_str));
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShadeError::GitError(format!("git add failed: {}", stderr)));
+    }
+
+    Ok(())
+}
+
+/// `add --no-copy`: exclude and record the patterns, leaving the copy to push
+fn register_without_copy(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    patterns: &[Pattern],
+    exclude: &[String],
+    dir_as_glob: bool,
+    chmod: Option<u32>,
+) -> Result<()> {
+    let patterns_to_exclude: Vec<String> = patterns
+        .iter()
+        .map(|pattern| pattern.to_exclude_line(dir_as_glob))
+        .collect();
+    add_to_exclude(project_path, &patterns_to_exclude)?;
+
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    for pattern in patterns {
+        if pattern.is_dir() && !exclude.is_empty() {
+            manifest.add_excludes(pattern.path(), exclude);
+        }
+        manifest.add_pending(pattern.path());
+        if let Some(mode) = chmod {
+            manifest.set_chmod(pattern.path(), mode);
+        }
+    }
+    manifest.save(&manifest_path)?;
+
+    outln!("{} Added to .git/info/exclude:", "✓".green().bold());
+    for (pattern, line) in patterns.iter().zip(&patterns_to_exclude) {
+        let note = if project_path.join(pattern.path()).exists() {
+            ""
+        } else {
+            " (doesn't exist yet)"
+        };
+        outln!("  - {}{}", line, note.bright_black());
+    }
+    outln!();
+    if let Some(mode) = chmod {
+        print_chmod(mode);
+    }
+    outln!("Nothing was copied to shade. Push copies these once they exist:");
+    outln!("  {}", "git-shade push".bold());
+
+    Ok(())
+}
+
+fn print_chmod(mode: u32) {
+    if cfg!(unix) {
+        outln!(
+            "{} Files get mode {:04o} on every push and pull",
+            "✓".green().bold(),
+            mode
+        );
+    } else {
+        outln!(
+            "{} Mode {:04o} recorded; push and pull apply it on Unix machines",
+            "→".blue(),
+            mode
+        );
+    }
+    outln!();
+}
+
+/// Project-relative path of `full_path`, which doesn't exist yet
+///
+/// Nothing can be resolved, so `..` is refused outright rather than followed.
+fn planned_path(project_path: &Path, full_path: &Path) -> Result<PathBuf> {
+    let rel_path = relative_to_project(project_path, full_path)
+        .map_err(|_| ShadeError::PathOutsideProject(full_path.to_path_buf()))?;
+    if rel_path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(ShadeError::PathOutsideProject(full_path.to_path_buf()));
+    }
+    Ok(rel_path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect())
+}
+
+/// Files about to be added that shade already has, with other contents
+///
+/// That's usually a version another machine added and pushed first.
+fn find_differing(
+    patterns: &[Pattern],
+    exclude: &[String],
+    project_path: &Path,
+    shade_file: impl Fn(&Path) -> PathBuf,
+    hashes: &mut HashCache,
+) -> Result<Vec<PathBuf>> {
+    let mut differing = Vec::new();
+    for pattern in patterns {
+        for rel in pattern.files_in(project_path)? {
+            let skipped = pattern.is_dir()
+                && rel
+                    .strip_prefix(pattern.path())
+                    .is_ok_and(|rest| is_excluded(rest, exclude));
+            let shade_file = shade_file(&rel);
+            if skipped || !shade_file.is_file() || differing.contains(&rel) {
+                continue;
+            }
+            if !hashes.same_contents(&project_path.join(&rel), &shade_file)? {
+                differing.push(rel);
+            }
+        }
+    }
+
+    Ok(differing)
+}
+
+/// Decide what to do with each differing file
+///
+/// Without a terminal to ask on, `--link-existing` keeps the shade versions
+/// and `--force` overwrites them; with neither, nothing is copied. With
+/// `--symlink` the local file becomes a link to shade, so both can't be kept.
+fn choose_existing(
+    differing: Vec<PathBuf>,
+    link_existing: bool,
+    force: bool,
+    symlink: bool,
+) -> Result<Vec<(PathBuf, Existing)>> {
+    if differing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let all = |choice| differing.iter().map(|rel| (rel.clone(), choice)).collect();
+    if link_existing {
+        return Ok(all(Existing::KeepShade));
+    }
+    if force {
+        return Ok(all(Existing::KeepLocal));
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::ShadeCopyDiffers {
+            files: differing
+                .iter()
+                .map(|rel| rel.display().to_string())
+                .collect(),
+        });
+    }
+
+    outln!(
+        "{} {} file(s) are already in shade with different contents:",
+        "⚠".yellow(),
+        differing.len()
+    );
+    let mut items = vec![
+        "Keep local (overwrite the shade copy)",
+        "Keep shade (overwrite the local file)",
+    ];
+    if !symlink {
+        items.push("Keep both (resolve later as a conflict)");
+    }
+
+    let mut choices = Vec::new();
+    for rel in differing {
+        let choice = Select::new()
+            .with_prompt(rel.display().to_string())
+            .items(&items)
+            .default(items.len() - 1)
+            .interact()
+            .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+        let choice = match choice {
+            0 => Existing::KeepLocal,
+            1 => Existing::KeepShade,
+            _ => Existing::Conflict,
+        };
+        choices.push((rel, choice));
+    }
+    outln!();
+
+    Ok(choices)
+}
+
+fn print_existing(existing: &[(PathBuf, Existing)]) {
+    let with = |wanted| -> Vec<&PathBuf> {
+        existing
+            .iter()
+            .filter(|(_, choice)| *choice == wanted)
+            .map(|(rel, _)| rel)
+            .collect()
+    };
+
+    let kept_shade = with(Existing::KeepShade);
+    if !kept_shade.is_empty() {
+        outln!("{} Kept the shade version of:", "←".blue());
+        for rel in kept_shade {
+            outln!("  - {}", rel.display());
+        }
+        outln!();
+    }
+
+    let conflicts = with(Existing::Conflict);
+    if !conflicts.is_empty() {
+        outln!(
+            "{} Left in conflict (resolve with {}):",
+            "⚠".yellow(),
+            "git-shade pull --strategy <local|remote>".bold()
+        );
+        for rel in conflicts {
+            outln!("  - {}", rel.display());
+        }
+        outln!();
+    }
+}
+
+/// Track a file outside the project (`add --as`) as `as_path` in shade
+///
+/// A source that doesn't exist yet is fine if shade already has the file:
+/// that's how a second machine maps it before pulling.
+fn add_external(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    project_shade_dir: &Path,
+    source: &Path,
+    as_path: &Path,
+) -> Result<()> {
+    let source = project_path.join(source);
+    let source = fs::canonicalize(&source).unwrap_or(source);
+
+    if source.starts_with(project_path) {
+        return Err(anyhow::anyhow!(
+            "{} is inside the project; add it without --as",
+            source.display()
+        )
+        .into());
+    }
+
+    let plain_relative = as_path
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)));
+    if !plain_relative || as_path.as_os_str().is_empty() {
+        return Err(anyhow::anyhow!(
+            "--as needs a path inside the project's shade dir, like external/settings (got {})",
+            as_path.display()
+        )
+        .into());
+    }
+
+    // It must not clash with a file the exclude patterns bring into the project
+    if let Some(pattern) = read_patterns(project_path)?
+        .into_iter()
+        .find(|pattern| as_path.starts_with(pattern.path()) || pattern.path().starts_with(as_path))
+    {
+        return Err(anyhow::anyhow!(
+            "--as {} overlaps the tracked pattern {}",
+            as_path.display(),
+            pattern
+        )
+        .into());
+    }
+
+    let shade_file = project_shade_dir.join(as_path);
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    if let Some((_, other)) = manifest
+        .externals()
+        .into_iter()
+        .find(|(rel, other)| rel == as_path && *other != source)
+    {
+        return Err(anyhow::anyhow!(
+            "{} is already tracked from {}",
+            as_path.display(),
+            other.display()
+        )
+        .into());
+    }
+
+    if source.is_dir() {
+        return Err(anyhow::anyhow!("--as only works with files: {}", source.display()).into());
+    }
+    if !source.exists() && !shade_file.is_file() {
+        return Err(ShadeError::FileNotFound(source));
+    }
+
+    manifest.add_external(as_path, &source);
+    if source.exists() {
+        let hash_cache_path = paths.hash_cache_file(project_name);
+        let mut hashes = HashCache::load(&hash_cache_path)?;
+        copy_file_to(&source, &shade_file)?;
+        manifest.record(as_path, &source, &mut hashes)?;
+        hashes.save(&hash_cache_path)?;
+    }
+    manifest.save(&manifest_path)?;
+
+    outln!(
+        "{} Tracking {} as {}",
+        "✓".green().bold(),
+        source.display(),
+        as_path.display()
+    );
+    outln!();
+
+    if source.exists() {
+        outln!("Ready to push with: {}", "git-shade push".bold());
+    } else {
+        outln!(
+            "Not there yet; fetch it from shade with: {}",
+            "git-shade pull".bold()
+        );
+    }
+
+    Ok(())
+}
+
+/// The files of template `name` that exist and aren't tracked yet
+///
+/// Prints the template with a note for every file that's skipped.
+fn expand_template(name: &str, config: &Config, project_path: &Path) -> Result<Vec<PathBuf>> {
+    let Some(entries) = template_files(name, &config.templates) else {
+        return Err(anyhow::anyhow!(
+            "Unknown template: {}\n\nAvailable templates: {}\nDefine your own under [templates] in {}",
+            name,
+            template_names(&config.templates).join(", "),
+            shown_config()
+        )
+        .into());
+    };
+
+    let tracked_patterns = read_patterns(project_path)?;
+    let mut files = Vec::new();
+
+    outln!("Template {}:", name.bold());
+    for entry in &entries {
+        let rel = PathBuf::from(entry.trim_end_matches('/'));
+        if tracked_patterns.iter().any(|pattern| pattern.covers(&rel)) {
+            outln!("  {} {} (already tracked)", "→".blue(), entry);
+        } else if !project_path.join(&rel).exists() {
+            outln!("  {} {} (not found, skipped)", "?".bright_black(), entry);
+        } else {
+            outln!("  {} {}", "+".green(), entry);
+            files.push(rel);
+        }
+    }
+    outln!();
+
+    Ok(files)
+}
+
+/// Paths piped in on stdin, empty entries skipped
+fn read_stdin_paths(format: StdinPaths) -> Result<Vec<PathBuf>> {
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+
+    let separator = match format {
+        StdinPaths::Lines => b'\n',
+        StdinPaths::Nul => b'\0',
+    };
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in input.split(|byte| *byte == separator) {
+        let entry = String::from_utf8_lossy(entry);
+        let entry = match format {
+            StdinPaths::Lines => entry.trim_end_matches('\r'),
+            StdinPaths::Nul => &entry,
+        };
+        let path = PathBuf::from(entry);
+        if !entry.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Ignored files and directories that exist, aren't shaded yet and aren't
+/// committed (a committed file stays tracked by git whatever .gitignore says)
+fn find_importable(project_path: &Path) -> Result<Vec<PathBuf>> {
+    let tracked_patterns = read_patterns(project_path)?;
+
+    let output = run_git(project_path, &["ls-files", "-z"])?;
+    let committed: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect();
+
+    Ok(find_ignored(project_path)?
+        .into_iter()
+        .filter(|path| !tracked_patterns.iter().any(|pattern| pattern.covers(path)))
+        .filter(|path| !committed.iter().any(|file| file.starts_with(path)))
+        .collect())
+}
+
+/// Ask before adding more than the configured size or file count
+///
+/// Returns whether to go ahead. Without a terminal there is nobody to ask,
+/// so going over the limits is an error instead.
+fn confirm_large_add(
+    patterns: &[Pattern],
+    exclude: &[String],
+    project_path: &Path,
+    config: &Config,
+) -> Result<bool> {
+    let mut count = 0;
+    let mut size = 0;
+    for pattern in patterns {
+        for rel in pattern.files_in(project_path)? {
+            let skipped = pattern.is_dir()
+                && rel
+                    .strip_prefix(pattern.path())
+                    .is_ok_and(|rest| is_excluded(rest, exclude));
+            if skipped {
+                continue;
+            }
+
+            count += 1;
+            size += fs::metadata(project_path.join(rel))?.len();
+        }
+    }
+
+    if count <= config.max_add_files && size <= config.max_add_size {
+        return Ok(true);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::AddLimitExceeded {
+            count,
+            size: format_size(size),
+        });
+    }
+
+    outln!(
+        "{} About to add {} file(s), {} in total (limits: {} files, {})",
+        "⚠".yellow(),
+        count,
+        format_size(size),
+        config.max_add_files,
+        format_size(config.max_add_size)
+    );
+
+    let confirmed = Confirm::new()
+        .with_prompt("Add them anyway?")
+        .default(false)
+        .interact()
+        .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+
+    Ok(confirmed)
+}
@@ -0,0 +1,91 @@
+use crate::commands::init::print_remote;
+use crate::commands::list;
+use crate::core::ShadePaths;
+use crate::error::{Result, ShadeError};
+use crate::git::{classify_pull_failure, run_git, PullFailure};
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::fs;
+
+pub fn run(url: String, depth: Option<u32>, single_branch: bool) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Refuse to clone over an existing shade repo or directory
+    if paths.projects.join(".git").exists() {
+        return Err(anyhow::anyhow!(
+            "A shade repo already exists at {}\n\n\
+             Set up its projects with git-shade init, or move it aside to clone again.",
+            paths.projects.display()
+        )
+        .into());
+    }
+    let has_files = paths.projects.is_dir() && fs::read_dir(&paths.projects)?.next().is_some();
+    if has_files {
+        return Err(ShadeError::ShadeDirNotRepo {
+            path: paths.projects.clone(),
+        });
+    }
+
+    // 3. Clone
+    let parent = paths
+        .projects
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid shade repo path"))?;
+    fs::create_dir_all(parent)?;
+
+    let depth_arg = depth.map(|depth| depth.to_string());
+    let mut args = vec!["clone", "--quiet"];
+    if let Some(depth) = &depth_arg {
+        args.extend(["--depth", depth]);
+    }
+    if single_branch {
+        args.push("--single-branch");
+    }
+    let target = paths.projects.to_string_lossy().to_string();
+    args.extend(["--", url.as_str(), target.as_str()]);
+
+    outln!("Cloning shade repo...");
+    let clone_output = run_git(parent, &args)?;
+    if !clone_output.status.success() {
+        let stderr = String::from_utf8_lossy(&clone_output.stderr);
+        return Err(match classify_pull_failure(&stderr) {
+            PullFailure::Remote => ShadeError::RemoteUnreachable(stderr.trim().to_string()),
+            _ => ShadeError::GitError(format!("git clone failed: {}", stderr.trim())),
+        });
+    }
+
+    outln!(
+        "{} Cloned shade repo into {}",
+        "✓".green().bold(),
+        paths.projects.display()
+    );
+    print_remote(&url, &paths.projects);
+
+    // 4. Explain what a partial clone leaves out
+    if let Some(depth) = depth {
+        outln!();
+        outln!(
+            "{} Shallow clone: only the last {} commit(s) are here.",
+            "→".blue(),
+            depth
+        );
+        outln!("  history and rollback can't go further back, and pulled files");
+        outln!("  last changed before that get the oldest fetched commit's time.");
+        outln!("  If the remote refuses a push from it, push fetches the rest first.");
+        outln!(
+            "  To fetch everything now: cd {} && git fetch --unshallow",
+            paths.projects.display()
+        );
+    } else if single_branch {
+        outln!();
+        outln!(
+            "{} Single-branch clone: other branches of the shade repo aren't fetched.",
+            "→".blue()
+        );
+    }
+
+    // 5. Show what there is to set up
+    outln!();
+    list::run(true)
+}
@@ -0,0 +1,155 @@
+use crate::core::{Config, ShadePaths, CONFIG_KEYS};
+use crate::error::Result;
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// What `git-shade config` should do
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum ConfigAction {
+    /// Print a value (nothing if it's unset)
+    Get {
+        #[arg(help = "Key, e.g. max_add_files or hooks.pre_push")]
+        key: String,
+    },
+    /// Validate and store a value (empty unsets optional keys)
+    Set {
+        #[arg(help = "Key, e.g. max_add_files or hooks.pre_push")]
+        key: String,
+        #[arg(help = "New value (lists are comma-separated)")]
+        value: String,
+    },
+    /// Print every key and its value
+    List,
+    /// Open config.toml in $VISUAL or $EDITOR, keeping the edit only if it parses
+    Edit,
+}
+
+pub fn run(action: ConfigAction) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // Editing must work even when the current file doesn't parse
+    if let ConfigAction::Edit = action {
+        return edit(&paths.config);
+    }
+
+    // 2. Load config (defaults if there is no file yet)
+    let mut config = Config::load(&paths.config)?;
+
+    match action {
+        // Unset keys print nothing, so `$(git-shade config get ...)` is empty
+        ConfigAction::Get { key } => {
+            if let Some(value) = config.get(&key)? {
+                outln!("{}", value);
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            config.set(&key, &value)?;
+            config.save(&paths.config)?;
+
+            match config.get(&key)? {
+                Some(value) => outln!("{} {} = {}", "✓".green(), key, value),
+                None => outln!("{} {} unset", "✓".green(), key),
+            }
+        }
+        ConfigAction::List => {
+            for key in CONFIG_KEYS {
+                match config.get(key)? {
+                    Some(value) => outln!("{} = {}", key, value),
+                    None => outln!("{} = {}", key, "(not set)".bright_black()),
+                }
+            }
+        }
+        ConfigAction::Edit => unreachable!("handled before loading the config"),
+    }
+
+    Ok(())
+}
+
+/// Let the user edit `path`, putting back what was there if the result doesn't parse
+fn edit(path: &Path) -> Result<()> {
+    let previous = if path.exists() {
+        Some(fs::read(path)?)
+    } else {
+        None
+    };
+    if previous.is_none() {
+        // Start from the defaults rather than an empty file
+        Config::default().save(path)?;
+    }
+
+    let restore = || -> Result<()> {
+        match &previous {
+            Some(contents) => fs::write(path, contents)?,
+            None => fs::remove_file(path)?,
+        }
+        Ok(())
+    };
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+
+    let status = editor_command(&editor, path).status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            restore()?;
+            return Err(anyhow::anyhow!(
+                "{} exited with {}; config.toml was left as it was",
+                editor,
+                status
+            )
+            .into());
+        }
+        Err(e) => {
+            restore()?;
+            return Err(anyhow::anyhow!("Failed to run {}: {}", editor, e).into());
+        }
+    }
+
+    if let Err(e) = Config::load(path) {
+        restore()?;
+        return Err(anyhow::anyhow!(
+            "The edited config.toml doesn't parse, so the edit was discarded\n\n{:#}",
+            e
+        )
+        .into());
+    }
+
+    outln!("{} Saved {}", "✓".green(), path.display());
+    Ok(())
+}
+
+#[cfg(unix)]
+const DEFAULT_EDITOR: &str = "vi";
+
+#[cfg(not(unix))]
+const DEFAULT_EDITOR: &str = "notepad";
+
+/// The editor command line with `path` appended, run through the shell like git does
+/// (so `EDITOR="code --wait"` works)
+#[cfg(unix)]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path);
+    command
+}
+
+#[cfg(not(unix))]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .arg(format!("{} \"{}\"", editor, path.display()));
+    command
+}
@@ -0,0 +1,234 @@
+use crate::core::{to_slash, Config, ShadePaths, Tracker};
+use crate::error::{Result, ShadeError};
+use crate::git::run_git;
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+
+/// Tally of check results, printed as they run
+struct Report {
+    critical: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn new() -> Self {
+        Self {
+            critical: 0,
+            warnings: 0,
+        }
+    }
+
+    fn ok(&self, message: &str) {
+        outln!("  {} {}", "✓".green(), message);
+    }
+
+    fn fail(&mut self, message: &str, fix: &str) {
+        self.critical += 1;
+        outln!("  {} {}", "✗".red(), message);
+        outln!("      {} {}", "Fix:".bold(), fix);
+    }
+
+    fn warn(&mut self, message: &str, fix: &str) {
+        self.warnings += 1;
+        outln!("  {} {}", "⚠".yellow(), message);
+        outln!("      {} {}", "Fix:".bold(), fix);
+    }
+}
+
+pub fn run(repair: bool) -> Result<()> {
+    let paths = ShadePaths::new()?;
+    let mut report = Report::new();
+
+    // 1. Shade root and repository
+    outln!("{}:", "Shade repository".bold());
+
+    if paths.root.exists() {
+        report.ok(&format!("Root exists: {}", paths.root.display()));
+    } else {
+        report.fail(
+            &format!("Root missing: {}", paths.root.display()),
+            &format!("mkdir -p {}", paths.root.display()),
+        );
+    }
+
+    if paths.projects.join(".git").exists() {
+        report.ok(&format!("Shade repo found: {}", paths.projects.display()));
+
+        let remote_output = run_git(&paths.projects, &["remote", "-v"])?;
+
+        let remote_info = String::from_utf8_lossy(&remote_output.stdout);
+        match remote_info
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+        {
+            Some(url) => report.ok(&format!("Remote configured: {}", url)),
+            None => report.warn(
+                "No remote configured - changes stay on this machine",
+                &format!(
+                    "cd {} && git remote add origin <url>",
+                    paths.projects.display()
+                ),
+            ),
+        }
+    } else if paths.projects.is_dir() {
+        report.fail(
+            &format!(
+                "Shade directory isn't a git repository: {}",
+                paths.projects.display()
+            ),
+            &format!("cd {} && git init", paths.projects.display()),
+        );
+    } else {
+        report.fail(
+            &format!("Shade repo missing: {}", paths.projects.display()),
+            &format!(
+                "git clone <your-shade-repo-url> {}",
+                paths.projects.display()
+            ),
+        );
+    }
+    outln!();
+
+    // 2. Config file
+    outln!("{}:", "Config".bold());
+
+    let config = if !paths.config.exists() {
+        report.warn(
+            &format!("No config yet: {}", paths.config.display()),
+            "Run git-shade init inside a project",
+        );
+        None
+    } else {
+        match Config::load(&paths.config) {
+            Ok(config) => {
+                report.ok(&format!("Config parses: {}", paths.config.display()));
+                Some(config)
+            }
+            Err(e) => {
+                report.fail(
+                    &format!("Config is invalid: {:#}", e),
+                    &format!("Fix or remove {}", paths.config.display()),
+                );
+                None
+            }
+        }
+    };
+    outln!();
+
+    // 3. Registered projects
+    if let Some(config) = config {
+        for project in &config.projects {
+            outln!("{} {}:", "Project".bold(), project.name.bold());
+
+            if project.local_path.exists() {
+                report.ok(&format!(
+                    "Local path exists: {}",
+                    project.local_path.display()
+                ));
+                check_shade_nesting(&mut report, &paths, &project.local_path)?;
+            } else {
+                report.fail(
+                    &format!("Local path missing: {}", project.local_path.display()),
+                    &format!(
+                        "Restore the project or remove '{}' from {}",
+                        project.name,
+                        paths.config.display()
+                    ),
+                );
+            }
+
+            let metadata_dir = paths.project_metadata_dir(&project.name);
+            if metadata_dir.exists() {
+                report.ok(&format!("Metadata dir exists: {}", metadata_dir.display()));
+            } else {
+                report.fail(
+                    &format!("Metadata dir missing: {}", metadata_dir.display()),
+                    &format!("mkdir -p {}", metadata_dir.display()),
+                );
+            }
+
+            let sync_file = paths.tracker_file(&config, &project.name);
+            if sync_file.exists() {
+                match Tracker::load(&sync_file) {
+                    Ok(_) => report.ok("Sync tracker exists (.shade-sync)"),
+                    Err(e) if repair => {
+                        Tracker::new().save(&sync_file)?;
+                        report.ok(&format!("Sync tracker reset (was invalid: {:#})", e));
+                    }
+                    Err(e) => report.warn(
+                        &format!("Sync tracker is invalid: {:#}", e),
+                        "git-shade doctor --repair (the next pull treats every difference as a conflict)",
+                    ),
+                }
+            } else {
+                report.warn(
+                    &format!("Sync tracker missing: {}", sync_file.display()),
+                    "Run git-shade pull to recreate it",
+                );
+            }
+            outln!();
+        }
+    }
+
+    // 4. Summary
+    if report.critical > 0 {
+        return Err(ShadeError::DoctorFailed(report.critical));
+    }
+
+    if report.warnings > 0 {
+        outln!(
+            "{} No critical problems, {} warning(s)",
+            "✓".green().bold(),
+            report.warnings
+        );
+    } else {
+        outln!("{} Everything looks good", "✓".green().bold());
+    }
+
+    Ok(())
+}
+
+/// Warn when the shade repo lives inside the project, unless the project ignores it
+///
+/// Its copies of the project's files would otherwise show up in the project
+/// as untracked files (tracked recursively if committed), and search tools
+/// find every match twice.
+fn check_shade_nesting(report: &mut Report, paths: &ShadePaths, project_path: &Path) -> Result<()> {
+    let (Ok(projects), Ok(project)) = (
+        fs::canonicalize(&paths.projects),
+        fs::canonicalize(project_path),
+    ) else {
+        return Ok(());
+    };
+    let Ok(rel) = projects.strip_prefix(&project) else {
+        return Ok(());
+    };
+
+    let rel = format!("{}/", to_slash(rel));
+    let ignored = run_git(&project, &["check-ignore", "-q", &rel])?
+        .status
+        .success();
+    if ignored {
+        report.ok(&format!(
+            "Shade repo is inside the project ({}), ignored",
+            rel
+        ));
+    } else {
+        report.warn(
+            &format!(
+                "Shade repo is inside the project ({}) - its copies show up as project files",
+                rel
+            ),
+            &format!(
+                "echo '/{}' >> {}",
+                rel,
+                project.join(".git/info/exclude").display()
+            ),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use crate::core::ShadePaths;
+use crate::error::Result;
+use crate::git::{git_version, run_git};
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::path::Path;
+
+pub fn run() -> Result<()> {
+    // 1. Version and platform
+    outln!("git-shade {}", env!("CARGO_PKG_VERSION"));
+    outln!(
+        "  Platform: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let git = git_version();
+    outln!(
+        "  Git:      {}",
+        git.as_deref().unwrap_or("not found on PATH")
+    );
+    outln!();
+
+    // 2. Resolved paths
+    let paths = ShadePaths::new()?;
+    outln!("{}:", "Paths".bold());
+    print_path("Root:    ", &paths.root);
+    print_path("Config:  ", &paths.config);
+    print_path("Metadata:", &paths.metadata);
+    print_path("Projects:", &paths.projects);
+    outln!();
+
+    // 3. Shade repo and its remote
+    outln!("{}:", "Shade repo".bold());
+    if !paths.projects.join(".git").exists() {
+        outln!("  {} Not found", "✗".red());
+        return Ok(());
+    }
+    outln!("  {} Found", "✓".green());
+
+    if git.is_none() {
+        return Ok(());
+    }
+    let output = run_git(&paths.projects, &["remote", "-v"])?;
+    let remotes = String::from_utf8_lossy(&output.stdout);
+    let fetch_urls: Vec<&str> = remotes
+        .lines()
+        .filter(|line| line.ends_with("(fetch)"))
+        .collect();
+    if fetch_urls.is_empty() {
+        outln!("  {} No remote configured", "⚠".yellow());
+    }
+    for line in fetch_urls {
+        let mut parts = line.split_whitespace();
+        if let (Some(name), Some(url)) = (parts.next(), parts.next()) {
+            outln!("  Remote: {} {}", name, url);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_path(label: &str, path: &Path) {
+    let note = if path.exists() {
+        String::new()
+    } else {
+        format!(" {}", "(missing)".dimmed())
+    };
+    outln!("  {} {}{}", label, path.display(), note);
+}
@@ -0,0 +1,57 @@
+use crate::core::{write_archive, ArchiveHeader, Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::utils::output::outln;
+use crate::utils::resolve_project;
+use colored::Colorize;
+use std::path::PathBuf;
+
+pub fn run(out: PathBuf, project: Option<String>) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project) and verify it's initialized
+    let (_project_path, project_name) = resolve_project(&config, project, None)?;
+
+    // 5. Nothing to export without shade files
+    let project_shade_dir = paths.project_shade_dir(&project_name);
+    if !project_shade_dir.exists() {
+        return Err(ShadeError::NoFilesTracked);
+    }
+
+    // 6. Write the archive
+    let header = ArchiveHeader {
+        project: project_name.clone(),
+        hostname: config.machine_name(),
+        exported_at: chrono::Utc::now(),
+    };
+
+    let count = write_archive(
+        &out,
+        &header,
+        &project_shade_dir,
+        &paths.project_metadata_dir(&project_name),
+    )?;
+
+    if count == 0 {
+        return Err(ShadeError::NoFilesTracked);
+    }
+
+    // 7. Print summary
+    outln!(
+        "{} Exported {} file(s) of {} to {}",
+        "✓".green().bold(),
+        count,
+        project_name.bold(),
+        out.display()
+    );
+    outln!();
+    outln!(
+        "On the other machine, run inside the project: {}",
+        format!("git-shade import {}", out.display()).bold()
+    );
+
+    Ok(())
+}
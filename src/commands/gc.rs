@@ -0,0 +1,70 @@
+use crate::core::ShadePaths;
+use crate::error::{Result, ShadeError};
+use crate::git::{object_store_size, run_git};
+use crate::utils::format_size;
+use crate::utils::output::outln;
+use colored::Colorize;
+
+pub fn run(expire: Option<String>) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Verify the shade repo exists
+    if !paths.projects.join(".git").exists() {
+        return Err(if paths.projects.is_dir() {
+            ShadeError::ShadeDirNotRepo {
+                path: paths.projects.clone(),
+            }
+        } else {
+            ShadeError::ShadeRepoNotFound
+        });
+    }
+
+    // 3. Measure before
+    let before = object_store_size(&paths.projects)?;
+    outln!("Compacting {}...", paths.projects.display());
+
+    // 4. Expire reflog entries first, or they keep replaced objects alive
+    if let Some(when) = &expire {
+        let output = run_git(
+            &paths.projects,
+            &["reflog", "expire", &format!("--expire={}", when), "--all"],
+        )?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ShadeError::GitError(format!(
+                "git reflog expire failed: {}",
+                stderr.trim()
+            )));
+        }
+        outln!(
+            "  {} Expired reflog entries older than {}",
+            "✓".green(),
+            when
+        );
+    }
+
+    // 5. Repack everything and drop unreachable objects
+    let output = run_git(&paths.projects, &["gc", "--aggressive", "--prune=now"])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShadeError::GitError(format!(
+            "git gc failed: {}",
+            stderr.trim()
+        )));
+    }
+    outln!("  {} Repacked objects", "✓".green());
+
+    // 6. Report the difference
+    let after = object_store_size(&paths.projects)?;
+    outln!();
+    outln!(
+        "{} Shade repo: {} → {} ({} freed)",
+        "✓".green().bold(),
+        format_size(before),
+        format_size(after),
+        format_size(before.saturating_sub(after))
+    );
+
+    Ok(())
+}
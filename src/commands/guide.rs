@@ -1,342 +1,474 @@
+use crate::core::{shown_config, shown_projects, shown_root};
+use crate::utils::output::outln;
 use colored::Colorize;
 
 pub fn run() {
     print_header();
-    println!();
+    outln!();
     print_what_is_git_shade();
-    println!();
+    outln!();
     print_how_it_works();
-    println!();
+    outln!();
     print_architecture();
-    println!();
+    outln!();
     print_first_time_setup();
-    println!();
+    outln!();
     print_daily_workflow();
-    println!();
+    outln!();
     print_commands_overview();
-    println!();
+    outln!();
     print_sync_states();
-    println!();
+    outln!();
     print_troubleshooting();
 }
 
 fn print_header() {
-    println!(
+    outln!(
         "{}",
         "═══════════════════════════════════════════════════════════════".bright_cyan()
     );
-    println!(
+    outln!(
         "{}",
         "                    git-shade User Guide                      "
             .bright_cyan()
             .bold()
     );
-    println!(
+    outln!(
         "{}",
         "═══════════════════════════════════════════════════════════════".bright_cyan()
     );
 }
 
 fn print_what_is_git_shade() {
-    println!("{}", "What is git-shade?".bold().underline());
-    println!();
-    println!("git-shade is a CLI tool that syncs git-excluded files across machines.");
-    println!();
-    println!("{}:", "Problem".yellow().bold());
-    println!("  Files in .gitignore or .git/info/exclude don't sync:");
-    println!("  • Local config files (.env.local, config.local)");
-    println!("  • API keys and secrets");
-    println!("  • Large binary files");
-    println!("  • Machine-specific settings");
-    println!();
-    println!("{}:", "Solution".green().bold());
-    println!(
+    outln!("{}", "What is git-shade?".bold().underline());
+    outln!();
+    outln!("git-shade is a CLI tool that syncs git-excluded files across machines.");
+    outln!();
+    outln!("{}:", "Problem".yellow().bold());
+    outln!("  Files in .gitignore or .git/info/exclude don't sync:");
+    outln!("  • Local config files (.env.local, config.local)");
+    outln!("  • API keys and secrets");
+    outln!("  • Large binary files");
+    outln!("  • Machine-specific settings");
+    outln!();
+    outln!("{}:", "Solution".green().bold());
+    outln!(
         "  git-shade maintains a {} at:",
         "single unified Git repository".bold()
     );
-    println!("    ~/.local/git-shade/projects/");
-    println!();
-    println!(
+    outln!("    {}/", shown_projects());
+    outln!();
+    outln!(
         "  This repository contains excluded files from {} your projects,",
         "ALL".bold()
     );
-    println!("  organized by project name, so they sync seamlessly across machines.");
+    outln!("  organized by project name, so they sync seamlessly across machines.");
 }
 
 fn print_how_it_works() {
-    println!("{}", "How It Works".bold().underline());
-    println!();
-    println!(
+    outln!("{}", "How It Works".bold().underline());
+    outln!();
+    outln!(
         "  1. You create/clone a {} Git repository",
         "single unified".bold()
     );
-    println!("     at ~/.local/git-shade/projects/");
-    println!();
-    println!("  2. For each project, git-shade:");
-    println!("     • Creates a subdirectory: projects/<project-name>/");
-    println!("     • Adds patterns to .git/info/exclude");
-    println!("     • Syncs files to/from the shade directory");
-    println!();
-    println!("  3. The unified repo structure:");
-    println!("     {}", "~/.local/git-shade/".bright_black());
-    println!(
+    outln!("     at {}/", shown_projects());
+    outln!();
+    outln!("  2. For each project, git-shade:");
+    outln!("     • Creates a subdirectory: projects/<project-name>/");
+    outln!("     • Adds patterns to .git/info/exclude");
+    outln!("     • Syncs files to/from the shade directory");
+    outln!();
+    outln!("  3. The unified repo structure:");
+    outln!("     {}", format!("{}/", shown_root()).bright_black());
+    outln!(
         "       {}",
         "projects/                   # Single git repo".bright_black()
     );
-    println!(
+    outln!(
         "         {}",
         "myapp/                   # Your first project".green()
     );
-    println!("           {}", "config.local".bright_black());
-    println!("           {}", "secrets/api.key".bright_black());
-    println!(
+    outln!("           {}", "config.local".bright_black());
+    outln!("           {}", "secrets/api.key".bright_black());
+    outln!(
         "         {}",
         "another-project/         # Another project".green()
     );
-    println!("           {}", ".env.local".bright_black());
-    println!(
+    outln!("           {}", ".env.local".bright_black());
+    outln!(
         "         {}",
         ".git/                    # One repo for all".cyan().bold()
     );
 }
 
 fn print_architecture() {
-    println!("{}", "Directory Architecture".bold().underline());
-    println!();
-    println!("  {} ~/.local/git-shade/", "Shade Storage:".cyan().bold());
-    println!("    ├── config.toml           # Global configuration");
-    println!("    ├── metadata/             # Per-project sync tracking");
-    println!("    │   └── myapp/");
-    println!("    │       └── .shade-sync   # Timestamps (last pull/push)");
-    println!(
+    outln!("{}", "Directory Architecture".bold().underline());
+    outln!();
+    let root = shown_root();
+    let config = shown_config();
+    outln!("  {} {}/", "Shade Storage:".cyan().bold(), root);
+    if config == format!("{}/config.toml", root) {
+        outln!("    ├── config.toml           # Global configuration");
+    } else {
+        outln!("    │   (configuration in {})", config);
+    }
+    outln!("    ├── metadata/             # Per-project sync tracking");
+    outln!("    │   └── myapp/");
+    outln!("    │       └── .shade-sync   # Timestamps (last pull/push)");
+    outln!(
         "    └── projects/             # {} Git repo",
         "Single unified".bold()
     );
-    println!("        ├── myapp/            # Files for project 1");
-    println!("        │   ├── config.local");
-    println!("        │   └── secrets/");
-    println!("        ├── another-app/      # Files for project 2");
-    println!("        │   └── .env.local");
-    println!(
+    outln!("        ├── myapp/            # Files for project 1");
+    outln!("        │   ├── config.local");
+    outln!("        │   └── secrets/");
+    outln!("        ├── another-app/      # Files for project 2");
+    outln!("        │   └── .env.local");
+    outln!(
         "        └── .git/             # {} for ALL projects",
         "One git repo".cyan().bold()
     );
-    println!();
-    println!("  {} ~/projects/myapp/", "Your Project:".green().bold());
-    println!("    ├── .git/                 # Main project git repo");
-    println!("    │   └── info/exclude      # Patterns added by git-shade");
-    println!("    ├── src/");
-    println!(
+    outln!();
+    outln!("  {} ~/projects/myapp/", "Your Project:".green().bold());
+    outln!("    ├── .git/                 # Main project git repo");
+    outln!("    │   └── info/exclude      # Patterns added by git-shade");
+    outln!("    ├── src/");
+    outln!(
         "    ├── config.local          # {} Synced by git-shade",
         "←".yellow()
     );
-    println!(
+    outln!(
         "    └── secrets/              # {} Synced by git-shade",
         "←".yellow()
     );
 }
 
 fn print_first_time_setup() {
-    println!("{}", "First Time Setup".bold().underline());
-    println!();
-    println!("{}", "On Your First Machine:".green().bold());
-    println!();
-    println!(
+    outln!("{}", "First Time Setup".bold().underline());
+    outln!();
+    outln!("{}", "On Your First Machine:".green().bold());
+    outln!();
+    outln!(
         "  {}",
         "Step 1: Create your unified shade repository".yellow()
     );
-    println!("  $ mkdir -p ~/.local/git-shade/projects");
-    println!("  $ cd ~/.local/git-shade/projects");
-    println!("  $ git init");
-    println!("  $ git remote add origin git@github.com:you/my-shade-files.git");
-    println!();
-    println!(
+    outln!("  $ mkdir -p {}", shown_projects());
+    outln!("  $ cd {}", shown_projects());
+    outln!("  $ git init");
+    outln!("  $ git remote add origin git@github.com:you/my-shade-files.git");
+    outln!();
+    outln!(
         "  {}",
         "Step 2: Initialize git-shade for your project".yellow()
     );
-    println!("  $ cd ~/projects/myapp");
-    println!("  $ git-shade init");
-    println!("  {} Initialized git-shade for project: myapp", "✓".green());
-    println!();
-    println!("  {}", "Step 3: Add files you want to sync".yellow());
-    println!("  $ git-shade add config.local secrets/ .env.local");
-    println!("  {} Added to .git/info/exclude", "✓".green());
-    println!("  {} Copied to shade", "✓".green());
-    println!();
-    println!("  {}", "Step 4: Push to remote".yellow());
-    println!("  $ git-shade push");
-    println!("  {} Pushed to origin/main", "✓".green());
-    println!();
-    println!("{}", "On Your Second Machine:".blue().bold());
-    println!();
-    println!(
+    outln!("  $ cd ~/projects/myapp");
+    outln!("  $ git-shade init");
+    outln!("  {} Initialized git-shade for project: myapp", "✓".green());
+    outln!();
+    outln!("  {}", "Step 3: Add files you want to sync".yellow());
+    outln!("  $ git-shade add config.local secrets/ .env.local");
+    outln!("  {} Added to .git/info/exclude", "✓".green());
+    outln!("  {} Copied to shade", "✓".green());
+    outln!();
+    outln!("  {}", "Step 4: Push to remote".yellow());
+    outln!("  $ git-shade push");
+    outln!("  {} Pushed to origin/main", "✓".green());
+    outln!();
+    outln!("{}", "On Your Second Machine:".blue().bold());
+    outln!();
+    outln!(
         "  {}",
         "Step 1: Clone your unified shade repository".yellow()
     );
-    println!("  $ git clone git@github.com:you/my-shade-files.git \\");
-    println!("      ~/.local/git-shade/projects");
-    println!();
-    println!("  {}", "Step 2: Clone your project and initialize".yellow());
-    println!("  $ git clone git@github.com:you/myapp.git ~/projects/myapp");
-    println!("  $ cd ~/projects/myapp");
-    println!("  $ git-shade init");
-    println!();
-    println!(
+    outln!("  $ git-shade clone git@github.com:you/my-shade-files.git");
+    outln!("    (lists the projects available to set up, like git-shade list --remote)");
+    outln!();
+    outln!("  {}", "Step 2: Clone your project and initialize".yellow());
+    outln!("  $ git clone git@github.com:you/myapp.git ~/projects/myapp");
+    outln!("  $ cd ~/projects/myapp");
+    outln!("  $ git-shade init");
+    outln!();
+    outln!(
         "  {} git-shade will auto-detect existing files and ask:",
         "→".blue()
     );
-    println!("  Found 3 files in shade:");
-    println!("    - config.local");
-    println!("    - secrets/api.key");
-    println!("    - .env.local");
-    println!();
-    println!("  Pull these files now? [Y/n]: {}", "y".green());
-    println!("  {} Done! Files synced.", "✓".green());
+    outln!("  Found 3 files in shade:");
+    outln!("    - config.local");
+    outln!("    - secrets/api.key");
+    outln!("    - .env.local");
+    outln!();
+    outln!("  Pull these files now? [Y/n]: {}", "y".green());
+    outln!("  {} Done! Files synced.", "✓".green());
 }
 
 fn print_daily_workflow() {
-    println!("{}", "Daily Workflow".bold().underline());
-    println!();
-    println!("  {}", "Typical workflow on any machine:".cyan());
-    println!();
-    println!("  # Check what needs syncing");
-    println!("  $ git-shade status");
-    println!("    {} config.local (in sync)", "✓".green());
-    println!("    {} secrets/api.key (local ahead)", "↑".yellow());
-    println!("    {} .env.local (remote ahead)", "↓".blue());
-    println!();
-    println!("  # Pull remote changes");
-    println!("  $ git-shade pull");
-    println!("    {} Synced .env.local", "↓".blue());
-    println!();
-    println!("  # Make local changes");
-    println!("  $ vim secrets/api.key");
-    println!();
-    println!("  # Push your changes");
-    println!("  $ git-shade push");
-    println!("    {} Pushed to origin/main", "✓".green());
-    println!();
-    println!("  {}", "On another machine:".cyan());
-    println!();
-    println!("  $ cd ~/projects/myapp");
-    println!("  $ git-shade pull");
-    println!("    {} Synced secrets/api.key", "↓".blue());
+    outln!("{}", "Daily Workflow".bold().underline());
+    outln!();
+    outln!("  {}", "Typical workflow on any machine:".cyan());
+    outln!();
+    outln!("  # Check what needs syncing");
+    outln!("  $ git-shade status");
+    outln!("    {} config.local (in sync)", "✓".green());
+    outln!("    {} secrets/api.key (local ahead)", "↑".yellow());
+    outln!("    {} .env.local (remote ahead)", "↓".blue());
+    outln!();
+    outln!("  # Pull remote changes");
+    outln!("  $ git-shade pull");
+    outln!("    {} Synced .env.local", "↓".blue());
+    outln!();
+    outln!("  # Make local changes");
+    outln!("  $ vim secrets/api.key");
+    outln!();
+    outln!("  # Push your changes");
+    outln!("  $ git-shade push");
+    outln!("    {} Pushed to origin/main", "✓".green());
+    outln!();
+    outln!("  {}", "On another machine:".cyan());
+    outln!();
+    outln!("  $ cd ~/projects/myapp");
+    outln!("  $ git-shade pull");
+    outln!("    {} Synced secrets/api.key", "↓".blue());
 }
 
 fn print_commands_overview() {
-    println!("{}", "Commands Reference".bold().underline());
-    println!();
-
-    println!("  {} git-shade init [--name <name>]", "●".green());
-    println!("    Initialize git-shade for current project");
-    println!("    Auto-detects and offers to pull existing files");
-    println!();
-
-    println!("  {} git-shade add <files...>", "●".green());
-    println!("    Add files/directories to shade");
-    println!("    Automatically updates .git/info/exclude");
-    println!("    Examples:");
-    println!("      git-shade add config.local");
-    println!("      git-shade add secrets/ .env.local");
-    println!();
-
-    println!("  {} git-shade push [-m \"message\"]", "●".green());
-    println!("    Sync local changes to shade and push to remote");
-    println!("    Uses automatic commit messages with hostname");
-    println!();
-
-    println!("  {} git-shade pull [--force] [--dry-run]", "●".green());
-    println!("    Pull changes from shade to local project");
-    println!("    Detects conflicts automatically");
-    println!("    --force: Overwrite local without checking");
-    println!("    --dry-run: Preview changes without applying");
-    println!();
-
-    println!("  {} git-shade status", "●".green());
-    println!("    Show sync state of all tracked files");
-    println!("    Displays helpful hints for next actions");
-    println!();
-
-    println!("  {} git-shade guide", "●".green());
-    println!("    Show this guide (you're reading it now!)");
+    outln!("{}", "Commands Reference".bold().underline());
+    outln!();
+
+    outln!("  {} git-shade init [--name <name>]", "●".green());
+    outln!("    Initialize git-shade for current project");
+    outln!("    Auto-detects and offers to pull existing files");
+    outln!("    Suggests local/secret files (.env.local, *.key, ...) to add");
+    outln!("    Reads name and files to add from a committed .git-shade.toml");
+    outln!("    --yes / --no-pull: Skip the prompt (for scripts)");
+    outln!("    --create-shade / --remote <url>: Create a missing shade repo");
+    outln!("    --import-from <dir>: Seed shade from an existing secrets folder");
+    outln!("    --dry-run: Show what init would create and pull, changing nothing");
+    outln!();
+
+    outln!("  {} git-shade uninit", "●".green());
+    outln!("    Deregister the project and remove its shade files");
+    outln!("    --keep-exclude / --keep-shade: Leave those parts alone");
+    outln!();
+
+    outln!("  {} git-shade clone <url> [--depth <n>]", "●".green());
+    outln!("    Clone an existing shade repo onto a new machine");
+    outln!("    --depth <n>: Only fetch the last n commits");
+    outln!("    --single-branch: Only fetch the default branch");
+    outln!();
+
+    outln!("  {} git-shade add <files...>", "●".green());
+    outln!("    Add files/directories to shade");
+    outln!("    Automatically updates .git/info/exclude");
+    outln!("    --force: Skip confirmations for large adds and existing shade copies");
+    outln!("    --exclude <pattern>: Skip matching paths inside directories");
+    outln!("    --from-gitignore: Add existing files your ignore rules match");
+    outln!("    --symlink: Move a file into shade, leaving a symlink behind");
+    outln!("    --stdin / --stdin0: Read paths from a pipe (lines / NUL-separated)");
+    outln!("    --as <path>: Track a file outside the project under <path>");
+    outln!("    --link-existing: Keep differing files already in shade");
+    outln!("    --template <name>: Add a template's files (node, rails, python, docker)");
+    outln!("    --no-copy: Track paths that don't exist yet; push copies them later");
+    outln!("    --chmod <mode>: Give the files this mode on every push and pull");
+    outln!("    --stage: Also git add the copies in the shade repo (not committed)");
+    outln!("    --per-os: Store the files as this OS's variants");
+    outln!("    Examples:");
+    outln!("      git-shade add config.local");
+    outln!("      git-shade add secrets/ .env.local");
+    outln!();
+
+    outln!("  {} git-shade mv <from> <to>", "●".green());
+    outln!("    Rename a tracked file, keeping its shade history");
+    outln!();
+
+    outln!(
+        "  {} git-shade pause <files...> / resume <files...>",
+        "●".green()
+    );
+    outln!("    Stop (and restart) syncing tracked files without untracking them");
+    outln!();
+
+    outln!("  {} git-shade push [-m \"message\"]", "●".green());
+    outln!("    Sync local changes to shade and push to remote");
+    outln!("    Uses automatic commit messages with the machine name");
+    outln!("    --amend: Fold into this project's last shade commit");
+    outln!("    --force-with-lease: Replace fetched remote commits with yours");
+    outln!("    --stat: Show which files changed in the commit just pushed");
+    outln!("    --no-sign: Don't sign the commit (see sign_commits)");
+    outln!("    --only-changed: Copy only files changed here, skipping ones in sync");
+    outln!("    --machine <name>: Label the commit with this machine name");
+    outln!("    --tag <name>: Tag the pushed snapshot as <project>/<name>");
+    outln!();
+
+    outln!("  {} git-shade pull [--force] [--dry-run]", "●".green());
+    outln!("    Pull changes from shade to local project");
+    outln!("    Detects conflicts automatically");
+    outln!("    --force: Overwrite local without checking");
+    outln!("    --dry-run: Preview changes without applying");
+    outln!("    --no-fetch: Sync from the shade dir without git pull (offline)");
+    outln!("    --strategy newest|local|remote: Settle conflicts automatically");
+    outln!("    --merge-markers: Write both versions of text conflicts into the file");
+    outln!("    --rebase/--ff-only/--merge: How the shade repo pulls (pull_strategy)");
+    outln!("    --set-exclude-only: Only add shade files to .git/info/exclude");
+    outln!("    --output <dir>: Write pulled files elsewhere to review them");
+    outln!("    --only/--except <glob>: Pull just some of the files");
+    outln!("    --backup-all: Snapshot local files before overwriting them");
+    outln!("    --push-after: Then push files the remote doesn't have yet");
+    outln!("    --prune: Delete local files that were removed from shade");
+    outln!();
+
+    outln!("  {} git-shade sync [--force]", "●".green());
+    outln!("    Pull remote changes, then push local ones");
+    outln!("    Stops before pushing if conflicts are found");
+    outln!();
+
+    outln!("  {} git-shade watch [--debounce <ms>]", "●".green());
+    outln!("    Push tracked files automatically whenever they change");
+    outln!("    Skips while the shade repo has merge conflicts; Ctrl-C stops");
+    outln!();
+
+    outln!(
+        "  {} git-shade status [--fetch] [--compact] [--plan] [--exit-code]",
+        "●".green()
+    );
+    outln!("    Show sync state of all tracked files");
+    outln!("    Displays helpful hints for next actions");
+    outln!("    --fetch: Also report commits waiting on the remote");
+    outln!("    --compact: Only files that need attention, grouped by state");
+    outln!("    --plan: Say what push and pull would each do with every file");
+    outln!("    --exit-code: Exit 1 if files need a push or pull, 2 on conflicts");
+    outln!();
+
+    outln!("  {} git-shade config get|set|list|edit", "●".green());
+    outln!("    Read or change settings in config.toml (edit opens $EDITOR)");
+    outln!("    Example: git-shade config set max_add_files 5000");
+    outln!();
+
+    outln!("  {} git-shade list [--remote]", "●".green());
+    outln!("    Projects initialized here; --remote: ones in shade to set up");
+    outln!();
+
+    outln!("  {} git-shade env", "●".green());
+    outln!("    Print version, paths, git and shade repo details for bug reports");
+    outln!();
+
+    outln!("  {} git-shade doctor [--repair]", "●".green());
+    outln!("    Check shade repo, config and project setup");
+    outln!("    Suggests a fix for every problem found");
+    outln!("    --repair: Reset sync trackers that fail validation");
+    outln!();
+
+    outln!("  {} git-shade gc [--expire <when>]", "●".green());
+    outln!("    Compact the shade repo and show how much space was freed");
+    outln!("    --expire: Also drop reflog entries older than <when>");
+    outln!();
+
+    outln!("  {} git-shade rollback [--yes]", "●".green());
+    outln!("    Undo the project's last shade commit and push the undo");
+    outln!();
+
+    outln!("  {} git-shade stash [--list] / stash pop", "●".green());
+    outln!("    Set local changes aside before a pull, then reapply them");
+    outln!();
+
+    outln!("  {} git-shade history <file> [--patch]", "●".green());
+    outln!("    When a shaded file changed, and from which machine");
+    outln!();
+
+    outln!("  {} git-shade verify [--fix]", "●".green());
+    outln!("    Check exclude patterns against shade and local files");
+    outln!("    --fix: Add missing excludes, push, clean up orphans");
+    outln!();
+
+    outln!(
+        "  {} git-shade export <archive> / import <archive>",
+        "●".green()
+    );
+    outln!("    Move shade files as a .tar.gz, without a git remote");
+    outln!();
+
+    outln!("  {} git-shade guide", "●".green());
+    outln!("    Show this guide (you're reading it now!)");
 }
 
 fn print_sync_states() {
-    println!("{}", "Understanding Sync States".bold().underline());
-    println!();
-    println!("  git-shade compares file modification times to determine state:");
-    println!();
-    println!("  {} In Sync", "✓".green());
-    println!("    Files are identical, no action needed");
-    println!();
-    println!("  {} Local Ahead", "↑".yellow());
-    println!("    Modified locally after last pull");
-    println!("    Action: Run {} to sync", "git-shade push".bold());
-    println!();
-    println!("  {} Remote Ahead", "↓".blue());
-    println!("    Modified in shade after last pull");
-    println!("    Action: Run {} to sync", "git-shade pull".bold());
-    println!();
-    println!("  {} Conflict", "⚠".red());
-    println!("    Modified {} locally and remotely", "both".bold());
-    println!("    Action: Manual resolution required");
-    println!("    Options:");
-    println!("      1. Review remote at ~/.local/git-shade/projects/<project>/");
-    println!("      2. Manually merge and copy back");
-    println!(
+    outln!("{}", "Understanding Sync States".bold().underline());
+    outln!();
+    outln!("  git-shade compares file modification times to determine state:");
+    outln!();
+    outln!("  {} In Sync", "✓".green());
+    outln!("    Files are identical, no action needed");
+    outln!();
+    outln!("  {} Local Ahead", "↑".yellow());
+    outln!("    Modified locally after last pull");
+    outln!("    Action: Run {} to sync", "git-shade push".bold());
+    outln!();
+    outln!("  {} Remote Ahead", "↓".blue());
+    outln!("    Modified in shade after last pull");
+    outln!("    Action: Run {} to sync", "git-shade pull".bold());
+    outln!();
+    outln!("  {} Conflict", "⚠".red());
+    outln!("    Modified {} locally and remotely", "both".bold());
+    outln!("    Action: Manual resolution required");
+    outln!("    Options:");
+    outln!("      1. Review remote at {}/<project>/", shown_projects());
+    outln!("      2. Manually merge and copy back");
+    outln!(
         "      3. Use {} to take remote",
         "git-shade pull --force".bold()
     );
-    println!("      4. Use {} to take local", "git-shade push".bold());
-    println!();
-    println!("  {} Local Only", "?".bright_black());
-    println!("    File exists locally but not in shade");
-    println!("    (Probably just added, not pushed yet)");
-    println!();
-    println!("  {} Remote Only", "←".bright_black());
-    println!("    File exists in shade but not locally");
-    println!("    (Probably deleted locally, will be pulled)");
+    outln!("      4. Use {} to take local", "git-shade push".bold());
+    outln!();
+    outln!("  {} Local Only", "?".bright_black());
+    outln!("    File exists locally but not in shade");
+    outln!("    (Probably just added, not pushed yet)");
+    outln!();
+    outln!("  {} Remote Only", "←".bright_black());
+    outln!("    File exists in shade but not locally");
+    outln!("    (Probably deleted locally, will be pulled)");
 }
 
 fn print_troubleshooting() {
-    println!("{}", "Troubleshooting".bold().underline());
-    println!();
-
-    println!("  {} \"Not a git repository\"", "Q:".red().bold());
-    println!("    You must run git-shade from inside a git repository.");
-    println!("    Solution: cd to your project and ensure .git/ exists");
-    println!();
-
-    println!("  {} \"Shade repository not found\"", "Q:".red().bold());
-    println!("    The unified shade repo doesn't exist yet.");
-    println!("    Solution: Run the first time setup (see above)");
-    println!();
-
-    println!("  {} \"Project not initialized\"", "Q:".red().bold());
-    println!("    This project hasn't been registered with git-shade.");
-    println!("    Solution: Run {} first", "git-shade init".bold());
-    println!();
-
-    println!("  {} \"Conflicts detected\"", "Q:".red().bold());
-    println!("    Files were modified on both machines since last sync.");
-    println!("    Solution: See \"Understanding Sync States\" above");
-    println!();
-
-    println!("  {} How do I add more projects?", "Q:".cyan().bold());
-    println!(
+    outln!("{}", "Troubleshooting".bold().underline());
+    outln!();
+
+    outln!("  {} \"Not a git repository\"", "Q:".red().bold());
+    outln!("    You must run git-shade from inside a git repository.");
+    outln!("    Solution: cd to your project and ensure .git/ exists");
+    outln!();
+
+    outln!("  {} \"Shade repository not found\"", "Q:".red().bold());
+    outln!("    The unified shade repo doesn't exist yet.");
+    outln!("    Solution: Run the first time setup (see above), or");
+    outln!("    {}", "git-shade init --remote <url>".bold());
+    outln!();
+
+    outln!("  {} \"Project not initialized\"", "Q:".red().bold());
+    outln!("    This project hasn't been registered with git-shade.");
+    outln!("    Solution: Run {} first", "git-shade init".bold());
+    outln!();
+
+    outln!("  {} \"Conflicts detected\"", "Q:".red().bold());
+    outln!("    Files were modified on both machines since last sync.");
+    outln!("    Solution: See \"Understanding Sync States\" above");
+    outln!();
+
+    outln!("  {} How do I add more projects?", "Q:".cyan().bold());
+    outln!(
         "    Just cd to the new project and run {}!",
         "git-shade init".bold()
     );
-    println!("    All projects share the same unified shade repository.");
-    println!();
+    outln!("    All projects share the same unified shade repository.");
+    outln!();
 
-    println!(
+    outln!(
         "  {} Can I use git-shade with existing shade repos?",
         "Q:".cyan().bold()
     );
-    println!("    Yes! Just clone your existing shade repo to");
-    println!(
-        "    ~/.local/git-shade/projects/ and run {} in projects.",
+    outln!("    Yes! Just clone your existing shade repo to");
+    outln!(
+        "    {}/ and run {} in projects.",
+        shown_projects(),
         "git-shade init".bold()
     );
 }
@@ -0,0 +1,91 @@
+use crate::core::{to_slash, Config, ShadePaths};
+use crate::error::Result;
+use crate::git::file_log;
+use crate::utils::output::outln;
+use crate::utils::{format_relative, relative_to_project, resolve_project};
+use chrono::Utc;
+use colored::Colorize;
+use std::path::PathBuf;
+
+pub fn run(
+    file: PathBuf,
+    patch: bool,
+    project: Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+
+    // 5. Find the file's path in the shade repo
+    let rel_path = relative_to_project(&project_path, &file)?;
+    let shade_path = format!("{}/{}", project_name, to_slash(&rel_path));
+
+    // 6. Read its log (renames followed)
+    let entries = file_log(&paths.projects, &shade_path, patch)?;
+    if entries.is_empty() {
+        outln!(
+            "{} No shade history for {} (added but never pushed, or not tracked)",
+            "→".blue(),
+            rel_path.display()
+        );
+        return Ok(());
+    }
+
+    // 7. Print the timeline, newest first
+    outln!(
+        "History of {} ({}):",
+        rel_path.display().to_string().bold(),
+        project_name
+    );
+    outln!();
+
+    let now = Utc::now();
+    for entry in &entries {
+        let host = entry.host.as_deref().unwrap_or("unknown host");
+        outln!(
+            "{} {} {}  {}",
+            "●".green(),
+            entry.date.format("%Y-%m-%d %H:%M:%S %:z"),
+            format!("({})", format_relative(&entry.date, now)).dimmed(),
+            host.bold()
+        );
+        outln!("  {} {}", entry.hash[..7].yellow(), entry.subject);
+
+        if let Some(patch) = &entry.patch {
+            outln!();
+            for line in patch.lines() {
+                outln!("    {}", color_diff_line(line));
+            }
+        }
+        outln!();
+    }
+
+    outln!(
+        "{} {} commit(s) in {}",
+        "→".blue(),
+        entries.len(),
+        paths.projects.display()
+    );
+
+    Ok(())
+}
+
+fn color_diff_line(line: &str) -> colored::ColoredString {
+    if line.starts_with("+++") || line.starts_with("---") {
+        line.bold()
+    } else if line.starts_with('+') {
+        line.green()
+    } else if line.starts_with('-') {
+        line.red()
+    } else if line.starts_with("@@") {
+        line.cyan()
+    } else {
+        line.normal()
+    }
+}
@@ -0,0 +1,73 @@
+use crate::commands::pull::{pull_project, PullOptions};
+use crate::core::{read_archive_header, unpack_shade_files, Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::git::run_git;
+use crate::utils::output::outln;
+use crate::utils::resolve_project;
+use colored::Colorize;
+use std::path::PathBuf;
+
+pub fn run(archive: PathBuf, project: Option<String>, verbose: bool) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3. Read the archive header before touching anything
+    let header = read_archive_header(&archive)?;
+
+    // 4. Resolve the target project; without --project it must match the archive
+    let explicit_project = project.is_some();
+    let (project_path, project_name) = resolve_project(&config, project, None)?;
+
+    if header.project != project_name && !explicit_project {
+        return Err(ShadeError::ArchiveProjectMismatch {
+            archive_project: header.project,
+            project_name,
+        });
+    }
+
+    outln!(
+        "Importing {} (exported from {} on {})...",
+        archive.display(),
+        header.hostname,
+        header.exported_at.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    // 5. Unpack into the shade dir
+    let project_shade_dir = paths.project_shade_dir(&project_name);
+    let files = unpack_shade_files(&archive, &project_shade_dir)?;
+    outln!(
+        "  {} Unpacked {} file(s) into {}",
+        "✓".green(),
+        files.len(),
+        project_shade_dir.display()
+    );
+
+    // 6. Commit them in the shade repo, so a later push carries them along
+    let add_output = run_git(&paths.projects, &["add", &format!("{}/", project_name)])?;
+    if !add_output.status.success() {
+        let stderr = String::from_utf8_lossy(&add_output.stderr);
+        return Err(ShadeError::GitError(format!("git add failed: {}", stderr)));
+    }
+
+    let commit_msg = format!("[{}] Import from {}", project_name, header.hostname);
+    let commit_output = run_git(&paths.projects, &["commit", "-m", commit_msg.as_str()])?;
+    if commit_output.status.success() {
+        outln!("  {} Committed: {}", "✓".green(), commit_msg);
+    } else {
+        outln!("  {} Shade repo already had these files", "→".blue());
+    }
+    outln!();
+
+    // 7. Sync into the project as a normal pull would (there's no remote to pull from)
+    let options = PullOptions {
+        verbose,
+        skip_git_pull: true,
+        ..PullOptions::default()
+    };
+    pull_project(&paths, &project_path, &project_name, &options)?;
+
+    Ok(())
+}
@@ -1,24 +1,71 @@
-use crate::core::{Config, ShadePaths, Tracker};
+use crate::commands::add::{self, AddOptions};
+use crate::core::config::Project;
+use crate::core::{
+    is_shade_metadata, same_contents, scan_for_secrets, to_slash, Config, FileModes, ProjectConfig,
+    ShadePaths, Tracker, PROJECT_CONFIG_FILE,
+};
 use crate::error::{Result, ShadeError};
-use crate::utils::{detect_project_name, verify_git_repo};
+use crate::git::{init_shade_repo, read_patterns, run_git, validate_remote_url};
+use crate::utils::output::outln;
+use crate::utils::{copy_file_to, migrate_tracker, project_name_in, tracker_path, verify_git_repo};
 use colored::Colorize;
-use dialoguer::Confirm;
+use dialoguer::{Confirm, Input, MultiSelect};
 use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-pub fn run(name_override: Option<String>) -> Result<()> {
+/// Flags for `git-shade init`
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    pub name: Option<String>,
+    pub no_pull: bool,
+    pub yes: bool,
+    /// Create the shade repo without asking if there is none
+    pub create_shade: bool,
+    /// `origin` for a newly created shade repo (implies `create_shade`)
+    pub remote: Option<String>,
+    /// Print what would be created and offered, without writing or prompting
+    pub dry_run: bool,
+    /// Existing folder of secrets to seed the project's shade dir with
+    pub import_from: Option<PathBuf>,
+}
+
+pub fn run(options: InitOptions) -> Result<()> {
     // 1. Verify it's a git repo
     let project_path = verify_git_repo()?;
 
-    // 2. Detect project name
-    let project_name = detect_project_name(name_override)?;
-
-    // 3. Setup paths
+    // 2. Setup paths
     let paths = ShadePaths::new()?;
 
-    // 4. Verify shade repo exists
-    if !paths.projects.join(".git").exists() {
-        return Err(ShadeError::ShadeRepoNotFound);
+    init_project(&paths, &project_path, options)
+}
+
+/// Set up the git repository at `project_path` as a git-shade project
+pub fn init_project(paths: &ShadePaths, project_path: &Path, options: InitOptions) -> Result<()> {
+    let InitOptions {
+        name: name_override,
+        no_pull,
+        yes,
+        create_shade,
+        remote,
+        dry_run,
+        import_from,
+    } = options;
+
+    // 3. Detect project name
+    let project_name = project_name_in(project_path, name_override)?;
+
+    if let Some(dir) = &import_from {
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("Directory to import not found: {}", dir.display()).into());
+        }
+    }
+
+    // 4. Verify shade repo exists, or offer to create it
+    let shade_repo_exists = paths.projects.join(".git").exists();
+    if !shade_repo_exists && !dry_run {
+        create_shade_repo(paths, create_shade, remote.clone())?;
     }
 
     // 5. Check if already initialized
@@ -27,64 +74,468 @@ pub fn run(name_override: Option<String>) -> Result<()> {
         return Err(ShadeError::AlreadyInitialized(project_name));
     }
 
+    if dry_run {
+        let shade_repo = (!shade_repo_exists).then_some(remote.as_deref());
+        return print_plan(
+            paths,
+            &config,
+            &project_name,
+            project_path,
+            shade_repo,
+            import_from.as_deref(),
+        );
+    }
+
     // 6. Create metadata directory
     paths.ensure_structure()?;
     let project_metadata_dir = paths.project_metadata_dir(&project_name);
     fs::create_dir_all(&project_metadata_dir)?;
 
-    // 7. Create tracker file
-    let tracker = Tracker::new();
-    tracker.save(&paths.shade_sync_file(&project_name))?;
+    // 7. Create tracker file (sync state that came along with the shade repo is kept)
+    migrate_tracker(paths, &config, &project_name)?;
+    let tracker_file = tracker_path(paths, &config, &project_name)?;
+    if config.metadata_in_repo && tracker_file.exists() {
+        outln!(
+            "{} Keeping sync state found in the shade repo: {}",
+            "→".blue(),
+            tracker_file.display()
+        );
+    } else {
+        Tracker::new().save(&tracker_file)?;
+    }
 
     // 8. Create project directory in shade
     let project_shade_dir = paths.project_shade_dir(&project_name);
     fs::create_dir_all(&project_shade_dir)?;
 
     // 9. Add to config
-    config.add_project(project_name.clone(), project_path.clone())?;
+    config.add_project(project_name.clone(), project_path.to_path_buf())?;
     config.save(&paths.config)?;
 
     // 10. Print success
-    println!(
+    outln!(
         "{} Initialized git-shade for project: {}",
         "✓".green().bold(),
         project_name.bold()
     );
-    println!("  Config: {}", paths.config.display());
-    println!("  Metadata: {}", project_metadata_dir.display());
-    println!("  Shade dir: {}", project_shade_dir.display());
-    println!();
+    outln!("  Config: {}", paths.config.display());
+    outln!("  Metadata: {}", project_metadata_dir.display());
+    outln!("  Shade dir: {}", project_shade_dir.display());
+    outln!();
+
+    // Seed shade from an existing folder; the pull offer below brings the
+    // files into the project
+    if let Some(dir) = &import_from {
+        let imported = import_files(dir, &project_shade_dir, &config)?;
+        add_to_exclude(project_path, &imported, &project_shade_dir)?;
+        outln!(
+            "Imported {} file(s) from {} (commit them with {}):",
+            imported.len(),
+            dir.display(),
+            "git-shade push".bold()
+        );
+        for file in &imported {
+            outln!("  {} {}", "+".green(), file.display());
+        }
+        outln!();
+    }
 
     // 11. Check if shade has files
     let existing_files = list_shade_files(&project_shade_dir)?;
 
     if !existing_files.is_empty() {
-        println!("Found {} files in shade:", existing_files.len());
+        outln!("Found {} files in shade:", existing_files.len());
         for file in &existing_files {
-            println!("  - {}", file.display());
+            outln!("  - {}", file.display());
+        }
+        outln!();
+
+        // Ask to pull (never prompt without a terminal, scripts would hang)
+        let should_pull = if no_pull {
+            false
+        } else if yes {
+            true
+        } else if !std::io::stdin().is_terminal() {
+            outln!(
+                "{} No terminal detected, not pulling. Use {} to pull automatically.",
+                "→".blue(),
+                "--yes".bold()
+            );
+            false
+        } else {
+            Confirm::new()
+                .with_prompt("Pull these files now?")
+                .default(true)
+                .interact()
+                .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?
+        };
+
+        if should_pull {
+            pull_files(&existing_files, &project_shade_dir, project_path)?;
+            add_to_exclude(project_path, &existing_files, &project_shade_dir)?;
+            outln!();
+            outln!("{} Done!", "✓".green().bold());
+        } else {
+            outln!("Skipped. Pull manually with: {}", "git-shade pull".bold());
+        }
+    }
+
+    // 12. Add the files listed in .git-shade.toml
+    if let Some(project_config) = ProjectConfig::load(project_path)? {
+        add_project_config_files(paths, &project_config, project_path, &project_name)?;
+    }
+
+    // 13. Offer to add well-known local/secret files
+    let suggestions = find_suggestions(project_path, &config.default_patterns)?;
+
+    if !suggestions.is_empty() {
+        outln!();
+        outln!("Found local files that usually belong in shade:");
+
+        if yes || !std::io::stdin().is_terminal() {
+            for path in &suggestions {
+                outln!("  - {}", path.display());
+            }
+            outln!();
+            outln!("Add them with: {}", "git-shade add <files...>".bold());
+        } else {
+            let items: Vec<String> = suggestions
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            let selected = MultiSelect::new()
+                .with_prompt("Select files to add (space to toggle, enter to confirm)")
+                .items(&items)
+                .defaults(&vec![true; items.len()])
+                .interact()
+                .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+
+            if !selected.is_empty() {
+                let files = selected
+                    .into_iter()
+                    .map(|index| suggestions[index].clone())
+                    .collect();
+                outln!();
+                add::add_files(
+                    paths,
+                    project_path,
+                    &project_name,
+                    files,
+                    add_options(&project_name),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// What `init` would do, step by step, without touching anything
+///
+/// `shade_repo` is `Some(remote)` when the shade repo would have to be created.
+fn print_plan(
+    paths: &ShadePaths,
+    config: &Config,
+    project_name: &str,
+    project_path: &std::path::Path,
+    shade_repo: Option<Option<&str>>,
+    import_from: Option<&Path>,
+) -> Result<()> {
+    let status = |path: &std::path::Path| {
+        if path.exists() {
+            " (exists)".bright_black().to_string()
+        } else {
+            String::new()
+        }
+    };
+
+    outln!("Initializing {} would (dry-run):", project_name.bold());
+    outln!();
+
+    if let Some(remote) = shade_repo {
+        let action = if paths.projects.is_dir() {
+            "Run git init in the existing shade directory"
+        } else {
+            "Create the shade repo"
+        };
+        outln!("  {} {}: {}", "+".green(), action, paths.projects.display());
+        match remote {
+            Some(url) => {
+                let check = validate_remote_url(url);
+                outln!("      with remote: {} ({})", url, check.kind);
+                for warning in &check.warnings {
+                    outln!("      {} {}", "⚠".yellow(), warning);
+                }
+            }
+            None => outln!("      (asks first, unless --create-shade or --remote is given)"),
+        }
+    }
+
+    let metadata_dir = paths.project_metadata_dir(project_name);
+    let tracker_file = paths.tracker_file(config, project_name);
+    let shade_dir = paths.project_shade_dir(project_name);
+    outln!(
+        "  {} Create metadata dir: {}{}",
+        "+".green(),
+        metadata_dir.display(),
+        status(&metadata_dir)
+    );
+    outln!(
+        "  {} Create sync tracker: {}{}",
+        "+".green(),
+        tracker_file.display(),
+        status(&tracker_file)
+    );
+    outln!(
+        "  {} Create shade dir: {}{}",
+        "+".green(),
+        shade_dir.display(),
+        status(&shade_dir)
+    );
+
+    let entry = Project {
+        name: project_name.to_string(),
+        local_path: project_path.to_path_buf(),
+        per_os: false,
+    };
+    let entry = toml::to_string(&entry).map_err(|e| anyhow::anyhow!(e))?;
+    outln!("  {} Add to {}:", "+".green(), paths.config.display());
+    outln!("      [[projects]]");
+    for line in entry.lines() {
+        outln!("      {}", line);
+    }
+
+    if let Some(dir) = import_from {
+        outln!(
+            "  {} Import from {} into the shade dir (and .git/info/exclude):",
+            "+".green(),
+            dir.display()
+        );
+        for file in list_importable(dir, config)? {
+            let note = if shade_dir.join(&file).exists() {
+                " (already in shade, kept)".bright_black().to_string()
+            } else {
+                String::new()
+            };
+            outln!("      {}{}", file.display(), note);
+        }
+    }
+
+    let existing_files = if shade_dir.exists() {
+        list_shade_files(&shade_dir)?
+    } else {
+        Vec::new()
+    };
+    outln!();
+    if existing_files.is_empty() {
+        outln!(
+            "No files in shade for {} yet, nothing to pull.",
+            project_name
+        );
+    } else {
+        outln!(
+            "Would offer to pull {} file(s) from shade:",
+            existing_files.len()
+        );
+        for file in &existing_files {
+            outln!("  {} {}", "↓".green(), file.display());
+        }
+    }
+
+    if let Some(project_config) = ProjectConfig::load(project_path)? {
+        if !project_config.files.is_empty() {
+            outln!();
+            outln!("Would add the files listed in {}:", PROJECT_CONFIG_FILE);
+            for file in &project_config.files {
+                outln!("  - {}", file.display());
+            }
+        }
+    }
+
+    let suggestions = find_suggestions(project_path, &config.default_patterns)?;
+    if !suggestions.is_empty() {
+        outln!();
+        outln!("Would suggest adding local files that usually belong in shade:");
+        for path in &suggestions {
+            outln!("  - {}", path.display());
+        }
+    }
+
+    outln!();
+    outln!("{} Dry-run completed (no changes made)", "✓".blue());
+
+    Ok(())
+}
+
+/// Options for adding files to the project being initialized, whatever its name
+fn add_options(project_name: &str) -> AddOptions {
+    AddOptions {
+        name: Some(project_name.to_string()),
+        ..AddOptions::default()
+    }
+}
+
+/// Set up an empty shade repo (and config) when there is none yet
+///
+/// Asks first unless `--create-shade` or `--remote` was given; without a
+/// terminal to ask on it fails as before.
+fn create_shade_repo(paths: &ShadePaths, create: bool, remote: Option<String>) -> Result<()> {
+    let interactive = !create && remote.is_none();
+    // A directory made by hand just needs `git init`, not to be created
+    let dir_exists = paths.projects.is_dir();
+    let not_found = || {
+        if dir_exists {
+            ShadeError::ShadeDirNotRepo {
+                path: paths.projects.clone(),
+            }
+        } else {
+            ShadeError::ShadeRepoNotFound
+        }
+    };
+
+    if interactive {
+        if !std::io::stdin().is_terminal() {
+            return Err(not_found());
         }
-        println!();
 
-        // Ask to pull
-        let should_pull = Confirm::new()
-            .with_prompt("Pull these files now?")
+        let prompt = if dir_exists {
+            outln!(
+                "{} exists but isn't a git repository",
+                paths.projects.display()
+            );
+            "Run git init in it now (its files are kept)?"
+        } else {
+            outln!("No shade repo found at {}", paths.projects.display());
+            "Create one now?"
+        };
+        let confirmed = Confirm::new()
+            .with_prompt(prompt)
             .default(true)
             .interact()
             .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+        if !confirmed {
+            return Err(not_found());
+        }
+    }
 
-        if should_pull {
-            pull_files(&existing_files, &project_shade_dir, &project_path)?;
-            add_to_exclude(&project_path, &existing_files, &project_shade_dir)?;
-            println!();
-            println!("{} Done!", "✓".green().bold());
+    let remote = match remote {
+        Some(url) => Some(url),
+        None if interactive => {
+            let url: String = Input::new()
+                .with_prompt("Remote URL to sync through (empty for none)")
+                .allow_empty(true)
+                .interact_text()
+                .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+            Some(url.trim().to_string()).filter(|url| !url.is_empty())
+        }
+        None => None,
+    };
+
+    paths.ensure_structure()?;
+    init_shade_repo(&paths.projects, remote.as_deref())?;
+    if !paths.config.exists() {
+        Config::default().save(&paths.config)?;
+    }
+
+    outln!(
+        "{} {} shade repo: {}",
+        "✓".green().bold(),
+        if dir_exists { "Initialized" } else { "Created" },
+        paths.projects.display()
+    );
+    match &remote {
+        Some(url) => print_remote(url, &paths.projects),
+        None => outln!(
+            "  No remote yet - add one with: cd {} && git remote add origin <url>",
+            paths.projects.display()
+        ),
+    }
+    outln!();
+
+    Ok(())
+}
+
+/// Show a new remote with its kind and anything that looks wrong with it
+///
+/// Only warns: the repo is created either way, and `git remote set-url`
+/// fixes a wrong URL.
+pub fn print_remote(url: &str, shade_repo: &std::path::Path) {
+    let check = validate_remote_url(url);
+    outln!("  Remote: {} ({})", url, check.kind);
+    for warning in &check.warnings {
+        outln!("  {} {}", "⚠".yellow(), warning);
+    }
+    if !check.warnings.is_empty() {
+        outln!(
+            "    Fix it with: cd {} && git remote set-url origin <url>",
+            shade_repo.display()
+        );
+    }
+}
+
+fn add_project_config_files(
+    paths: &ShadePaths,
+    project_config: &ProjectConfig,
+    project_path: &std::path::Path,
+    project_name: &str,
+) -> Result<()> {
+    if project_config.files.is_empty() {
+        return Ok(());
+    }
+
+    let tracked_patterns = read_patterns(project_path)?;
+    let mut files = Vec::new();
+
+    outln!();
+    outln!("Files listed in {}:", PROJECT_CONFIG_FILE);
+    for file in &project_config.files {
+        let rel = std::path::PathBuf::from(file.to_string_lossy().trim_end_matches('/'));
+        if tracked_patterns.iter().any(|pattern| pattern.covers(&rel)) {
+            outln!("  {} {} (already tracked)", "→".blue(), file.display());
+        } else if !project_path.join(&rel).exists() {
+            outln!("  {} {} (not found, skipped)", "⚠".yellow(), file.display());
         } else {
-            println!("Skipped. Pull manually with: {}", "git-shade pull".bold());
+            files.push(rel);
         }
     }
 
+    if !files.is_empty() {
+        outln!();
+        add::add_files(
+            paths,
+            project_path,
+            project_name,
+            files,
+            add_options(project_name),
+        )?;
+    }
+
     Ok(())
 }
 
+/// Files matching `patterns` that are neither committed nor already shaded
+fn find_suggestions(
+    project_path: &std::path::Path,
+    patterns: &[String],
+) -> Result<Vec<std::path::PathBuf>> {
+    let tracked_patterns = read_patterns(project_path)?;
+
+    let output = run_git(project_path, &["ls-files", "-z"])?;
+    let committed: Vec<std::path::PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(std::path::PathBuf::from)
+        .collect();
+
+    let suggestions = scan_for_secrets(project_path, patterns)?
+        .into_iter()
+        .filter(|path| !tracked_patterns.iter().any(|pattern| pattern.covers(path)))
+        .filter(|path| !committed.iter().any(|file| file.starts_with(path)))
+        .collect();
+
+    Ok(suggestions)
+}
+
 fn list_shade_files(shade_dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>> {
     let mut files = Vec::new();
 
@@ -92,7 +543,9 @@ fn list_shade_files(shade_dir: &std::path::Path) -> Result<Vec<std::path::PathBu
         let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read directory: {}", e))?;
         if entry.file_type().is_file() {
             if let Ok(rel) = entry.path().strip_prefix(shade_dir) {
-                files.push(rel.to_path_buf());
+                if !is_shade_metadata(rel) {
+                    files.push(rel.to_path_buf());
+                }
             }
         }
     }
@@ -100,18 +553,73 @@ fn list_shade_files(shade_dir: &std::path::Path) -> Result<Vec<std::path::PathBu
     Ok(files)
 }
 
+/// Files under `dir` that `--import-from` would bring in, relative to it
+///
+/// `.git` and editor/OS junk are skipped.
+fn list_importable(dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    let walker = WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read directory: {}", e))?;
+        if entry.file_type().is_file() {
+            if let Ok(rel) = entry.path().strip_prefix(dir) {
+                if !is_shade_metadata(rel) && !config.is_junk(rel) {
+                    files.push(rel.to_path_buf());
+                }
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Copy the files of `dir` into the shade dir, keeping their layout
+///
+/// A file shade already has with other contents (e.g. pushed from another
+/// machine) is kept and reported. Returns the files now in shade.
+fn import_files(dir: &Path, shade_dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+    let mut imported = Vec::new();
+
+    for file in list_importable(dir, config)? {
+        let src = dir.join(&file);
+        let dest = shade_dir.join(&file);
+        if dest.exists() && !same_contents(&src, &dest)? {
+            outln!(
+                "  {} {} (already in shade with other contents, kept)",
+                "⚠".yellow(),
+                file.display()
+            );
+            continue;
+        }
+        copy_file_to(&src, &dest)?;
+        imported.push(file);
+    }
+
+    Ok(imported)
+}
+
 fn pull_files(
     files: &[std::path::PathBuf],
     shade_dir: &std::path::Path,
     project_dir: &std::path::Path,
 ) -> Result<()> {
-    use crate::utils::copy_file_preserve_structure;
+    use crate::utils::{copy_file_preserve_structure, set_file_mode};
+
+    let modes = FileModes::load(shade_dir)?;
 
-    println!("Pulling files...");
+    outln!("Pulling files...");
     for file in files {
         let src = shade_dir.join(file);
-        copy_file_preserve_structure(&src, shade_dir, project_dir)?;
-        println!("  {} {}", "✓".green(), file.display());
+        let dest = copy_file_preserve_structure(&src, shade_dir, project_dir)?;
+        if let Some(mode) = modes.get(file) {
+            set_file_mode(&dest, mode)?;
+        }
+        outln!("  {} {}", "✓".green(), file.display());
     }
 
     Ok(())
@@ -124,10 +632,7 @@ fn add_to_exclude(
 ) -> Result<()> {
     use crate::git::add_to_exclude as git_add_to_exclude;
 
-    let patterns: Vec<String> = files
-        .iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect();
+    let patterns: Vec<String> = files.iter().map(|p| to_slash(p)).collect();
 
     git_add_to_exclude(project_dir, &patterns)?;
     Ok(())
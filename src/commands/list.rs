@@ -0,0 +1,121 @@
+use crate::core::{is_shade_metadata, Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+pub fn run(remote: bool) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    if remote {
+        list_available(&paths, &config)
+    } else {
+        list_registered(&paths, &config)
+    }
+}
+
+/// Projects initialized on this machine, with where they live
+fn list_registered(paths: &ShadePaths, config: &Config) -> Result<()> {
+    if config.projects.is_empty() {
+        outln!("{} No projects initialized on this machine", "→".blue());
+        outln!(
+            "  See what the shade repo has with: {}",
+            "git-shade list --remote".bold()
+        );
+        return Ok(());
+    }
+
+    outln!("Projects on this machine:");
+    for project in &config.projects {
+        let note = if !project.local_path.is_dir() {
+            format!(" {}", "(directory missing)".red())
+        } else if !paths.project_shade_dir(&project.name).is_dir() {
+            format!(" {}", "(nothing in shade yet)".bright_black())
+        } else {
+            String::new()
+        };
+        outln!(
+            "  {} {}  {}{}",
+            "●".green(),
+            project.name.bold(),
+            project.local_path.display(),
+            note
+        );
+    }
+
+    Ok(())
+}
+
+/// Project directories in the shade repo that aren't initialized here
+fn list_available(paths: &ShadePaths, config: &Config) -> Result<()> {
+    if !paths.projects.join(".git").exists() {
+        return Err(if paths.projects.is_dir() {
+            ShadeError::ShadeDirNotRepo {
+                path: paths.projects.clone(),
+            }
+        } else {
+            ShadeError::ShadeRepoNotFound
+        });
+    }
+
+    // 3. Walk the shade repo's top level (skipping .git and other dot dirs)
+    let mut available = Vec::new();
+    for entry in fs::read_dir(&paths.projects)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_dir() || name.starts_with('.') {
+            continue;
+        }
+        if config.find_project(&name).is_none() {
+            available.push((name, count_files(&entry.path())));
+        }
+    }
+    available.sort();
+
+    if available.is_empty() {
+        outln!(
+            "{} Every project in the shade repo is initialized on this machine",
+            "✓".green()
+        );
+        return Ok(());
+    }
+
+    // 4. Suggest how to adopt each one
+    outln!("Available in the shade repo, not initialized here:");
+    for (name, files) in &available {
+        outln!("  {} {} ({} file(s))", "●".green(), name.bold(), files);
+        outln!(
+            "      Set up from its checkout: {}",
+            format!("git-shade init --name {}", name).bold()
+        );
+    }
+    outln!();
+    outln!(
+        "{} Without --name, init uses the checkout's directory name.",
+        "→".blue()
+    );
+
+    Ok(())
+}
+
+/// Files a project has in shade, not counting git-shade's own
+fn count_files(project_shade_dir: &Path) -> usize {
+    WalkDir::new(project_shade_dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            entry
+                .path()
+                .strip_prefix(project_shade_dir)
+                .is_ok_and(|rel| !is_shade_metadata(rel))
+        })
+        .count()
+}
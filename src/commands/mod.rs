@@ -1,6 +1,23 @@
 pub mod add;
+pub mod clone;
+pub mod config;
+pub mod doctor;
+pub mod env;
+pub mod export;
+pub mod gc;
 pub mod guide;
+pub mod history;
+pub mod import;
 pub mod init;
+pub mod list;
+pub mod mv;
+pub mod pause;
 pub mod pull;
 pub mod push;
+pub mod rollback;
+pub mod stash;
 pub mod status;
+pub mod sync;
+pub mod uninit;
+pub mod verify;
+pub mod watch;
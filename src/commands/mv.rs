@@ -0,0 +1,170 @@
+use crate::core::{Config, FileModes, Manifest, ShadeLayout, ShadePaths, MODES_FILE};
+use crate::error::{Result, ShadeError};
+use crate::git::{add_to_exclude, read_patterns, remove_from_exclude, run_git, Pattern};
+use crate::utils::output::outln;
+use crate::utils::{
+    detect_project_name, links_to, relative_to_project, symlink_file, verify_git_repo,
+};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn run(from: PathBuf, to: PathBuf, name: Option<String>) -> Result<()> {
+    // 1. Verify it's an initialized project
+    let project_path = verify_git_repo()?;
+    let project_name = detect_project_name(name)?;
+
+    let paths = ShadePaths::new()?;
+    let config = Config::load(&paths.config)?;
+    if config.find_project(&project_name).is_none() {
+        return Err(ShadeError::NotInitialized { project_name });
+    }
+
+    let layout = ShadeLayout::new(&paths, &config, &project_name);
+    let project_shade_dir = layout.shared_dir();
+
+    // 2. Resolve both paths relative to the project root
+    let from_rel = relative_to_project(&project_path, &from)?;
+    let to_rel = relative_to_project(&project_path, &to)?;
+
+    // 3. `from` must be a tracked entry, `to` must be free
+    let old_pattern = read_patterns(&project_path)?
+        .into_iter()
+        .find(|pattern| pattern.path() == from_rel)
+        .ok_or_else(|| ShadeError::NotTracked(from.clone()))?;
+
+    let local_from = project_path.join(&from_rel);
+    let local_to = project_path.join(&to_rel);
+    // The shared copy and, with the per-OS layout, this OS's variant
+    let shade_dirs: Vec<PathBuf> = std::iter::once(project_shade_dir.to_path_buf())
+        .chain(layout.os_dir())
+        .collect();
+
+    if local_to.exists() {
+        return Err(ShadeError::DestinationExists(to));
+    }
+    if let Some(shade_to) = shade_dirs
+        .iter()
+        .map(|dir| dir.join(&to_rel))
+        .find(|shade_to| shade_to.exists())
+    {
+        return Err(ShadeError::DestinationExists(shade_to));
+    }
+
+    // Added with --symlink: point the link at the moved shade copy afterwards
+    let relink = links_to(&local_from, &layout.file(&from_rel));
+
+    // 4. Rename the local file
+    if local_from.exists() {
+        rename(&local_from, &local_to)?;
+        outln!(
+            "{} Renamed {} → {}",
+            "✓".green(),
+            from_rel.display(),
+            to_rel.display()
+        );
+    }
+
+    // 5. Swap the .git/info/exclude entry, keeping its style
+    let removed = remove_from_exclude(&project_path, &old_pattern)?;
+    let dir_as_glob = removed.iter().any(|line| line.ends_with("/**"));
+    let new_pattern = match old_pattern {
+        Pattern::File(_) => Pattern::File(to_rel.clone()),
+        Pattern::Dir(_) => Pattern::Dir(to_rel.clone()),
+    };
+    add_to_exclude(&project_path, &[new_pattern.to_exclude_line(dir_as_glob)])?;
+    outln!(
+        "{} Updated .git/info/exclude: {} → {}",
+        "✓".green(),
+        old_pattern,
+        new_pattern.to_exclude_line(dir_as_glob)
+    );
+
+    // 6. Move the shade copies, keeping their git history
+    for dir in &shade_dirs {
+        move_in_shade(
+            &paths,
+            &project_name,
+            project_shade_dir,
+            dir,
+            &from_rel,
+            &to_rel,
+        )?;
+    }
+
+    if relink {
+        fs::remove_file(&local_to)?;
+        symlink_file(&layout.file(&to_rel), &local_to)?;
+    }
+
+    // 7. Carry over manifest entries and file modes
+    let manifest_path = paths.manifest_file(&project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    manifest.rename(&from_rel, &to_rel);
+    manifest.save(&manifest_path)?;
+
+    if project_shade_dir.join(MODES_FILE).exists() {
+        let mut modes = FileModes::load(project_shade_dir)?;
+        modes.rename(&from_rel, &to_rel);
+        modes.save(project_shade_dir)?;
+    }
+
+    outln!();
+    outln!("Ready to push with: {}", "git-shade push".bold());
+
+    Ok(())
+}
+
+/// Move `dir/<from>` to `dir/<to>` with `git mv`, if it's there
+fn move_in_shade(
+    paths: &ShadePaths,
+    project_name: &str,
+    project_shade_dir: &Path,
+    dir: &Path,
+    from_rel: &Path,
+    to_rel: &Path,
+) -> Result<()> {
+    let shade_from = dir.join(from_rel);
+    if !shade_from.exists() {
+        return Ok(());
+    }
+
+    let shade_to = dir.join(to_rel);
+    let repo_dir = Path::new(project_name).join(dir.strip_prefix(project_shade_dir).unwrap_or(dir));
+    let shade_from_rel = repo_dir.join(from_rel);
+    let shade_to_rel = repo_dir.join(to_rel);
+
+    if let Some(parent) = shade_to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let output = run_git(
+        &paths.projects,
+        &[
+            Path::new("mv"),
+            shade_from_rel.as_path(),
+            shade_to_rel.as_path(),
+        ],
+    )?;
+
+    // Not committed yet: a plain rename is all there is to do
+    if !output.status.success() {
+        rename(&shade_from, &shade_to)?;
+    }
+
+    outln!(
+        "{} Moved in shade: {} → {}",
+        "✓".green(),
+        shade_from_rel.display(),
+        shade_to_rel.display()
+    );
+    Ok(())
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
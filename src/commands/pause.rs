@@ -0,0 +1,76 @@
+use crate::core::{Config, Manifest, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::git::read_patterns;
+use crate::utils::output::outln;
+use crate::utils::{relative_to_project, resolve_project};
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Pause (or with `pause` false, resume) syncing tracked files
+///
+/// Paused files stay tracked: they keep their exclude entry and manifest
+/// record, push and pull just skip them.
+pub fn run(
+    files: Vec<PathBuf>,
+    pause: bool,
+    project: Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+
+    // 5. Every path must be tracked (a file, or a directory inside a tracked one)
+    let manifest_path = paths.manifest_file(&project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    let patterns = read_patterns(&project_path)?;
+
+    let mut rel_paths = Vec::new();
+    for file in &files {
+        let rel = relative_to_project(&project_path, file)?;
+        let tracked = patterns.iter().any(|pattern| pattern.covers(&rel))
+            || manifest.get(&rel).is_some()
+            || manifest.is_external(&rel);
+        if !tracked {
+            return Err(ShadeError::NotTracked(file.clone()));
+        }
+        rel_paths.push(rel);
+    }
+
+    // 6. Update the manifest
+    for rel in &rel_paths {
+        if pause {
+            if manifest.pause(rel) {
+                outln!("{} Paused {}", "✓".green(), rel.display());
+            } else {
+                outln!("{} {} is already paused", "→".blue(), rel.display());
+            }
+        } else if manifest.resume(rel) {
+            outln!("{} Resumed {}", "✓".green(), rel.display());
+        } else {
+            outln!("{} {} isn't paused", "→".blue(), rel.display());
+        }
+    }
+    manifest.save(&manifest_path)?;
+
+    outln!();
+    if pause {
+        outln!(
+            "Push and pull skip them until you run {}",
+            "git-shade resume <file>".bold()
+        );
+    } else {
+        outln!(
+            "They sync again with the next {} or {}",
+            "git-shade push".bold(),
+            "git-shade pull".bold()
+        );
+    }
+
+    Ok(())
+}
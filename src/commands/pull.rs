@@ -1,94 +1,452 @@
+use crate::commands::push::{follow_rebased_pushes, push_project, PushOptions};
 use crate::core::{
-    detect_sync_state, format_conflict_message, Config, ConflictInfo, FileMetadata, ShadePaths,
-    SyncState, Tracker,
+    case_collisions, detect_sync_state, format_case_collision_warning, format_conflict_message,
+    format_merge_conflict_message, is_binary, is_excluded, is_shade_metadata, list_marked_dirs,
+    merge_with_markers, to_slash, Config, ConflictInfo, ConflictKind, ConflictSide,
+    ConflictStrategy, FileMetadata, FileModes, HashCache, Manifest, PullStrategy, ShadeLayout,
+    ShadePaths, SyncState, Tracker,
 };
 use crate::error::{Result, ShadeError};
-use crate::git::{add_to_exclude, read_exclude};
-use crate::utils::{copy_file_preserve_structure, detect_project_name, verify_git_repo};
+use crate::git::{
+    add_to_exclude, classify_pull_failure, config_value, conflicted_files, fetch,
+    last_commit_mtime, operation_in_progress, read_patterns, remove_from_exclude, run_git,
+    unpushed_files, upstream_changes, InProgress, Pattern, PullFailure, UpstreamFile,
+};
+use crate::utils::output::{out, outln};
+use crate::utils::{
+    copy_file_to, format_size, load_tracker, resolve_project, run_hook, set_file_mode, set_mtime,
+    tracker_path, unshare_file, CopyProgress,
+};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
-use std::process::Command;
-use walkdir::WalkDir;
+use dialoguer::Confirm;
+use filetime::FileTime;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
-pub fn run(force: bool, dry_run: bool) -> Result<()> {
-    // 1. Verify it's a git repo
-    let project_path = verify_git_repo()?;
+/// Flags for `git-shade pull`
+#[derive(Debug, Clone, Default)]
+pub struct PullOptions {
+    pub force: bool,
+    pub dry_run: bool,
+    pub verbose: bool,
+    /// Sync from the shade dir as it is, without `git pull` (`--no-fetch`, and import)
+    pub skip_git_pull: bool,
+    /// How to settle files changed on both sides
+    pub strategy: ConflictStrategy,
+    /// Write synced files here instead of into the project, leaving the
+    /// project's exclude file, manifest and last_pull untouched
+    pub output: Option<PathBuf>,
+    /// Only sync files matching one of these globs (all files when empty)
+    pub only: Vec<String>,
+    /// Never sync files matching these globs
+    pub except: Vec<String>,
+    /// Copy every local file about to be overwritten into a timestamped backup dir
+    pub backup_all: bool,
+    /// Push afterwards if this machine has files the remote doesn't
+    pub push_after: bool,
+    /// Delete local copies of synced files that were removed from shade
+    pub prune: bool,
+    /// Don't ask before pruning
+    pub yes: bool,
+    /// Write both versions of conflicting text files into the local file, with markers
+    pub merge_markers: bool,
+    /// `git pull` strategy for this run, over `pull_strategy` in the config
+    pub pull_strategy: Option<PullStrategy>,
+    /// Only add the shade files to `.git/info/exclude`, copying nothing
+    pub exclude_only: bool,
+}
 
-    // 2. Detect project name
-    let project_name = detect_project_name(None)?;
+/// What `sync_from_shade` did
+struct Pulled {
+    synced: usize,
+    /// Some tracked files are only here (not in shade, changed locally or not pushed yet)
+    needs_push: bool,
+}
 
-    // 3. Setup paths
+pub fn run(options: PullOptions, project: Option<String>, name: Option<String>) -> Result<()> {
+    // 1. Setup paths
     let paths = ShadePaths::new()?;
 
-    // 4. Verify project is initialized
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+
+    pull_project(&paths, &project_path, &project_name, &options)?;
+    Ok(())
+}
+
+/// Pull the shade repo and sync a resolved project's files from it
+///
+/// Runs the `pre_pull`/`post_pull` hooks around it (not on a dry run or
+/// with `output`), then pushes if `push_after` is set and anything needs it.
+/// Returns the number of files copied to the project.
+pub fn pull_project(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    options: &PullOptions,
+) -> Result<usize> {
     let config = Config::load(&paths.config)?;
-    if config.find_project(&project_name).is_none() {
-        return Err(ShadeError::NotInitialized { project_name });
+    let hooks = &config.hooks;
+    let project_shade_dir = paths.project_shade_dir(project_name);
+    let _span = tracing::info_span!("pull", project = project_name).entered();
+    // Only tell git to ignore the shade files; nothing is pulled or copied
+    if options.exclude_only {
+        register_excludes(paths, &config, project_path, project_name, options)?;
+        return Ok(0);
     }
 
-    let project_shade_dir = paths.project_shade_dir(&project_name);
+    // Hooks act on the live project, which --output leaves alone
+    let run_hooks = !options.dry_run && options.output.is_none();
 
-    // 5. Pull from git remote
-    println!("Pulling from shade repo...");
+    if run_hooks {
+        run_hook(
+            "pre_pull",
+            hooks.pre_pull.as_deref(),
+            project_name,
+            project_path,
+            &project_shade_dir,
+        )?;
+    }
 
-    if !dry_run {
-        // Change to shade projects directory
-        let original_dir = std::env::current_dir()?;
-        std::env::set_current_dir(&paths.projects)?;
+    let pulled = sync_from_shade(paths, &config, project_path, project_name, options)?;
 
-        let pull_output = Command::new("git").args(["pull"]).output()?;
+    if config.preserve_empty_dirs && !options.dry_run {
+        let dest_base = options.output.as_deref().unwrap_or(project_path);
+        let layout = ShadeLayout::new(paths, &config, project_name);
+        restore_empty_dirs(&layout, dest_base)?;
+    }
 
-        // Change back
-        std::env::set_current_dir(&original_dir)?;
+    if run_hooks {
+        run_hook(
+            "post_pull",
+            hooks.post_pull.as_deref(),
+            project_name,
+            project_path,
+            &project_shade_dir,
+        )?;
+    }
+
+    // Files added on this machine and on another one only converge once
+    // both have pushed, so finish the job here
+    if options.push_after && run_hooks && pulled.needs_push {
+        outln!();
+        outln!("{}", "==> Push".bold());
+        let push_options = PushOptions {
+            verbose: options.verbose,
+            ..PushOptions::default()
+        };
+        push_project(paths, project_path, project_name, &push_options)?;
+    }
+
+    Ok(pulled.synced)
+}
+
+/// `pull --set-exclude-only`: add the project's shade files to `.git/info/exclude`
+///
+/// Uses the shade dir as it is. The local files, the shade repo and last_pull
+/// are left alone, so the next pull still compares every file (a differing
+/// local file is a conflict, not overwritten).
+fn register_excludes(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+    options: &PullOptions,
+) -> Result<()> {
+    let layout = ShadeLayout::new(paths, config, project_name);
+    let tracked_patterns = read_patterns(project_path)?;
+    let manifest = Manifest::load(&paths.manifest_file(project_name))?;
+
+    let mut new_patterns = Vec::new();
+    let mut covered = 0;
+    for file in layout.list_files()? {
+        if !is_selected(&file, &options.only, &options.except)
+            || config.is_junk(&file)
+            || manifest.is_paused(&file)
+            || manifest.is_external(&file)
+        {
+            continue;
+        }
+        if tracked_patterns.iter().any(|p| p.covers(&file)) {
+            covered += 1;
+        } else {
+            new_patterns.push(to_slash(&file));
+        }
+    }
+
+    if new_patterns.is_empty() {
+        outln!(
+            "{} All {} shade file(s) are already in .git/info/exclude",
+            "→".blue(),
+            covered
+        );
+        return Ok(());
+    }
+
+    add_to_exclude(project_path, &new_patterns)?;
+    outln!("{} Added to .git/info/exclude:", "✓".green().bold());
+    for pattern in &new_patterns {
+        outln!("  - {}", pattern);
+    }
+    outln!();
+    outln!(
+        "Nothing was copied. Pull the contents when ready with: {}",
+        "git-shade pull".bold()
+    );
+
+    Ok(())
+}
+
+fn sync_from_shade(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+    options: &PullOptions,
+) -> Result<Pulled> {
+    let PullOptions {
+        force,
+        dry_run,
+        verbose,
+        skip_git_pull,
+        strategy,
+        output,
+        only,
+        except,
+        backup_all,
+        push_after: _,
+        prune,
+        yes: _,
+        merge_markers,
+        pull_strategy,
+        exclude_only: _,
+    } = options.clone();
+    let filtered = !only.is_empty() || !except.is_empty();
+
+    // Sync state is still judged against the project; only the writes move
+    let dest_base = output.as_deref().unwrap_or(project_path);
+
+    let project_shade_dir = paths.project_shade_dir(project_name);
+    let layout = ShadeLayout::new(paths, config, project_name);
+
+    // 5. Pull from git remote
+    outln!("Pulling from shade repo...");
+
+    // Remote versions not merged into the shade dir yet (only on a dry run)
+    let mut upstream = BTreeMap::new();
+
+    if skip_git_pull {
+        outln!(
+            "  {} Skipped git pull, syncing from the shade dir as it is",
+            "→".blue()
+        );
+    } else if !dry_run {
+        let pull_args = pull_args(&paths.projects, pull_strategy.or(config.pull_strategy))?;
+        let pull_output = run_git(&paths.projects, &pull_args)?;
 
         if !pull_output.status.success() {
             let stderr = String::from_utf8_lossy(&pull_output.stderr);
-            return Err(ShadeError::GitError(format!("git pull failed: {}", stderr)));
+            let stdout = String::from_utf8_lossy(&pull_output.stdout);
+
+            return Err(
+                match classify_pull_failure(&format!("{}\n{}", stdout, stderr)) {
+                    PullFailure::MergeConflict => {
+                        let files = conflicted_files(&paths.projects)?;
+                        let operation =
+                            operation_in_progress(&paths.projects)?.unwrap_or(InProgress::Merge);
+                        outln!(
+                            "{}",
+                            format_merge_conflict_message(&files, operation.command())
+                        );
+                        ShadeError::ShadeMergeConflict { files }
+                    }
+                    PullFailure::NotFastForward => ShadeError::PullNotFastForward,
+                    PullFailure::Remote => ShadeError::RemoteUnreachable(stderr.trim().to_string()),
+                    PullFailure::Other => {
+                        ShadeError::GitError(format!("git pull failed: {}", stderr))
+                    }
+                },
+            );
         }
 
-        println!("  {} Git pull successful", "✓".green());
+        outln!("  {} Git pull successful", "✓".green());
+        follow_rebased_pushes(paths)?;
+
+        // Checkout stamps files with the current time; use the commit time
+        // instead, the same on every machine (copies below keep it)
+        if config.normalize_mtime {
+            for file in layout.list_files()? {
+                let repo_path = layout.repo_path(project_name, &file);
+                if let Some(modified) = last_commit_mtime(&paths.projects, &repo_path)? {
+                    // A hardlinked file shares its mtime with the other links
+                    let shade_file = layout.file(&file);
+                    unshare_file(&shade_file)?;
+                    set_mtime(&shade_file, modified)?;
+                }
+            }
+        }
     } else {
-        println!("  {} Git pull successful (dry-run)", "✓".green());
+        // Nothing is merged, so fetch and judge files against the remote's versions
+        match fetch(&paths.projects) {
+            Ok(()) => outln!(
+                "  {} Git fetch successful (dry-run, not merged)",
+                "✓".green()
+            ),
+            Err(err) => outln!(
+                "  {} Git fetch failed, using the last fetched state: {}",
+                "⚠".yellow(),
+                err
+            ),
+        }
+        for file in upstream_changes(&paths.projects, project_name)? {
+            if let Some(rel) = layout.logical(&file.path) {
+                upstream.insert(rel, file);
+            }
+        }
     }
 
     // Show which projects were updated
     let updated_projects = list_updated_projects(&paths.projects)?;
     if !updated_projects.is_empty() {
-        print!("  Updated: ");
+        out!("  Updated: ");
         for (i, proj) in updated_projects.iter().enumerate() {
             if i > 0 {
-                print!(", ");
+                out!(", ");
             }
-            print!("{}", proj);
+            out!("{}", proj);
         }
-        println!();
+        outln!();
     }
-    println!();
+    outln!();
 
     // 6. Load tracker to get last_pull time
-    let tracker =
-        Tracker::load(&paths.shade_sync_file(&project_name)).unwrap_or_else(|_| Tracker::new());
+    let tracker_file = tracker_path(paths, config, project_name)?;
+    let tracker = load_tracker(&tracker_file);
     let last_pull = tracker.last_pull;
 
-    // 7. Get all files from shade directory
-    let shade_files = list_all_files(&project_shade_dir)?;
+    // 7. Get tracked patterns from .git/info/exclude
+    let tracked_patterns = read_patterns(project_path)?;
 
-    if shade_files.is_empty() {
-        println!("No files in shade directory.");
-        return Ok(());
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+
+    // Tracked files that only exist locally are never touched by pull
+    let mut local_only_files =
+        list_local_only(&tracked_patterns, &manifest, project_path, &layout)?;
+    local_only_files.retain(|file| {
+        is_selected(file, &only, &except) && !config.is_junk(file) && !manifest.is_paused(file)
+    });
+
+    // Synced before but gone from shade now: removed on purpose on another
+    // machine. Ones edited here since then are conflicts, the rest can be pruned
+    let mut gone = Vec::new();
+    let mut deleted_edited = Vec::new();
+    for file in manifest.paths() {
+        if !is_selected(&file, &only, &except)
+            || manifest.is_paused(&file)
+            || layout.file(&file).exists()
+            || upstream.contains_key(&file)
+        {
+            continue;
+        }
+        let local = manifest.local_path(project_path, &file);
+        let synced_hash = manifest.get(&file).map(|entry| entry.hash.clone());
+        if local.is_file() && synced_hash != Some(hashes.hash(&local)?) {
+            deleted_edited.push(file);
+        } else {
+            gone.push(file);
+        }
+    }
+
+    if prune {
+        prune_local_files(
+            paths,
+            project_path,
+            project_name,
+            &mut manifest,
+            gone,
+            options,
+        )?;
+        if !dry_run {
+            manifest.save(&manifest_path)?;
+        }
+    }
+
+    // Shade files this machine hasn't pushed (e.g. added here, pushed elsewhere)
+    let mut unpushed = list_unpushed(paths, project_name, &layout)?;
+    unpushed.retain(|file| is_selected(file, &only, &except) && !manifest.is_paused(file));
+
+    // 8. Get all files from shade directory (narrowed by --only/--except, so
+    // conflicts in files left out don't block the pull)
+    let mut shade_files = layout.list_files()?;
+    for file in upstream.keys() {
+        if !shade_files.contains(file) && !is_shade_metadata(file) {
+            shade_files.push(file.clone());
+        }
+    }
+    shade_files.retain(|file| is_selected(file, &only, &except) && !config.is_junk(file));
+    let paused_count = shade_files.len();
+    shade_files.retain(|file| !manifest.is_paused(file));
+    let paused_count = paused_count - shade_files.len();
+    if paused_count > 0 {
+        outln!("{} {} paused file(s) skipped", "→".blue(), paused_count);
+    }
+
+    // Both copies land on one file where the filesystem ignores case
+    let collisions = case_collisions(&shade_files);
+    if !collisions.is_empty() {
+        outln!("{}", format_case_collision_warning(&collisions));
     }
 
-    // 8. Get tracked patterns from .git/info/exclude
-    let tracked_patterns = read_exclude(&project_path)?;
+    // The checkout gave changed shade files fresh mtimes; where the contents
+    // match the local file, line the mtimes up before judging anything
+    if !dry_run && output.is_none() {
+        let aligned = align_mtimes(
+            &shade_files,
+            &layout,
+            project_path,
+            &manifest,
+            &mut hashes,
+            config.normalize_mtime,
+        )?;
+        if aligned > 0 && verbose {
+            outln!(
+                "  {} Matched mtimes of {} identical file(s)",
+                "✓".green(),
+                aligned
+            );
+        }
+    }
+
+    if shade_files.is_empty() && deleted_edited.is_empty() {
+        outln!("No files in shade directory.");
+        print_local_only_note(&local_only_files);
+        return Ok(Pulled {
+            synced: 0,
+            needs_push: !local_only_files.is_empty(),
+        });
+    }
 
     // 9. Analyze sync state for each file
-    println!("Checking for conflicts in {}...", project_name);
+    outln!("Checking for conflicts in {}...", project_name);
 
     let mut conflicts = Vec::new();
     let mut files_to_sync = Vec::new();
     let mut files_to_add_to_exclude = Vec::new();
+    // Files changed locally (or on both sides) where the local copy was kept
+    let mut kept_files = Vec::new();
+    // Files `add` left in conflict that this pull settles
+    let mut settled = Vec::new();
 
     for shade_file_path in &shade_files {
-        let local_file_path = project_path.join(shade_file_path);
+        let local_file_path = manifest.local_path(project_path, shade_file_path);
 
         // Get metadata
         let local_meta = if local_file_path.exists() {
@@ -97,52 +455,205 @@ pub fn run(force: bool, dry_run: bool) -> Result<()> {
             None
         };
 
-        let shade_full_path = project_shade_dir.join(shade_file_path);
+        let shade_full_path = layout.file(shade_file_path);
         let remote_meta = if shade_full_path.exists() {
             Some(FileMetadata::from_path(&shade_full_path)?)
         } else {
             None
         };
+        let upstream_file = upstream.get(shade_file_path);
+        let remote_modified = upstream_file
+            .map(|file| file.modified)
+            .or(remote_meta.as_ref().map(|meta| meta.modified));
 
-        // Detect state
-        let state = detect_sync_state(local_meta.as_ref(), remote_meta.as_ref(), last_pull);
+        // Detect state (with sync.hash, identical contents are in sync whatever
+        // the timestamps say)
+        let state = if let Some(upstream_file) = upstream_file {
+            upstream_state(
+                &local_file_path,
+                local_meta.as_ref(),
+                upstream_file,
+                last_pull,
+            )?
+        } else if local_meta.is_some()
+            && remote_meta.is_some()
+            && config.sync.hash
+            && hashes.same_contents(&local_file_path, &shade_full_path)?
+        {
+            SyncState::InSync
+        } else {
+            detect_sync_state(
+                local_meta.as_ref(),
+                remote_meta.as_ref(),
+                last_pull,
+                &config.sync,
+            )
+        };
+        // `add` kept both versions: a conflict until one side wins
+        let conflicted = manifest.is_conflicted(shade_file_path);
+        let state = if conflicted
+            && local_meta.is_some()
+            && remote_meta.is_some()
+            && !hashes.same_contents(&local_file_path, &shade_full_path)?
+        {
+            SyncState::Conflict
+        } else {
+            if conflicted {
+                settled.push(shade_file_path.clone());
+            }
+            state
+        };
+        tracing::debug!(file = %shade_file_path.display(), ?state, "pull decision");
 
         match state {
             SyncState::Conflict => {
-                if !force {
-                    conflicts.push(ConflictInfo::new(
-                        shade_file_path.clone(),
-                        local_meta.as_ref().unwrap().modified,
-                        remote_meta.as_ref().unwrap().modified,
-                        last_pull.unwrap(),
-                    ));
-                } else {
+                if force {
                     // Force mode: treat as remote ahead
                     files_to_sync.push((shade_file_path.clone(), "overwritten".to_string()));
+                    settled.push(shade_file_path.clone());
+                    continue;
+                }
+
+                let conflict = ConflictInfo::new(
+                    shade_file_path.clone(),
+                    local_meta.as_ref().unwrap().modified,
+                    remote_modified.unwrap(),
+                    last_pull,
+                );
+
+                match strategy.resolve(&conflict) {
+                    Some(ConflictSide::Remote) => {
+                        outln!(
+                            "  {} {} (conflict, remote wins)",
+                            "⚠".yellow(),
+                            shade_file_path.display()
+                        );
+                        files_to_sync
+                            .push((shade_file_path.clone(), "conflict, remote wins".to_string()));
+                        settled.push(shade_file_path.clone());
+                    }
+                    Some(ConflictSide::Local) => {
+                        outln!(
+                            "  {} {} (conflict, local wins - kept)",
+                            "⚠".yellow(),
+                            shade_file_path.display()
+                        );
+                        kept_files.push(shade_file_path.clone());
+                        settled.push(shade_file_path.clone());
+                    }
+                    None => conflicts.push(conflict),
                 }
             }
             SyncState::RemoteAhead | SyncState::RemoteOnly => {
                 files_to_sync.push((shade_file_path.clone(), "copied".to_string()));
 
-                // Check if this file is tracked in exclude
-                let pattern = shade_file_path.to_string_lossy().to_string();
-                if !tracked_patterns.contains(&pattern) {
-                    files_to_add_to_exclude.push(pattern);
+                // Check if this file is tracked in exclude (directly or via a directory)
+                if !tracked_patterns.iter().any(|p| p.covers(shade_file_path))
+                    && !manifest.is_external(shade_file_path)
+                {
+                    files_to_add_to_exclude.push(to_slash(shade_file_path));
                 }
             }
             SyncState::InSync => {
-                // No action needed
+                // Nothing to do (mtimes were lined up before the analysis)
+            }
+            SyncState::LocalAhead => {
+                // Skip - local is ahead, push will send it
+                kept_files.push(shade_file_path.clone());
+            }
+            SyncState::LocalOnly => {
+                // Skip - only exists locally
+            }
+        }
+    }
+
+    // Files deleted from shade that were edited here: the deletion isn't
+    // applied silently, and the edit isn't pushed back without asking
+    let mut files_to_delete = Vec::new();
+    for file in &deleted_edited {
+        let local = manifest.local_path(project_path, file);
+        let local_modified = FileMetadata::from_path(&local)?.modified;
+        let deleted_at = last_commit_mtime(&paths.projects, &layout.repo_path(project_name, file))?
+            .and_then(|time| DateTime::from_timestamp(time.unix_seconds(), 0))
+            .unwrap_or_else(Utc::now);
+        let conflict = ConflictInfo::deleted(file.clone(), local_modified, deleted_at, last_pull);
+
+        let side = if force {
+            Some(ConflictSide::Remote)
+        } else {
+            strategy.resolve(&conflict)
+        };
+        match side {
+            Some(ConflictSide::Remote) => {
+                outln!(
+                    "  {} {} (deleted remotely, local copy backed up and removed)",
+                    "⚠".yellow(),
+                    file.display()
+                );
+                files_to_delete.push((file.clone(), local));
+            }
+            Some(ConflictSide::Local) => {
+                // Still listed as local-only, so the next push restores it in shade
+                outln!(
+                    "  {} {} (deleted remotely, local edit kept)",
+                    "⚠".yellow(),
+                    file.display()
+                );
+            }
+            None => conflicts.push(conflict),
+        }
+    }
+    local_only_files.retain(|file| !files_to_delete.iter().any(|(deleted, _)| deleted == file));
+
+    // 10. Handle conflicts (with --merge-markers, text files take both versions inline)
+    if merge_markers && !conflicts.is_empty() && !dry_run && output.is_none() {
+        if let Some(merged) = merge_conflicts(&conflicts, &manifest, project_path, &layout)? {
+            let mut unresolved = Vec::new();
+            for (file, local, contents) in merged {
+                std::fs::write(&local, contents)?;
+                outln!(
+                    "  {} {} (conflict, merge markers written)",
+                    "⚠".yellow(),
+                    file.display()
+                );
+                unresolved.push(file);
             }
-            SyncState::LocalAhead | SyncState::LocalOnly => {
-                // Skip - local is ahead or only exists locally
+
+            // The markers take over from both versions `add` kept
+            let cleared = unresolved
+                .iter()
+                .filter(|file| manifest.clear_conflict(file))
+                .count();
+            if cleared > 0 {
+                manifest.save(&manifest_path)?;
             }
+
+            outln!();
+            outln!(
+                "{} {} file(s) with merge markers to resolve:",
+                "⚠".yellow(),
+                unresolved.len()
+            );
+            for file in &unresolved {
+                outln!("  - {}", file.display());
+            }
+            outln!(
+                "  {} Resolve the markers, then run {}",
+                "→".blue(),
+                "git-shade push".bold()
+            );
+            return Err(ShadeError::ConflictDetected {
+                files: unresolved
+                    .iter()
+                    .map(|file| file.to_string_lossy().to_string())
+                    .collect(),
+            });
         }
     }
 
-    // 10. Handle conflicts
     if !conflicts.is_empty() && !force {
-        println!();
-        println!(
+        outln!();
+        outln!(
             "{}",
             format_conflict_message(&conflicts, &project_shade_dir)
         );
@@ -155,27 +666,123 @@ pub fn run(force: bool, dry_run: bool) -> Result<()> {
     }
 
     if conflicts.is_empty() && !force {
-        println!("  No conflicts detected");
+        outln!("  No conflicts detected");
+    }
+
+    if !dry_run && output.is_none() {
+        let cleared = settled
+            .iter()
+            .filter(|file| manifest.clear_conflict(file))
+            .count();
+        if cleared > 0 {
+            manifest.save(&manifest_path)?;
+        }
+    }
+
+    outln!();
+
+    let kept_count = kept_files.len();
+    for file in kept_files {
+        if !unpushed.contains(&file) {
+            unpushed.push(file);
+        }
     }
+    unpushed.sort();
+    let needs_push = !local_only_files.is_empty() || !unpushed.is_empty();
 
-    println!();
+    // Accepted deletions (with --output the project isn't touched)
+    if !files_to_delete.is_empty() && output.is_none() {
+        if dry_run {
+            outln!(
+                "Would delete {} local file(s) removed from shade",
+                files_to_delete.len()
+            );
+        } else {
+            let backup_dir = remove_local_files(
+                paths,
+                project_path,
+                project_name,
+                &mut manifest,
+                &files_to_delete,
+            )?;
+            manifest.save(&manifest_path)?;
+            outln!(
+                "  {} Deleted {} file(s) removed from shade (backed up to {})",
+                "✓".green(),
+                files_to_delete.len(),
+                backup_dir.display()
+            );
+        }
+        outln!();
+    }
 
     // 11. Sync files
     if files_to_sync.is_empty() {
-        println!("All files are in sync. No changes needed.");
-        return Ok(());
+        hashes.save(&hash_cache_path)?;
+        outln!("All files are in sync. No changes needed.");
+        print_local_only_note(&local_only_files);
+        print_unpushed_note(&unpushed);
+        return Ok(Pulled {
+            synced: 0,
+            needs_push,
+        });
     }
 
     if force {
-        println!("{} Force mode: overwriting all local files", "⚠".yellow());
+        outln!("{} Force mode: overwriting all local files", "⚠".yellow());
+    }
+
+    if let Some(output) = &output {
+        outln!("Syncing files into {}...", output.display());
+    } else {
+        outln!("Syncing files...");
+    }
+
+    if backup_all && !dry_run {
+        let local_files: Vec<(PathBuf, PathBuf)> = files_to_sync
+            .iter()
+            .map(|(file, _)| (file.clone(), manifest.local_path(project_path, file)))
+            .filter(|(_, local)| local.is_file())
+            .collect();
+        if !local_files.is_empty() {
+            let backup_dir = backup_local_files(paths, project_name, &local_files)?;
+            outln!(
+                "  {} Backed up {} file(s) to {}",
+                "✓".green(),
+                local_files.len(),
+                backup_dir.display()
+            );
+        }
     }
 
-    println!("Syncing files...");
+    // Dry-run always lists files, since that's its whole output
+    let progress = CopyProgress::new(files_to_sync.len(), verbose || dry_run);
+    let modes = FileModes::load(&project_shade_dir)?;
+    let mut bytes_synced = 0;
 
     for (file_path, action) in &files_to_sync {
+        bytes_synced += match upstream.get(file_path) {
+            Some(file) => file.contents.len() as u64,
+            None => FileMetadata::from_path(&layout.file(file_path))?.size,
+        };
+
         if !dry_run {
-            let src = project_shade_dir.join(file_path);
-            copy_file_preserve_structure(&src, &project_shade_dir, &project_path)?;
+            progress.start(&file_path.display().to_string());
+            let src = layout.file(file_path);
+            let dest = if output.is_none() && manifest.is_external(file_path) {
+                manifest.local_path(project_path, file_path)
+            } else {
+                dest_base.join(file_path)
+            };
+            copy_file_to(&src, &dest)?;
+            // add --chmod on this machine wins over the mode recorded in shade
+            if let Some(mode) = manifest.chmod_for(file_path).or(modes.get(file_path)) {
+                set_file_mode(&dest, mode)?;
+            }
+            if output.is_none() {
+                manifest.record(file_path, &dest, &mut hashes)?;
+            }
+            progress.inc();
         }
 
         let symbol = if *action == "overwritten" {
@@ -183,56 +790,327 @@ pub fn run(force: bool, dry_run: bool) -> Result<()> {
         } else {
             "↓"
         };
-        println!("  {} {} ({})", symbol.green(), file_path.display(), action);
+        progress.verbose(&format!(
+            "  {} {} ({})",
+            symbol.green(),
+            file_path.display(),
+            action
+        ));
     }
 
-    // 12. Add new files to .git/info/exclude
-    if !files_to_add_to_exclude.is_empty() && !dry_run {
-        add_to_exclude(&project_path, &files_to_add_to_exclude)?;
-        println!();
-        println!("Updated .git/info/exclude");
+    progress.finish();
+    if !dry_run && output.is_none() {
+        manifest.save(&manifest_path)?;
+    }
+    hashes.save(&hash_cache_path)?;
+    if !verbose && !dry_run {
+        outln!("  {} {} file(s) synced", "✓".green(), files_to_sync.len());
     }
 
-    // 13. Update tracker
-    if !dry_run {
-        let mut tracker =
-            Tracker::load(&paths.shade_sync_file(&project_name)).unwrap_or_else(|_| Tracker::new());
-        tracker.update_pull();
-        tracker.save(&paths.shade_sync_file(&project_name))?;
+    // 12. Add new files to .git/info/exclude
+    if !files_to_add_to_exclude.is_empty() && !dry_run && output.is_none() {
+        add_to_exclude(project_path, &files_to_add_to_exclude)?;
+        outln!();
+        outln!("Updated .git/info/exclude");
+    }
 
+    // 13. Update tracker (not with --output or a filter: files that weren't
+    // pulled would look in sync afterwards and their changes would be lost)
+    if output.is_none() && !filtered {
         let timestamp = chrono::Utc::now().to_rfc3339();
-        println!("Updated last_pull: {}", timestamp);
-    } else {
-        let timestamp = chrono::Utc::now().to_rfc3339();
-        println!();
-        println!("Would update last_pull: {}", timestamp);
+        if !dry_run {
+            let mut tracker = Tracker::load(&tracker_file).unwrap_or_else(|_| Tracker::new());
+            tracker.update_pull();
+            tracker.save(&tracker_file)?;
+
+            outln!("Updated last_pull: {}", timestamp);
+        } else {
+            outln!();
+            outln!("Would update last_pull: {}", timestamp);
+        }
     }
 
-    println!();
+    outln!();
     if dry_run {
-        println!("{} Dry-run completed (no changes made)", "✓".blue());
+        outln!("{} Dry-run completed (no changes made)", "✓".blue());
     } else if force {
-        println!("{} Pull completed (forced)", "✓".green().bold());
+        outln!("{} Pull completed (forced)", "✓".green().bold());
     } else {
-        println!("{} Pull completed successfully", "✓".green().bold());
+        outln!("{} Pull completed successfully", "✓".green().bold());
     }
 
+    let destination = match &output {
+        Some(output) => output.display().to_string(),
+        None => format!("project {}", project_name),
+    };
+    let mut summary = format!(
+        "{} {} file(s) ({}) into {}",
+        if dry_run { "Would pull" } else { "Pulled" },
+        files_to_sync.len(),
+        format_size(bytes_synced),
+        destination
+    );
+    if kept_count > 0 {
+        summary.push_str(&format!(", {} skipped (local changes kept)", kept_count));
+    }
+    if !local_only_files.is_empty() {
+        summary.push_str(&format!(", {} missing in shade", local_only_files.len()));
+    }
+    outln!("  {}", summary);
+
+    print_local_only_note(&local_only_files);
+    print_unpushed_note(&unpushed);
+
+    Ok(Pulled {
+        synced: files_to_sync.len(),
+        needs_push,
+    })
+}
+
+/// Give content-identical local/shade pairs the same mtime
+///
+/// With `normalize_mtime` the local file takes the shade file's (commit)
+/// time, as push does. Otherwise the shade file takes the local time: shade
+/// mtimes only matter to git-shade, the user's files are left alone.
+/// Returns how many pairs were changed.
+fn align_mtimes(
+    files: &[PathBuf],
+    layout: &ShadeLayout,
+    project_path: &Path,
+    manifest: &Manifest,
+    hashes: &mut HashCache,
+    normalize_mtime: bool,
+) -> Result<usize> {
+    let mut aligned = 0;
+    for file in files {
+        let local = manifest.local_path(project_path, file);
+        let shade = layout.file(file);
+        if !local.is_file() || !shade.is_file() {
+            continue;
+        }
+
+        let local_modified = FileTime::from_last_modification_time(&std::fs::metadata(&local)?);
+        let shade_modified = FileTime::from_last_modification_time(&std::fs::metadata(&shade)?);
+        if local_modified == shade_modified || !hashes.same_contents(&local, &shade)? {
+            continue;
+        }
+
+        if normalize_mtime {
+            set_mtime(&local, shade_modified)?;
+        } else {
+            // A hardlinked file shares its mtime with the other links
+            unshare_file(&shade)?;
+            set_mtime(&shade, local_modified)?;
+        }
+        aligned += 1;
+    }
+
+    Ok(aligned)
+}
+
+/// Sync state of a file against its unmerged upstream version
+///
+/// The upstream commit is newer than the last pull by definition, so the file
+/// is remote ahead, or a conflict if it also changed locally since then.
+fn upstream_state(
+    local_path: &Path,
+    local_meta: Option<&FileMetadata>,
+    upstream_file: &UpstreamFile,
+    last_pull: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<SyncState> {
+    let Some(local_meta) = local_meta else {
+        return Ok(SyncState::RemoteOnly);
+    };
+
+    if std::fs::read(local_path)? == upstream_file.contents {
+        return Ok(SyncState::InSync);
+    }
+
+    Ok(match last_pull {
+        Some(last_pull) if local_meta.modified <= last_pull => SyncState::RemoteAhead,
+        _ => SyncState::Conflict,
+    })
+}
+
+/// Each content conflict as (file, local path, local and shade versions merged
+/// with markers), or `None` if one of them can't take markers
+///
+/// All or nothing, so a pull that stops on a conflict still touches nothing.
+fn merge_conflicts(
+    conflicts: &[ConflictInfo],
+    manifest: &Manifest,
+    project_path: &Path,
+    layout: &ShadeLayout,
+) -> Result<Option<Vec<(PathBuf, PathBuf, String)>>> {
+    let mut merged = Vec::new();
+    for conflict in conflicts {
+        if conflict.kind != ConflictKind::ContentConflict {
+            outln!(
+                "  {} {} was deleted in shade, no merge markers possible",
+                "⚠".yellow(),
+                conflict.file.display()
+            );
+            return Ok(None);
+        }
+
+        let local = manifest.local_path(project_path, &conflict.file);
+        let local_contents = std::fs::read(&local)?;
+        let shade_contents = std::fs::read(layout.file(&conflict.file))?;
+        if is_binary(&local_contents) || is_binary(&shade_contents) {
+            outln!(
+                "  {} {} is binary, no merge markers possible",
+                "⚠".yellow(),
+                conflict.file.display()
+            );
+            return Ok(None);
+        }
+
+        let contents = merge_with_markers(
+            &String::from_utf8_lossy(&local_contents),
+            &String::from_utf8_lossy(&shade_contents),
+        );
+        merged.push((conflict.file.clone(), local, contents));
+    }
+    Ok(Some(merged))
+}
+
+/// Delete local copies of synced files that are gone from shade (`--prune`)
+///
+/// Only files still as they were last synced are deleted (after a backup):
+/// one edited since would lose that edit, so it's kept and reported instead.
+fn prune_local_files(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    manifest: &mut Manifest,
+    gone: Vec<PathBuf>,
+    options: &PullOptions,
+) -> Result<()> {
+    // Files edited since aren't here: they're delete/edit conflicts
+    let mut to_delete = Vec::new();
+    for file in gone {
+        let local = manifest.local_path(project_path, &file);
+        if local.is_file() {
+            to_delete.push((file, local));
+        } else if !options.dry_run {
+            // Deleted here too, only the record is left
+            manifest.forget(&file);
+        }
+    }
+
+    if to_delete.is_empty() {
+        return Ok(());
+    }
+
+    outln!("Removed from shade since the last sync:");
+    for (file, _) in &to_delete {
+        outln!("  {} {}", "-".red(), file.display());
+    }
+    outln!();
+
+    if options.dry_run {
+        outln!("Would delete {} local file(s)", to_delete.len());
+        outln!();
+        return Ok(());
+    }
+
+    let confirmed = if options.yes {
+        true
+    } else if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::ConfirmationRequired(format!(
+            "Deleting {} local file(s) removed from shade",
+            to_delete.len()
+        )));
+    } else {
+        Confirm::new()
+            .with_prompt(format!("Delete {} local file(s)?", to_delete.len()))
+            .default(false)
+            .interact()
+            .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?
+    };
+    if !confirmed {
+        outln!("{} Kept local files", "→".blue());
+        outln!();
+        return Ok(());
+    }
+
+    let backup_dir = remove_local_files(paths, project_path, project_name, manifest, &to_delete)?;
+    outln!(
+        "  {} Deleted {} file(s) (backed up to {})",
+        "✓".green(),
+        to_delete.len(),
+        backup_dir.display()
+    );
+    outln!();
+
     Ok(())
 }
 
-fn list_all_files(dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>> {
-    let mut files = Vec::new();
+/// Back up and delete `(shade path, local path)` files that are gone from shade
+///
+/// Returns the backup dir. They're dropped from the manifest, and a file
+/// tracked on its own leaves a stale exclude line, which is removed too
+/// (directories stay).
+fn remove_local_files(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    manifest: &mut Manifest,
+    files: &[(PathBuf, PathBuf)],
+) -> Result<PathBuf> {
+    let backup_dir = backup_local_files(paths, project_name, files)?;
+    let patterns = read_patterns(project_path)?;
+    for (file, local) in files {
+        std::fs::remove_file(local)?;
+        manifest.forget(file);
 
-    if !dir.exists() {
-        return Ok(files);
+        let pattern = Pattern::File(file.clone());
+        if patterns.contains(&pattern) {
+            remove_from_exclude(project_path, &pattern)?;
+        }
     }
 
-    for entry in WalkDir::new(dir).min_depth(1) {
-        let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read directory: {}", e))?;
+    Ok(backup_dir)
+}
+
+/// Copy `(shade path, local path)` files into `backups/<project>/<timestamp>/`
+///
+/// Files keep their shade-relative path, so the snapshot mirrors the shade dir.
+fn backup_local_files(
+    paths: &ShadePaths,
+    project_name: &str,
+    files: &[(PathBuf, PathBuf)],
+) -> Result<PathBuf> {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let backup_dir = paths.project_backups_dir(project_name).join(timestamp);
+
+    for (file, local) in files {
+        copy_file_to(local, &backup_dir.join(file))?;
+    }
+
+    Ok(backup_dir)
+}
+
+/// Whether `--only`/`--except` let `file` through (see `is_excluded` for the glob rules)
+fn is_selected(file: &Path, only: &[String], except: &[String]) -> bool {
+    (only.is_empty() || is_excluded(file, only)) && !is_excluded(file, except)
+}
+
+fn list_local_only(
+    patterns: &[Pattern],
+    manifest: &Manifest,
+    project_dir: &std::path::Path,
+    layout: &ShadeLayout,
+) -> Result<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
 
-        if entry.file_type().is_file() {
-            if let Ok(rel) = entry.path().strip_prefix(dir) {
-                files.push(rel.to_path_buf());
+    for pattern in patterns {
+        for file in pattern.files_in(project_dir)? {
+            if !layout.file(&file).exists()
+                && !manifest.is_excluded(&file)
+                && !files.contains(&file)
+            {
+                files.push(file);
             }
         }
     }
@@ -240,6 +1118,88 @@ fn list_all_files(dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>> {
     Ok(files)
 }
 
+fn print_local_only_note(files: &[std::path::PathBuf]) {
+    if files.is_empty() {
+        return;
+    }
+
+    outln!();
+    outln!(
+        "{} {} file(s) exist only locally - run {} to sync them:",
+        "?".bright_black(),
+        files.len(),
+        "git-shade push".bold()
+    );
+    for file in files {
+        outln!("  - {}", file.display());
+    }
+}
+
+/// Paths in the project's shade dir that the shade remote doesn't have yet
+fn list_unpushed(
+    paths: &ShadePaths,
+    project_name: &str,
+    layout: &ShadeLayout,
+) -> Result<Vec<PathBuf>> {
+    let prefix = format!("{}/", project_name);
+    let mut files: Vec<PathBuf> = unpushed_files(&paths.projects, &prefix)?
+        .iter()
+        .filter_map(|file| file.strip_prefix(&prefix))
+        .filter_map(|file| layout.logical(Path::new(file)))
+        .filter(|file| !is_shade_metadata(file))
+        .collect();
+    files.dedup();
+    Ok(files)
+}
+
+fn print_unpushed_note(files: &[PathBuf]) {
+    if files.is_empty() {
+        return;
+    }
+
+    outln!();
+    outln!(
+        "{} {} file(s) changed on this machine aren't on the shade remote - run {} so other machines get them:",
+        "↑".yellow(),
+        files.len(),
+        "git-shade push".bold()
+    );
+    for file in files {
+        outln!("  - {}", file.display());
+    }
+}
+
+/// Recreate directories that were pushed empty (marked with `.shade-keep`)
+fn restore_empty_dirs(layout: &ShadeLayout, project_path: &Path) -> Result<()> {
+    let marked = list_marked_dirs(layout.shared_dir())?;
+    for dir in marked.iter().filter_map(|dir| layout.logical(dir)) {
+        let local_dir = project_path.join(&dir);
+        if !local_dir.exists() {
+            std::fs::create_dir_all(&local_dir)?;
+            outln!("  {} {}/ (empty dir created)", "✓".green(), dir.display());
+        }
+    }
+    Ok(())
+}
+
+/// `git pull` arguments for `strategy`
+///
+/// Unset, git's own `pull.rebase`/`pull.ff` settings decide; without those
+/// it merges, since newer git refuses divergent pulls otherwise.
+fn pull_args(repo: &Path, strategy: Option<PullStrategy>) -> Result<Vec<&'static str>> {
+    let flag = match strategy {
+        Some(strategy) => Some(strategy.git_flag()),
+        None if config_value(repo, "pull.rebase")?.is_some()
+            || config_value(repo, "pull.ff")?.is_some() =>
+        {
+            None
+        }
+        None => Some(PullStrategy::Merge.git_flag()),
+    };
+
+    Ok(std::iter::once("pull").chain(flag).collect())
+}
+
 fn list_updated_projects(projects_dir: &std::path::Path) -> Result<Vec<String>> {
     let mut updated = Vec::new();
 
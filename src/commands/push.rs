@@ -1,107 +1,494 @@
-use crate::core::{Config, ShadePaths, Tracker};
+use crate::commands::status::sync_state;
+use crate::core::{
+    find_empty_dirs, has_markers, is_keep_file, is_sync_file, mark_empty_dirs,
+    render_commit_message, Config, FileMetadata, FileModes, HashCache, Manifest, PendingPush,
+    PushState, ShadeLayout, ShadePaths, SyncState, Tracker, MODES_FILE, SYNC_FILE,
+};
 use crate::error::{Result, ShadeError};
-use crate::git::read_exclude;
+use crate::git::{
+    classify_push_failure, config_bool, is_signing_failure, last_commit_mtime,
+    operation_in_progress, parse_push_transfer, read_patterns, run_git, upstream_counts,
+    PushFailure, MACHINE_TRAILER, PROJECT_TRAILER,
+};
+use crate::utils::output::outln;
 use crate::utils::{
-    copy_dir_preserve_structure, copy_file_preserve_structure, detect_project_name, verify_git_repo,
+    format_size, resolve_project, run_hook, set_mtime, tracker_path, unshare_file, CopyProgress,
+    ShadeStore,
 };
 use colored::Colorize;
-use std::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Flags for `git-shade push`
+#[derive(Debug, Clone, Default)]
+pub struct PushOptions {
+    pub message: Option<String>,
+    pub verbose: bool,
+    pub amend: bool,
+    /// Replace remote commits we've fetched, but not ones we haven't seen
+    pub force_with_lease: bool,
+    /// Print a diffstat of the commit just made
+    pub stat: bool,
+    /// Don't sign the commit, whatever `sign_commits` or `commit.gpgsign` say
+    pub no_sign: bool,
+    /// Only copy files changed here since the last sync, skipping the rest
+    pub only_changed: bool,
+    /// Label for this push instead of `machine_name` or the hostname
+    pub machine: Option<String>,
+    /// After pushing, tag the shade repo as `<project>/<tag>` and push the tag
+    pub tag: Option<String>,
+}
 
-pub fn run(message: Option<String>) -> Result<()> {
-    // 1. Verify it's a git repo
-    let project_path = verify_git_repo()?;
+pub fn run(options: PushOptions, project: Option<String>, name: Option<String>) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
 
-    // 2. Detect project name
-    let project_name = detect_project_name(None)?;
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
 
-    // 3. Setup paths
-    let paths = ShadePaths::new()?;
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+
+    push_project(&paths, &project_path, &project_name, &options)?;
+    Ok(())
+}
+
+/// `<project>/<name>`, the tag `push --tag` makes, if it's valid and free
+/// both here and on the remote
+///
+/// Namespaced by project, since every project shares the shade repo.
+fn check_tag(repo: &Path, project_name: &str, name: &str) -> Result<String> {
+    let tag = format!("{}/{}", project_name, name.trim());
+    let ref_name = format!("refs/tags/{}", tag);
+
+    let valid = run_git(repo, &["check-ref-format", &ref_name])?
+        .status
+        .success();
+    if name.trim().is_empty() || !valid {
+        return Err(anyhow::anyhow!("'{}' isn't a valid tag name", name).into());
+    }
 
-    // 4. Verify project is initialized
+    let exists = run_git(repo, &["rev-parse", "--quiet", "--verify", &ref_name])?
+        .status
+        .success();
+    if exists {
+        return Err(anyhow::anyhow!(
+            "Tag {} already exists in the shade repo; pick another name",
+            tag
+        )
+        .into());
+    }
+
+    // Another machine may have pushed it; an unreachable remote fails the push later anyway
+    if let Some(remote) = tag_remote(repo)? {
+        let output = run_git(repo, &["ls-remote", "--tags", &remote, &ref_name])?;
+        if output.status.success() && !output.stdout.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Tag {} already exists on {}; pick another name",
+                tag,
+                remote
+            )
+            .into());
+        }
+    }
+
+    Ok(tag)
+}
+
+/// Remote tags go to: `origin`, else the first one
+fn tag_remote(repo: &Path) -> Result<Option<String>> {
+    let remotes = git_stdout(repo, &["remote"])?;
+    Ok(remotes
+        .lines()
+        .find(|remote| *remote == "origin")
+        .or_else(|| remotes.lines().next())
+        .map(str::to_string))
+}
+
+/// Make the annotated tag on the shade repo's HEAD and push it
+fn create_tag(repo: &Path, tag: &str, project_name: &str, machine: &str) -> Result<()> {
+    let message = format!("[{}] Snapshot from {}", project_name, machine);
+    let output = run_git(repo, &["tag", "-a", tag, "-m", &message])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShadeError::GitError(format!("git tag failed: {}", stderr)));
+    }
+
+    let Some(remote) = tag_remote(repo)? else {
+        outln!("{} Tagged {} (no remote to push it to)", "✓".green(), tag);
+        return Ok(());
+    };
+
+    let output = run_git(repo, &["push", &remote, &format!("refs/tags/{}", tag)])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match classify_push_failure(&stderr) {
+            PushFailure::Remote => ShadeError::RemoteUnreachable(stderr.trim().to_string()),
+            _ => ShadeError::GitError(format!("git push of tag {} failed: {}", tag, stderr)),
+        });
+    }
+    outln!("{} Tagged {} and pushed it to {}", "✓".green(), tag, remote);
+
+    Ok(())
+}
+
+/// Copy a resolved project's tracked files to shade, commit and push, then
+/// tag it if `options.tag` asks for it
+///
+/// Runs the `pre_push`/`post_push` hooks around it. Returns whether a commit
+/// was made.
+pub fn push_project(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    options: &PushOptions,
+) -> Result<bool> {
     let config = Config::load(&paths.config)?;
-    if config.find_project(&project_name).is_none() {
-        return Err(ShadeError::NotInitialized { project_name });
+    let hooks = &config.hooks;
+    let project_shade_dir = paths.project_shade_dir(project_name);
+    let _span = tracing::info_span!("push", project = project_name).entered();
+
+    // A commit on top of an unfinished merge or rebase fails, or worse, concludes it
+    if let Some(operation) = operation_in_progress(&paths.projects)? {
+        return Err(ShadeError::ShadeRepoBusy {
+            operation: operation.command().to_string(),
+        });
     }
 
-    let project_shade_dir = paths.project_shade_dir(&project_name);
+    // Refuse a bad or taken tag before anything is copied
+    let tag = match &options.tag {
+        Some(tag) => Some(check_tag(&paths.projects, project_name, tag)?),
+        None => None,
+    };
 
-    // 5. Get tracked files from .git/info/exclude
-    let patterns = read_exclude(&project_path)?;
+    // A commit from a push that failed earlier goes out first, as it was made
+    retry_pending_push(paths, project_name)?;
+
+    run_hook(
+        "pre_push",
+        hooks.pre_push.as_deref(),
+        project_name,
+        project_path,
+        &project_shade_dir,
+    )?;
+
+    let committed = copy_and_commit(paths, &config, project_path, project_name, options)?;
+
+    if let Some(tag) = tag {
+        let machine = options
+            .machine
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| config.machine_name());
+        create_tag(&paths.projects, &tag, project_name, &machine)?;
+    }
 
-    if patterns.is_empty() {
+    run_hook(
+        "post_push",
+        hooks.post_push.as_deref(),
+        project_name,
+        project_path,
+        &project_shade_dir,
+    )?;
+
+    Ok(committed)
+}
+
+fn copy_and_commit(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+    options: &PushOptions,
+) -> Result<bool> {
+    let PushOptions {
+        message,
+        verbose,
+        amend,
+        force_with_lease,
+        stat,
+        no_sign,
+        only_changed,
+        machine,
+        tag: _,
+    } = options.clone();
+
+    let project_shade_dir = paths.project_shade_dir(project_name);
+    // Files with a variant for this OS go to its subdirectory, the rest stay shared
+    let mut layout = ShadeLayout::new(paths, config, project_name);
+    let per_os = config
+        .find_project(project_name)
+        .is_some_and(|project| project.per_os);
+    if per_os && layout.enable()? {
+        outln!(
+            "{} Recorded the per-OS layout in the shade repo",
+            "→".blue()
+        );
+    }
+
+    // 5. Get tracked files from .git/info/exclude (and any from outside the project)
+    let patterns = read_patterns(project_path)?;
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    let externals = manifest.externals();
+
+    if patterns.is_empty() && externals.is_empty() {
         return Err(ShadeError::NoFilesTracked);
     }
+    let (paused_externals, externals): (Vec<_>, Vec<_>) = externals
+        .into_iter()
+        .partition(|(rel, _)| manifest.is_paused(rel));
 
     // 6. Copy files from local to shade
-    println!("Copying files to shade...");
+    outln!("Copying files to shade...");
     let mut copied_count = 0;
 
-    for pattern in &patterns {
-        // Remove trailing slash if it's a directory pattern
-        let clean_pattern = pattern.trim_end_matches('/');
-        let file_path = project_path.join(clean_pattern);
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+    let last_pull = if only_changed {
+        Tracker::load(&tracker_path(paths, config, project_name)?)
+            .ok()
+            .and_then(|tracker| tracker.last_pull)
+    } else {
+        None
+    };
 
-        if !file_path.exists() {
-            println!("  {} {} (not found, skipped)", "⚠".yellow(), clean_pattern);
+    let mut expanded = Vec::new();
+    let mut paused_count = 0;
+    for pattern in &patterns {
+        let mut files = pattern.files_in(project_path)?;
+        files.retain(|file| !manifest.is_excluded(file) && !config.is_junk(file));
+        let before = files.len();
+        files.retain(|file| !manifest.is_paused(file));
+        paused_count += before - files.len();
+        expanded.push((pattern, files));
+    }
+    let total_files: usize =
+        expanded.iter().map(|(_, files)| files.len()).sum::<usize>() + externals.len();
+
+    let progress = CopyProgress::new(total_files, verbose);
+    let mut copying = Copying {
+        config,
+        layout: &layout,
+        manifest: &mut manifest,
+        hashes: &mut hashes,
+        store: ShadeStore::new(config.storage, &paths.projects)?,
+        modes: FileModes::load(&project_shade_dir)?,
+        progress: &progress,
+        only_changed,
+        last_pull,
+        files_copied: 0,
+        bytes_copied: 0,
+        unchanged_count: 0,
+        pushed: Vec::new(),
+        conflicted: Vec::new(),
+        unresolved: Vec::new(),
+    };
+    let mut missing_count = 0;
+
+    for (pattern, files) in &expanded {
+        if !project_path.join(pattern.path()).exists() {
+            missing_count += 1;
+            progress.println(&format!(
+                "  {} {} (not found, skipped)",
+                "⚠".yellow(),
+                pattern
+            ));
             continue;
         }
 
-        if file_path.is_dir() {
-            copy_dir_preserve_structure(&file_path, &project_path, &project_shade_dir)?;
-        } else {
-            copy_file_preserve_structure(&file_path, &project_path, &project_shade_dir)?;
+        for file in files {
+            copying.push_file(file, &project_path.join(file))?;
         }
 
-        println!("  {} {}", "✓".green(), clean_pattern);
+        progress.verbose(&format!("  {} {}", "✓".green(), pattern));
         copied_count += 1;
     }
 
+    for (rel, source) in &externals {
+        if !source.is_file() {
+            missing_count += 1;
+            progress.println(&format!(
+                "  {} {} (not found at {}, skipped)",
+                "⚠".yellow(),
+                rel.display(),
+                source.display()
+            ));
+            continue;
+        }
+
+        if copying.push_file(rel, source)? {
+            progress.verbose(&format!(
+                "  {} {} (from {})",
+                "✓".green(),
+                rel.display(),
+                source.display()
+            ));
+            copied_count += 1;
+        }
+    }
+
+    let Copying {
+        modes,
+        files_copied,
+        bytes_copied,
+        unchanged_count,
+        pushed,
+        conflicted,
+        unresolved,
+        ..
+    } = copying;
+
+    // Git drops empty directories, so mark them for pull to recreate
+    if config.preserve_empty_dirs {
+        for pattern in patterns.iter().filter(|pattern| pattern.is_dir()) {
+            let empty_dirs = find_empty_dirs(project_path, pattern.path())?;
+            mark_empty_dirs(&empty_dirs, &project_shade_dir)?;
+            for dir in &empty_dirs {
+                progress.verbose(&format!("  {} {}/ (empty dir)", "✓".green(), dir.display()));
+            }
+        }
+    }
+
+    progress.finish();
+    if !conflicted.is_empty() {
+        outln!(
+            "  {} {} file(s) in conflict skipped (settle with {}):",
+            "⚠".yellow(),
+            conflicted.len(),
+            "git-shade pull --strategy <local|remote>".bold()
+        );
+        for file in &conflicted {
+            outln!("    - {}", file.display());
+        }
+    }
+    if !unresolved.is_empty() {
+        outln!(
+            "  {} {} file(s) with unresolved merge markers skipped (resolve them first):",
+            "⚠".yellow(),
+            unresolved.len()
+        );
+        for file in &unresolved {
+            outln!("    - {}", file.display());
+        }
+    }
+    paused_count += paused_externals.len();
+    if paused_count > 0 {
+        outln!("  {} {} paused file(s) skipped", "→".blue(), paused_count);
+    }
+    if unchanged_count > 0 {
+        outln!(
+            "  {} {} unchanged file(s) skipped",
+            "→".blue(),
+            unchanged_count
+        );
+    }
+    if files_copied > 0 {
+        modes.save(&project_shade_dir)?;
+        manifest.save(&manifest_path)?;
+    }
+    if files_copied > 0 || unchanged_count > 0 {
+        hashes.save(&hash_cache_path)?;
+    }
+    if !verbose && copied_count > 0 {
+        outln!("  {} {} file(s) copied", "✓".green(), files_copied);
+    }
+
     if copied_count == 0 {
-        println!("  No files copied (all tracked files are missing)");
-        return Ok(());
+        outln!("  No files copied (all tracked files are missing)");
+        return Ok(false);
     }
 
-    println!();
+    outln!();
 
     // 7. Git operations
-    println!("Git operations in {}...", paths.projects.display());
+    outln!("Git operations in {}...", paths.projects.display());
+
+    // Label for the commit message: --machine, machine_name, or the hostname
+    let machine = machine
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| config.machine_name());
+
+    // Git add (only this project's directory). With metadata_in_repo this
+    // machine's tracker is committed too, but only along with real changes,
+    // so a pull on its own never makes a commit
+    let project_dir = format!("{}/", project_name);
+    let sync_spec = config
+        .metadata_in_repo
+        .then(|| format!(":(exclude){}{}*", project_dir, SYNC_FILE));
+    let mut add_args = vec!["add", project_dir.as_str()];
+    add_args.extend(sync_spec.as_deref());
+    let add_output = run_git(&paths.projects, &add_args)?;
 
-    // Change to shade projects directory
-    std::env::set_current_dir(&paths.projects)?;
+    if !add_output.status.success() {
+        let stderr = String::from_utf8_lossy(&add_output.stderr);
+        return Err(ShadeError::GitError(format!("git add failed: {}", stderr)));
+    }
 
-    // Get hostname for commit message
-    let hostname = hostname::get()
-        .ok()
-        .and_then(|h| h.into_string().ok())
-        .unwrap_or_else(|| "unknown".to_string());
+    let mut tracker_staged = false;
+    if config.metadata_in_repo && staged_file_count(&paths.projects, project_name)? > 0 {
+        let tracker_file = tracker_path(paths, config, project_name)?;
+        let mut tracker = Tracker::load(&tracker_file).unwrap_or_else(|_| Tracker::new());
+        tracker.update_push();
+        tracker.save(&tracker_file)?;
+        run_git(
+            &paths.projects,
+            &["add", "--", &tracker_file.to_string_lossy()],
+        )?;
+        tracker_staged = true;
+    }
+
+    outln!("  {} Added: {}/", "✓".green(), project_name);
 
     // Build commit message
     let commit_msg = if let Some(msg) = message {
         format!("[{}] {}", project_name, msg)
     } else {
-        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
-        format!(
-            "[{}] Update from {} - {}",
-            project_name, hostname, timestamp
+        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let count = staged_file_count(&paths.projects, project_name)?;
+        render_commit_message(
+            &config.commit_template(),
+            project_name,
+            &machine,
+            &timestamp,
+            count,
         )
     };
 
-    // Git add (only this project's directory)
-    let add_output = Command::new("git")
-        .args(["add", &format!("{}/", project_name)])
-        .output()?;
+    // Git commit (or fold into this project's previous commit). With nothing
+    // staged a plain commit reports "nothing to commit", which is what we want.
+    let amend = amend
+        && has_staged_changes(&paths.projects, project_name)?
+        && should_amend(&paths.projects, project_name)?;
+
+    // The machine and project also go into trailers, so history and --amend
+    // find them whatever the template
+    let trailer = format!(
+        "{}: {}\n{}: {}",
+        MACHINE_TRAILER, machine, PROJECT_TRAILER, project_name
+    );
+    let mut commit_args = vec!["commit", "-m", commit_msg.as_str(), "-m", trailer.as_str()];
+    if amend {
+        commit_args.push("--amend");
+    }
 
-    if !add_output.status.success() {
-        let stderr = String::from_utf8_lossy(&add_output.stderr);
-        return Err(ShadeError::GitError(format!("git add failed: {}", stderr)));
+    // The shade repo's own commit.gpgsign (or the global one) applies anyway;
+    // sign too when the config or the project asks for it
+    let sign = config.sign_commits || config_bool(project_path, "commit.gpgsign")?;
+    if no_sign {
+        commit_args.push("--no-gpg-sign");
+    } else if sign {
+        commit_args.push("-S");
     }
 
-    println!("  {} Added: {}/", "✓".green(), project_name);
+    // Only this project's directory: other projects may have files staged by `add --stage`
+    commit_args.extend(["--", project_dir.as_str()]);
+    if !tracker_staged {
+        commit_args.extend(sync_spec.as_deref());
+    }
 
-    // Git commit
-    let commit_output = Command::new("git")
-        .args(["commit", "-m", &commit_msg])
-        .output()?;
+    tracing::info!(amend, sign, message = %commit_msg, "committing");
+    let commit_output = run_git(&paths.projects, &commit_args)?;
 
     let has_changes = if !commit_output.status.success() {
         let stderr = String::from_utf8_lossy(&commit_output.stderr);
@@ -111,10 +498,13 @@ pub fn run(message: Option<String>) -> Result<()> {
         if stderr.contains("nothing to commit")
             || stderr.contains("no changes added")
             || stdout.contains("nothing to commit")
+            || stdout.contains("no changes added")
             || stderr.contains("nothing added to commit")
         {
-            println!("  {} No changes to commit", "→".blue());
+            outln!("  {} No changes to commit", "→".blue());
             false // No changes, but not an error
+        } else if is_signing_failure(&stderr) {
+            return Err(ShadeError::SigningFailed(stderr.trim().to_string()));
         } else {
             return Err(ShadeError::GitError(format!(
                 "git commit failed: {}",
@@ -122,52 +512,439 @@ pub fn run(message: Option<String>) -> Result<()> {
             )));
         }
     } else {
-        println!("  {} Committed: {}", "✓".green(), commit_msg);
+        if amend {
+            outln!("  {} Amended: {}", "✓".green(), commit_msg);
+        } else {
+            outln!("  {} Committed: {}", "✓".green(), commit_msg);
+        }
         true // Successful commit
     };
 
     // Check if remote exists
-    let remote_output = Command::new("git").args(["remote", "-v"]).output()?;
+    let remote_output = run_git(&paths.projects, &["remote", "-v"])?;
 
     let has_remote = !remote_output.stdout.is_empty();
 
+    // Recorded before pushing, so a push that fails or is rejected is retried
+    // as is next time (see `retry_pending_push`)
+    let state_file = paths.push_state_file();
+    let mut push_state = PushState::load(&state_file)?;
+    if has_changes && has_remote {
+        let head = git_stdout(&paths.projects, &["rev-parse", "HEAD"])?;
+        push_state.mark_pending(project_name, &head, amend || force_with_lease);
+        push_state.save(&state_file)?;
+    }
+
     // Only push if there were actual changes
     if has_changes {
         if has_remote {
-            // Git push
-            let push_output = Command::new("git").args(["push"]).output()?;
-
-            if !push_output.status.success() {
-                let stderr = String::from_utf8_lossy(&push_output.stderr);
-                return Err(ShadeError::GitError(format!("git push failed: {}", stderr)));
-            }
-
-            println!("  {} Pushed to origin/main", "✓".green());
+            // An amended commit rewrites history, so force safely
+            push_to_remote(&paths.projects, amend || force_with_lease)?;
+            push_state.clear_all();
+            push_state.save(&state_file)?;
         } else {
-            println!();
-            println!(
+            outln!();
+            outln!(
                 "{} No remote configured. Changes saved locally only.",
                 "⚠".yellow()
             );
-            println!("  To sync across machines, add a remote:");
-            println!("    cd {}", paths.projects.display());
-            println!("    git remote add origin <url>");
+            outln!("  To sync across machines, add a remote:");
+            outln!("    cd {}", paths.projects.display());
+            outln!("    git remote add origin <url>");
         }
     } else {
-        println!();
-        println!("{} Nothing to push - all files are up to date", "→".blue());
+        outln!();
+        outln!("{} Nothing to push - all files are up to date", "→".blue());
+    }
+
+    if stat && has_changes {
+        print_commit_stat(&paths.projects, project_name)?;
+    }
+
+    // Git doesn't keep mtimes, so stamp both copies with the commit time
+    // that every other machine will see too
+    if config.normalize_mtime {
+        for (file, local) in &pushed {
+            let shade_file = layout.repo_path(project_name, file);
+            if let Some(modified) = last_commit_mtime(&paths.projects, &shade_file)? {
+                // A hardlinked file shares its mtime with the other links
+                let stored = layout.file(file);
+                unshare_file(&stored)?;
+                set_mtime(&stored, modified)?;
+                set_mtime(local, modified)?;
+            }
+        }
+        outln!("  {} Set mtimes to commit time", "✓".green());
     }
 
-    println!();
+    outln!();
 
-    // 8. Update tracker
-    let mut tracker =
-        Tracker::load(&paths.shade_sync_file(&project_name)).unwrap_or_else(|_| Tracker::new());
-    tracker.update_push();
-    tracker.save(&paths.shade_sync_file(&project_name))?;
+    // 8. Update tracker (already done if it went into the commit)
+    if !tracker_staged {
+        let tracker_file = tracker_path(paths, config, project_name)?;
+        let mut tracker = Tracker::load(&tracker_file).unwrap_or_else(|_| Tracker::new());
+        tracker.update_push();
+        tracker.save(&tracker_file)?;
+    }
 
     let timestamp = chrono::Utc::now().to_rfc3339();
-    println!("Updated last_push: {}", timestamp);
+    outln!("Updated last_push: {}", timestamp);
+
+    if has_changes {
+        let mut summary = format!(
+            "Pushed {} file(s) ({}) in project {}",
+            files_copied,
+            format_size(bytes_copied),
+            project_name
+        );
+        if missing_count > 0 {
+            summary.push_str(&format!(", {} missing (skipped)", missing_count));
+        }
+        outln!();
+        outln!("{} {}", "✓".green().bold(), summary);
+    }
+
+    Ok(has_changes)
+}
+
+/// `git push` the shade repo, fetching full history once if a shallow clone is refused
+fn push_to_remote(repo: &Path, force: bool) -> Result<()> {
+    // --progress makes git report the pack size even without a terminal
+    let push_args: &[&str] = if force {
+        &["push", "--progress", "--force-with-lease"]
+    } else {
+        &["push", "--progress"]
+    };
+    let mut push_output = run_git(repo, push_args)?;
+
+    // A shade repo cloned with --depth can't always push; fetch the
+    // history it's missing and try once more
+    if !push_output.status.success()
+        && classify_push_failure(&String::from_utf8_lossy(&push_output.stderr))
+            == PushFailure::Shallow
+    {
+        outln!(
+            "  {} The remote refused a push from a shallow clone, fetching full history",
+            "→".blue()
+        );
+        let unshallow_output = run_git(repo, &["fetch", "--unshallow"])?;
+        if !unshallow_output.status.success() {
+            let stderr = String::from_utf8_lossy(&unshallow_output.stderr);
+            return Err(ShadeError::GitError(format!(
+                "git fetch --unshallow failed: {}",
+                stderr
+            )));
+        }
+        push_output = run_git(repo, push_args)?;
+    }
+
+    if !push_output.status.success() {
+        let stderr = String::from_utf8_lossy(&push_output.stderr);
+        return Err(match classify_push_failure(&stderr) {
+            PushFailure::Rejected => ShadeError::PushRejected,
+            PushFailure::Remote => ShadeError::RemoteUnreachable(stderr.trim().to_string()),
+            PushFailure::Shallow | PushFailure::Other => {
+                ShadeError::GitError(format!("git push failed: {}", stderr))
+            }
+        });
+    }
+
+    let stderr = String::from_utf8_lossy(&push_output.stderr);
+    match parse_push_transfer(&stderr) {
+        Some(transfer) => outln!(
+            "  {} Pushed to origin/main ({} object(s), {} as deltas, {} sent)",
+            "✓".green(),
+            transfer.objects,
+            transfer.deltas,
+            format_size(transfer.bytes)
+        ),
+        // Nothing written, or output git-shade can't read (e.g. localized)
+        None => outln!("  {} Pushed to origin/main", "✓".green()),
+    }
+
+    Ok(())
+}
+
+/// This project's commit from a push that didn't go through, if it still waits
+///
+/// It must be on the current branch and the remote (as of the last fetch)
+/// not have it yet; pushed by hand, or dropped by a rollback, it's stale.
+pub fn pending_push(paths: &ShadePaths, project_name: &str) -> Result<Option<PendingPush>> {
+    let state = PushState::load(&paths.push_state_file())?;
+    let Some(pending) = state.pending(project_name) else {
+        return Ok(None);
+    };
+
+    let on_branch = is_on_branch(&paths.projects, &pending.commit)?;
+    let pushed = matches!(upstream_counts(&paths.projects)?, Some((_, 0)));
+
+    Ok((on_branch && !pushed).then(|| pending.clone()))
+}
+
+/// Keep commits waiting to be pushed waiting after `git pull --rebase`
+///
+/// A waiting commit that's no longer on the branch while the branch is still
+/// ahead of the remote was replayed on top of the remote's commits, so HEAD
+/// carries it now.
+pub fn follow_rebased_pushes(paths: &ShadePaths) -> Result<()> {
+    let state_file = paths.push_state_file();
+    let mut state = PushState::load(&state_file)?;
+    let projects = state.projects();
+    if projects.is_empty()
+        || !matches!(upstream_counts(&paths.projects)?, Some((_, ahead)) if ahead > 0)
+    {
+        return Ok(());
+    }
+
+    let head = git_stdout(&paths.projects, &["rev-parse", "HEAD"])?;
+    let mut moved = false;
+    for project in projects {
+        let Some(pending) = state.pending(&project) else {
+            continue;
+        };
+        if !is_on_branch(&paths.projects, &pending.commit)? {
+            state.repoint(&project, &head);
+            moved = true;
+        }
+    }
+    if moved {
+        state.save(&state_file)?;
+    }
+    Ok(())
+}
 
+/// Whether `commit` is HEAD or one of its ancestors
+fn is_on_branch(repo: &Path, commit: &str) -> Result<bool> {
+    Ok(
+        run_git(repo, &["merge-base", "--is-ancestor", commit, "HEAD"])?
+            .status
+            .success(),
+    )
+}
+
+/// Push the commit an earlier push made but couldn't send, before copying anything
+///
+/// The only way a push that failed or was rejected goes out: the commit is
+/// recorded in `.push-state` before pushing. If it fails again, the error is
+/// returned and nothing else is done.
+fn retry_pending_push(paths: &ShadePaths, project_name: &str) -> Result<()> {
+    let state_file = paths.push_state_file();
+    let mut state = PushState::load(&state_file)?;
+
+    let Some(pending) = pending_push(paths, project_name)? else {
+        if state.pending(project_name).is_some() {
+            state.clear(project_name);
+            state.save(&state_file)?;
+        }
+        return Ok(());
+    };
+
+    outln!(
+        "Retrying push of {} (committed {}, not pushed yet)...",
+        &pending.commit[..7.min(pending.commit.len())],
+        pending.committed_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    push_to_remote(&paths.projects, pending.force)?;
+    state.clear_all();
+    state.save(&state_file)?;
+    outln!();
+
+    Ok(())
+}
+
+/// `git diff --stat` of the last commit, limited to this project's shade dir
+///
+/// Uses `git show` so the very first commit (which has no parent) works too.
+fn print_commit_stat(repo: &Path, project_name: &str) -> Result<()> {
+    let stat = git_stdout(
+        repo,
+        &[
+            "show",
+            "--stat",
+            "--format=",
+            "HEAD",
+            "--",
+            &format!("{}/", project_name),
+        ],
+    )?;
+    if stat.is_empty() {
+        return Ok(());
+    }
+
+    outln!();
+    for line in stat.lines() {
+        outln!("    {}", line.trim_start());
+    }
     Ok(())
 }
+
+/// Decide whether `push --amend` may rewrite the last shade commit
+///
+/// Only commits made for this project by the current git identity are amended.
+/// The project comes from the `Shade-Project` trailer, or for older commits
+/// the `[<project>]` subject prefix.
+fn should_amend(repo: &Path, project_name: &str) -> Result<bool> {
+    let format = format!("--format=%(trailers:key={},valueonly)", PROJECT_TRAILER);
+    let trailer = git_stdout(repo, &["log", "-1", &format])?;
+    let for_project = if trailer.is_empty() {
+        let subject = git_stdout(repo, &["log", "-1", "--format=%s"])?;
+        subject.starts_with(&format!("[{}]", project_name))
+    } else {
+        trailer.lines().any(|line| line.trim() == project_name)
+    };
+    if !for_project {
+        outln!(
+            "  {} Last commit is not for {}, creating a new commit instead",
+            "→".blue(),
+            project_name
+        );
+        return Ok(false);
+    }
+
+    // `git var` honors GIT_AUTHOR_* env vars as well as config
+    let author_email = git_stdout(repo, &["log", "-1", "--format=%ae"])?;
+    let ident = git_stdout(repo, &["var", "GIT_AUTHOR_IDENT"])?;
+    let my_email = ident
+        .split('<')
+        .nth(1)
+        .and_then(|rest| rest.split('>').next())
+        .unwrap_or("");
+    if author_email != my_email {
+        outln!(
+            "  {} Last commit was made by {}, creating a new commit instead",
+            "→".blue(),
+            author_email
+        );
+        return Ok(false);
+    }
+
+    let pushed_to = git_stdout(repo, &["branch", "-r", "--contains", "HEAD"])?;
+    if !pushed_to.is_empty() {
+        outln!(
+            "  {} Last commit was already pushed; machines that pulled it will see diverged history",
+            "⚠".yellow()
+        );
+    }
+
+    Ok(true)
+}
+
+/// What `copy_and_commit` carries from one tracked file to the next
+struct Copying<'a> {
+    config: &'a Config,
+    layout: &'a ShadeLayout,
+    manifest: &'a mut Manifest,
+    hashes: &'a mut HashCache,
+    store: ShadeStore,
+    modes: FileModes,
+    progress: &'a CopyProgress,
+    only_changed: bool,
+    last_pull: Option<chrono::DateTime<chrono::Utc>>,
+    files_copied: usize,
+    bytes_copied: u64,
+    unchanged_count: usize,
+    // (shade-relative, local) path of every file copied
+    pushed: Vec<(PathBuf, PathBuf)>,
+    // Kept in both versions by `add`: pushing would overwrite the shade one
+    conflicted: Vec<PathBuf>,
+    // Still holding markers from `pull --merge-markers`
+    unresolved: Vec<PathBuf>,
+}
+
+impl Copying<'_> {
+    /// Copy `src` to shade as `rel`, returning whether it was copied
+    ///
+    /// Files in conflict, with merge markers, or unchanged with
+    /// `--only-changed` are counted and left alone.
+    fn push_file(&mut self, rel: &Path, src: &Path) -> Result<bool> {
+        if self.manifest.is_conflicted(rel) {
+            self.conflicted.push(rel.to_path_buf());
+            self.progress.inc();
+            return Ok(false);
+        }
+        let stored = self.layout.file(rel);
+        if self.only_changed
+            && !changed_here(self.config, self.hashes, src, &stored, self.last_pull)?
+        {
+            self.unchanged_count += 1;
+            self.progress.inc();
+            return Ok(false);
+        }
+        if has_markers(&fs::read(src)?) {
+            self.unresolved.push(rel.to_path_buf());
+            self.progress.inc();
+            return Ok(false);
+        }
+
+        self.progress.start(&rel.display().to_string());
+        let chmod = self.manifest.chmod_for(rel);
+        self.store.store_file_with_mode(src, &stored, chmod)?;
+        let metadata = FileMetadata::from_path(src)?;
+        if let Some(mode) = chmod.or(metadata.mode) {
+            self.modes.set(rel, mode);
+        }
+        self.bytes_copied += metadata.size;
+        self.manifest.record(rel, src, self.hashes)?;
+        self.progress.inc();
+        self.files_copied += 1;
+        self.pushed.push((rel.to_path_buf(), src.to_path_buf()));
+        Ok(true)
+    }
+}
+
+/// Whether `push --only-changed` copies a file: it changed here since the
+/// last sync, or shade doesn't have it yet
+///
+/// Files only changed in shade are left alone as well as ones in sync.
+fn changed_here(
+    config: &Config,
+    hashes: &mut HashCache,
+    local: &Path,
+    shade: &Path,
+    last_pull: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<bool> {
+    let state = sync_state(config, hashes, local, shade, last_pull)?;
+    Ok(matches!(
+        state,
+        SyncState::LocalAhead | SyncState::LocalOnly | SyncState::Conflict
+    ))
+}
+
+fn has_staged_changes(repo: &Path, project_name: &str) -> Result<bool> {
+    let output = run_git(
+        repo,
+        &[
+            "diff",
+            "--cached",
+            "--quiet",
+            "--",
+            &format!("{}/", project_name),
+        ],
+    )?;
+    Ok(!output.status.success())
+}
+
+/// Files staged under the project's shade dir, not counting sidecar files
+fn staged_file_count(repo: &Path, project_name: &str) -> Result<usize> {
+    let staged = git_stdout(
+        repo,
+        &[
+            "diff",
+            "--cached",
+            "--name-only",
+            "--",
+            &format!("{}/", project_name),
+        ],
+    )?;
+    Ok(staged
+        .lines()
+        .map(Path::new)
+        .filter(|path| !path.ends_with(MODES_FILE) && !is_keep_file(path))
+        .filter(|path| !path.strip_prefix(project_name).is_ok_and(is_sync_file))
+        .count())
+}
+
+fn git_stdout(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = run_git(repo, args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
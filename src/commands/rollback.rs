@@ -0,0 +1,193 @@
+use crate::core::{Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::git::{config_bool, is_signing_failure, run_git};
+use crate::utils::output::outln;
+use crate::utils::resolve_project;
+use colored::Colorize;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+use std::path::Path;
+
+pub fn run(project: Option<String>, name: Option<String>, yes: bool) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+    let shade_rel = format!("{}/", project_name);
+
+    // 5. Find the last commit that touched this project
+    // (fails on a repo without commits, which has nothing to roll back either)
+    let log_output = run_git(
+        &paths.projects,
+        &["log", "-1", "--format=%H%x1f%s", "--", &shade_rel],
+    )?;
+    let last = String::from_utf8_lossy(&log_output.stdout)
+        .trim()
+        .to_string();
+    let Some((rev, subject)) = last.split_once('\x1f') else {
+        outln!(
+            "{} No commits for {} in the shade repo, nothing to roll back",
+            "→".blue(),
+            project_name
+        );
+        return Ok(());
+    };
+    let short = &rev[..7];
+
+    // Uncommitted shade changes would be overwritten by the restore
+    if !git_stdout(
+        &paths.projects,
+        &["status", "--porcelain", "--", &shade_rel],
+    )?
+    .is_empty()
+    {
+        return Err(ShadeError::GitError(format!(
+            "{} has uncommitted changes in the shade repo; push or discard them first",
+            shade_rel
+        )));
+    }
+
+    // The first commit has no parent: rolling it back removes the project's files
+    let parent = git_stdout(
+        &paths.projects,
+        &["rev-parse", "--verify", "--quiet", &format!("{}^", rev)],
+    )
+    .ok()
+    .filter(|parent| !parent.is_empty());
+
+    // 6. Show what will change and confirm
+    let changes = match &parent {
+        Some(parent) => git_stdout(
+            &paths.projects,
+            &["diff", "--name-status", rev, parent, "--", &shade_rel],
+        )?,
+        None => git_stdout(&paths.projects, &["ls-files", "--", &shade_rel])?
+            .lines()
+            .map(|file| format!("D\t{}", file))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+
+    outln!(
+        "Rolling back {} in {}:",
+        format!("{} {}", short, subject).bold(),
+        project_name.bold()
+    );
+    for line in changes.lines() {
+        let Some((status, file)) = line.split_once('\t') else {
+            continue;
+        };
+        let file = file.strip_prefix(&shade_rel).unwrap_or(file);
+        match status {
+            "A" => outln!("  {} {} (restored)", "+".green(), file),
+            "D" => outln!("  {} {} (removed)", "-".red(), file),
+            _ => outln!("  {} {} (reverted)", "~".yellow(), file),
+        }
+    }
+    outln!("Other projects in the shade repo are not touched.");
+    outln!();
+
+    let confirmed = if yes {
+        true
+    } else if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::ConfirmationRequired(format!(
+            "Rolling back {} in {}",
+            short, project_name
+        )));
+    } else {
+        Confirm::new()
+            .with_prompt("Roll back?")
+            .default(false)
+            .interact()
+            .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?
+    };
+
+    if !confirmed {
+        outln!("{} Nothing changed", "→".blue());
+        return Ok(());
+    }
+
+    // 7. Restore the project's directory (only) to before that commit
+    let restore_output = match &parent {
+        Some(parent) => run_git(
+            &paths.projects,
+            &[
+                "restore",
+                "--source",
+                parent,
+                "--staged",
+                "--worktree",
+                "--",
+                &shade_rel,
+            ],
+        )?,
+        None => run_git(&paths.projects, &["rm", "-r", "-q", "--", &shade_rel])?,
+    };
+    if !restore_output.status.success() {
+        let stderr = String::from_utf8_lossy(&restore_output.stderr);
+        return Err(ShadeError::GitError(format!("restore failed: {}", stderr)));
+    }
+
+    // 8. Commit just this project's paths, whatever else is staged
+    let commit_msg = format!("[{}] Roll back {} ({})", project_name, short, subject);
+    let mut commit_args = vec!["commit", "-m", commit_msg.as_str()];
+    if config.sign_commits || config_bool(&project_path, "commit.gpgsign")? {
+        commit_args.push("-S");
+    }
+    commit_args.extend(["--", &shade_rel]);
+    let commit_output = run_git(&paths.projects, &commit_args)?;
+    if !commit_output.status.success() {
+        let stderr = String::from_utf8_lossy(&commit_output.stderr);
+        if is_signing_failure(&stderr) {
+            return Err(ShadeError::SigningFailed(stderr.trim().to_string()));
+        }
+        return Err(ShadeError::GitError(format!(
+            "git commit failed: {}",
+            stderr
+        )));
+    }
+    outln!("  {} Committed: {}", "✓".green(), commit_msg);
+
+    // 9. Push, if there is somewhere to push to
+    let remote_output = run_git(&paths.projects, &["remote"])?;
+    if remote_output.stdout.is_empty() {
+        outln!(
+            "  {} No remote configured. Rollback saved locally only.",
+            "⚠".yellow()
+        );
+    } else {
+        let push_output = run_git(&paths.projects, &["push"])?;
+        if !push_output.status.success() {
+            let stderr = String::from_utf8_lossy(&push_output.stderr);
+            return Err(ShadeError::GitError(format!("git push failed: {}", stderr)));
+        }
+        outln!("  {} Pushed", "✓".green());
+    }
+
+    outln!();
+    outln!(
+        "{} Rolled back {} in shade. Update your local files with: {}",
+        "✓".green().bold(),
+        short,
+        "git-shade pull --strategy remote".bold()
+    );
+
+    Ok(())
+}
+
+fn git_stdout(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = run_git(repo, args)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ShadeError::GitError(format!(
+            "git {} failed: {}",
+            args[0],
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,221 @@
+use crate::core::{Config, HashCache, Manifest, ShadeLayout, ShadePaths};
+use crate::error::Result;
+use crate::git::read_patterns;
+use crate::utils::output::outln;
+use crate::utils::{copy_file_to, resolve_project, set_mtime};
+use colored::Colorize;
+use filetime::FileTime;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// What `git-shade stash` should do besides stashing
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum StashAction {
+    /// Put the most recent stash back over the project and drop it
+    Pop,
+}
+
+pub fn run(
+    action: Option<StashAction>,
+    list: bool,
+    project: Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+    let stash_dir = paths.project_stash_dir(&project_name);
+
+    match action {
+        _ if list => list_stashes(&stash_dir),
+        Some(StashAction::Pop) => pop(&paths, &project_path, &project_name, &stash_dir),
+        None => stash(&paths, &config, &project_path, &project_name, &stash_dir),
+    }
+}
+
+/// Copy locally changed files into a new stash and put the shade versions back
+fn stash(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+    stash_dir: &Path,
+) -> Result<()> {
+    let layout = ShadeLayout::new(paths, config, project_name);
+    let manifest_path = paths.manifest_file(project_name);
+    let mut manifest = Manifest::load(&manifest_path)?;
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+
+    // 5. Find tracked files whose contents differ from shade (files only
+    // here aren't touched by pull, so there's nothing to set aside)
+    let mut files = manifest.paths();
+    files.extend(manifest.externals().into_iter().map(|(rel, _)| rel));
+    for pattern in read_patterns(project_path)? {
+        files.extend(pattern.files_in(project_path)?);
+    }
+    // Paused files aren't pulled either
+    files.retain(|file| {
+        !manifest.is_excluded(file) && !config.is_junk(file) && !manifest.is_paused(file)
+    });
+    files.sort();
+    files.dedup();
+
+    let mut changed = Vec::new();
+    for file in files {
+        let local = manifest.local_path(project_path, &file);
+        let shade = layout.file(&file);
+        if local.is_file() && shade.is_file() && !hashes.same_contents(&local, &shade)? {
+            changed.push((file, local, shade));
+        }
+    }
+
+    if changed.is_empty() {
+        hashes.save(&hash_cache_path)?;
+        outln!("{} No local changes to stash", "→".blue());
+        return Ok(());
+    }
+
+    // 6. Copy them aside, then put the shade versions in their place
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let target = stash_dir.join(&timestamp);
+    if target.exists() {
+        return Err(anyhow::anyhow!(
+            "Stash {} already exists; wait a second and try again",
+            timestamp
+        )
+        .into());
+    }
+
+    for (file, local, _) in &changed {
+        copy_file_to(local, &target.join(file))?;
+    }
+    for (file, local, shade) in &changed {
+        copy_file_to(shade, local)?;
+        manifest.record(file, local, &mut hashes)?;
+    }
+    manifest.save(&manifest_path)?;
+    hashes.save(&hash_cache_path)?;
+
+    outln!(
+        "{} Stashed {} file(s) as {}:",
+        "✓".green().bold(),
+        changed.len(),
+        timestamp
+    );
+    for (file, _, _) in &changed {
+        outln!("  {} {}", "←".blue(), file.display());
+    }
+    outln!();
+    outln!(
+        "They now match shade. Reapply them with: {}",
+        "git-shade stash pop".bold()
+    );
+
+    Ok(())
+}
+
+/// Restore the newest stash over the project, then delete it
+fn pop(
+    paths: &ShadePaths,
+    project_path: &Path,
+    project_name: &str,
+    stash_dir: &Path,
+) -> Result<()> {
+    let Some(latest) = stashes(stash_dir)?.pop() else {
+        outln!("{} No stashes for {}", "→".blue(), project_name);
+        return Ok(());
+    };
+
+    let manifest = Manifest::load(&paths.manifest_file(project_name))?;
+    let files = stashed_files(&latest)?;
+
+    // Restored edits are newer than anything pulled meanwhile, so they must
+    // look like fresh local changes rather than an older version
+    let now = FileTime::now();
+    for file in &files {
+        let local = manifest.local_path(project_path, file);
+        copy_file_to(&latest.join(file), &local)?;
+        set_mtime(&local, now)?;
+    }
+    fs::remove_dir_all(&latest)?;
+
+    outln!(
+        "{} Restored {} file(s) from stash {}:",
+        "✓".green().bold(),
+        files.len(),
+        stash_name(&latest)
+    );
+    for file in &files {
+        outln!("  {} {}", "→".blue(), file.display());
+    }
+    outln!();
+    outln!(
+        "Check them and sync the result with: {}",
+        "git-shade push".bold()
+    );
+
+    Ok(())
+}
+
+fn list_stashes(stash_dir: &Path) -> Result<()> {
+    let stashes = stashes(stash_dir)?;
+    if stashes.is_empty() {
+        outln!("{} No stashes", "→".blue());
+        return Ok(());
+    }
+
+    // Newest first, the one `pop` takes at the top
+    for stash in stashes.iter().rev() {
+        outln!(
+            "  {}  {} file(s)",
+            stash_name(stash),
+            stashed_files(stash)?.len()
+        );
+    }
+    Ok(())
+}
+
+/// The project's stashes, oldest first (their timestamp names sort by time)
+fn stashes(stash_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !stash_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut stashes = Vec::new();
+    for entry in fs::read_dir(stash_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            stashes.push(entry.path());
+        }
+    }
+    stashes.sort();
+    Ok(stashes)
+}
+
+/// Files in a stash, relative to it
+fn stashed_files(stash: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(stash).min_depth(1).sort_by_file_name() {
+        let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read stash: {}", e))?;
+        if entry.file_type().is_file() {
+            if let Ok(rel) = entry.path().strip_prefix(stash) {
+                files.push(rel.to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn stash_name(stash: &Path) -> String {
+    stash
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
@@ -1,236 +1,631 @@
-use crate::core::{detect_sync_state, Config, FileMetadata, ShadePaths, SyncState, Tracker};
-use crate::error::{Result, ShadeError};
-use crate::git::read_exclude;
-use crate::utils::{detect_project_name, verify_git_repo};
+use crate::commands::push::pending_push;
+use crate::core::{
+    detect_sync_state, Config, FileMetadata, HashCache, Manifest, ShadeLayout, ShadePaths,
+    SyncState,
+};
+use crate::error::Result;
+use crate::git::{fetch, read_patterns, run_git, upstream_counts};
+use crate::utils::output::outln;
+use crate::utils::{
+    format_timestamp, load_tracker, migrate_tracker, resolve_project, tracker_path,
+};
 use colored::Colorize;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 
-pub fn run() -> Result<()> {
-    // 1. Verify it's a git repo
-    let project_path = verify_git_repo()?;
-
-    // 2. Detect project name
-    let project_name = detect_project_name(None)?;
-
-    // 3. Setup paths
+/// Print the status and return the exit code for `--exit-code`
+///
+/// Like `git diff --exit-code`: 2 if any file is in conflict, 1 if any needs
+/// a push or pull, 0 otherwise. Always 0 without `exit_code`. Times print as
+/// `3 hours ago`, with the absolute UTC time in front when `verbose`.
+pub fn run(
+    project: Option<String>,
+    name: Option<String>,
+    fetch: bool,
+    compact: bool,
+    plan: bool,
+    exit_code: bool,
+    verbose: bool,
+) -> Result<i32> {
+    // 1. Setup paths
     let paths = ShadePaths::new()?;
 
-    // 4. Verify project is initialized
+    // 2. Load config
     let config = Config::load(&paths.config)?;
-    if config.find_project(&project_name).is_none() {
-        return Err(ShadeError::NotInitialized { project_name });
-    }
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
 
     let project_shade_dir = paths.project_shade_dir(&project_name);
+    let _span = tracing::info_span!("status", project = %project_name).entered();
 
     // 5. Load tracker
-    let tracker =
-        Tracker::load(&paths.shade_sync_file(&project_name)).unwrap_or_else(|_| Tracker::new());
+    migrate_tracker(&paths, &config, &project_name)?;
+    let tracker = load_tracker(&tracker_path(&paths, &config, &project_name)?);
 
     // 6. Print header
-    println!("{}: {}", "Project".bold(), project_name);
-    println!("{}: {}", "Local".bold(), project_path.display());
-    println!("{}: {}", "Shade".bold(), project_shade_dir.display());
+    outln!("{}: {}", "Project".bold(), project_name);
+    outln!("{}: {}", "Local".bold(), project_path.display());
+    outln!("{}: {}", "Shade".bold(), project_shade_dir.display());
+    outln!("{}: {}", "Machine".bold(), config.machine_name());
 
     if let Some(last_pull) = tracker.last_pull {
-        println!(
+        outln!(
             "{}: {}",
             "Last pull".bold(),
-            last_pull.format("%Y-%m-%d %H:%M:%S")
+            format_timestamp(last_pull, verbose)
         );
     } else {
-        println!("{}: {}", "Last pull".bold(), "never".italic());
+        outln!("{}: {}", "Last pull".bold(), "never".italic());
     }
 
     if let Some(last_push) = tracker.last_push {
-        println!(
+        outln!(
             "{}: {}",
             "Last push".bold(),
-            last_push.format("%Y-%m-%d %H:%M:%S")
+            format_timestamp(last_push, verbose)
         );
     } else {
-        println!("{}: {}", "Last push".bold(), "never".italic());
+        outln!("{}: {}", "Last push".bold(), "never".italic());
     }
 
-    println!();
+    // A commit push made but couldn't send (network down, rejected)
+    let pending = pending_push(&paths, &project_name)?;
+    if let Some(pending) = &pending {
+        outln!(
+            "{}: {} from {} was committed but not pushed - run {} to retry",
+            "Pending push".bold(),
+            pending.commit[..7.min(pending.commit.len())].yellow(),
+            format_timestamp(pending.committed_at, verbose),
+            "git-shade push".bold()
+        );
+    }
+
+    outln!();
 
     // 7. Get tracked files
-    let tracked_patterns = read_exclude(&project_path)?;
+    let manifest = Manifest::load(&paths.manifest_file(&project_name))?;
+    let tracked_patterns = read_patterns(&project_path)?;
 
-    if tracked_patterns.is_empty() {
-        println!("No files tracked yet.");
-        println!();
-        println!("Add files with: {}", "git-shade add <files>".bold());
-        return Ok(());
+    if tracked_patterns.is_empty() && manifest.is_empty() {
+        outln!("No files tracked yet.");
+        outln!();
+        outln!("Add files with: {}", "git-shade add <files>".bold());
+        return Ok(0);
     }
 
-    // 8. Analyze each tracked file
-    println!("{}:", "Files".bold());
+    // Tracked files whose exclude entry was removed by hand
+    let unexcluded: Vec<PathBuf> = manifest
+        .paths()
+        .into_iter()
+        .filter(|file| {
+            !tracked_patterns.iter().any(|p| p.covers(file)) && !manifest.is_external(file)
+        })
+        .collect();
 
-    let mut has_conflicts = false;
-    let mut needs_push = false;
-    let mut needs_pull = false;
+    // 8. Analyze each tracked file
+    let results = file_statuses(&paths, &config, &project_path, &project_name)?;
 
-    for pattern in &tracked_patterns {
-        let clean_pattern = pattern.trim_end_matches('/');
-        let local_path = project_path.join(clean_pattern);
-        let shade_path = project_shade_dir.join(clean_pattern);
+    let has_conflicts = results
+        .iter()
+        .any(|(_, status)| *status == FileStatus::State(SyncState::Conflict));
+    let needs_push = pending.is_some()
+        || results
+            .iter()
+            .any(|(_, status)| *status == FileStatus::State(SyncState::LocalAhead));
+    let needs_pull = results.iter().any(|(_, status)| {
+        matches!(
+            status,
+            FileStatus::State(SyncState::RemoteAhead | SyncState::RemoteOnly)
+        )
+    });
 
-        // Get metadata
-        let local_meta = if local_path.exists() && local_path.is_file() {
-            Some(FileMetadata::from_path(&local_path).ok())
-        } else {
-            None
-        }
-        .flatten();
+    if plan {
+        print_plan(&results);
+    } else if compact {
+        print_compact(&results);
+    } else {
+        print_files(&results);
+    }
 
-        let remote_meta = if shade_path.exists() && shade_path.is_file() {
-            Some(FileMetadata::from_path(&shade_path).ok())
-        } else {
-            None
+    if !unexcluded.is_empty() {
+        outln!();
+        outln!(
+            "{} Tracked but missing from .git/info/exclude (could be committed to your project):",
+            "⚠".yellow()
+        );
+        for file in &unexcluded {
+            outln!("  - {}", file.display());
         }
-        .flatten();
-
-        // Detect state
-        let state = detect_sync_state(local_meta.as_ref(), remote_meta.as_ref(), tracker.last_pull);
-
-        // Display with appropriate symbol and color
-        let (symbol, description, color_fn): (_, _, fn(&str) -> colored::ColoredString) =
-            match state {
-                SyncState::InSync => ("✓", "in sync", |s: &str| s.green()),
-                SyncState::LocalAhead => {
-                    needs_push = true;
-                    (
-                        "↑",
-                        "local ahead - modified locally, ready to push",
-                        |s: &str| s.yellow(),
-                    )
-                }
-                SyncState::RemoteAhead => {
-                    needs_pull = true;
-                    (
-                        "↓",
-                        "remote ahead - modified in shade, safe to pull",
-                        |s: &str| s.blue(),
-                    )
-                }
-                SyncState::Conflict => {
-                    has_conflicts = true;
-                    (
-                        "⚠",
-                        "conflict - modified both locally and remotely",
-                        |s: &str| s.red(),
-                    )
-                }
-                SyncState::LocalOnly => {
-                    ("?", "local only, not in shade", |s: &str| s.bright_black())
-                }
-                SyncState::RemoteOnly => {
-                    needs_pull = true;
-                    ("←", "remote only, deleted locally", |s: &str| {
-                        s.bright_black()
-                    })
-                }
-            };
-
-        println!("  {} {} ({})", color_fn(symbol), clean_pattern, description);
-    }
-
-    println!();
-
-    // 9. Print legend
-    println!("{}:", "Legend".bold());
-    println!(
-        "  {} In sync           Both files are identical",
-        "✓".green()
-    );
-    println!(
-        "  {} Local ahead       Modified locally, needs push",
-        "↑".yellow()
-    );
-    println!(
-        "  {} Remote ahead      Modified in shade, safe to pull",
-        "↓".blue()
-    );
-    println!(
-        "  {} Conflict          Modified in both places, manual resolution needed",
-        "⚠".red()
-    );
-    println!(
-        "  {} Local only        File exists locally but not in shade",
-        "?".bright_black()
-    );
-    println!(
-        "  {} Remote only       File exists in shade but not locally",
-        "←".bright_black()
-    );
-    println!();
-
-    // 10. Check git remote
-    let original_dir = std::env::current_dir()?;
-    std::env::set_current_dir(&paths.projects)?;
+        outln!(
+            "  Re-add them with {} to exclude them again.",
+            "git-shade add <files>".bold()
+        );
+    }
 
-    let remote_output = Command::new("git").args(["remote", "-v"]).output()?;
+    outln!();
 
-    let remote_status_output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .output()?;
+    // 9. Print legend (the compact view and the plan name each state instead)
+    if !compact && !plan {
+        print_legend();
+    }
 
-    std::env::set_current_dir(&original_dir)?;
+    // 10. Check git remote
+    let remote_output = run_git(&paths.projects, &["remote", "-v"])?;
+    let remote_status_output = run_git(&paths.projects, &["status", "--porcelain"])?;
 
     if !remote_output.stdout.is_empty() {
         let remote_info = String::from_utf8_lossy(&remote_output.stdout);
         let first_line = remote_info.lines().next().unwrap_or("");
         if let Some(url) = first_line.split_whitespace().nth(1) {
-            println!("{}: {}", "Git remote".bold(), url);
+            outln!("{}: {}", "Git remote".bold(), url);
         }
     } else {
-        println!(
+        outln!(
             "{}: {} - changes are local only",
             "Git remote".bold(),
             "(none)".italic()
         );
-        println!("  Add remote with:");
-        println!("    cd {}", paths.projects.display());
-        println!("    git remote add origin <url>");
-        println!();
+        outln!("  Add remote with:");
+        outln!("    cd {}", paths.projects.display());
+        outln!("    git remote add origin <url>");
+        outln!();
     }
 
     let is_clean = remote_status_output.stdout.is_empty();
     if is_clean {
-        println!(
+        outln!(
             "{}: {} (no uncommitted changes)",
             "Git status".bold(),
             "Clean".green()
         );
     } else {
-        println!(
+        outln!(
             "{}: {} (uncommitted changes in shade)",
             "Git status".bold(),
             "Modified".yellow()
         );
     }
 
-    // 11. Provide helpful hints
-    println!();
+    // 11. Compare with the remote (fetch only moves remote-tracking refs, the
+    // shade working tree is left alone)
+    if fetch {
+        fetch_and_compare(&paths.projects)?;
+    }
+
+    // 12. Provide helpful hints
+    outln!();
     if has_conflicts {
-        println!(
+        outln!(
             "{} You have conflicts that need manual resolution.",
             "⚠".red().bold()
         );
-        println!(
+        outln!(
             "  Review files and run {} after resolving.",
             "git-shade push".bold()
         );
     } else if needs_pull {
-        println!("{} Some files can be pulled from shade.", "→".blue());
-        println!("  Run {} to sync them.", "git-shade pull".bold());
+        outln!("{} Some files can be pulled from shade.", "→".blue());
+        outln!("  Run {} to sync them.", "git-shade pull".bold());
     }
 
     if needs_push {
-        println!("{} Some files have local changes.", "→".yellow());
-        println!("  Run {} to sync them to shade.", "git-shade push".bold());
+        outln!("{} Some files have local changes.", "→".yellow());
+        outln!("  Run {} to sync them to shade.", "git-shade push".bold());
+    }
+
+    // 13. Exit code for scripts
+    let code = if !exit_code {
+        0
+    } else if has_conflicts {
+        2
+    } else if needs_push || needs_pull {
+        1
+    } else {
+        0
+    };
+
+    Ok(code)
+}
+
+/// What each of a project's tracked files is, against its shade copy
+///
+/// The manifest is authoritative; exclude patterns add files created since
+/// (e.g. new files inside a tracked directory). Sorted by path.
+pub fn file_statuses(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+) -> Result<Vec<(PathBuf, FileStatus)>> {
+    let project_shade_dir = paths.project_shade_dir(project_name);
+    let layout = ShadeLayout::new(paths, config, project_name);
+    let tracker = load_tracker(&tracker_path(paths, config, project_name)?);
+
+    let manifest = Manifest::load(&paths.manifest_file(project_name))?;
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+    let tracked_patterns = read_patterns(project_path)?;
+
+    let mut files = manifest.paths();
+    files.extend(manifest.externals().into_iter().map(|(rel, _)| rel));
+    for pattern in &tracked_patterns {
+        // Directory patterns expand to every file found locally or in shade
+        let mut pattern_files = pattern.files_in(project_path)?;
+        pattern_files.extend(pattern.files_in(&project_shade_dir)?);
+        if let Some(os_dir) = layout.os_dir() {
+            pattern_files.extend(pattern.files_in(&os_dir)?);
+        }
+
+        pattern_files.retain(|file| !manifest.is_excluded(file) && !config.is_junk(file));
+
+        if pattern_files.is_empty() {
+            pattern_files.push(pattern.path().to_path_buf());
+        }
+        files.extend(pattern_files);
+    }
+    files.sort();
+    files.dedup();
+
+    let mut results = Vec::new();
+
+    for file in &files {
+        if manifest.is_paused(file) {
+            results.push((file.clone(), FileStatus::Paused));
+            continue;
+        }
+
+        let local_path = manifest.local_path(project_path, file);
+        let shade_path = layout.file(file);
+
+        let local_exists = local_path.is_file();
+        let shade_exists = shade_path.is_file();
+
+        // Added with --no-copy and not created yet
+        if !local_exists && !shade_exists && manifest.is_pending(file) {
+            results.push((file.clone(), FileStatus::Pending));
+            continue;
+        }
+
+        // Deleted on both sides since it was last synced
+        if !local_exists && !shade_exists && manifest.get(file).is_some() {
+            results.push((file.clone(), FileStatus::Missing));
+            continue;
+        }
+
+        let state = if manifest.is_conflicted(file)
+            && local_exists
+            && shade_exists
+            && !hashes.same_contents(&local_path, &shade_path)?
+        {
+            // `add` kept both versions
+            SyncState::Conflict
+        } else {
+            sync_state(
+                config,
+                &mut hashes,
+                &local_path,
+                &shade_path,
+                tracker.last_pull,
+            )?
+        };
+        tracing::debug!(file = %file.display(), ?state, "status");
+        results.push((file.clone(), FileStatus::State(state)));
+    }
+
+    hashes.save(&hash_cache_path)?;
+
+    Ok(results)
+}
+
+/// Sync state of a local file and its shade copy
+///
+/// With `sync.hash`, identical contents are in sync whatever the timestamps
+/// say; otherwise it's down to `detect_sync_state`.
+pub fn sync_state(
+    config: &Config,
+    hashes: &mut HashCache,
+    local_path: &Path,
+    shade_path: &Path,
+    last_pull: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<SyncState> {
+    let local_meta = if local_path.is_file() {
+        FileMetadata::from_path(local_path).ok()
+    } else {
+        None
+    };
+    let remote_meta = if shade_path.is_file() {
+        FileMetadata::from_path(shade_path).ok()
+    } else {
+        None
+    };
+
+    let state = if local_meta.is_some()
+        && remote_meta.is_some()
+        && config.sync.hash
+        && hashes.same_contents(local_path, shade_path)?
+    {
+        SyncState::InSync
+    } else {
+        detect_sync_state(
+            local_meta.as_ref(),
+            remote_meta.as_ref(),
+            last_pull,
+            &config.sync,
+        )
+    };
+    Ok(state)
+}
+
+fn fetch_and_compare(repo: &std::path::Path) -> Result<()> {
+    if let Err(err) = fetch(repo) {
+        outln!(
+            "{}: {} {}",
+            "Remote".bold(),
+            "⚠ fetch failed, showing the last known state:".yellow(),
+            err
+        );
+    }
+
+    match upstream_counts(repo)? {
+        None => outln!(
+            "{}: {} (no upstream branch to compare with)",
+            "Remote".bold(),
+            "unknown".italic()
+        ),
+        Some((0, 0)) => outln!("{}: {}", "Remote".bold(), "Up to date".green()),
+        Some((behind, ahead)) => {
+            if behind > 0 {
+                outln!(
+                    "{}: {} commit(s) behind remote - run {}",
+                    "Remote".bold(),
+                    behind.to_string().blue(),
+                    "git-shade pull".bold()
+                );
+            }
+            if ahead > 0 {
+                outln!(
+                    "{}: {} commit(s) not pushed yet - run {}",
+                    "Remote".bold(),
+                    ahead.to_string().yellow(),
+                    "git-shade push".bold()
+                );
+            }
+        }
     }
 
     Ok(())
 }
+
+fn print_legend() {
+    outln!("{}:", "Legend".bold());
+    outln!(
+        "  {} In sync           Both files are identical",
+        "✓".green()
+    );
+    outln!(
+        "  {} Local ahead       Modified locally, needs push",
+        "↑".yellow()
+    );
+    outln!(
+        "  {} Remote ahead      Modified in shade, safe to pull",
+        "↓".blue()
+    );
+    outln!(
+        "  {} Conflict          Modified in both places, manual resolution needed",
+        "⚠".red()
+    );
+    outln!(
+        "  {} Local only        File exists locally but not in shade",
+        "?".bright_black()
+    );
+    outln!(
+        "  {} Remote only       File exists in shade but not locally",
+        "←".bright_black()
+    );
+    outln!(
+        "  {} Paused            Skipped by push and pull until resumed",
+        "‖".bright_black()
+    );
+    outln!(
+        "  {} Pending           Added with --no-copy, pushed once it exists",
+        "○".bright_black()
+    );
+    outln!();
+}
+
+/// Widest the path column gets; longer paths push their description out
+const MAX_PATH_COLUMN: usize = 60;
+
+/// One line per file: symbol, path padded to a common width, description
+fn print_files(results: &[(PathBuf, FileStatus)]) {
+    let rows: Vec<_> = results
+        .iter()
+        .map(|(file, status)| {
+            let (symbol, description, color_fn) = status.display();
+            (symbol, file.display().to_string(), description, color_fn)
+        })
+        .collect();
+    let width = rows
+        .iter()
+        .map(|(_, path, _, _)| path.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_PATH_COLUMN);
+
+    outln!("{}:", "Files".bold());
+    for (symbol, path, description, color_fn) in &rows {
+        let padding = width.saturating_sub(path.chars().count());
+        outln!(
+            "  {} {}{}  {}",
+            color_fn(symbol),
+            path,
+            " ".repeat(padding),
+            description
+        );
+    }
+}
+
+/// Only files that need attention, grouped by state, most urgent first
+fn print_compact(results: &[(PathBuf, FileStatus)]) {
+    let groups = [
+        (FileStatus::State(SyncState::Conflict), "Conflict"),
+        (FileStatus::State(SyncState::LocalAhead), "Local ahead"),
+        (FileStatus::State(SyncState::RemoteAhead), "Remote ahead"),
+        (FileStatus::State(SyncState::RemoteOnly), "Remote only"),
+        (FileStatus::State(SyncState::LocalOnly), "Local only"),
+        (FileStatus::Missing, "Missing"),
+        (FileStatus::Paused, "Paused"),
+        (FileStatus::Pending, "Pending"),
+    ];
+
+    for (status, title) in groups {
+        let files: Vec<&PathBuf> = results
+            .iter()
+            .filter(|(_, s)| *s == status)
+            .map(|(file, _)| file)
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+
+        let (symbol, _, color_fn) = status.display();
+        outln!("{} {} ({}):", color_fn(symbol), title.bold(), files.len());
+        for file in files {
+            outln!("  {}", file.display());
+        }
+    }
+
+    let in_sync = results
+        .iter()
+        .filter(|(_, status)| *status == FileStatus::State(SyncState::InSync))
+        .count();
+    outln!(
+        "{} {} of {} file(s) in sync",
+        "✓".green(),
+        in_sync,
+        results.len()
+    );
+}
+
+/// What push and pull would each do with every file, in plain words
+fn print_plan(results: &[(PathBuf, FileStatus)]) {
+    outln!("{}:", "Plan".bold());
+    for (file, status) in results {
+        let (symbol, _, color_fn) = status.display();
+        let (push, pull) = status.plan();
+        outln!(
+            "  {} {} ({})",
+            color_fn(symbol),
+            file.display(),
+            status.name()
+        );
+        outln!("      push would {}", push);
+        outln!("      pull would {}", pull);
+    }
+}
+
+/// What `status` found for one tracked file
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileStatus {
+    State(SyncState),
+    /// Deleted locally and in shade since it was last synced
+    Missing,
+    /// Skipped by push and pull until resumed
+    Paused,
+    /// Added with `add --no-copy` and not created anywhere yet
+    Pending,
+}
+
+impl FileStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileStatus::State(SyncState::InSync) => "in sync",
+            FileStatus::State(SyncState::LocalAhead) => "local ahead",
+            FileStatus::State(SyncState::RemoteAhead) => "remote ahead",
+            FileStatus::State(SyncState::Conflict) => "conflict",
+            FileStatus::State(SyncState::LocalOnly) => "local only",
+            FileStatus::State(SyncState::RemoteOnly) => "remote only",
+            FileStatus::Missing => "missing",
+            FileStatus::Paused => "paused",
+            FileStatus::Pending => "pending",
+        }
+    }
+
+    /// What push and pull would do with the file, completing "push would ..."
+    ///
+    /// Push copies every local file to shade as it is; pull only takes what's
+    /// newer in shade and stops on conflicts (without `--strategy`/`--force`).
+    fn plan(&self) -> (&'static str, &'static str) {
+        match self {
+            FileStatus::State(SyncState::InSync) => ("change nothing", "change nothing"),
+            FileStatus::State(SyncState::LocalAhead) => (
+                "upload your local changes",
+                "keep your local file (it's newer)",
+            ),
+            FileStatus::State(SyncState::RemoteAhead) => (
+                "overwrite the newer shade copy with your local file",
+                "download the shade changes",
+            ),
+            FileStatus::State(SyncState::Conflict) => (
+                "overwrite the shade copy with your local file",
+                "stop and report the conflict, touching nothing",
+            ),
+            FileStatus::State(SyncState::LocalOnly) => {
+                ("upload it to shade for the first time", "leave it alone")
+            }
+            FileStatus::State(SyncState::RemoteOnly) => {
+                ("leave the shade copy alone", "restore it from shade")
+            }
+            FileStatus::Missing => ("skip it (not found)", "skip it (not in shade)"),
+            FileStatus::Paused => ("skip it (paused)", "skip it (paused)"),
+            FileStatus::Pending => ("skip it (doesn't exist yet)", "skip it (not in shade)"),
+        }
+    }
+
+    /// Symbol, description and color for the full view
+    fn display(
+        &self,
+    ) -> (
+        &'static str,
+        &'static str,
+        fn(&str) -> colored::ColoredString,
+    ) {
+        match self {
+            FileStatus::State(SyncState::InSync) => ("✓", "in sync", |s: &str| s.green()),
+            FileStatus::State(SyncState::LocalAhead) => (
+                "↑",
+                "local ahead - modified locally, ready to push",
+                |s: &str| s.yellow(),
+            ),
+            FileStatus::State(SyncState::RemoteAhead) => (
+                "↓",
+                "remote ahead - modified in shade, safe to pull",
+                |s: &str| s.blue(),
+            ),
+            FileStatus::State(SyncState::Conflict) => (
+                "⚠",
+                "conflict - modified both locally and remotely",
+                |s: &str| s.red(),
+            ),
+            FileStatus::State(SyncState::LocalOnly) => {
+                ("?", "local only, not in shade", |s: &str| s.bright_black())
+            }
+            FileStatus::State(SyncState::RemoteOnly) => {
+                ("←", "remote only, deleted locally", |s: &str| {
+                    s.bright_black()
+                })
+            }
+            FileStatus::Missing => (
+                "✗",
+                "missing - deleted locally and in shade",
+                |s: &str| s.red(),
+            ),
+            FileStatus::Paused => ("‖", "paused", |s: &str| s.bright_black()),
+            FileStatus::Pending => (
+                "○",
+                "pending - added with --no-copy, doesn't exist yet",
+                |s: &str| s.bright_black(),
+            ),
+        }
+    }
+}
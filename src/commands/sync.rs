@@ -0,0 +1,68 @@
+use crate::commands::pull::{pull_project, PullOptions};
+use crate::commands::push::{push_project, PushOptions};
+use crate::core::{Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::utils::output::outln;
+use crate::utils::resolve_project;
+use colored::Colorize;
+
+pub fn run(
+    force: bool,
+    message: Option<String>,
+    verbose: bool,
+    project: Option<String>,
+) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, None)?;
+
+    // 5. Pull first, so local changes are pushed on top of remote ones.
+    // Conflicts abort here, before anything is pushed.
+    outln!("{}", "==> Pull".bold());
+    let pull_options = PullOptions {
+        force,
+        verbose,
+        ..PullOptions::default()
+    };
+    let pulled = pull_project(&paths, &project_path, &project_name, &pull_options)?;
+    outln!();
+
+    // 6. Push local changes
+    outln!("{}", "==> Push".bold());
+    let push_options = PushOptions {
+        message,
+        verbose,
+        ..PushOptions::default()
+    };
+    let pushed = match push_project(&paths, &project_path, &project_name, &push_options) {
+        Ok(committed) => committed,
+        Err(ShadeError::NoFilesTracked) => {
+            outln!("  {} No files tracked, nothing to push", "→".blue());
+            false
+        }
+        Err(e) => return Err(e),
+    };
+
+    // 7. Summary
+    if pulled == 0 && !pushed {
+        outln!("{} Already up to date", "✓".green().bold());
+    } else {
+        outln!(
+            "{} Sync completed ({} file(s) pulled, {})",
+            "✓".green().bold(),
+            pulled,
+            if pushed {
+                "changes pushed"
+            } else {
+                "nothing to push"
+            }
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::core::{Config, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::git::{read_patterns, remove_exclude_block, run_git};
+use crate::utils::output::outln;
+use crate::utils::{detect_project_name, links_to, replace_link_with_copy, verify_git_repo};
+use colored::Colorize;
+use dialoguer::Confirm;
+use std::fs;
+use std::io::IsTerminal;
+
+pub fn run(
+    name_override: Option<String>,
+    keep_exclude: bool,
+    keep_shade: bool,
+    yes: bool,
+) -> Result<()> {
+    // 1. Verify it's a git repo
+    let project_path = verify_git_repo()?;
+
+    // 2. Detect project name
+    let project_name = detect_project_name(name_override)?;
+
+    // 3. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 4. Verify project is initialized
+    let mut config = Config::load(&paths.config)?;
+    if config.find_project(&project_name).is_none() {
+        return Err(ShadeError::NotInitialized { project_name });
+    }
+
+    let project_metadata_dir = paths.project_metadata_dir(&project_name);
+    let project_shade_dir = paths.project_shade_dir(&project_name);
+
+    // 5. Patterns git-shade manages in .git/info/exclude
+    let shaded_patterns = read_patterns(&project_path)?;
+
+    // 6. Show the plan and confirm
+    outln!(
+        "This will uninitialize git-shade for {}:",
+        project_name.bold()
+    );
+    outln!("  - Remove it from {}", paths.config.display());
+    outln!("  - Delete {}", project_metadata_dir.display());
+    if !keep_exclude && !shaded_patterns.is_empty() {
+        outln!("  - Remove from .git/info/exclude (they'll show up in git status again):");
+        for pattern in &shaded_patterns {
+            outln!("      {}", pattern);
+        }
+    }
+    if !keep_shade && project_shade_dir.exists() {
+        outln!(
+            "  - Delete {} and commit the removal in the shade repo",
+            project_shade_dir.display()
+        );
+    }
+    outln!("Your project files are not touched.");
+    outln!();
+
+    let confirmed = if yes {
+        true
+    } else if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::ConfirmationRequired(format!(
+            "Uninitializing {}",
+            project_name
+        )));
+    } else {
+        Confirm::new()
+            .with_prompt("Continue?")
+            .default(false)
+            .interact()
+            .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?
+    };
+
+    if !confirmed {
+        outln!("{} Nothing changed", "→".blue());
+        return Ok(());
+    }
+
+    // 7. Remove shade files (committed, so other machines see the removal on pull)
+    if !keep_shade && project_shade_dir.exists() {
+        // Files added with --symlink would be left dangling
+        for pattern in &shaded_patterns {
+            let local = project_path.join(pattern.path());
+            if links_to(&local, &project_shade_dir.join(pattern.path())) {
+                replace_link_with_copy(&local)?;
+                outln!(
+                    "  {} Replaced symlink with a copy: {}",
+                    "✓".green(),
+                    pattern
+                );
+            }
+        }
+
+        let shade_rel = format!("{}/", project_name);
+        let rm_output = run_git(
+            &paths.projects,
+            &["rm", "-r", "-q", "--ignore-unmatch", &shade_rel],
+        )?;
+        if !rm_output.status.success() {
+            let stderr = String::from_utf8_lossy(&rm_output.stderr);
+            return Err(ShadeError::GitError(format!("git rm failed: {}", stderr)));
+        }
+
+        // Anything left was never committed
+        if project_shade_dir.exists() {
+            fs::remove_dir_all(&project_shade_dir)?;
+        }
+
+        let commit_msg = format!("[{}] Remove project", project_name);
+        let commit_output = run_git(&paths.projects, &["commit", "-m", commit_msg.as_str()])?;
+        if commit_output.status.success() {
+            outln!("  {} Committed: {}", "✓".green(), commit_msg);
+        }
+        outln!("  {} Removed {}", "✓".green(), project_shade_dir.display());
+    }
+
+    // 8. Remove git-shade's block from .git/info/exclude
+    if !keep_exclude {
+        remove_exclude_block(&project_path)?;
+        if !shaded_patterns.is_empty() {
+            outln!(
+                "  {} Removed {} pattern(s) from .git/info/exclude",
+                "✓".green(),
+                shaded_patterns.len()
+            );
+        }
+    }
+
+    // 9. Delete metadata (.shade-sync, manifest)
+    if project_metadata_dir.exists() {
+        fs::remove_dir_all(&project_metadata_dir)?;
+        outln!(
+            "  {} Removed {}",
+            "✓".green(),
+            project_metadata_dir.display()
+        );
+    }
+
+    // 10. Deregister
+    config.remove_project(&project_name);
+    config.save(&paths.config)?;
+    outln!("  {} Removed {} from config", "✓".green(), project_name);
+
+    outln!();
+    outln!(
+        "{} Uninitialized git-shade for project: {}",
+        "✓".green().bold(),
+        project_name.bold()
+    );
+    if !keep_shade {
+        outln!(
+            "Push the removal with: {}",
+            format!("cd {} && git push", paths.projects.display()).bold()
+        );
+    }
+
+    Ok(())
+}
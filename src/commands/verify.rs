@@ -0,0 +1,248 @@
+use crate::commands::push::{push_project, PushOptions};
+use crate::core::{is_keep_file, to_slash, Config, Manifest, ShadeLayout, ShadePaths};
+use crate::error::{Result, ShadeError};
+use crate::git::{add_to_exclude, normalize_exclude, read_patterns, redundant_excludes, Pattern};
+use crate::utils::output::outln;
+use crate::utils::resolve_project;
+use colored::Colorize;
+use dialoguer::Confirm;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// Ways `.git/info/exclude`, the shade dir and the project disagree
+#[derive(Debug, Default)]
+struct Drift {
+    /// Exclude patterns with nothing behind them in shade
+    patterns_without_shade: Vec<Pattern>,
+    /// Shade files no exclude pattern covers, that also exist locally
+    unexcluded: Vec<PathBuf>,
+    /// Shade files no exclude pattern covers, gone locally
+    orphaned_shade: Vec<PathBuf>,
+    /// Tracked files that exist locally but were never pushed
+    never_pushed: Vec<PathBuf>,
+    /// Exclude lines repeating another spelling of a pattern (`/config.local`)
+    redundant_lines: Vec<String>,
+}
+
+impl Drift {
+    fn problems(&self) -> usize {
+        self.patterns_without_shade.len()
+            + self.unexcluded.len()
+            + self.orphaned_shade.len()
+            + self.never_pushed.len()
+            + self.redundant_lines.len()
+    }
+}
+
+pub fn run(project: Option<String>, fix: bool, yes: bool, verbose: bool) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, None)?;
+    let layout = ShadeLayout::new(&paths, &config, &project_name);
+
+    // 5. Cross-check exclude patterns, shade files and local files
+    let patterns = read_patterns(&project_path)?;
+    let manifest = Manifest::load(&paths.manifest_file(&project_name))?;
+    let mut drift = find_drift(&patterns, &manifest, &config, &project_path, &layout)?;
+    drift.redundant_lines = redundant_excludes(&project_path)?;
+
+    outln!("Verifying {}...", project_name.bold());
+    outln!();
+    print_drift(&drift);
+
+    if drift.problems() == 0 {
+        outln!(
+            "{} Exclude patterns, shade and local files agree",
+            "✓".green().bold()
+        );
+        return Ok(());
+    }
+
+    if !fix {
+        return Err(ShadeError::VerifyFailed(drift.problems()));
+    }
+
+    // 6. Reconcile (asking first, so a refusal leaves everything as it was)
+    let delete_orphans =
+        !drift.orphaned_shade.is_empty() && confirm_delete(&drift.orphaned_shade, yes)?;
+    outln!("{}", "Fixing...".bold());
+
+    if !drift.redundant_lines.is_empty() {
+        normalize_exclude(&project_path)?;
+        outln!(
+            "  {} Normalized {} exclude line(s) in .git/info/exclude",
+            "✓".green(),
+            drift.redundant_lines.len()
+        );
+    }
+
+    if !drift.unexcluded.is_empty() {
+        let lines: Vec<String> = drift.unexcluded.iter().map(|f| to_slash(f)).collect();
+        add_to_exclude(&project_path, &lines)?;
+        outln!(
+            "  {} Added {} file(s) to .git/info/exclude",
+            "✓".green(),
+            lines.len()
+        );
+    }
+
+    let mut deleted = 0;
+    if delete_orphans {
+        for file in &drift.orphaned_shade {
+            fs::remove_file(layout.file(file))?;
+        }
+        deleted = drift.orphaned_shade.len();
+        outln!(
+            "  {} Deleted {} orphaned shade file(s)",
+            "✓".green(),
+            deleted
+        );
+    }
+
+    // Push copies never-pushed files and commits the deletions
+    if !drift.never_pushed.is_empty() || deleted > 0 {
+        outln!();
+        let options = PushOptions {
+            verbose,
+            ..PushOptions::default()
+        };
+        push_project(&paths, &project_path, &project_name, &options)?;
+    }
+
+    let stale: Vec<&Pattern> = drift
+        .patterns_without_shade
+        .iter()
+        .filter(|pattern| !project_path.join(pattern.path()).exists())
+        .collect();
+    if !stale.is_empty() {
+        outln!();
+        outln!(
+            "{} Left {} pattern(s) with no files anywhere; remove them from .git/info/exclude if they're stale",
+            "→".blue(),
+            stale.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn find_drift(
+    patterns: &[Pattern],
+    manifest: &Manifest,
+    config: &Config,
+    project_path: &Path,
+    layout: &ShadeLayout,
+) -> Result<Drift> {
+    let mut drift = Drift::default();
+    let all_shade_files = layout.list_files()?;
+
+    for pattern in patterns {
+        let has_shade_files = all_shade_files.iter().any(|file| {
+            pattern.covers(file)
+                && !is_keep_file(file)
+                && !manifest.is_excluded(file)
+                && !config.is_junk(file)
+        });
+        if !has_shade_files {
+            drift.patterns_without_shade.push(pattern.clone());
+        }
+
+        for file in pattern.files_in(project_path)? {
+            if !manifest.is_excluded(&file)
+                && !config.is_junk(&file)
+                && !manifest.is_paused(&file)
+                && !layout.file(&file).exists()
+                && !drift.never_pushed.contains(&file)
+            {
+                drift.never_pushed.push(file);
+            }
+        }
+    }
+
+    for file in all_shade_files {
+        if patterns.iter().any(|pattern| pattern.covers(&file)) || manifest.is_external(&file) {
+            continue;
+        }
+        if project_path.join(&file).is_file() {
+            drift.unexcluded.push(file);
+        } else {
+            drift.orphaned_shade.push(file);
+        }
+    }
+
+    Ok(drift)
+}
+
+fn print_drift(drift: &Drift) {
+    print_category(
+        "Exclude lines to normalize (repeated or non-canonical)",
+        drift.redundant_lines.iter().cloned(),
+        "--fix rewrites .git/info/exclude with one canonical entry per pattern",
+    );
+    print_category(
+        "Excluded but not in shade",
+        drift.patterns_without_shade.iter().map(|p| p.to_string()),
+        "push them, or remove the pattern if it's stale",
+    );
+    print_category(
+        "In shade but not excluded (exists locally)",
+        drift.unexcluded.iter().map(|f| f.display().to_string()),
+        "--fix adds them to .git/info/exclude",
+    );
+    print_category(
+        "In shade but not excluded (gone locally)",
+        drift.orphaned_shade.iter().map(|f| f.display().to_string()),
+        "--fix offers to delete them from shade",
+    );
+    print_category(
+        "Local files never pushed",
+        drift.never_pushed.iter().map(|f| f.display().to_string()),
+        "--fix pushes them",
+    );
+}
+
+fn print_category(title: &str, items: impl Iterator<Item = String>, hint: &str) {
+    let items: Vec<String> = items.collect();
+    if items.is_empty() {
+        return;
+    }
+
+    outln!("{} {} ({}):", "⚠".yellow(), title.bold(), items.len());
+    for item in &items {
+        outln!("  - {}", item);
+    }
+    outln!("  {}", hint.italic());
+    outln!();
+}
+
+fn confirm_delete(files: &[PathBuf], yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(ShadeError::ConfirmationRequired(format!(
+            "Deleting {} orphaned shade file(s)",
+            files.len()
+        )));
+    }
+
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "Delete {} shade file(s) that no longer exist locally?",
+            files.len()
+        ))
+        .default(false)
+        .interact()
+        .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?;
+
+    if !confirmed {
+        outln!("  {} Kept orphaned shade files", "→".blue());
+    }
+    Ok(confirmed)
+}
@@ -0,0 +1,300 @@
+use crate::commands::push::{push_project, PushOptions};
+use crate::commands::status::sync_state;
+use crate::core::{Config, HashCache, Manifest, ShadeLayout, ShadePaths, SyncState};
+use crate::error::{Result, ShadeError};
+use crate::git::{conflicted_files, read_patterns};
+use crate::utils::output::outln;
+use crate::utils::{load_tracker, resolve_project, tracker_path};
+use colored::Colorize;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+/// What the watch loop waits for
+enum Message {
+    Changed(PathBuf),
+    Stop,
+}
+
+pub fn run(
+    debounce_ms: u64,
+    verbose: bool,
+    project: Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    // 1. Setup paths
+    let paths = ShadePaths::new()?;
+
+    // 2. Load config
+    let config = Config::load(&paths.config)?;
+
+    // 3-4. Resolve the project (current git repo, or --project/--name) and verify it's initialized
+    let (project_path, project_name) = resolve_project(&config, project, name)?;
+
+    // 5. Work out what to watch: tracked directories recursively, tracked
+    // files through their parent (editors often save by replacing the file)
+    let patterns = read_patterns(&project_path)?;
+    let manifest = Manifest::load(&paths.manifest_file(&project_name))?;
+    let externals: Vec<PathBuf> = manifest
+        .externals()
+        .into_iter()
+        .map(|(_, source)| source)
+        .collect();
+
+    let mut watched = BTreeSet::new();
+    for pattern in &patterns {
+        let path = project_path.join(pattern.path());
+        if path.is_dir() {
+            watched.insert((path, RecursiveMode::Recursive));
+        } else if let Some(parent) = path.parent().filter(|parent| parent.is_dir()) {
+            watched.insert((parent.to_path_buf(), RecursiveMode::NonRecursive));
+        }
+    }
+    for source in &externals {
+        if let Some(parent) = source.parent().filter(|parent| parent.is_dir()) {
+            watched.insert((parent.to_path_buf(), RecursiveMode::NonRecursive));
+        }
+    }
+
+    if watched.is_empty() {
+        return Err(ShadeError::NoFilesTracked);
+    }
+
+    // 6. Start watching; file events and Ctrl-C both go through one channel
+    let (tx, rx) = mpsc::channel();
+
+    let events = tx.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if is_content_change(&event.kind) => {
+                for path in event.paths {
+                    let _ = events.send(Message::Changed(path));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "watch error"),
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to start watching: {}", e))?;
+
+    for (path, mode) in &watched {
+        watcher
+            .watch(path, *mode)
+            .map_err(|e| anyhow::anyhow!("Failed to watch {}: {}", path.display(), e))?;
+    }
+
+    ctrlc::set_handler(move || {
+        let _ = tx.send(Message::Stop);
+    })
+    .map_err(|e| anyhow::anyhow!("Failed to handle Ctrl-C: {}", e))?;
+
+    outln!(
+        "{} Watching {} tracked path(s) in {} (debounce {}ms). Press Ctrl-C to stop.",
+        "→".blue(),
+        patterns.len() + externals.len(),
+        project_name.bold(),
+        debounce_ms
+    );
+
+    // 7. Push after each burst of changes has settled
+    let tracked = |path: &Path| {
+        if externals.iter().any(|source| source == path) {
+            return true;
+        }
+        path.strip_prefix(&project_path).is_ok_and(|rel| {
+            patterns.iter().any(|pattern| pattern.covers(rel))
+                && !manifest.is_excluded(rel)
+                && !config.is_junk(rel)
+                && !manifest.is_paused(rel)
+        })
+    };
+    let debounce = Duration::from_millis(debounce_ms);
+    let options = PushOptions {
+        verbose,
+        ..PushOptions::default()
+    };
+
+    // Edits made while a push ran, for the next round
+    let mut carried = BTreeSet::new();
+    'watch: loop {
+        let mut changed = std::mem::take(&mut carried);
+        if changed.is_empty() {
+            match rx.recv() {
+                Ok(Message::Changed(path)) => {
+                    if tracked(&path) {
+                        changed.insert(path);
+                    }
+                }
+                Ok(Message::Stop) | Err(_) => break,
+            }
+        }
+
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(Message::Changed(path)) => {
+                    if tracked(&path) {
+                        changed.insert(path);
+                    }
+                }
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => break 'watch,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+
+        outln!();
+        outln!(
+            "{} {} Changed: {}",
+            "●".green(),
+            chrono::Local::now().format("%H:%M:%S"),
+            changed
+                .iter()
+                .map(|path| display_path(path, &project_path))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let conflicts = conflicted_files(&paths.projects)?;
+        if !conflicts.is_empty() {
+            outln!(
+                "  {} Shade repo has unresolved merge conflicts, skipping. Resolve them and run {}.",
+                "⚠".yellow(),
+                "git-shade pull".bold()
+            );
+            continue;
+        }
+
+        // Push copies every tracked file, so it would overwrite the shade side
+        let conflicts = changed_in_both(&paths, &config, &project_path, &project_name, &changed)?;
+        if !conflicts.is_empty() {
+            outln!(
+                "  {} Also changed in shade, skipping: {}. Run {} to settle it.",
+                "⚠".yellow(),
+                conflicts
+                    .iter()
+                    .map(|path| display_path(path, &project_path))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                "git-shade pull".bold()
+            );
+            continue;
+        }
+
+        // Keep watching whatever goes wrong; the next change retries
+        if let Err(e) = push_project(&paths, &project_path, &project_name, &options) {
+            outln!(
+                "  {} Auto-sync failed: {}",
+                "⚠".yellow(),
+                e.to_string().lines().next().unwrap_or_default()
+            );
+        }
+
+        // Hooks may have rewritten tracked files, and push touches them too;
+        // only files that now differ from their shade copy were edited meanwhile
+        let layout = ShadeLayout::new(&paths, &config, &project_name);
+        let manifest = Manifest::load(&paths.manifest_file(&project_name))?;
+        let mut hashes = HashCache::load(&paths.hash_cache_file(&project_name))?;
+        while let Ok(message) = rx.try_recv() {
+            match message {
+                Message::Stop => break 'watch,
+                Message::Changed(path) if tracked(&path) => {
+                    let pushed = shade_rel(&path, &project_path, &manifest)
+                        .map(|rel| layout.file(&rel))
+                        .filter(|shade| shade.is_file() && path.is_file());
+                    let edited = match pushed {
+                        Some(shade) => !hashes.same_contents(&path, &shade)?,
+                        None => true,
+                    };
+                    if edited {
+                        carried.insert(path);
+                    }
+                }
+                Message::Changed(_) => {}
+            }
+        }
+    }
+
+    outln!();
+    outln!("{} Stopped watching {}", "✓".green(), project_name);
+
+    Ok(())
+}
+
+/// Changed files whose shade copy changed too since this machine last synced them
+///
+/// `sync_state` calls a file pushed from here and edited again a conflict as
+/// well (both sides are newer than the last pull), so a conflict only counts
+/// when the shade copy no longer matches the version the manifest recorded.
+fn changed_in_both(
+    paths: &ShadePaths,
+    config: &Config,
+    project_path: &Path,
+    project_name: &str,
+    changed: &BTreeSet<PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    let layout = ShadeLayout::new(paths, config, project_name);
+    let manifest = Manifest::load(&paths.manifest_file(project_name))?;
+    let tracker = load_tracker(&tracker_path(paths, config, project_name)?);
+    let hash_cache_path = paths.hash_cache_file(project_name);
+    let mut hashes = HashCache::load(&hash_cache_path)?;
+
+    let mut conflicts = Vec::new();
+    for path in changed {
+        let Some(rel) = shade_rel(path, project_path, &manifest) else {
+            continue;
+        };
+        let shade = layout.file(&rel);
+        let state = sync_state(config, &mut hashes, path, &shade, tracker.last_pull)?;
+        let conflict = if manifest.is_conflicted(&rel) {
+            state != SyncState::InSync
+        } else if state == SyncState::Conflict {
+            match manifest.get(&rel) {
+                Some(synced) => hashes.hash(&shade)? != synced.hash,
+                None => true,
+            }
+        } else {
+            false
+        };
+        if conflict {
+            conflicts.push(path.clone());
+        }
+    }
+    hashes.save(&hash_cache_path)?;
+
+    Ok(conflicts)
+}
+
+/// The shade path of a watched file: project-relative, or its `add --as` path
+fn shade_rel(path: &Path, project_path: &Path, manifest: &Manifest) -> Option<PathBuf> {
+    if let Some((rel, _)) = manifest
+        .externals()
+        .into_iter()
+        .find(|(_, source)| source == path)
+    {
+        return Some(rel);
+    }
+    path.strip_prefix(project_path).ok().map(Path::to_path_buf)
+}
+
+/// Creations, writes, renames and removals (not reads or metadata-only changes,
+/// which push itself causes with `normalize_mtime`)
+fn is_content_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        _ => false,
+    }
+}
+
+fn display_path(path: &Path, project_path: &Path) -> String {
+    path.strip_prefix(project_path)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,204 @@
+use super::paths::is_shade_metadata;
+use super::tracker::is_sync_file;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Describes the archive; always the first entry
+const HEADER_FILE: &str = "git-shade-export.toml";
+/// Archive directory holding the project's shade files
+const SHADE_PREFIX: &str = "shade";
+/// Archive directory holding the project's metadata (manifest, .shade-sync)
+const METADATA_PREFIX: &str = "metadata";
+
+/// Header of an exported project archive
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveHeader {
+    pub project: String,
+    pub hostname: String,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Write a `.tar.gz` with the project's shade dir and metadata dir
+///
+/// Returns the number of shade files written (not counting the modes sidecar
+/// or empty-dir markers).
+pub fn write_archive(
+    out: &Path,
+    header: &ArchiveHeader,
+    shade_dir: &Path,
+    metadata_dir: &Path,
+) -> Result<usize> {
+    let file = File::create(out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let contents = toml::to_string_pretty(header).context("Failed to serialize header")?;
+    let mut entry_header = tar::Header::new_gnu();
+    entry_header.set_size(contents.len() as u64);
+    entry_header.set_mode(0o644);
+    entry_header.set_mtime(header.exported_at.timestamp().max(0) as u64);
+    entry_header.set_cksum();
+    builder.append_data(&mut entry_header, HEADER_FILE, contents.as_bytes())?;
+
+    let count = append_files(&mut builder, shade_dir, SHADE_PREFIX)?;
+    if metadata_dir.exists() {
+        append_files(&mut builder, metadata_dir, METADATA_PREFIX)?;
+    }
+
+    builder
+        .into_inner()?
+        .finish()
+        .context("Failed to finish archive")?;
+
+    Ok(count)
+}
+
+fn append_files<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    dir: &Path,
+    prefix: &str,
+) -> Result<usize> {
+    let mut count = 0;
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let rel = entry.path().strip_prefix(dir)?;
+        // Sync times are per machine
+        if is_sync_file(rel) {
+            continue;
+        }
+        builder
+            .append_path_with_name(entry.path(), Path::new(prefix).join(rel))
+            .with_context(|| format!("Failed to archive {}", entry.path().display()))?;
+        if !is_shade_metadata(rel) {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Read the header of an archive written by `write_archive`
+pub fn read_archive_header(archive: &Path) -> Result<ArchiveHeader> {
+    let mut tar = open_archive(archive)?;
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == Path::new(HEADER_FILE) {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            return toml::from_str(&contents).context("Failed to parse archive header");
+        }
+    }
+
+    bail!(
+        "{} is not a git-shade export (no {} inside)",
+        archive.display(),
+        HEADER_FILE
+    )
+}
+
+/// Extract the archive's shade files into `dest`
+///
+/// Files get a fresh mtime so the following pull sees them as changed
+/// remotely. Returns the extracted paths (without the modes sidecar or
+/// empty-dir markers), relative to `dest`.
+pub fn unpack_shade_files(archive: &Path, dest: &Path) -> Result<Vec<PathBuf>> {
+    let mut tar = open_archive(archive)?;
+    let mut files = Vec::new();
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?.into_owned();
+        let Ok(rel) = path.strip_prefix(SHADE_PREFIX) else {
+            continue;
+        };
+
+        if !rel
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("Refusing to extract unsafe path: {}", path.display());
+        }
+
+        let target = dest.join(rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        entry.set_preserve_mtime(false);
+        entry
+            .unpack(&target)
+            .with_context(|| format!("Failed to extract {}", rel.display()))?;
+        if !is_shade_metadata(rel) {
+            files.push(rel.to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
+
+fn open_archive(archive: &Path) -> Result<tar::Archive<GzDecoder<File>>> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let shade_dir = temp.path().join("shade/myapp");
+        let metadata_dir = temp.path().join("metadata/myapp");
+        fs::create_dir_all(shade_dir.join("secrets")).unwrap();
+        fs::create_dir_all(&metadata_dir).unwrap();
+        fs::write(shade_dir.join("config.local"), "a").unwrap();
+        fs::write(shade_dir.join("secrets/api.key"), "b").unwrap();
+        fs::write(metadata_dir.join(".shade-sync"), "").unwrap();
+
+        let header = ArchiveHeader {
+            project: "myapp".to_string(),
+            hostname: "laptop".to_string(),
+            exported_at: Utc::now(),
+        };
+        let archive = temp.path().join("myapp.tar.gz");
+        let count = write_archive(&archive, &header, &shade_dir, &metadata_dir).unwrap();
+        assert_eq!(count, 2);
+
+        assert_eq!(read_archive_header(&archive).unwrap().project, "myapp");
+
+        let dest = temp.path().join("dest");
+        let files = unpack_shade_files(&archive, &dest).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("config.local"),
+                PathBuf::from("secrets/api.key")
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("secrets/api.key")).unwrap(),
+            "b"
+        );
+        assert!(!dest.join(".shade-sync").exists());
+    }
+}
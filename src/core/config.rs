@@ -1,5 +1,9 @@
+use super::manifest::is_excluded;
+use super::secrets::DEFAULT_PATTERNS;
+use super::sync::SyncPolicy;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,21 +11,225 @@ pub struct Config {
     pub version: String,
     #[serde(default)] // If missing in TOML, use Vec::new()
     pub projects: Vec<Project>,
+    /// `add` asks for confirmation above this many bytes
+    #[serde(default = "default_max_add_size")]
+    pub max_add_size: u64,
+    /// `add` asks for confirmation above this many files
+    #[serde(default = "default_max_add_files")]
+    pub max_add_files: usize,
+    /// Patterns `init` looks for to suggest files to add
+    #[serde(default = "default_patterns")]
+    pub default_patterns: Vec<String>,
+    /// Editor/OS files never synced from tracked directories (see `is_junk`)
+    #[serde(default = "default_junk_patterns")]
+    pub junk_patterns: Vec<String>,
+    /// Keep empty directories inside tracked directories (via `.shade-keep` markers)
+    #[serde(default)]
+    pub preserve_empty_dirs: bool,
+    /// Give pushed and pulled files the time of their last shade commit as mtime,
+    /// so every machine sees the same timestamps
+    #[serde(default)]
+    pub normalize_mtime: bool,
+    /// Keep each project's `.shade-sync` in its shade dir (committed, one per
+    /// machine) instead of the metadata dir, so it moves along with the shade repo
+    #[serde(default)]
+    pub metadata_in_repo: bool,
+    /// Sign shade commits (`git commit -S`), for orgs that require signed commits
+    #[serde(default)]
+    pub sign_commits: bool,
+    /// Message for automatic push commits; see `render_commit_message`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_template: Option<String>,
+    /// Name pushes from this machine are labeled with, instead of the hostname
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_name: Option<String>,
+    /// How files are written into the shade repo
+    #[serde(default)]
+    pub storage: Storage,
+    /// How `pull` merges the remote into the shade repo; git's own
+    /// `pull.rebase`/`pull.ff` settings when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_strategy: Option<PullStrategy>,
+    /// How local files and their shade copies are compared
+    #[serde(default)]
+    pub sync: SyncPolicy,
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Named file lists for `add --template`, on top of the built-in ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, Vec<String>>,
+}
+
+/// How `add` and `push` write files into the shade repo
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    /// Every file is its own copy
+    #[default]
+    Copy,
+    /// A file identical to one already in the shade repo (any project) is
+    /// hardlinked to it instead of copied, falling back to a copy when that
+    /// isn't possible. Git still sees ordinary files.
+    Hardlink,
+}
+
+impl Storage {
+    fn as_str(self) -> &'static str {
+        match self {
+            Storage::Copy => "copy",
+            Storage::Hardlink => "hardlink",
+        }
+    }
+}
+
+/// How `pull` brings remote commits into the shade repo (`git pull` flags)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullStrategy {
+    /// Merge commit when both sides have new commits (`--no-rebase`)
+    Merge,
+    /// Replay local commits on top of the remote, for a linear history (`--rebase`)
+    Rebase,
+    /// Only fast-forward; fails when local commits aren't pushed yet (`--ff-only`)
+    FfOnly,
+}
+
+impl PullStrategy {
+    fn as_str(self) -> &'static str {
+        match self {
+            PullStrategy::Merge => "merge",
+            PullStrategy::Rebase => "rebase",
+            PullStrategy::FfOnly => "ff-only",
+        }
+    }
+
+    /// The `git pull` flag selecting it
+    pub fn git_flag(self) -> &'static str {
+        match self {
+            PullStrategy::Merge => "--no-rebase",
+            PullStrategy::Rebase => "--rebase",
+            PullStrategy::FfOnly => "--ff-only",
+        }
+    }
+}
+
+/// Environment variable that overrides `commit_template`
+pub const COMMIT_TEMPLATE_ENV: &str = "GIT_SHADE_COMMIT_TEMPLATE";
+
+/// Commit message used when no template is configured
+pub const DEFAULT_COMMIT_TEMPLATE: &str = "[{project}] Update from {hostname} - {timestamp}";
+
+/// Files editors and operating systems leave around, never meant to be synced
+///
+/// Same rules as `add --exclude`: matched against every name in the path.
+pub const DEFAULT_JUNK_PATTERNS: &[&str] = &[
+    ".DS_Store",
+    "._*",
+    "Thumbs.db",
+    "desktop.ini",
+    "*.swp",
+    "*.swo",
+    "*~",
+    "4913",
+    ".#*",
+];
+
+/// Shell commands run around push and pull, from the project root
+///
+/// A hook that exits non-zero aborts the operation.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Hooks {
+    /// Before local files are copied to shade
+    pub pre_push: Option<String>,
+    /// After the shade repo was committed and pushed
+    pub post_push: Option<String>,
+    /// Before the shade repo is pulled
+    pub pre_pull: Option<String>,
+    /// After files were copied into the project
+    pub post_pull: Option<String>,
+}
+
+/// Keys `git-shade config` can read and write, in display order
+pub const CONFIG_KEYS: &[&str] = &[
+    "version",
+    "max_add_size",
+    "max_add_files",
+    "default_patterns",
+    "junk_patterns",
+    "preserve_empty_dirs",
+    "normalize_mtime",
+    "metadata_in_repo",
+    "sign_commits",
+    "commit_template",
+    "machine_name",
+    "storage",
+    "pull_strategy",
+    "sync.mtime_tolerance_secs",
+    "sync.hash",
+    "sync.size_only",
+    "hooks.pre_push",
+    "hooks.post_push",
+    "hooks.pre_pull",
+    "hooks.post_pull",
+];
+
+fn default_max_add_size() -> u64 {
+    50 * 1024 * 1024
+}
+
+fn default_max_add_files() -> usize {
+    1000
+}
+
+fn default_patterns() -> Vec<String> {
+    DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+fn default_junk_patterns() -> Vec<String> {
+    DEFAULT_JUNK_PATTERNS
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: "1.0".to_string(),
+            projects: Vec::new(),
+            max_add_size: default_max_add_size(),
+            max_add_files: default_max_add_files(),
+            default_patterns: default_patterns(),
+            junk_patterns: default_junk_patterns(),
+            preserve_empty_dirs: false,
+            normalize_mtime: false,
+            metadata_in_repo: false,
+            sign_commits: false,
+            commit_template: None,
+            machine_name: None,
+            storage: Storage::default(),
+            pull_strategy: None,
+            sync: SyncPolicy::default(),
+            hooks: Hooks::default(),
+            templates: BTreeMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Project {
     pub name: String,
     pub local_path: PathBuf,
+    /// Turn on the per-OS layout (see `ShadeLayout`); push records it in the
+    /// shade repo, which is what every machine reads
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub per_os: bool,
 }
 
 impl Config {
     pub fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
-            return Ok(Self {
-                version: "1.0".to_string(),
-                projects: Vec::new(),
-            });
+            return Ok(Self::default());
         }
 
         let contents = std::fs::read_to_string(path).context("Failed to read config file")?;
@@ -49,16 +257,208 @@ impl Config {
             anyhow::bail!("Project already exists: {}", name);
         }
 
-        self.projects.push(Project { name, local_path });
+        self.projects.push(Project {
+            name,
+            local_path,
+            per_os: false,
+        });
         Ok(())
     }
 
+    /// Remove a project, returning whether it was registered
+    pub fn remove_project(&mut self, name: &str) -> bool {
+        let before = self.projects.len();
+        self.projects.retain(|p| p.name != name);
+        self.projects.len() != before
+    }
+
+    /// Template for push commits: `GIT_SHADE_COMMIT_TEMPLATE`, then config, then default
+    pub fn commit_template(&self) -> String {
+        std::env::var(COMMIT_TEMPLATE_ENV)
+            .ok()
+            .filter(|template| !template.trim().is_empty())
+            .or_else(|| self.commit_template.clone())
+            .unwrap_or_else(|| DEFAULT_COMMIT_TEMPLATE.to_string())
+    }
+
+    /// Label for this machine in commits: `machine_name`, else the hostname
+    pub fn machine_name(&self) -> String {
+        self.machine_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or_else(|| hostname::get().ok().and_then(|h| h.into_string().ok()))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Value of a config key as text, `None` if it's unset
+    ///
+    /// Lists are comma-separated. Fails for keys not in `CONFIG_KEYS`.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let value = match key {
+            "version" => Some(self.version.clone()),
+            "max_add_size" => Some(self.max_add_size.to_string()),
+            "max_add_files" => Some(self.max_add_files.to_string()),
+            "default_patterns" => Some(self.default_patterns.join(",")),
+            "junk_patterns" => Some(self.junk_patterns.join(",")),
+            "preserve_empty_dirs" => Some(self.preserve_empty_dirs.to_string()),
+            "normalize_mtime" => Some(self.normalize_mtime.to_string()),
+            "metadata_in_repo" => Some(self.metadata_in_repo.to_string()),
+            "sign_commits" => Some(self.sign_commits.to_string()),
+            "commit_template" => self.commit_template.clone(),
+            "machine_name" => self.machine_name.clone(),
+            "storage" => Some(self.storage.as_str().to_string()),
+            "pull_strategy" => self
+                .pull_strategy
+                .map(|strategy| strategy.as_str().to_string()),
+            "sync.mtime_tolerance_secs" => Some(self.sync.mtime_tolerance_secs.to_string()),
+            "sync.hash" => Some(self.sync.hash.to_string()),
+            "sync.size_only" => Some(self.sync.size_only.to_string()),
+            "hooks.pre_push" => self.hooks.pre_push.clone(),
+            "hooks.post_push" => self.hooks.post_push.clone(),
+            "hooks.pre_pull" => self.hooks.pre_pull.clone(),
+            "hooks.post_pull" => self.hooks.post_pull.clone(),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
+
+    /// Parse `value` for `key` and store it
+    ///
+    /// Lists are comma-separated; an empty value unsets optional keys.
+    /// `version` is managed by git-shade and can't be set.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let optional = |value: &str| (!value.trim().is_empty()).then(|| value.to_string());
+        let list = |value: &str| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        match key {
+            "version" => anyhow::bail!("version is managed by git-shade and can't be set"),
+            "max_add_size" => {
+                self.max_add_size = value.trim().parse().with_context(|| {
+                    format!("max_add_size must be a number of bytes, got '{}'", value)
+                })?;
+            }
+            "max_add_files" => {
+                self.max_add_files = value.trim().parse().with_context(|| {
+                    format!("max_add_files must be a number of files, got '{}'", value)
+                })?;
+            }
+            "default_patterns" => self.default_patterns = list(value),
+            "junk_patterns" => self.junk_patterns = list(value),
+            "preserve_empty_dirs" => {
+                self.preserve_empty_dirs = value.trim().parse().with_context(|| {
+                    format!("preserve_empty_dirs must be true or false, got '{}'", value)
+                })?;
+            }
+            "normalize_mtime" => {
+                self.normalize_mtime = value.trim().parse().with_context(|| {
+                    format!("normalize_mtime must be true or false, got '{}'", value)
+                })?;
+            }
+            "metadata_in_repo" => {
+                self.metadata_in_repo = value.trim().parse().with_context(|| {
+                    format!("metadata_in_repo must be true or false, got '{}'", value)
+                })?;
+            }
+            "sign_commits" => {
+                self.sign_commits = value.trim().parse().with_context(|| {
+                    format!("sign_commits must be true or false, got '{}'", value)
+                })?;
+            }
+            "commit_template" => self.commit_template = optional(value),
+            "machine_name" => self.machine_name = optional(value),
+            "storage" => {
+                self.storage = match value.trim() {
+                    "copy" => Storage::Copy,
+                    "hardlink" => Storage::Hardlink,
+                    other => anyhow::bail!("storage must be copy or hardlink, got '{}'", other),
+                };
+            }
+            "pull_strategy" => {
+                self.pull_strategy = match value.trim() {
+                    "" => None,
+                    "merge" => Some(PullStrategy::Merge),
+                    "rebase" => Some(PullStrategy::Rebase),
+                    "ff-only" => Some(PullStrategy::FfOnly),
+                    other => anyhow::bail!(
+                        "pull_strategy must be merge, rebase or ff-only, got '{}'",
+                        other
+                    ),
+                };
+            }
+            "sync.mtime_tolerance_secs" => {
+                self.sync.mtime_tolerance_secs = value.trim().parse().with_context(|| {
+                    format!(
+                        "sync.mtime_tolerance_secs must be a number of seconds, got '{}'",
+                        value
+                    )
+                })?;
+            }
+            "sync.hash" => {
+                self.sync.hash = value
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("sync.hash must be true or false, got '{}'", value))?;
+            }
+            "sync.size_only" => {
+                self.sync.size_only = value.trim().parse().with_context(|| {
+                    format!("sync.size_only must be true or false, got '{}'", value)
+                })?;
+            }
+            "hooks.pre_push" => self.hooks.pre_push = optional(value),
+            "hooks.post_push" => self.hooks.post_push = optional(value),
+            "hooks.pre_pull" => self.hooks.pre_pull = optional(value),
+            "hooks.post_pull" => self.hooks.post_pull = optional(value),
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    /// Whether `rel_path` is editor/OS junk (`.DS_Store`, swap files, ...)
+    /// that's left out when syncing tracked directories
+    pub fn is_junk(&self, rel_path: &Path) -> bool {
+        is_excluded(rel_path, &self.junk_patterns)
+    }
+
     // Returns Option (like Ruby's nil, Go's nil, Elixir's nil)
     pub fn find_project(&self, name: &str) -> Option<&Project> {
         self.projects.iter().find(|p| p.name == name)
     }
 }
 
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown config key: {}\n\nKnown keys: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+/// Fill in `{project}`, `{hostname}`, `{timestamp}` and `{count}` (files changed)
+///
+/// Unknown placeholders are left as they are.
+pub fn render_commit_message(
+    template: &str,
+    project: &str,
+    hostname: &str,
+    timestamp: &str,
+    count: usize,
+) -> String {
+    template
+        .replace("{project}", project)
+        .replace("{hostname}", hostname)
+        .replace("{timestamp}", timestamp)
+        .replace("{count}", &count.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,8 +470,9 @@ mod tests {
         let config_path = temp.path().join("config.toml");
 
         let mut config = Config {
-            version: "1.0".to_string(),
-            projects: Vec::new(),
+            max_add_size: 1024,
+            max_add_files: 10,
+            ..Config::default()
         };
 
         config
@@ -83,8 +484,132 @@ mod tests {
 
         config.save(&config_path).unwrap();
 
-        let loaded = Config::load(&config_path).unwrap();
+        let mut loaded = Config::load(&config_path).unwrap();
         assert_eq!(loaded.projects.len(), 1);
         assert_eq!(loaded.projects[0].name, "myapp");
+        assert_eq!(loaded.max_add_size, 1024);
+        assert_eq!(loaded.max_add_files, 10);
+
+        assert!(loaded.remove_project("myapp"));
+        assert!(!loaded.remove_project("myapp"));
+        assert!(loaded.projects.is_empty());
+    }
+
+    #[test]
+    fn test_config_add_limits_default_when_missing() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("config.toml");
+        std::fs::write(&config_path, "version = \"1.0\"\n").unwrap();
+
+        let loaded = Config::load(&config_path).unwrap();
+        assert_eq!(loaded.max_add_size, 50 * 1024 * 1024);
+        assert_eq!(loaded.max_add_files, 1000);
+        assert!(loaded.default_patterns.contains(&".env.local".to_string()));
+        assert!(loaded.hooks.pre_push.is_none());
+        assert_eq!(loaded.sync, SyncPolicy::default());
+    }
+
+    #[test]
+    fn test_config_hooks_section() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "version = \"1.0\"\n\n[hooks]\npre_push = \"sops -e -i secrets.yml\"\n",
+        )
+        .unwrap();
+
+        let loaded = Config::load(&config_path).unwrap();
+        assert_eq!(
+            loaded.hooks.pre_push.as_deref(),
+            Some("sops -e -i secrets.yml")
+        );
+        assert!(loaded.hooks.post_pull.is_none());
+    }
+
+    #[test]
+    fn test_render_commit_message() {
+        let default = render_commit_message(
+            DEFAULT_COMMIT_TEMPLATE,
+            "myapp",
+            "laptop",
+            "2025-10-18 14:30:00",
+            3,
+        );
+        assert_eq!(default, "[myapp] Update from laptop - 2025-10-18 14:30:00");
+
+        let custom = render_commit_message(
+            "chore({project}): sync {count} file(s) {branch}",
+            "myapp",
+            "laptop",
+            "2025-10-18 14:30:00",
+            3,
+        );
+        assert_eq!(custom, "chore(myapp): sync 3 file(s) {branch}");
+    }
+
+    #[test]
+    fn test_config_get_and_set_keys() {
+        let mut config = Config::default();
+
+        config.set("max_add_files", "20").unwrap();
+        config.set("preserve_empty_dirs", "true").unwrap();
+        config.set("normalize_mtime", "true").unwrap();
+        config.set("metadata_in_repo", "true").unwrap();
+        config.set("default_patterns", ".env, *.key").unwrap();
+        config.set("hooks.pre_push", "make encrypt").unwrap();
+        config.set("storage", "hardlink").unwrap();
+        config.set("sync.mtime_tolerance_secs", "5").unwrap();
+        config.set("sync.size_only", "true").unwrap();
+        config.set("sync.hash", "true").unwrap();
+        config.set("machine_name", "work-vm").unwrap();
+        config.set("pull_strategy", "ff-only").unwrap();
+
+        assert_eq!(config.storage, Storage::Hardlink);
+        assert_eq!(config.sync.mtime_tolerance_secs, 5);
+        assert!(config.sync.size_only);
+        assert!(config.sync.hash);
+        assert_eq!(config.machine_name(), "work-vm");
+        assert_eq!(config.pull_strategy, Some(PullStrategy::FfOnly));
+        assert_eq!(
+            config.get("pull_strategy").unwrap().as_deref(),
+            Some("ff-only")
+        );
+        assert_eq!(config.get("max_add_files").unwrap().as_deref(), Some("20"));
+        assert!(config.preserve_empty_dirs);
+        assert!(config.normalize_mtime);
+        assert!(config.metadata_in_repo);
+        assert_eq!(config.default_patterns, vec![".env", "*.key"]);
+        assert_eq!(
+            config.get("hooks.pre_push").unwrap().as_deref(),
+            Some("make encrypt")
+        );
+
+        // Empty value unsets optional keys
+        config.set("hooks.pre_push", "").unwrap();
+        assert_eq!(config.get("hooks.pre_push").unwrap(), None);
+
+        assert!(config.set("max_add_size", "lots").is_err());
+        assert!(config.set("preserve_empty_dirs", "yes").is_err());
+        assert!(config.set("storage", "symlink").is_err());
+        assert!(config.set("pull_strategy", "squash").is_err());
+        assert!(config.set("sync.hash", "maybe").is_err());
+        assert!(config.set("version", "2.0").is_err());
+        assert!(config.get("nope").is_err());
+        assert_eq!(config.max_add_files, 20);
+    }
+
+    #[test]
+    fn test_config_is_junk() {
+        let mut config = Config::default();
+        assert!(config.is_junk(Path::new("secrets/.DS_Store")));
+        assert!(config.is_junk(Path::new("secrets/prod/.api.key.swp")));
+        assert!(config.is_junk(Path::new("config/4913")));
+        assert!(!config.is_junk(Path::new("secrets/api.key")));
+
+        // The list can be replaced (or emptied to sync everything)
+        config.set("junk_patterns", "*.bak").unwrap();
+        assert!(config.is_junk(Path::new("secrets/api.key.bak")));
+        assert!(!config.is_junk(Path::new("secrets/.DS_Store")));
     }
 }
@@ -1,13 +1,27 @@
+use super::paths::shown_projects;
 use chrono::{DateTime, Utc};
 use colored::Colorize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// What happened to a file on each side of a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Changed both locally and in shade
+    ContentConflict,
+    /// Removed from shade (on another machine) but changed locally since the last sync
+    DeleteEditConflict,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConflictInfo {
     pub file: PathBuf,
+    pub kind: ConflictKind,
     pub local_modified: DateTime<Utc>,
+    /// When the shade file last changed, or was deleted
     pub remote_modified: DateTime<Utc>,
-    pub last_pull: DateTime<Utc>,
+    /// `None` when this machine has never pulled
+    pub last_pull: Option<DateTime<Utc>>,
 }
 
 impl ConflictInfo {
@@ -15,15 +29,66 @@ impl ConflictInfo {
         file: PathBuf,
         local_modified: DateTime<Utc>,
         remote_modified: DateTime<Utc>,
-        last_pull: DateTime<Utc>,
+        last_pull: Option<DateTime<Utc>>,
     ) -> Self {
         Self {
             file,
+            kind: ConflictKind::ContentConflict,
             local_modified,
             remote_modified,
             last_pull,
         }
     }
+
+    /// A file deleted from shade at `deleted_at` that was edited locally
+    pub fn deleted(
+        file: PathBuf,
+        local_modified: DateTime<Utc>,
+        deleted_at: DateTime<Utc>,
+        last_pull: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            kind: ConflictKind::DeleteEditConflict,
+            ..Self::new(file, local_modified, deleted_at, last_pull)
+        }
+    }
+}
+
+/// How `pull` settles a file changed on both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ConflictStrategy {
+    /// Keep whichever side was modified last
+    Newest,
+    /// Keep the local file
+    Local,
+    /// Take the shade (remote) file
+    Remote,
+    /// Stop and report the conflicts
+    #[default]
+    Abort,
+}
+
+/// Side of a conflict that a strategy keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictSide {
+    Local,
+    Remote,
+}
+
+impl ConflictStrategy {
+    /// The side to keep, or `None` when the conflict must be reported
+    ///
+    /// `Newest` keeps local on a tie, so nothing is overwritten without reason.
+    pub fn resolve(&self, conflict: &ConflictInfo) -> Option<ConflictSide> {
+        match self {
+            ConflictStrategy::Newest if conflict.remote_modified > conflict.local_modified => {
+                Some(ConflictSide::Remote)
+            }
+            ConflictStrategy::Newest | ConflictStrategy::Local => Some(ConflictSide::Local),
+            ConflictStrategy::Remote => Some(ConflictSide::Remote),
+            ConflictStrategy::Abort => None,
+        }
+    }
 }
 
 /// Format conflict information into a user-friendly message
@@ -31,40 +96,183 @@ pub fn format_conflict_message(conflicts: &[ConflictInfo], shade_dir: &std::path
     let mut message = String::new();
 
     message.push_str(&format!("{} CONFLICTS DETECTED\n\n", "⚠".red().bold()));
-    message.push_str(
-        "The following files were modified both locally and remotely since last pull:\n\n",
-    );
 
-    for conflict in conflicts {
-        message.push_str(&format!("  {} {}\n", "⚠".yellow(), conflict.file.display()));
+    let (edited, deleted): (Vec<&ConflictInfo>, Vec<&ConflictInfo>) = conflicts
+        .iter()
+        .partition(|conflict| conflict.kind == ConflictKind::ContentConflict);
+
+    if !edited.is_empty() {
+        message.push_str(
+            "The following files were modified both locally and remotely since last pull:\n\n",
+        );
+
+        for conflict in &edited {
+            let since = since_last_pull(conflict);
+            message.push_str(&format!("  {} {}\n", "⚠".yellow(), conflict.file.display()));
+            message.push_str(&format!(
+                "    Local:  modified {} ({})\n",
+                conflict.local_modified.format("%Y-%m-%d %H:%M:%S"),
+                since
+            ));
+            message.push_str(&format!(
+                "    Remote: modified {} ({})\n",
+                conflict.remote_modified.format("%Y-%m-%d %H:%M:%S"),
+                since
+            ));
+            message.push('\n');
+        }
+
+        message.push_str("Manual resolution required:\n");
+        message.push_str(&format!("  1. Go to {}\n", shade_dir.display()));
+        message.push_str("  2. Review the remote versions\n");
+        message.push_str("  3. Choose which version to keep, OR manually merge\n");
+        message.push_str("  4. Copy resolved files to your project\n");
+        message.push_str(&format!(
+            "  5. OR use {} to overwrite local with remote\n",
+            "git-shade pull --force".bold()
+        ));
+        message.push('\n');
+    }
+
+    if !deleted.is_empty() {
+        message.push_str(
+            "The following files were deleted remotely but modified locally since last pull:\n\n",
+        );
+
+        for conflict in &deleted {
+            let since = since_last_pull(conflict);
+            message.push_str(&format!("  {} {}\n", "⚠".yellow(), conflict.file.display()));
+            message.push_str(&format!(
+                "    Local:  modified {} ({})\n",
+                conflict.local_modified.format("%Y-%m-%d %H:%M:%S"),
+                since
+            ));
+            message.push_str(&format!(
+                "    Remote: deleted {}\n",
+                conflict.remote_modified.format("%Y-%m-%d %H:%M:%S")
+            ));
+            message.push('\n');
+        }
+
+        message.push_str("Decide whether the file should still exist:\n");
         message.push_str(&format!(
-            "    Local:  modified {} (after last pull at {})\n",
-            conflict.local_modified.format("%Y-%m-%d %H:%M:%S"),
-            conflict.last_pull.format("%Y-%m-%d %H:%M:%S")
+            "  - Keep it: {} then {} to put it back in shade\n",
+            "git-shade pull --strategy local".bold(),
+            "git-shade push".bold()
         ));
         message.push_str(&format!(
-            "    Remote: modified {} (after last pull at {})\n",
-            conflict.remote_modified.format("%Y-%m-%d %H:%M:%S"),
-            conflict.last_pull.format("%Y-%m-%d %H:%M:%S")
+            "  - Accept the deletion: {} (your copy is backed up first)\n",
+            "git-shade pull --strategy remote".bold()
         ));
         message.push('\n');
     }
 
-    message.push_str("Manual resolution required:\n");
-    message.push_str(&format!("  1. Go to {}\n", shade_dir.display()));
-    message.push_str("  2. Review the remote versions\n");
-    message.push_str("  3. Choose which version to keep, OR manually merge\n");
-    message.push_str("  4. Copy resolved files to your project\n");
+    message.push_str("Aborted. No files were modified.");
+
+    message
+}
+
+fn since_last_pull(conflict: &ConflictInfo) -> String {
+    match conflict.last_pull {
+        Some(last_pull) => format!(
+            "after last pull at {}",
+            last_pull.format("%Y-%m-%d %H:%M:%S")
+        ),
+        None => "never pulled on this machine".to_string(),
+    }
+}
+
+/// Format a git merge conflict inside the shade repo itself, grouped by project
+pub fn format_merge_conflict_message(files: &[String], operation: &str) -> String {
+    let mut message = String::new();
+
     message.push_str(&format!(
-        "  5. OR use {} to overwrite local with remote\n",
-        "git-shade pull --force".bold()
+        "{} MERGE CONFLICT IN SHADE REPO\n\n",
+        "⚠".red().bold()
     ));
+    message.push_str(
+        "git pull found files changed differently on another machine and in your shade repo:\n\n",
+    );
+
+    let mut projects: Vec<&str> = files
+        .iter()
+        .map(|f| f.split('/').next().unwrap_or(f))
+        .collect();
+    projects.dedup();
+
+    for project in projects {
+        message.push_str(&format!("  {}\n", project.bold()));
+        for file in files
+            .iter()
+            .filter(|f| f.split('/').next() == Some(project))
+        {
+            message.push_str(&format!("    {} {}\n", "⚠".yellow(), file));
+        }
+    }
     message.push('\n');
-    message.push_str("Aborted. No files were modified.");
+
+    let finish = if operation == "rebase" {
+        "git rebase --continue"
+    } else {
+        "git commit"
+    };
+    message.push_str(&format!(
+        "The shade repo is left mid-{}. To resolve:\n",
+        operation
+    ));
+    message.push_str(&format!("  1. cd {}\n", shown_projects()));
+    message.push_str("  2. Edit each file above and remove the conflict markers\n");
+    message.push_str(&format!("  3. git add <files> && {}\n", finish));
+    message.push_str(&format!("  4. Run {} again\n", "git-shade pull".bold()));
+    message.push_str(&format!(
+        "  OR abort the {} with: git {} --abort\n",
+        operation, operation
+    ));
+    message.push('\n');
+    message.push_str("No files in your project were modified.");
 
     message
 }
 
+/// Pairs of paths that differ only by case
+///
+/// On a case-insensitive filesystem (macOS and Windows by default) they are
+/// the same file, so syncing both makes one silently overwrite the other.
+/// Each later path is paired with the first one it collides with.
+pub fn case_collisions(files: &[PathBuf]) -> Vec<(PathBuf, PathBuf)> {
+    let mut seen: HashMap<String, &PathBuf> = HashMap::new();
+    let mut collisions = Vec::new();
+
+    for file in files {
+        let folded = file.to_string_lossy().to_lowercase();
+        match seen.get(&folded) {
+            Some(first) if *first != file => collisions.push(((*first).clone(), file.clone())),
+            Some(_) => {}
+            None => {
+                seen.insert(folded, file);
+            }
+        }
+    }
+
+    collisions
+}
+
+pub fn format_case_collision_warning(collisions: &[(PathBuf, PathBuf)]) -> String {
+    let mut message = format!(
+        "{} {} tracked path(s) differ only by case:\n",
+        "⚠".yellow(),
+        collisions.len()
+    );
+    for (first, second) in collisions {
+        message.push_str(&format!("    {} / {}\n", first.display(), second.display()));
+    }
+    message.push_str(
+        "  On a case-insensitive filesystem (macOS, Windows) they are the same file and\n\
+         \x20 overwrite each other. Rename one of them with git-shade mv.",
+    );
+    message
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,7 +284,7 @@ mod tests {
             PathBuf::from("config.local"),
             Utc::now(),
             Utc::now(),
-            Utc::now() - chrono::Duration::hours(1),
+            Some(Utc::now() - chrono::Duration::hours(1)),
         )];
 
         let message = format_conflict_message(&conflicts, &PathBuf::from("/test/shade"));
@@ -85,4 +293,112 @@ mod tests {
         assert!(message.contains("config.local"));
         assert!(message.contains("Manual resolution required"));
     }
+
+    #[test]
+    fn test_format_conflict_message_never_pulled() {
+        let conflicts = vec![ConflictInfo::new(
+            PathBuf::from(".env.local"),
+            Utc::now(),
+            Utc::now(),
+            None,
+        )];
+
+        let message = format_conflict_message(&conflicts, &PathBuf::from("/test/shade"));
+
+        assert!(message.contains(".env.local"));
+        assert!(message.contains("never pulled on this machine"));
+    }
+
+    #[test]
+    fn test_format_conflict_message_delete_edit() {
+        let conflicts = vec![
+            ConflictInfo::new(PathBuf::from("config.local"), Utc::now(), Utc::now(), None),
+            ConflictInfo::deleted(PathBuf::from("old.env"), Utc::now(), Utc::now(), None),
+        ];
+        assert_eq!(conflicts[1].kind, ConflictKind::DeleteEditConflict);
+
+        let message = format_conflict_message(&conflicts, &PathBuf::from("/test/shade"));
+
+        assert!(message.contains("deleted remotely but modified locally"));
+        assert!(message.contains("old.env"));
+        assert!(message.contains("Remote: deleted"));
+        assert!(message.contains("--strategy remote"));
+        assert!(message.contains("Manual resolution required"));
+    }
+
+    #[test]
+    fn test_format_merge_conflict_message() {
+        let files = vec![
+            "myapp/config.local".to_string(),
+            "myapp/secrets/api.key".to_string(),
+            "other/.env".to_string(),
+        ];
+
+        let message = format_merge_conflict_message(&files, "merge");
+
+        assert!(message.contains("MERGE CONFLICT IN SHADE REPO"));
+        assert!(message.contains("myapp/secrets/api.key"));
+        assert!(message.contains("other/.env"));
+        assert!(message.contains("git add <files> && git commit"));
+        assert!(message.contains("git merge --abort"));
+
+        let message = format_merge_conflict_message(&files, "rebase");
+        assert!(message.contains("left mid-rebase"));
+        assert!(message.contains("git rebase --continue"));
+        assert!(message.contains("git rebase --abort"));
+    }
+
+    #[test]
+    fn test_case_collisions() {
+        let files = vec![
+            PathBuf::from("Config.local"),
+            PathBuf::from("config.local"),
+            PathBuf::from("secrets/API.key"),
+            PathBuf::from("Secrets/api.key"),
+            PathBuf::from("other.env"),
+            PathBuf::from("other.env"),
+        ];
+
+        let collisions = case_collisions(&files);
+        assert_eq!(
+            collisions,
+            vec![
+                (PathBuf::from("Config.local"), PathBuf::from("config.local")),
+                (
+                    PathBuf::from("secrets/API.key"),
+                    PathBuf::from("Secrets/api.key")
+                ),
+            ]
+        );
+
+        let message = format_case_collision_warning(&collisions);
+        assert!(message.contains("2 tracked path(s) differ only by case"));
+        assert!(message.contains("Config.local / config.local"));
+    }
+
+    #[test]
+    fn test_conflict_strategy_resolve() {
+        let earlier = Utc::now() - chrono::Duration::minutes(5);
+        let later = Utc::now();
+        let remote_newer = ConflictInfo::new(PathBuf::from("a"), earlier, later, None);
+        let local_newer = ConflictInfo::new(PathBuf::from("a"), later, earlier, None);
+
+        assert_eq!(
+            ConflictStrategy::Newest.resolve(&remote_newer),
+            Some(ConflictSide::Remote)
+        );
+        assert_eq!(
+            ConflictStrategy::Newest.resolve(&local_newer),
+            Some(ConflictSide::Local)
+        );
+        assert_eq!(
+            ConflictStrategy::Local.resolve(&remote_newer),
+            Some(ConflictSide::Local)
+        );
+        assert_eq!(
+            ConflictStrategy::Remote.resolve(&local_newer),
+            Some(ConflictSide::Remote)
+        );
+        assert_eq!(ConflictStrategy::Abort.resolve(&remote_newer), None);
+    }
 }
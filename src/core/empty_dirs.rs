@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Marker committed in the shade copy of an empty directory
+///
+/// Git can't store empty directories, so with `preserve_empty_dirs` push writes
+/// this file into them and pull recreates the directory instead of copying it.
+pub const KEEP_FILE: &str = ".shade-keep";
+
+pub fn is_keep_file(rel_path: &Path) -> bool {
+    rel_path.file_name().is_some_and(|name| name == KEEP_FILE)
+}
+
+/// Empty directories at or under `base/rel_dir`, relative to `base`
+pub fn find_empty_dirs(base: &Path, rel_dir: &Path) -> Result<Vec<PathBuf>> {
+    let root = base.join(rel_dir);
+    let mut dirs = Vec::new();
+
+    if !root.is_dir() {
+        return Ok(dirs);
+    }
+
+    for entry in WalkDir::new(&root) {
+        let entry = entry?;
+        if entry.file_type().is_dir() && fs::read_dir(entry.path())?.next().is_none() {
+            if let Ok(rel) = entry.path().strip_prefix(base) {
+                dirs.push(rel.to_path_buf());
+            }
+        }
+    }
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Write a keep marker into the shade copy of each empty directory
+pub fn mark_empty_dirs(empty_dirs: &[PathBuf], shade_dir: &Path) -> Result<()> {
+    for dir in empty_dirs {
+        let target = shade_dir.join(dir);
+        fs::create_dir_all(&target)?;
+        fs::write(target.join(KEEP_FILE), "")?;
+    }
+    Ok(())
+}
+
+/// Directories marked as empty in `shade_dir`, relative to it
+pub fn list_marked_dirs(shade_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+
+    if !shade_dir.exists() {
+        return Ok(dirs);
+    }
+
+    for entry in WalkDir::new(shade_dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.file_name() == KEEP_FILE {
+            if let Some(dir) = entry
+                .path()
+                .parent()
+                .and_then(|parent| parent.strip_prefix(shade_dir).ok())
+            {
+                dirs.push(dir.to_path_buf());
+            }
+        }
+    }
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_mark_and_list_empty_dirs() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let shade = temp.path().join("shade");
+        fs::create_dir_all(project.join("data/logs")).unwrap();
+        fs::create_dir_all(project.join("data/cache/tmp")).unwrap();
+        fs::write(project.join("data/seed.sql"), "").unwrap();
+
+        let empty = find_empty_dirs(&project, Path::new("data")).unwrap();
+        assert_eq!(
+            empty,
+            vec![PathBuf::from("data/cache/tmp"), PathBuf::from("data/logs")]
+        );
+
+        mark_empty_dirs(&empty, &shade).unwrap();
+        assert!(is_keep_file(Path::new("data/logs/.shade-keep")));
+        assert_eq!(list_marked_dirs(&shade).unwrap(), empty);
+    }
+}
@@ -0,0 +1,161 @@
+use super::manifest::hash_file;
+use super::sync::same_contents_by;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Hashes of local and shade files, reused while a file's size and mtime stay put
+///
+/// Lives next to the manifest as `hash-cache.toml`. Any change to size or
+/// mtime, including an mtime that moves backward (e.g. a restored backup),
+/// forces a rehash.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HashCache {
+    #[serde(default)]
+    files: BTreeMap<String, CachedHash>,
+    #[serde(skip)]
+    hashed: usize,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct CachedHash {
+    size: u64,
+    modified: DateTime<Utc>,
+    hash: String,
+}
+
+impl HashCache {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).context("Failed to read hash cache")?;
+        // A cache that can't be parsed is only a missed optimization
+        Ok(toml::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable hash cache");
+            Self::default()
+        }))
+    }
+
+    /// Write the cache if anything was hashed, dropping files that no longer exist
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        self.files.retain(|file, _| Path::new(file).exists());
+        let contents = toml::to_string_pretty(self).context("Failed to serialize hash cache")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, contents).context("Failed to write hash cache")?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// SHA-256 of `path`, read from disk only if it changed since it was cached
+    pub fn hash(&mut self, path: &Path) -> Result<String> {
+        let metadata =
+            fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let size = metadata.len();
+        let modified: DateTime<Utc> = metadata.modified()?.into();
+        let key = cache_key(path);
+
+        if let Some(cached) = self.files.get(&key) {
+            if cached.size == size && cached.modified == modified {
+                return Ok(cached.hash.clone());
+            }
+        }
+
+        let hash = hash_file(path)?;
+        self.hashed += 1;
+        self.dirty = true;
+        self.files.insert(
+            key,
+            CachedHash {
+                size,
+                modified,
+                hash: hash.clone(),
+            },
+        );
+        Ok(hash)
+    }
+
+    /// `core::same_contents`, reusing cached hashes
+    pub fn same_contents(&mut self, a: &Path, b: &Path) -> Result<bool> {
+        same_contents_by(a, b, |path| self.hash(path))
+    }
+
+    /// Number of files actually read since the cache was loaded
+    pub fn hashed(&self) -> usize {
+        self.hashed
+    }
+}
+
+fn cache_key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::FileTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hash_cache_skips_unchanged_files() {
+        let temp = TempDir::new().unwrap();
+        let cache_path = temp.path().join("hash-cache.toml");
+        let local = temp.path().join("local");
+        let shade = temp.path().join("shade");
+        fs::create_dir_all(&local).unwrap();
+        fs::create_dir_all(&shade).unwrap();
+        for i in 0..50 {
+            let contents = format!("file {}", i);
+            fs::write(local.join(format!("{}.conf", i)), &contents).unwrap();
+            fs::write(shade.join(format!("{}.conf", i)), &contents).unwrap();
+        }
+
+        // Two status-like passes over the same tree, each with a fresh load
+        let mut hashed = Vec::new();
+        for _ in 0..2 {
+            let mut cache = HashCache::load(&cache_path).unwrap();
+            for i in 0..50 {
+                let name = format!("{}.conf", i);
+                assert!(cache
+                    .same_contents(&local.join(&name), &shade.join(&name))
+                    .unwrap());
+            }
+            hashed.push(cache.hashed());
+            cache.save(&cache_path).unwrap();
+        }
+
+        assert_eq!(hashed, vec![100, 0]);
+    }
+
+    #[test]
+    fn test_hash_cache_rehashes_when_mtime_moves_backward() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("config.local");
+        fs::write(&file, "aaaa").unwrap();
+
+        let mut cache = HashCache::default();
+        let first = cache.hash(&file).unwrap();
+
+        // Same size, older mtime: e.g. a backup restored over the file
+        fs::write(&file, "bbbb").unwrap();
+        let yesterday = FileTime::from_unix_time(FileTime::now().unix_seconds() - 86400, 0);
+        filetime::set_file_mtime(&file, yesterday).unwrap();
+
+        assert_ne!(cache.hash(&file).unwrap(), first);
+        assert_eq!(cache.hashed(), 2);
+    }
+}
@@ -0,0 +1,193 @@
+use super::config::Config;
+use super::paths::{is_shade_metadata, to_slash, ShadePaths};
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Values of `std::env::consts::OS` that name a per-OS directory
+pub const OS_DIRS: &[&str] = &[
+    "linux",
+    "macos",
+    "windows",
+    "freebsd",
+    "openbsd",
+    "netbsd",
+    "dragonfly",
+    "solaris",
+    "illumos",
+    "android",
+    "ios",
+];
+
+/// Marker in a project's shade dir saying its `<os>/` directories hold
+/// per-OS variants. Committed, so every machine reads the layout the same way
+pub const PER_OS_FILE: &str = ".shade-per-os";
+
+/// Where a project's files live in its shade dir
+///
+/// Normally that's `<project>/<file>`. With the per-OS layout, a file may also
+/// have a variant in `<project>/<os>/<file>`: this machine reads and writes its
+/// OS's variant when there is one, the shared `<project>/<file>` otherwise.
+/// Other OSes' directories are left alone.
+#[derive(Debug, Clone)]
+pub struct ShadeLayout {
+    shared: PathBuf,
+    os: Option<&'static str>,
+}
+
+impl ShadeLayout {
+    /// The project's layout: per-OS if the shade repo records it (or this
+    /// machine's config turns it on and push hasn't recorded it yet)
+    pub fn new(paths: &ShadePaths, config: &Config, project_name: &str) -> Self {
+        let shared = paths.project_shade_dir(project_name);
+        let per_os = shared.join(PER_OS_FILE).exists()
+            || config
+                .find_project(project_name)
+                .is_some_and(|project| project.per_os);
+        Self::with_os(shared, per_os.then_some(std::env::consts::OS))
+    }
+
+    /// Record the per-OS layout in the shade dir, returning whether it was new
+    pub fn enable(&mut self) -> Result<bool> {
+        self.os = Some(std::env::consts::OS);
+        let marker = self.shared.join(PER_OS_FILE);
+        if marker.exists() {
+            return Ok(false);
+        }
+
+        std::fs::create_dir_all(&self.shared)?;
+        std::fs::write(
+            &marker,
+            "Files under an OS directory (linux/, macos/, ...) are that OS's variants\n",
+        )?;
+        Ok(true)
+    }
+
+    fn with_os(shared: PathBuf, os: Option<&'static str>) -> Self {
+        Self { shared, os }
+    }
+
+    /// The project's shade dir
+    pub fn shared_dir(&self) -> &Path {
+        &self.shared
+    }
+
+    /// This OS's variant directory, with the per-OS layout
+    pub fn os_dir(&self) -> Option<PathBuf> {
+        self.os.map(|os| self.shared.join(os))
+    }
+
+    /// Path to read and write `rel`: this OS's variant if there is one, so
+    /// edits to a shared file stay shared
+    pub fn file(&self, rel: &Path) -> PathBuf {
+        if let Some(os) = self.os {
+            let variant = self.shared.join(os).join(rel);
+            if variant.exists() {
+                return variant;
+            }
+        }
+        self.shared.join(rel)
+    }
+
+    /// `rel` as a path in the shade repo (`<project>/[<os>/]<file>`)
+    pub fn repo_path(&self, project_name: &str, rel: &Path) -> String {
+        let file = self.file(rel);
+        let rel = file.strip_prefix(&self.shared).unwrap_or(rel);
+        format!("{}/{}", project_name, to_slash(rel))
+    }
+
+    /// The project-relative path of a file found at `raw` in the shade dir,
+    /// `None` for another OS's variant
+    pub fn logical(&self, raw: &Path) -> Option<PathBuf> {
+        let Some(os) = self.os else {
+            return Some(raw.to_path_buf());
+        };
+        let mut components = raw.components();
+        let first = components.next();
+        let rest = components.as_path();
+        match first {
+            Some(Component::Normal(first))
+                if OS_DIRS.iter().any(|dir| first == *dir) && !rest.as_os_str().is_empty() =>
+            {
+                (first == os).then(|| rest.to_path_buf())
+            }
+            _ => Some(raw.to_path_buf()),
+        }
+    }
+
+    /// Every file this machine sees in the shade dir, project-relative and sorted
+    pub fn list_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        if !self.shared.exists() {
+            return Ok(files);
+        }
+
+        for entry in WalkDir::new(&self.shared).min_depth(1) {
+            let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read directory: {}", e))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(raw) = entry.path().strip_prefix(&self.shared) else {
+                continue;
+            };
+            if let Some(rel) = self.logical(raw).filter(|rel| !is_shade_metadata(rel)) {
+                files.push(rel);
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_per_os_layout_prefers_this_os_variant() {
+        let temp = TempDir::new().unwrap();
+        let shared = temp.path().join("myapp");
+        let (this, other) = if std::env::consts::OS == "linux" {
+            ("linux", "macos")
+        } else {
+            (std::env::consts::OS, "linux")
+        };
+        fs::create_dir_all(shared.join(this)).unwrap();
+        fs::create_dir_all(shared.join(other)).unwrap();
+        fs::write(shared.join("config.local"), "shared").unwrap();
+        fs::write(shared.join("paths.local"), "shared").unwrap();
+        fs::write(shared.join(this).join("paths.local"), "this os").unwrap();
+        fs::write(shared.join(other).join("paths.local"), "other os").unwrap();
+        fs::write(shared.join(other).join("other.only"), "other os").unwrap();
+
+        let layout = ShadeLayout::with_os(shared.clone(), Some(this));
+        assert_eq!(
+            layout.list_files().unwrap(),
+            vec![PathBuf::from("config.local"), PathBuf::from("paths.local")]
+        );
+        assert_eq!(
+            layout.file(Path::new("paths.local")),
+            shared.join(this).join("paths.local")
+        );
+        assert_eq!(
+            layout.file(Path::new("config.local")),
+            shared.join("config.local")
+        );
+        assert_eq!(
+            layout.repo_path("myapp", Path::new("paths.local")),
+            format!("myapp/{}/paths.local", this)
+        );
+        assert_eq!(layout.os_dir(), Some(shared.join(this)));
+
+        // Without per_os the OS directories are ordinary tracked directories
+        let flat = ShadeLayout::with_os(shared.clone(), None);
+        assert!(flat
+            .list_files()
+            .unwrap()
+            .contains(&Path::new(other).join("other.only")));
+    }
+}
@@ -0,0 +1,493 @@
+use super::hash_cache::HashCache;
+use super::paths::to_slash;
+use anyhow::{Context, Result};
+use glob::Pattern as Glob;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Record of every file git-shade tracks for a project
+///
+/// Lives in the project's metadata dir, so it is what git-shade tracks
+/// regardless of what `.git/info/exclude` currently says.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub files: BTreeMap<String, ManifestEntry>,
+    /// Subpaths skipped inside tracked directories (`add --exclude`), by directory
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub excludes: BTreeMap<String, Vec<String>>,
+    /// Files from outside the project (`add --as`): shade path to absolute source
+    ///
+    /// `.git/info/exclude` can't name them, so this is the only record.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub external: BTreeMap<String, PathBuf>,
+    /// Tracked paths push and pull skip for now (`pause`); a directory covers its files
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub paused: BTreeSet<String>,
+    /// Paths added with `add --no-copy` that haven't been synced yet; a directory covers its files
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub pending: BTreeSet<String>,
+    /// Permission bits from `add --chmod`, applied on push and pull whatever the
+    /// local mode; a directory covers its files
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub chmod: BTreeMap<String, u32>,
+    /// Files `add` kept in both versions ("Keep both"): a conflict until pull
+    /// settles it, whatever the timestamps say
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub conflicts: BTreeSet<String>,
+}
+
+/// Last-synced state of a single tracked file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub hash: String,
+    pub size: u64,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).context("Failed to read manifest")?;
+        let manifest: Manifest = toml::from_str(&contents).context("Failed to parse manifest")?;
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string_pretty(self).context("Failed to serialize manifest")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, contents).context("Failed to write manifest")?;
+        Ok(())
+    }
+
+    /// Hash `full_path` (through `hashes`) and record it under `rel_path`
+    pub fn record(
+        &mut self,
+        rel_path: &Path,
+        full_path: &Path,
+        hashes: &mut HashCache,
+    ) -> Result<()> {
+        let entry = ManifestEntry {
+            hash: hashes.hash(full_path)?,
+            size: fs::metadata(full_path)?.len(),
+        };
+        self.files.insert(to_slash(rel_path), entry);
+        self.pending
+            .retain(|pending| !rel_path.starts_with(pending));
+        Ok(())
+    }
+
+    pub fn get(&self, rel_path: &Path) -> Option<&ManifestEntry> {
+        self.files.get(&to_slash(rel_path))
+    }
+
+    /// Stop tracking `rel_path` (its external mapping too), returning whether it was tracked
+    pub fn forget(&mut self, rel_path: &Path) -> bool {
+        let key = to_slash(rel_path);
+        self.external.remove(&key);
+        self.pending.remove(&key);
+        self.chmod.remove(&key);
+        self.conflicts.remove(&key);
+        self.files.remove(&key).is_some()
+    }
+
+    /// Add exclusion patterns for the tracked directory `dir`
+    pub fn add_excludes(&mut self, dir: &Path, patterns: &[String]) {
+        let excludes = self.excludes.entry(to_slash(dir)).or_default();
+        for pattern in patterns {
+            if !excludes.contains(pattern) {
+                excludes.push(pattern.clone());
+            }
+        }
+    }
+
+    /// Whether `rel_path` falls under an `add --exclude` of its tracked directory
+    pub fn is_excluded(&self, rel_path: &Path) -> bool {
+        self.excludes.iter().any(|(dir, patterns)| {
+            rel_path
+                .strip_prefix(dir)
+                .is_ok_and(|rest| is_excluded(rest, patterns))
+        })
+    }
+
+    /// Move every entry at or under `from` to the same place under `to`
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        rekey_map(&mut self.files, from, to);
+        rekey_map(&mut self.excludes, from, to);
+        rekey_set(&mut self.paused, from, to);
+        rekey_set(&mut self.pending, from, to);
+        rekey_map(&mut self.chmod, from, to);
+        rekey_set(&mut self.conflicts, from, to);
+    }
+
+    /// Stop syncing `rel_path` until it's resumed, returning whether it wasn't paused yet
+    pub fn pause(&mut self, rel_path: &Path) -> bool {
+        self.paused.insert(to_slash(rel_path))
+    }
+
+    /// Sync `rel_path` again, returning whether it was paused
+    pub fn resume(&mut self, rel_path: &Path) -> bool {
+        self.paused.remove(&to_slash(rel_path))
+    }
+
+    /// Whether `rel_path` or a directory it's in is paused
+    pub fn is_paused(&self, rel_path: &Path) -> bool {
+        self.paused
+            .iter()
+            .any(|paused| rel_path.starts_with(paused))
+    }
+
+    /// Track `rel_path` before it exists; the first push that finds it records it
+    pub fn add_pending(&mut self, rel_path: &Path) {
+        self.pending.insert(to_slash(rel_path));
+    }
+
+    /// Whether `rel_path` was added with `--no-copy` (itself or its directory) and not synced since
+    pub fn is_pending(&self, rel_path: &Path) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| rel_path.starts_with(pending))
+    }
+
+    /// Keep `rel_path` as a conflict until pull settles it
+    pub fn mark_conflict(&mut self, rel_path: &Path) {
+        self.conflicts.insert(to_slash(rel_path));
+    }
+
+    /// Whether `add` kept both versions of `rel_path`
+    pub fn is_conflicted(&self, rel_path: &Path) -> bool {
+        self.conflicts.contains(&to_slash(rel_path))
+    }
+
+    /// The conflict on `rel_path` is settled, returning whether there was one
+    pub fn clear_conflict(&mut self, rel_path: &Path) -> bool {
+        self.conflicts.remove(&to_slash(rel_path))
+    }
+
+    /// Always give `rel_path` (or the files in it) these permission bits
+    pub fn set_chmod(&mut self, rel_path: &Path, mode: u32) {
+        self.chmod.insert(to_slash(rel_path), mode & 0o777);
+    }
+
+    /// Mode set with `add --chmod` for `rel_path`, the closest directory's if
+    /// several cover it
+    pub fn chmod_for(&self, rel_path: &Path) -> Option<u32> {
+        self.chmod
+            .iter()
+            .filter(|(path, _)| rel_path.starts_with(path))
+            .max_by_key(|(path, _)| Path::new(path).components().count())
+            .map(|(_, mode)| *mode)
+    }
+
+    /// Track `source` (outside the project) as `rel_path` in shade
+    pub fn add_external(&mut self, rel_path: &Path, source: &Path) {
+        self.external
+            .insert(to_slash(rel_path), source.to_path_buf());
+    }
+
+    pub fn is_external(&self, rel_path: &Path) -> bool {
+        self.external.contains_key(&to_slash(rel_path))
+    }
+
+    /// Out-of-tree files as (shade path, source) pairs
+    pub fn externals(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.external
+            .iter()
+            .map(|(rel, source)| (PathBuf::from(rel), source.clone()))
+            .collect()
+    }
+
+    /// Where the shade file `rel_path` lives on this machine
+    pub fn local_path(&self, project_path: &Path, rel_path: &Path) -> PathBuf {
+        match self.external.get(&to_slash(rel_path)) {
+            Some(source) => source.clone(),
+            None => project_path.join(rel_path),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.external.is_empty()
+    }
+
+    /// Tracked paths, relative to the project root
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.keys().map(PathBuf::from).collect()
+    }
+}
+
+/// Keys at or under `from`, paired with the same place under `to`
+fn rekeyed<'a>(
+    keys: impl Iterator<Item = &'a String>,
+    from: &Path,
+    to: &Path,
+) -> Vec<(String, String)> {
+    keys.filter_map(|key| {
+        let rest = Path::new(key).strip_prefix(from).ok()?;
+        Some((key.clone(), to_slash(&to.join(rest))))
+    })
+    .collect()
+}
+
+/// Move every entry at or under `from` to the same place under `to`
+fn rekey_map<V>(map: &mut BTreeMap<String, V>, from: &Path, to: &Path) {
+    for (key, new_key) in rekeyed(map.keys(), from, to) {
+        if let Some(value) = map.remove(&key) {
+            map.insert(new_key, value);
+        }
+    }
+}
+
+/// `rekey_map` for a set of paths
+fn rekey_set(set: &mut BTreeSet<String>, from: &Path, to: &Path) {
+    for (key, new_key) in rekeyed(set.iter(), from, to) {
+        set.remove(&key);
+        set.insert(new_key);
+    }
+}
+
+/// Whether a path relative to a tracked directory matches one of its exclusions
+///
+/// Same rules as .gitignore: a pattern without a slash matches a file or
+/// directory name at any depth, one with a slash matches from the directory
+/// root. Everything inside an excluded directory is excluded too.
+pub fn is_excluded(rel_path: &Path, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let Ok(glob) = Glob::new(pattern.trim_start_matches('/')) else {
+            return false;
+        };
+
+        if anchored {
+            rel_path
+                .ancestors()
+                .any(|prefix| !prefix.as_os_str().is_empty() && glob.matches_path(prefix))
+        } else {
+            rel_path
+                .components()
+                .any(|component| glob.matches(&component.as_os_str().to_string_lossy()))
+        }
+    })
+}
+
+/// SHA-256 of a file's contents, as lowercase hex
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_record_save_and_load() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("config.local");
+        fs::write(&file, "hello").unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest
+            .record(
+                &PathBuf::from("config.local"),
+                &file,
+                &mut HashCache::default(),
+            )
+            .unwrap();
+
+        let manifest_path = temp.path().join("metadata/manifest.toml");
+        manifest.save(&manifest_path).unwrap();
+
+        let loaded = Manifest::load(&manifest_path).unwrap();
+        let entry = loaded.get(&PathBuf::from("config.local")).unwrap();
+        assert_eq!(entry.size, 5);
+        assert_eq!(
+            entry.hash,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(loaded.paths(), vec![PathBuf::from("config.local")]);
+    }
+
+    #[test]
+    fn test_manifest_rename_moves_nested_entries() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("file");
+        fs::write(&file, "x").unwrap();
+
+        let mut manifest = Manifest::default();
+        let mut hashes = HashCache::default();
+        manifest
+            .record(Path::new("secrets/a.key"), &file, &mut hashes)
+            .unwrap();
+        manifest
+            .record(Path::new("secrets/prod/b.key"), &file, &mut hashes)
+            .unwrap();
+        manifest
+            .record(Path::new("secrets-old"), &file, &mut hashes)
+            .unwrap();
+
+        manifest.rename(Path::new("secrets"), Path::new("keys"));
+        assert_eq!(
+            manifest.paths(),
+            vec![
+                PathBuf::from("keys/a.key"),
+                PathBuf::from("keys/prod/b.key"),
+                PathBuf::from("secrets-old"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_excludes() {
+        let mut manifest = Manifest::default();
+        manifest.add_excludes(
+            Path::new("uploads"),
+            &[
+                "tmp/".to_string(),
+                "*.log".to_string(),
+                "cache/thumbs".to_string(),
+            ],
+        );
+
+        assert!(manifest.is_excluded(Path::new("uploads/tmp/a.png")));
+        assert!(manifest.is_excluded(Path::new("uploads/2024/tmp/a.png")));
+        assert!(manifest.is_excluded(Path::new("uploads/2024/debug.log")));
+        assert!(manifest.is_excluded(Path::new("uploads/cache/thumbs/a.png")));
+        assert!(!manifest.is_excluded(Path::new("uploads/2024/cache/thumbs/a.png")));
+        assert!(!manifest.is_excluded(Path::new("uploads/a.png")));
+        assert!(!manifest.is_excluded(Path::new("tmp/a.png")));
+
+        manifest.rename(Path::new("uploads"), Path::new("media"));
+        assert!(manifest.is_excluded(Path::new("media/tmp/a.png")));
+    }
+
+    #[test]
+    fn test_manifest_paused_paths() {
+        let mut manifest = Manifest::default();
+        assert!(manifest.pause(Path::new("secrets/dev.key")));
+        assert!(!manifest.pause(Path::new("secrets/dev.key")));
+        assert!(manifest.pause(Path::new("cache")));
+
+        assert!(manifest.is_paused(Path::new("secrets/dev.key")));
+        assert!(!manifest.is_paused(Path::new("secrets/prod.key")));
+        assert!(manifest.is_paused(Path::new("cache/a/b.bin")));
+        assert!(!manifest.is_paused(Path::new("cache2/b.bin")));
+
+        manifest.rename(Path::new("secrets"), Path::new("keys"));
+        assert!(manifest.is_paused(Path::new("keys/dev.key")));
+        assert!(manifest.resume(Path::new("keys/dev.key")));
+        assert!(!manifest.resume(Path::new("keys/dev.key")));
+        assert!(!manifest.is_paused(Path::new("keys/dev.key")));
+    }
+
+    #[test]
+    fn test_manifest_pending_paths_clear_when_recorded() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("api.key");
+        fs::write(&file, "key").unwrap();
+        let mut hashes = HashCache::default();
+
+        let mut manifest = Manifest::default();
+        manifest.add_pending(Path::new("secrets"));
+        manifest.add_pending(Path::new(".env.local"));
+        assert!(manifest.is_pending(Path::new("secrets/api.key")));
+        assert!(manifest.is_pending(Path::new(".env.local")));
+        assert!(!manifest.is_pending(Path::new("config.local")));
+
+        manifest
+            .record(Path::new("secrets/api.key"), &file, &mut hashes)
+            .unwrap();
+        assert!(!manifest.is_pending(Path::new("secrets/api.key")));
+        assert!(manifest.is_pending(Path::new(".env.local")));
+
+        manifest.forget(Path::new(".env.local"));
+        assert!(manifest.pending.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_chmod_covers_files_and_follows_renames() {
+        let mut manifest = Manifest::default();
+        manifest.set_chmod(Path::new("secrets"), 0o700);
+        manifest.set_chmod(Path::new("secrets/api.key"), 0o100600);
+
+        assert_eq!(
+            manifest.chmod_for(Path::new("secrets/api.key")),
+            Some(0o600)
+        );
+        assert_eq!(manifest.chmod_for(Path::new("secrets/db.key")), Some(0o700));
+        assert_eq!(manifest.chmod_for(Path::new("secrets.txt")), None);
+
+        manifest.rename(Path::new("secrets"), Path::new("keys"));
+        assert_eq!(manifest.chmod_for(Path::new("keys/api.key")), Some(0o600));
+        assert_eq!(manifest.chmod_for(Path::new("secrets/api.key")), None);
+
+        manifest.forget(Path::new("keys/api.key"));
+        assert_eq!(manifest.chmod_for(Path::new("keys/api.key")), Some(0o700));
+    }
+
+    #[test]
+    fn test_manifest_conflicts_follow_renames_until_cleared() {
+        let mut manifest = Manifest::default();
+        manifest.mark_conflict(Path::new("config/app.local"));
+        assert!(manifest.is_conflicted(Path::new("config/app.local")));
+        assert!(!manifest.is_conflicted(Path::new("config")));
+
+        manifest.rename(Path::new("config"), Path::new("settings"));
+        assert!(manifest.is_conflicted(Path::new("settings/app.local")));
+        assert!(manifest.clear_conflict(Path::new("settings/app.local")));
+        assert!(!manifest.clear_conflict(Path::new("settings/app.local")));
+        assert!(manifest.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_external_files() {
+        let temp = TempDir::new().unwrap();
+        let project = Path::new("/work/app");
+        let source = Path::new("/home/me/.config/app/settings");
+
+        let mut manifest = Manifest::default();
+        manifest.add_external(Path::new("external/settings"), source);
+
+        let manifest_path = temp.path().join("manifest.toml");
+        manifest.save(&manifest_path).unwrap();
+        let loaded = Manifest::load(&manifest_path).unwrap();
+
+        assert!(loaded.is_external(Path::new("external/settings")));
+        assert!(!loaded.is_empty());
+        assert_eq!(
+            loaded.local_path(project, Path::new("external/settings")),
+            source
+        );
+        assert_eq!(
+            loaded.local_path(project, Path::new("config.local")),
+            project.join("config.local")
+        );
+    }
+}
@@ -0,0 +1,149 @@
+/// Whether `contents` looks binary, so it can't take merge markers
+///
+/// Same check as git: a NUL byte in the first 8000 bytes. Text that isn't
+/// UTF-8 counts as binary too, since it can't be merged line by line here.
+pub fn is_binary(contents: &[u8]) -> bool {
+    contents.iter().take(8000).any(|byte| *byte == 0) || std::str::from_utf8(contents).is_err()
+}
+
+/// Opens each hunk `merge_with_markers` writes
+const LOCAL_MARKER: &str = "<<<<<<< local";
+
+/// Whether `contents` still has a hunk from `merge_with_markers` in it
+pub fn has_markers(contents: &[u8]) -> bool {
+    contents
+        .split(|byte| *byte == b'\n')
+        .any(|line| line.strip_suffix(b"\r").unwrap_or(line) == LOCAL_MARKER.as_bytes())
+}
+
+/// Lines compared above this many pairs get a single hunk for the whole file
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// `local` and `shade` merged line by line, each stretch where they differ
+/// wrapped in git-style markers:
+///
+/// ```text
+/// <<<<<<< local
+/// port = 3000
+/// =======
+/// port = 8080
+/// >>>>>>> shade
+/// ```
+pub fn merge_with_markers(local: &str, shade: &str) -> String {
+    let ours: Vec<&str> = local.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = shade.split_inclusive('\n').collect();
+
+    let mut merged = String::new();
+    if ours.len().saturating_mul(theirs.len()) > MAX_DIFF_CELLS {
+        push_hunk(&mut merged, &ours, &theirs);
+        return merged;
+    }
+
+    // common[i][j]: length of the longest common subsequence of ours[i..] and theirs[j..]
+    let mut common = vec![vec![0usize; theirs.len() + 1]; ours.len() + 1];
+    for i in (0..ours.len()).rev() {
+        for j in (0..theirs.len()).rev() {
+            common[i][j] = if ours[i] == theirs[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut ours_hunk, mut theirs_hunk) = (Vec::new(), Vec::new());
+    while i < ours.len() || j < theirs.len() {
+        if i < ours.len() && j < theirs.len() && ours[i] == theirs[j] {
+            push_hunk(&mut merged, &ours_hunk, &theirs_hunk);
+            ours_hunk.clear();
+            theirs_hunk.clear();
+            merged.push_str(ours[i]);
+            i += 1;
+            j += 1;
+        } else if j == theirs.len() || (i < ours.len() && common[i + 1][j] >= common[i][j + 1]) {
+            ours_hunk.push(ours[i]);
+            i += 1;
+        } else {
+            theirs_hunk.push(theirs[j]);
+            j += 1;
+        }
+    }
+    push_hunk(&mut merged, &ours_hunk, &theirs_hunk);
+
+    merged
+}
+
+fn push_hunk(merged: &mut String, ours: &[&str], theirs: &[&str]) {
+    if ours.is_empty() && theirs.is_empty() {
+        return;
+    }
+
+    merged.push_str(LOCAL_MARKER);
+    merged.push('\n');
+    push_lines(merged, ours);
+    merged.push_str("=======\n");
+    push_lines(merged, theirs);
+    merged.push_str(">>>>>>> shade\n");
+}
+
+/// Lines inside a hunk, the last one given a newline if the file had none
+fn push_lines(merged: &mut String, lines: &[&str]) {
+    for line in lines {
+        merged.push_str(line);
+        if !line.ends_with('\n') {
+            merged.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_with_markers_wraps_each_differing_line() {
+        let local = "host = localhost\nport = 3000\nuser = me\nlog = debug\n";
+        let shade = "host = localhost\nport = 8080\nuser = me\nlog = info\n";
+
+        assert_eq!(
+            merge_with_markers(local, shade),
+            "host = localhost\n\
+             <<<<<<< local\n\
+             port = 3000\n\
+             =======\n\
+             port = 8080\n\
+             >>>>>>> shade\n\
+             user = me\n\
+             <<<<<<< local\n\
+             log = debug\n\
+             =======\n\
+             log = info\n\
+             >>>>>>> shade\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_with_markers_added_lines_and_missing_newline() {
+        assert_eq!(
+            merge_with_markers("a\nb", "a\nc\nd"),
+            "a\n<<<<<<< local\nb\n=======\nc\nd\n>>>>>>> shade\n"
+        );
+        assert_eq!(merge_with_markers("same\n", "same\n"), "same\n");
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b"KEY=value\n"));
+        assert!(is_binary(b"PK\x03\x04\x00\x00"));
+        assert!(is_binary(&[0xff, 0xfe, b'a']));
+    }
+
+    #[test]
+    fn test_has_markers() {
+        assert!(has_markers(merge_with_markers("a\n", "b\n").as_bytes()));
+        assert!(has_markers(b"x\r\n<<<<<<< local\r\ny\r\n"));
+        assert!(!has_markers(b"<<<<<<< HEAD\nkey = 1\n"));
+        assert!(!has_markers(b"  <<<<<<< local\n"));
+    }
+}
@@ -1,11 +1,38 @@
+pub mod archive;
 pub mod config;
 pub mod conflict;
+pub mod empty_dirs;
+pub mod hash_cache;
+pub mod layout;
+pub mod manifest;
+pub mod merge;
 pub mod paths;
+pub mod permissions;
+pub mod project_config;
+pub mod push_state;
+pub mod secrets;
 pub mod sync;
+pub mod templates;
 pub mod tracker;
 
-pub use config::Config;
-pub use conflict::{format_conflict_message, ConflictInfo};
-pub use paths::ShadePaths;
-pub use sync::{detect_sync_state, FileMetadata, SyncState};
-pub use tracker::Tracker;
+pub use archive::{read_archive_header, unpack_shade_files, write_archive, ArchiveHeader};
+pub use config::{render_commit_message, Config, Hooks, PullStrategy, Storage, CONFIG_KEYS};
+pub use conflict::{
+    case_collisions, format_case_collision_warning, format_conflict_message,
+    format_merge_conflict_message, ConflictInfo, ConflictKind, ConflictSide, ConflictStrategy,
+};
+pub use empty_dirs::{find_empty_dirs, is_keep_file, list_marked_dirs, mark_empty_dirs, KEEP_FILE};
+pub use hash_cache::HashCache;
+pub use layout::{ShadeLayout, OS_DIRS, PER_OS_FILE};
+pub use manifest::{hash_file, is_excluded, Manifest, ManifestEntry};
+pub use merge::{has_markers, is_binary, merge_with_markers};
+pub use paths::{
+    display_path, is_shade_metadata, shown_config, shown_projects, shown_root, to_slash, ShadePaths,
+};
+pub use permissions::{FileModes, MODES_FILE};
+pub use project_config::{ProjectConfig, PROJECT_CONFIG_FILE};
+pub use push_state::{PendingPush, PushState};
+pub use secrets::{scan_for_secrets, DEFAULT_PATTERNS};
+pub use sync::{detect_sync_state, same_contents, FileMetadata, SyncPolicy, SyncState};
+pub use templates::{template_files, template_names, BUILTIN_TEMPLATES};
+pub use tracker::{is_sync_file, Tracker, SYNC_FILE};
@@ -1,7 +1,18 @@
+use super::config::Config;
+use super::empty_dirs::is_keep_file;
+use super::layout::PER_OS_FILE;
+use super::manifest::Manifest;
+use super::permissions::MODES_FILE;
+use super::tracker::{is_sync_file, repo_sync_file_name, SYNC_FILE};
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+
+/// Set to `1` to keep git-shade's files in the XDG base directories
+pub const XDG_ENV: &str = "GIT_SHADE_XDG";
 
 pub struct ShadePaths {
+    /// Data root: metadata, the shade repo, backups and stashes
+    pub root: PathBuf,
     pub config: PathBuf,
     pub metadata: PathBuf,
     pub projects: PathBuf,
@@ -9,18 +20,48 @@ pub struct ShadePaths {
 
 // impl = implementation block (like Ruby's class methods)
 impl ShadePaths {
+    /// Where git-shade's files are on this machine
+    ///
+    /// Everything lives under `default_root()` unless the XDG layout is in use
+    /// (`GIT_SHADE_XDG=1`, or a config already in `$XDG_CONFIG_HOME`). Switching
+    /// moves an existing root over once.
     pub fn new() -> Result<Self> {
-        let home =
-            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+        let legacy_root = default_root()?;
+        match xdg_homes()? {
+            Some((config_home, data_home)) => {
+                let paths = Self::xdg(&config_home, &data_home);
+                migrate_to_xdg(&legacy_root, &paths)?;
+                Ok(paths)
+            }
+            None => Ok(Self::at(legacy_root)),
+        }
+    }
 
-        let root = home.join(".local/git-shade");
+    /// Like `new`, without moving anything, for messages that name the paths
+    pub fn locate() -> Result<Self> {
+        match xdg_homes()? {
+            Some((config_home, data_home)) => Ok(Self::xdg(&config_home, &data_home)),
+            None => Ok(Self::at(default_root()?)),
+        }
+    }
+
+    /// XDG layout: `config.toml` in `<config_home>/git-shade`, everything else
+    /// (metadata, shade repo, backups) in `<data_home>/git-shade`
+    pub fn xdg(config_home: &Path, data_home: &Path) -> Self {
+        let mut paths = Self::at(data_home.join("git-shade"));
+        paths.config = config_home.join("git-shade").join("config.toml");
+        paths
+    }
 
-        Ok(Self {
+    /// The same layout under another root, e.g. for embedding git-shade as a library
+    pub fn at(root: PathBuf) -> Self {
+        Self {
             // Self = ShadePaths (like @class in Ruby)
             config: root.join("config.toml"),
             metadata: root.join("metadata"),
             projects: root.join("projects"),
-        })
+            root,
+        }
     }
 
     // Instance method (&self = readonly access, like Ruby's regular method)
@@ -39,8 +80,211 @@ impl ShadePaths {
     }
 
     pub fn shade_sync_file(&self, project_name: &str) -> PathBuf {
-        self.project_metadata_dir(project_name).join(".shade-sync")
+        self.project_metadata_dir(project_name).join(SYNC_FILE)
+    }
+
+    /// `.shade-sync.<machine>` inside the project's shade dir, used with `metadata_in_repo`
+    pub fn repo_sync_file(&self, project_name: &str, machine: &str) -> PathBuf {
+        self.project_shade_dir(project_name)
+            .join(repo_sync_file_name(machine))
+    }
+
+    /// Where the project's sync tracker lives with this config
+    pub fn tracker_file(&self, config: &Config, project_name: &str) -> PathBuf {
+        if config.metadata_in_repo {
+            self.repo_sync_file(project_name, &config.machine_name())
+        } else {
+            self.shade_sync_file(project_name)
+        }
+    }
+
+    pub fn manifest_file(&self, project_name: &str) -> PathBuf {
+        self.project_metadata_dir(project_name)
+            .join("manifest.toml")
+    }
+
+    pub fn hash_cache_file(&self, project_name: &str) -> PathBuf {
+        self.project_metadata_dir(project_name)
+            .join("hash-cache.toml")
+    }
+
+    /// Where `pull --backup-all` snapshots the project's files, one dir per pull
+    pub fn project_backups_dir(&self, project_name: &str) -> PathBuf {
+        self.root.join("backups").join(project_name)
+    }
+
+    /// Commits push made but couldn't push yet, see `PushState`
+    pub fn push_state_file(&self) -> PathBuf {
+        self.root.join(".push-state")
     }
+
+    /// Where `stash` sets local changes aside, one dir per stash
+    pub fn project_stash_dir(&self, project_name: &str) -> PathBuf {
+        self.root.join("stash").join(project_name)
+    }
+}
+
+/// `path` as shown in messages, with the home directory as `~`
+pub fn display_path(path: &Path) -> String {
+    let home = dirs::home_dir().filter(|home| !home.as_os_str().is_empty());
+    match home.and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(rel) => format!("~/{}", to_slash(&rel)),
+        None => path.display().to_string(),
+    }
+}
+
+/// One of this machine's git-shade paths for a hint or error message, the
+/// default layout's if they can't be found
+fn shown(pick: fn(&ShadePaths) -> &Path, default: &str) -> String {
+    ShadePaths::locate()
+        .map(|paths| display_path(pick(&paths)))
+        .unwrap_or_else(|_| default.to_string())
+}
+
+/// The config file as shown in messages, e.g. `~/.local/git-shade/config.toml`
+pub fn shown_config() -> String {
+    shown(|paths| &paths.config, "~/.local/git-shade/config.toml")
+}
+
+/// The shade repo as shown in messages, e.g. `~/.local/git-shade/projects`
+pub fn shown_projects() -> String {
+    shown(|paths| &paths.projects, "~/.local/git-shade/projects")
+}
+
+/// The data root as shown in messages, e.g. `~/.local/git-shade`
+pub fn shown_root() -> String {
+    shown(|paths| &paths.root, "~/.local/git-shade")
+}
+
+/// `~/.local/git-shade` on Unix, `%LOCALAPPDATA%\git-shade` on Windows
+#[cfg(not(windows))]
+fn default_root() -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    Ok(home.join(".local").join("git-shade"))
+}
+
+#[cfg(windows)]
+fn default_root() -> Result<PathBuf> {
+    let local = dirs::data_local_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not find local app data directory"))?;
+    Ok(local.join("git-shade"))
+}
+
+/// `$XDG_CONFIG_HOME` and `$XDG_DATA_HOME` (or their defaults), if the XDG
+/// layout is asked for or already set up
+fn xdg_homes() -> Result<Option<(PathBuf, PathBuf)>> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    // The spec says relative values are invalid and should be ignored
+    let dir = |var: &str, default: PathBuf| {
+        std::env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .unwrap_or(default)
+    };
+    let config_home = dir("XDG_CONFIG_HOME", home.join(".config"));
+    let data_home = dir("XDG_DATA_HOME", home.join(".local").join("share"));
+
+    let asked = std::env::var(XDG_ENV).is_ok_and(|value| matches!(value.trim(), "1" | "true"));
+    let set_up = config_home.join("git-shade").join("config.toml").exists();
+    Ok((asked || set_up).then_some((config_home, data_home)))
+}
+
+/// Move a root from before the switch into the XDG layout, if there is one
+/// and nothing is in the XDG directories yet
+///
+/// Returns whether anything was moved.
+fn migrate_to_xdg(legacy_root: &Path, paths: &ShadePaths) -> Result<bool> {
+    if !legacy_root.is_dir() || paths.root.exists() || paths.config.exists() {
+        return Ok(false);
+    }
+
+    let move_to = |from: &Path, to: &Path| -> Result<()> {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(from, to).with_context(|| {
+            format!(
+                "Failed to move {} to {} for the XDG layout; move it by hand or unset {}",
+                from.display(),
+                to.display(),
+                XDG_ENV
+            )
+        })
+    };
+
+    move_to(legacy_root, &paths.root)?;
+    let moved_config = paths.root.join("config.toml");
+    if moved_config.exists() {
+        move_to(&moved_config, &paths.config)?;
+    }
+    let relinked = relink_moved_files(legacy_root, paths)?;
+
+    eprintln!(
+        "Moved {} to the XDG layout: config in {}, data in {}",
+        legacy_root.display(),
+        paths.config.display(),
+        paths.root.display()
+    );
+    if relinked > 0 {
+        eprintln!("Re-pointed {} file(s) added with --symlink", relinked);
+    }
+    Ok(true)
+}
+
+/// git-shade's own files in a shade dir, never synced into the project
+pub fn is_shade_metadata(rel: &Path) -> bool {
+    rel == Path::new(MODES_FILE)
+        || is_sync_file(rel)
+        || rel == Path::new(PER_OS_FILE)
+        || is_keep_file(rel)
+}
+
+/// Relative path with `/` separators, as git and git-shade's files expect
+///
+/// On Windows `config\app.local` becomes `config/app.local`; on Unix a
+/// backslash is part of the file name and is kept.
+pub fn to_slash(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            Component::ParentDir => Some("..".into()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Re-point the links `add --symlink` left in projects at the moved root
+///
+/// Returns how many were re-pointed.
+#[cfg(unix)]
+fn relink_moved_files(legacy_root: &Path, paths: &ShadePaths) -> Result<usize> {
+    let config = Config::load(&paths.config)?;
+    let mut relinked = 0;
+    for project in &config.projects {
+        let manifest = Manifest::load(&paths.manifest_file(&project.name))?;
+        for rel in manifest.paths() {
+            let link = project.local_path.join(&rel);
+            let Ok(target) = std::fs::read_link(&link) else {
+                continue;
+            };
+            let Ok(inside) = target.strip_prefix(legacy_root) else {
+                continue;
+            };
+            std::fs::remove_file(&link)?;
+            std::os::unix::fs::symlink(paths.root.join(inside), &link)
+                .with_context(|| format!("Failed to re-point {}", link.display()))?;
+            relinked += 1;
+        }
+    }
+    Ok(relinked)
+}
+
+/// `add --symlink` is Unix only, so there are no links to re-point
+#[cfg(not(unix))]
+fn relink_moved_files(_legacy_root: &Path, _paths: &ShadePaths) -> Result<usize> {
+    Ok(0)
 }
 
 #[cfg(test)] // Only compiled for tests
@@ -50,7 +294,62 @@ mod tests {
     #[test]
     fn test_paths_structure() {
         let paths = ShadePaths::new().unwrap();
+        #[cfg(not(windows))]
         assert!(paths.root.ends_with(".local/git-shade"));
+        #[cfg(windows)]
+        assert!(paths.root.ends_with("git-shade"));
         assert!(paths.config.ends_with("config.toml"));
     }
+
+    #[test]
+    fn test_xdg_layout_and_migration() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let legacy_root = temp.path().join(".local/git-shade");
+        std::fs::create_dir_all(legacy_root.join("projects/.git")).unwrap();
+        std::fs::create_dir_all(legacy_root.join("metadata/myapp")).unwrap();
+        std::fs::write(legacy_root.join("config.toml"), "version = \"1.0\"\n").unwrap();
+
+        let paths = ShadePaths::xdg(&temp.path().join("config"), &temp.path().join("data"));
+        assert_eq!(
+            paths.config,
+            temp.path().join("config/git-shade/config.toml")
+        );
+        assert_eq!(paths.projects, temp.path().join("data/git-shade/projects"));
+        assert_eq!(
+            paths.manifest_file("myapp"),
+            temp.path()
+                .join("data/git-shade/metadata/myapp/manifest.toml")
+        );
+
+        assert!(migrate_to_xdg(&legacy_root, &paths).unwrap());
+        assert!(!legacy_root.exists());
+        assert!(paths.config.is_file());
+        assert!(paths.projects.join(".git").is_dir());
+        assert!(paths.project_metadata_dir("myapp").is_dir());
+        assert!(!paths.root.join("config.toml").exists());
+
+        // Only once: an XDG layout that's there already is left alone
+        std::fs::create_dir_all(&legacy_root).unwrap();
+        assert!(!migrate_to_xdg(&legacy_root, &paths).unwrap());
+        assert!(legacy_root.exists());
+    }
+
+    #[test]
+    fn test_to_slash() {
+        assert_eq!(
+            to_slash(Path::new("config/keys/api.key")),
+            "config/keys/api.key"
+        );
+        assert_eq!(to_slash(Path::new("./secrets/")), "secrets");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_to_slash_converts_backslashes() {
+        assert_eq!(
+            to_slash(Path::new(r"config\keys\api.key")),
+            "config/keys/api.key"
+        );
+        assert_eq!(to_slash(Path::new(r"secrets\")), "secrets");
+    }
 }
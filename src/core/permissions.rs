@@ -0,0 +1,83 @@
+use super::paths::to_slash;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Sidecar file in each project's shade dir, committed alongside the files
+pub const MODES_FILE: &str = ".shade-modes.toml";
+
+/// Unix permission bits of shaded files, keyed by path relative to the project
+///
+/// Git only keeps the executable bit and some checkouts drop even that, so the
+/// modes travel with the shade repo and are restored on pull.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FileModes {
+    #[serde(default)]
+    pub files: BTreeMap<String, u32>,
+}
+
+impl FileModes {
+    pub fn load(shade_dir: &Path) -> Result<Self> {
+        let path = shade_dir.join(MODES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        let modes: FileModes = toml::from_str(&contents)?;
+        Ok(modes)
+    }
+
+    pub fn save(&self, shade_dir: &Path) -> Result<()> {
+        let contents = toml::to_string_pretty(self)?;
+        std::fs::create_dir_all(shade_dir)?;
+        std::fs::write(shade_dir.join(MODES_FILE), contents)?;
+        Ok(())
+    }
+
+    pub fn set(&mut self, rel_path: &Path, mode: u32) {
+        self.files.insert(to_slash(rel_path), mode & 0o777);
+    }
+
+    /// Move every mode at or under `from` to the same place under `to`
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<String> = self
+            .files
+            .keys()
+            .filter(|key| Path::new(key).starts_with(from))
+            .cloned()
+            .collect();
+
+        for key in moved {
+            if let Some(mode) = self.files.remove(&key) {
+                let rest = Path::new(&key).strip_prefix(from).unwrap_or(Path::new(""));
+                self.files.insert(to_slash(&to.join(rest)), mode);
+            }
+        }
+    }
+
+    pub fn get(&self, rel_path: &Path) -> Option<u32> {
+        self.files.get(&to_slash(rel_path)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_modes_save_and_load() {
+        let temp = TempDir::new().unwrap();
+
+        let mut modes = FileModes::default();
+        modes.set(&PathBuf::from("scripts/deploy.sh"), 0o100755);
+        modes.save(temp.path()).unwrap();
+
+        let loaded = FileModes::load(temp.path()).unwrap();
+        assert_eq!(loaded.get(&PathBuf::from("scripts/deploy.sh")), Some(0o755));
+        assert_eq!(loaded.get(&PathBuf::from("missing")), None);
+    }
+}
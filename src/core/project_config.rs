@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Optional file committed in the project root to pre-configure git-shade
+pub const PROJECT_CONFIG_FILE: &str = ".git-shade.toml";
+
+/// Team-shared settings read from `.git-shade.toml`
+///
+/// `name` replaces the directory name as the project name for every command
+/// (`--name` still wins). `files` are added on `init`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProjectConfig {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+impl ProjectConfig {
+    /// Load `.git-shade.toml` from `project_path`, `None` if there is none
+    pub fn load(project_path: &Path) -> Result<Option<Self>> {
+        let path = project_path.join(PROJECT_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        Ok(Some(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_project_config_load() {
+        let temp = TempDir::new().unwrap();
+        assert!(ProjectConfig::load(temp.path()).unwrap().is_none());
+
+        std::fs::write(
+            temp.path().join(PROJECT_CONFIG_FILE),
+            "name = \"billing-api\"\nfiles = [\".env.local\", \"secrets/\"]\n",
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(temp.path()).unwrap().unwrap();
+        assert_eq!(config.name.as_deref(), Some("billing-api"));
+        assert_eq!(
+            config.files,
+            vec![PathBuf::from(".env.local"), PathBuf::from("secrets/")]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Shade commits made by push whose `git push` hasn't gone through yet
+///
+/// Lives at `<root>/.push-state`. Push records its commit before pushing and
+/// clears the file once a push succeeds, so after a failed push the next one
+/// retries it without copying or committing again.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PushState {
+    #[serde(default)]
+    pending: BTreeMap<String, PendingPush>,
+}
+
+/// A project's commit waiting to be pushed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PendingPush {
+    pub commit: String,
+    pub committed_at: DateTime<Utc>,
+    /// Pushed with `--force-with-lease` (`--amend` or `--force-with-lease`)
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl PushState {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).context("Failed to read push state")?;
+        // Without it push just copies and commits as usual, and pushes what's waiting
+        Ok(toml::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable push state");
+            Self::default()
+        }))
+    }
+
+    /// Write the state, removing the file once nothing is pending
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if self.pending.is_empty() {
+            if path.exists() {
+                fs::remove_file(path).context("Failed to remove push state")?;
+            }
+            return Ok(());
+        }
+
+        let contents = toml::to_string_pretty(self).context("Failed to serialize push state")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents).context("Failed to write push state")?;
+        Ok(())
+    }
+
+    pub fn pending(&self, project_name: &str) -> Option<&PendingPush> {
+        self.pending.get(project_name)
+    }
+
+    /// Record `commit` as made for `project_name` but not pushed
+    pub fn mark_pending(&mut self, project_name: &str, commit: &str, force: bool) {
+        self.pending.insert(
+            project_name.to_string(),
+            PendingPush {
+                commit: commit.to_string(),
+                committed_at: Utc::now(),
+                force,
+            },
+        );
+    }
+
+    /// Every project with a commit waiting
+    pub fn projects(&self) -> Vec<String> {
+        self.pending.keys().cloned().collect()
+    }
+
+    /// Point `project_name`'s waiting push at `commit`, e.g. once a rebase rewrote it
+    pub fn repoint(&mut self, project_name: &str, commit: &str) {
+        if let Some(pending) = self.pending.get_mut(project_name) {
+            pending.commit = commit.to_string();
+        }
+    }
+
+    pub fn clear(&mut self, project_name: &str) {
+        self.pending.remove(project_name);
+    }
+
+    /// Forget every project's commit: a successful push sends them all
+    pub fn clear_all(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_push_state_round_trip_and_removal() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".push-state");
+
+        let mut state = PushState::load(&path).unwrap();
+        assert!(state.pending("myapp").is_none());
+
+        state.mark_pending("myapp", "3f2a9c1", false);
+        state.mark_pending("other", "8b41d07", true);
+        state.save(&path).unwrap();
+
+        let mut state = PushState::load(&path).unwrap();
+        assert_eq!(state.pending("myapp").unwrap().commit, "3f2a9c1");
+        state.clear("myapp");
+        assert!(state.pending("myapp").is_none());
+        assert!(state.pending("other").unwrap().force);
+
+        state.clear_all();
+        state.save(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use glob::Pattern as Glob;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Well-known local/secret files offered on `init`
+///
+/// Same rules as .gitignore: a pattern without a slash matches the name at any
+/// depth, a trailing slash only matches directories.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.local",
+    ".env.*.local",
+    "config.local",
+    "*.local.yml",
+    "*.key",
+    "*.pem",
+    "secrets/",
+    "credentials.json",
+];
+
+/// Find files and directories under `project_path` that match `patterns`
+///
+/// Matched directories are returned as a whole and not descended into.
+/// `.git` is always skipped. Results are relative to `project_path` and sorted.
+pub fn scan_for_secrets(project_path: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let globs: Vec<(Glob, bool, bool)> = patterns
+        .iter()
+        .filter_map(|pattern| {
+            let dir_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            Glob::new(pattern)
+                .ok()
+                .map(|glob| (glob, dir_only, anchored))
+        })
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut walker = WalkDir::new(project_path).min_depth(1).into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+
+        let Ok(rel) = entry.path().strip_prefix(project_path) else {
+            continue;
+        };
+        let is_dir = entry.file_type().is_dir();
+        let name = entry.file_name().to_string_lossy();
+
+        let matched = globs.iter().any(|(glob, dir_only, anchored)| {
+            if *dir_only && !is_dir {
+                return false;
+            }
+            if *anchored {
+                glob.matches_path(rel)
+            } else {
+                glob.matches(&name)
+            }
+        });
+
+        if matched {
+            matches.push(rel.to_path_buf());
+            if is_dir {
+                walker.skip_current_dir();
+            }
+        }
+    }
+
+    matches.sort();
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_for_secrets_default_patterns() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path();
+        fs::create_dir_all(base.join(".git")).unwrap();
+        fs::create_dir_all(base.join("secrets/prod")).unwrap();
+        fs::create_dir_all(base.join("config")).unwrap();
+        fs::write(base.join(".git/config.local"), "").unwrap();
+        fs::write(base.join(".env.local"), "").unwrap();
+        fs::write(base.join(".env.test.local"), "").unwrap();
+        fs::write(base.join("config/server.key"), "").unwrap();
+        fs::write(base.join("secrets/prod/api.key"), "").unwrap();
+        fs::write(base.join("README.md"), "").unwrap();
+        // A file named like a directory pattern doesn't match it
+        fs::write(base.join("config/secrets"), "").unwrap();
+
+        let patterns: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+        let found = scan_for_secrets(base, &patterns).unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from(".env.local"),
+                PathBuf::from(".env.test.local"),
+                PathBuf::from("config/server.key"),
+                PathBuf::from("secrets"),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::core::manifest::hash_file;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
@@ -13,10 +15,52 @@ pub enum SyncState {
     RemoteOnly,  // ← Only exists remotely
 }
 
+/// How strictly a local file and its shade copy are compared (`[sync]` in config)
+///
+/// By default only metadata is compared: files with the same size and mtimes
+/// at most 2 seconds apart are in sync (FAT/exFAT store mtimes with 2-second
+/// resolution and some network drives truncate too, so a byte-identical copy
+/// can come back with a different mtime). Contents aren't read.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct SyncPolicy {
+    /// Largest mtime difference, in seconds, still treated as "same time"
+    pub mtime_tolerance_secs: u64,
+    /// Compare contents by hash before falling back to metadata
+    pub hash: bool,
+    /// Same size alone counts as in sync, whatever the mtimes
+    pub size_only: bool,
+}
+
+impl Default for SyncPolicy {
+    fn default() -> Self {
+        Self {
+            mtime_tolerance_secs: 2,
+            hash: false,
+            size_only: false,
+        }
+    }
+}
+
+impl SyncPolicy {
+    /// Whether the metadata alone says the two files are the same
+    pub fn looks_identical(&self, a: &FileMetadata, b: &FileMetadata) -> bool {
+        if a.size != b.size {
+            return false;
+        }
+        let tolerance_ms = i64::try_from(self.mtime_tolerance_secs)
+            .unwrap_or(i64::MAX / 1000)
+            .saturating_mul(1000);
+        self.size_only || (a.modified - b.modified).num_milliseconds().abs() <= tolerance_ms
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileMetadata {
     pub modified: DateTime<Utc>,
     pub size: u64,
+    /// Unix permission bits, `None` on other platforms
+    pub mode: Option<u32>,
 }
 
 impl FileMetadata {
@@ -28,17 +72,50 @@ impl FileMetadata {
         Ok(Self {
             modified: modified_utc,
             size: metadata.len(),
+            mode: file_mode(&metadata),
         })
     }
 }
 
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Whether two files have identical contents (sizes are compared first)
+pub fn same_contents(a: &Path, b: &Path) -> Result<bool> {
+    same_contents_by(a, b, hash_file)
+}
+
+/// `same_contents`, hashing with `hash` (e.g. through a `HashCache`)
+pub fn same_contents_by(
+    a: &Path,
+    b: &Path,
+    mut hash: impl FnMut(&Path) -> Result<String>,
+) -> Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    Ok(hash(a)? == hash(b)?)
+}
+
 /// Detect the sync state of a file by comparing local, remote, and last pull time
+///
+/// Only metadata is looked at; callers hash contents first when `policy.hash` is set.
 pub fn detect_sync_state(
     local_file: Option<&FileMetadata>,
     remote_file: Option<&FileMetadata>,
     last_pull: Option<DateTime<Utc>>,
+    policy: &SyncPolicy,
 ) -> SyncState {
-    match (local_file, remote_file, last_pull) {
+    let state = match (local_file, remote_file, last_pull) {
         // File doesn't exist anywhere
         (None, None, _) => SyncState::InSync, // Shouldn't happen, but treat as in sync
 
@@ -50,33 +127,42 @@ pub fn detect_sync_state(
 
         // Exists in both places
         (Some(local), Some(remote), Some(last_pull_time)) => {
-            // If files are identical, they're in sync regardless of timestamps
-            if local.modified == remote.modified && local.size == remote.size {
-                return SyncState::InSync;
-            }
-
-            let local_modified_since_pull = local.modified > last_pull_time;
-            let remote_modified_since_pull = remote.modified > last_pull_time;
-
-            match (local_modified_since_pull, remote_modified_since_pull) {
-                (false, false) => SyncState::InSync,
-                (true, false) => SyncState::LocalAhead,
-                (false, true) => SyncState::RemoteAhead,
-                (true, true) => SyncState::Conflict,
+            // If files look identical, they're in sync regardless of last pull
+            if policy.looks_identical(local, remote) {
+                SyncState::InSync
+            } else {
+                let local_modified_since_pull = local.modified > last_pull_time;
+                let remote_modified_since_pull = remote.modified > last_pull_time;
+
+                match (local_modified_since_pull, remote_modified_since_pull) {
+                    (false, false) => SyncState::InSync,
+                    (true, false) => SyncState::LocalAhead,
+                    (false, true) => SyncState::RemoteAhead,
+                    (true, true) => SyncState::Conflict,
+                }
             }
         }
 
         // Exists in both but never pulled before
         (Some(local), Some(remote), None) => {
-            // Check if files are identical
-            if local.modified == remote.modified && local.size == remote.size {
+            // Check if files look identical
+            if policy.looks_identical(local, remote) {
                 SyncState::InSync
             } else {
-                // First time, assume remote is source of truth
-                SyncState::RemoteAhead
+                // No common baseline, so neither side can be assumed newer
+                SyncState::Conflict
             }
         }
-    }
+    };
+
+    tracing::debug!(
+        local = ?local_file,
+        remote = ?remote_file,
+        ?last_pull,
+        ?state,
+        "detected sync state"
+    );
+    state
 }
 
 #[cfg(test)]
@@ -97,7 +183,12 @@ mod tests {
         // Both local and remote have same metadata, and last_pull is before the file was modified
         let last_pull = metadata.modified - chrono::Duration::seconds(10);
 
-        let state = detect_sync_state(Some(&metadata), Some(&metadata), Some(last_pull));
+        let state = detect_sync_state(
+            Some(&metadata),
+            Some(&metadata),
+            Some(last_pull),
+            &SyncPolicy::default(),
+        );
         // Since both were modified after last_pull with identical times, it's actually in sync
         assert_eq!(state, SyncState::InSync);
     }
@@ -110,7 +201,7 @@ mod tests {
 
         let metadata = FileMetadata::from_path(&file).unwrap();
 
-        let state = detect_sync_state(Some(&metadata), None, None);
+        let state = detect_sync_state(Some(&metadata), None, None, &SyncPolicy::default());
         assert_eq!(state, SyncState::LocalOnly);
     }
 
@@ -122,7 +213,7 @@ mod tests {
 
         let metadata = FileMetadata::from_path(&file).unwrap();
 
-        let state = detect_sync_state(None, Some(&metadata), None);
+        let state = detect_sync_state(None, Some(&metadata), None, &SyncPolicy::default());
         assert_eq!(state, SyncState::RemoteOnly);
     }
 
@@ -145,7 +236,12 @@ mod tests {
         let local_meta = FileMetadata::from_path(&local).unwrap();
         let remote_meta = FileMetadata::from_path(&remote).unwrap();
 
-        let state = detect_sync_state(Some(&local_meta), Some(&remote_meta), Some(last_pull));
+        let state = detect_sync_state(
+            Some(&local_meta),
+            Some(&remote_meta),
+            Some(last_pull),
+            &SyncPolicy::default(),
+        );
         assert_eq!(state, SyncState::Conflict);
     }
 
@@ -167,7 +263,12 @@ mod tests {
         let local_meta = FileMetadata::from_path(&local).unwrap();
         let remote_meta = FileMetadata::from_path(&remote).unwrap();
 
-        let state = detect_sync_state(Some(&local_meta), Some(&remote_meta), Some(last_pull));
+        let state = detect_sync_state(
+            Some(&local_meta),
+            Some(&remote_meta),
+            Some(last_pull),
+            &SyncPolicy::default(),
+        );
         assert_eq!(state, SyncState::LocalAhead);
     }
 
@@ -189,7 +290,155 @@ mod tests {
         let local_meta = FileMetadata::from_path(&local).unwrap();
         let remote_meta = FileMetadata::from_path(&remote).unwrap();
 
-        let state = detect_sync_state(Some(&local_meta), Some(&remote_meta), Some(last_pull));
+        let state = detect_sync_state(
+            Some(&local_meta),
+            Some(&remote_meta),
+            Some(last_pull),
+            &SyncPolicy::default(),
+        );
         assert_eq!(state, SyncState::RemoteAhead);
     }
+
+    #[test]
+    fn test_detect_sync_state_never_pulled_differs_is_conflict() {
+        let temp = TempDir::new().unwrap();
+        let local = temp.path().join("local.txt");
+        let remote = temp.path().join("remote.txt");
+
+        fs::write(&local, "local content").unwrap();
+        thread::sleep(Duration::from_millis(10));
+        fs::write(&remote, "remote").unwrap();
+
+        let local_meta = FileMetadata::from_path(&local).unwrap();
+        let remote_meta = FileMetadata::from_path(&remote).unwrap();
+
+        let state = detect_sync_state(
+            Some(&local_meta),
+            Some(&remote_meta),
+            None,
+            &SyncPolicy::default(),
+        );
+        assert_eq!(state, SyncState::Conflict);
+    }
+
+    #[test]
+    fn test_detect_sync_state_truncated_mtime_is_in_sync() {
+        // A copy on FAT/exFAT: same size, mtime rounded down to 2 seconds
+        let modified = DateTime::parse_from_rfc3339("2025-10-18T14:30:01.734512900Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let local = FileMetadata {
+            modified,
+            size: 42,
+            mode: None,
+        };
+        let remote = FileMetadata {
+            modified: DateTime::parse_from_rfc3339("2025-10-18T14:30:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            ..local.clone()
+        };
+
+        let last_pull = modified - chrono::Duration::seconds(10);
+        assert_eq!(
+            detect_sync_state(
+                Some(&local),
+                Some(&remote),
+                Some(last_pull),
+                &SyncPolicy::default()
+            ),
+            SyncState::InSync
+        );
+        assert_eq!(
+            detect_sync_state(Some(&local), Some(&remote), None, &SyncPolicy::default()),
+            SyncState::InSync
+        );
+
+        // A real edit of the same size a few seconds later still counts
+        let edited = FileMetadata {
+            modified: modified + chrono::Duration::seconds(5),
+            ..local.clone()
+        };
+        assert_eq!(
+            detect_sync_state(
+                Some(&edited),
+                Some(&remote),
+                Some(last_pull),
+                &SyncPolicy::default()
+            ),
+            SyncState::Conflict
+        );
+    }
+
+    #[test]
+    fn test_detect_sync_state_under_policies() {
+        let modified = DateTime::parse_from_rfc3339("2025-10-18T14:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let remote = FileMetadata {
+            modified,
+            size: 42,
+            mode: None,
+        };
+        let last_pull = Some(modified - chrono::Duration::seconds(10));
+
+        let strict = SyncPolicy {
+            mtime_tolerance_secs: 0,
+            ..SyncPolicy::default()
+        };
+        let lenient = SyncPolicy {
+            mtime_tolerance_secs: 10,
+            ..SyncPolicy::default()
+        };
+        let size_only = SyncPolicy {
+            size_only: true,
+            ..strict
+        };
+
+        // Same size, written some seconds later, both after the last pull
+        let cases = [
+            (1, SyncPolicy::default(), SyncState::InSync),
+            (5, SyncPolicy::default(), SyncState::Conflict),
+            (1, strict, SyncState::Conflict),
+            (5, lenient, SyncState::InSync),
+            (60, lenient, SyncState::Conflict),
+            (60, size_only, SyncState::InSync),
+        ];
+        for (seconds_later, policy, expected) in cases {
+            let local = FileMetadata {
+                modified: modified + chrono::Duration::seconds(seconds_later),
+                ..remote.clone()
+            };
+            assert_eq!(
+                detect_sync_state(Some(&local), Some(&remote), last_pull, &policy),
+                expected,
+                "{}s later under {:?}",
+                seconds_later,
+                policy
+            );
+        }
+
+        // A different size is never in sync on metadata alone
+        let resized = FileMetadata {
+            size: 43,
+            ..remote.clone()
+        };
+        for policy in [SyncPolicy::default(), lenient, size_only] {
+            assert!(!policy.looks_identical(&resized, &remote), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_same_contents() {
+        let temp = TempDir::new().unwrap();
+        let a = temp.path().join("a.txt");
+        let b = temp.path().join("b.txt");
+        let c = temp.path().join("c.txt");
+        fs::write(&a, "same").unwrap();
+        fs::write(&b, "same").unwrap();
+        fs::write(&c, "diff").unwrap();
+
+        assert!(same_contents(&a, &b).unwrap());
+        assert!(!same_contents(&a, &c).unwrap());
+    }
 }
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+/// Files `add --template <name>` adds, relative to the project root
+///
+/// Directories end in `/`. A template of the same name in `config.toml`
+/// replaces the built-in one.
+pub const BUILTIN_TEMPLATES: &[(&str, &[&str])] = &[
+    (
+        "node",
+        &[
+            ".env",
+            ".env.local",
+            ".env.development.local",
+            ".env.test.local",
+            ".env.production.local",
+            ".npmrc",
+        ],
+    ),
+    (
+        "rails",
+        &[
+            ".env",
+            "config/master.key",
+            "config/credentials/development.key",
+            "config/credentials/production.key",
+            "config/database.local.yml",
+        ],
+    ),
+    (
+        "python",
+        &[
+            ".env",
+            ".env.local",
+            ".pypirc",
+            "instance/",
+            "local_settings.py",
+        ],
+    ),
+    (
+        "docker",
+        &[".env", "docker-compose.override.yml", "secrets/"],
+    ),
+];
+
+/// Paths in template `name`, user-defined first, then built-in
+pub fn template_files(name: &str, user: &BTreeMap<String, Vec<String>>) -> Option<Vec<String>> {
+    if let Some(files) = user.get(name) {
+        return Some(files.clone());
+    }
+
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, files)| files.iter().map(|file| file.to_string()).collect())
+}
+
+/// Every template name, built-in and user-defined, sorted
+pub fn template_names(user: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(user.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_templates_extend_and_override_builtins() {
+        let mut user = BTreeMap::new();
+        user.insert("node".to_string(), vec![".env.mine".to_string()]);
+        user.insert("go".to_string(), vec!["config.local.yaml".to_string()]);
+
+        assert_eq!(
+            template_files("node", &user),
+            Some(vec![".env.mine".to_string()])
+        );
+        assert!(template_files("rails", &user)
+            .unwrap()
+            .contains(&"config/master.key".to_string()));
+        assert_eq!(template_files("java", &user), None);
+
+        assert_eq!(
+            template_names(&user),
+            vec!["docker", "go", "node", "python", "rails"]
+        );
+    }
+}
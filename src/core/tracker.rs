@@ -1,14 +1,64 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// File name of the tracker, in the metadata dir or (with `metadata_in_repo`) the shade dir
+pub const SYNC_FILE: &str = ".shade-sync";
+
+/// Name of the tracker in a shade dir (with `metadata_in_repo`): one per
+/// machine, so each machine's sync times can be committed without clashing
+pub fn repo_sync_file_name(machine: &str) -> String {
+    let machine: String = machine
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{}.{}", SYNC_FILE, machine)
+}
+
+/// Whether `rel`, relative to a shade dir, is a tracker of any machine
+pub fn is_sync_file(rel: &Path) -> bool {
+    let top_level = rel.parent().is_some_and(|p| p.as_os_str().is_empty());
+    let name = rel.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    top_level
+        && (name == SYNC_FILE
+            || name
+                .strip_prefix(SYNC_FILE)
+                .is_some_and(|rest| rest.starts_with('.')))
+}
+
+/// How far ahead of this machine's clock a timestamp may be before it's
+/// considered bogus (the tracker is only ever written by this machine)
+const CLOCK_SKEW: i64 = 5 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Tracker {
     pub last_pull: Option<DateTime<Utc>>,
     pub last_push: Option<DateTime<Utc>>,
 }
 
+/// `.shade-sync` as stored: the timestamps plus a checksum over them
+#[derive(Debug, Serialize, Deserialize)]
+struct TrackerFile {
+    last_pull: Option<DateTime<Utc>>,
+    last_push: Option<DateTime<Utc>>,
+    /// Missing in files written before checksums were added
+    checksum: Option<String>,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tracker {
     pub fn new() -> Self {
         Self {
@@ -17,18 +67,44 @@ impl Tracker {
         }
     }
 
+    /// Load and validate the tracker
+    ///
+    /// Fails if the checksum doesn't match (a partial write or manual edit)
+    /// or a timestamp is in the future, which would make every file look
+    /// like a conflict.
     pub fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::new());
         }
 
         let contents = std::fs::read_to_string(path)?;
-        let tracker: Tracker = toml::from_str(&contents)?;
+        let file: TrackerFile = toml::from_str(&contents)
+            .with_context(|| format!("{} can't be parsed", path.display()))?;
+        let tracker = Tracker {
+            last_pull: file.last_pull,
+            last_push: file.last_push,
+        };
+
+        if let Some(checksum) = &file.checksum {
+            if *checksum != tracker.checksum()? {
+                anyhow::bail!(
+                    "{} doesn't match its checksum (edited or partially written)",
+                    path.display()
+                );
+            }
+        }
+        tracker.validate()?;
+
         Ok(tracker)
     }
 
     pub fn save(&self, path: &Path) -> Result<()> {
-        let contents = toml::to_string_pretty(self)?;
+        let file = TrackerFile {
+            last_pull: self.last_pull,
+            last_push: self.last_push,
+            checksum: Some(self.checksum()?),
+        };
+        let contents = toml::to_string_pretty(&file)?;
 
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -38,6 +114,19 @@ impl Tracker {
         Ok(())
     }
 
+    /// Reject timestamps this machine can't have written yet
+    pub fn validate(&self) -> Result<()> {
+        let limit = Utc::now() + Duration::seconds(CLOCK_SKEW);
+        for (name, timestamp) in [("last_pull", self.last_pull), ("last_push", self.last_push)] {
+            if let Some(timestamp) = timestamp {
+                if timestamp > limit {
+                    anyhow::bail!("{} is in the future ({})", name, timestamp.to_rfc3339());
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn update_pull(&mut self) {
         self.last_pull = Some(Utc::now());
     }
@@ -45,4 +134,75 @@ impl Tracker {
     pub fn update_push(&mut self) {
         self.last_push = Some(Utc::now());
     }
+
+    /// SHA-256 of the timestamps as serialized, so formatting doesn't matter
+    fn checksum(&self) -> Result<String> {
+        let contents = toml::to_string(self)?;
+        Ok(format!("{:x}", Sha256::digest(contents.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tracker_save_and_load() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".shade-sync");
+
+        let mut tracker = Tracker::new();
+        tracker.update_pull();
+        tracker.save(&path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("checksum = "));
+        assert_eq!(Tracker::load(&path).unwrap(), tracker);
+    }
+
+    #[test]
+    fn test_tracker_tampered_timestamp_is_rejected() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".shade-sync");
+
+        let tracker = Tracker {
+            last_pull: Some("2024-05-01T10:00:00Z".parse().unwrap()),
+            last_push: None,
+        };
+        tracker.save(&path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace("2024-05-01", "2024-04-01")).unwrap();
+
+        let err = Tracker::load(&path).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+    }
+
+    #[test]
+    fn test_tracker_future_timestamp_is_rejected() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".shade-sync");
+
+        // No checksum, like a tracker written by an older version
+        std::fs::write(&path, "last_pull = \"2999-01-01T00:00:00Z\"\n").unwrap();
+        let err = Tracker::load(&path).unwrap_err();
+        assert!(err.to_string().contains("last_pull is in the future"));
+
+        std::fs::write(&path, "last_pull = \"2024-05-01T10:00:00Z\"\n").unwrap();
+        let tracker = Tracker::load(&path).unwrap();
+        assert!(tracker.last_pull.is_some());
+        assert_eq!(tracker.last_push, None);
+    }
+
+    #[test]
+    fn test_repo_sync_file_name_per_machine() {
+        assert_eq!(repo_sync_file_name("laptop"), ".shade-sync.laptop");
+        assert_eq!(repo_sync_file_name("Jo's Mac"), ".shade-sync.Jo-s-Mac");
+
+        assert!(is_sync_file(Path::new(".shade-sync")));
+        assert!(is_sync_file(Path::new(".shade-sync.laptop")));
+        assert!(!is_sync_file(Path::new(".shade-syncer")));
+        assert!(!is_sync_file(Path::new("config/.shade-sync.laptop")));
+    }
 }
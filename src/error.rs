@@ -1,3 +1,4 @@
+use crate::core::{shown_config, shown_projects};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -21,12 +22,31 @@ pub enum ShadeError {
              Run:\n  \
              git-shade init\n\n\
              This will:\n  \
-             - Register your project in ~/.local/git-shade/config.toml\n  \
+             - Register your project in {config}\n  \
              - Create metadata directory\n  \
-             - Set up sync tracking"
+             - Set up sync tracking",
+        config = shown_config()
     )]
     NotInitialized { project_name: String },
 
+    #[error(
+        "Project not registered: {0}\n\n\
+             No project with this name is listed in {config}.\n\n\
+             Check the name, or initialize it from inside the project:\n  \
+             git-shade init",
+        config = shown_config()
+    )]
+    ProjectNotRegistered(String),
+
+    #[error(
+        "Project directory not found: {path}\n\n\
+             Project '{project_name}' is registered at {path}, but that directory no longer exists.\n\n\
+             Restore the project there, or remove it from {config}\n\
+             and run git-shade init from its new location.",
+        config = shown_config()
+    )]
+    ProjectPathMissing { project_name: String, path: PathBuf },
+
     #[error(
         "Project already initialized: {0}\n\n\
              This project is already set up with git-shade.\n\n\
@@ -40,19 +60,34 @@ pub enum ShadeError {
 
     #[error("Shade repository not found\n\n\
              git-shade requires a unified shade repository at:\n  \
-             ~/.local/git-shade/projects/\n\n\
+             {projects}/\n\n\
              This repository doesn't exist yet. You need to set it up first.\n\n\
              FIRST TIME SETUP:\n\n\
              Option 1 - Clone existing shade repo (if you have one):\n  \
-             git clone git@github.com:yourusername/my-shade-files.git ~/.local/git-shade/projects\n\n\
+             git clone git@github.com:yourusername/my-shade-files.git {projects}\n\n\
              Option 2 - Create new shade repo:\n  \
-             mkdir -p ~/.local/git-shade/projects\n  \
-             cd ~/.local/git-shade/projects\n  \
+             mkdir -p {projects}\n  \
+             cd {projects}\n  \
              git init\n  \
              git remote add origin git@github.com:yourusername/my-shade-files.git\n\n\
-             Then try git-shade init again.")]
+             Then try git-shade init again.",
+        projects = shown_projects()
+    )]
     ShadeRepoNotFound,
 
+    #[error(
+        "Shade directory is not a git repository: {path}\n\n\
+             The directory exists, but has no .git, so git-shade can't sync through it.\n\n\
+             Make it the shade repo in place (its files are kept):\n  \
+             git-shade init --create-shade [--remote <url>]\n\n\
+             Or by hand:\n  \
+             cd {path}\n  \
+             git init\n  \
+             git remote add origin git@github.com:yourusername/my-shade-files.git\n\n\
+             To clone an existing shade repo instead, move the directory aside first."
+    )]
+    ShadeDirNotRepo { path: PathBuf },
+
     #[error(
         "File not found: {0}\n\n\
              The file or directory you're trying to add doesn't exist.\n\n\
@@ -60,6 +95,68 @@ pub enum ShadeError {
     )]
     FileNotFound(PathBuf),
 
+    #[error(
+        "Path is outside the project: {0}\n\n\
+             Only files inside the project can be added. Through `..` or a symlink,\n\
+             this path resolves to somewhere else, where pull would later write to.\n\n\
+             To track a file from outside the project, give it a path inside it:\n  \
+             git-shade add <file> --as <path>"
+    )]
+    PathOutsideProject(PathBuf),
+
+    #[error(
+        "Archive is for a different project: {archive_project}\n\n\
+             This archive was exported from '{archive_project}', but you're importing\n\
+             into '{project_name}'.\n\n\
+             Run the import from inside '{archive_project}', or import into this project anyway:\n  \
+             git-shade import <archive> --project {project_name}"
+    )]
+    ArchiveProjectMismatch {
+        archive_project: String,
+        project_name: String,
+    },
+
+    #[error(
+        "Not tracked: {0}\n\n\
+             Only paths that were added with git-shade add can be moved or paused.\n\n\
+             Check what is tracked with:\n  \
+             git-shade status"
+    )]
+    NotTracked(PathBuf),
+
+    #[error(
+        "Destination already exists: {0}\n\n\
+             Remove or rename it first, or pick a different name."
+    )]
+    DestinationExists(PathBuf),
+
+    #[error(
+        "{0} needs confirmation, but there is no terminal to ask on\n\n\
+             Run again with --yes to go ahead."
+    )]
+    ConfirmationRequired(String),
+
+    #[error(
+        "Refusing to add {count} file(s) ({size}) without confirmation\n\n\
+             This is above the add limits in {config}\n\
+             (max_add_size / max_add_files) and there is no terminal to ask on.\n\n\
+             If this is intended, run again with --force:\n  \
+             git-shade add --force <files>",
+        config = shown_config()
+    )]
+    AddLimitExceeded { count: usize, size: String },
+
+    #[error(
+        "{} file(s) already in shade have different contents: {}\n\n\
+             They were probably added on another machine. There is no terminal to ask\n\
+             which version to keep, so run again with:\n  \
+             --link-existing  to keep the shade versions (copied into your project)\n  \
+             --force          to overwrite them with your local files",
+        files.len(),
+        files.join(", ")
+    )]
+    ShadeCopyDiffers { files: Vec<String> },
+
     #[error(
         "No files tracked\n\n\
              This project has no files being tracked by git-shade.\n\n\
@@ -75,9 +172,105 @@ pub enum ShadeError {
     #[error("Conflicts detected. Manual resolution required.")]
     ConflictDetected { files: Vec<String> },
 
+    #[error(
+        "Doctor found {0} critical problem(s)\n\n\
+             Apply the suggested fixes above and run git-shade doctor again."
+    )]
+    DoctorFailed(usize),
+
+    #[error(
+        "Verify found {0} problem(s)\n\n\
+             Reconcile exclude patterns, shade and local files with:\n  \
+             git-shade verify --fix"
+    )]
+    VerifyFailed(usize),
+
+    #[error(
+        "Merge conflict in shade repo. Resolve it in {projects} and pull again.",
+        projects = shown_projects()
+    )]
+    ShadeMergeConflict { files: Vec<String> },
+
+    #[error(
+        "Can't fast-forward the shade repo\n\n\
+             pull_strategy is ff-only, but the shade repo has commits the remote doesn't\n\
+             and the remote has commits it doesn't. Nothing was changed.\n\n\
+             Pull once with another strategy:\n  \
+             git-shade pull --rebase   (replay your shade commits on top, history stays linear)\n  \
+             git-shade pull --merge    (join both with a merge commit)\n\n\
+             Or change the default:\n  \
+             git-shade config set pull_strategy rebase"
+    )]
+    PullNotFastForward,
+
+    #[error(
+        "The shade repo is in the middle of a {operation}\n\n\
+             Nothing was copied or committed. Finish or abort the {operation} first:\n  \
+             cd {projects}\n  \
+             git status\n  \
+             git {operation} --continue   (after resolving any conflicts)\n  \
+             git {operation} --abort      (to drop it)",
+        projects = shown_projects()
+    )]
+    ShadeRepoBusy { operation: String },
+
+    #[error(
+        "Could not reach the shade remote\n\n\
+             {0}\n\n\
+             Check your network connection and that your credentials (SSH key or token)\n\
+             can access the remote:\n  \
+             cd {projects}\n  \
+             git remote -v\n  \
+             git fetch",
+        projects = shown_projects()
+    )]
+    RemoteUnreachable(String),
+
+    #[error(
+        "The shade remote has changes you haven't pulled\n\n\
+             Run git-shade pull first, then push again.\n\n\
+             To replace the remote's changes with yours instead, fetch them and\n\
+             push with --force-with-lease:\n  \
+             git-shade status --fetch\n  \
+             git-shade push --force-with-lease"
+    )]
+    PushRejected,
+
+    #[error(
+        "{hook} hook failed ({status}): {command}\n\n\
+             The operation was aborted. Fix the hook or remove it from the\n\
+             [hooks] section of {config}.",
+        config = shown_config()
+    )]
+    HookFailed {
+        hook: String,
+        command: String,
+        status: String,
+    },
+
+    #[error(
+        "Could not sign the shade commit\n\n\
+             {0}\n\n\
+             Check that your signing key is available (and unlocked) to git:\n  \
+             git config --get user.signingkey\n  \
+             echo test | gpg --clearsign\n\n\
+             To push without a signature this once:\n  \
+             git-shade push --no-sign"
+    )]
+    SigningFailed(String),
+
     #[error("Git command failed: {0}")]
     GitError(String),
 
+    #[error(
+        "git is not installed\n\n\
+             git-shade runs git for everything it does, but no `git` executable was\n\
+             found on your PATH.\n\n\
+             Install git (https://git-scm.com/downloads) and make sure it runs:\n  \
+             git --version"
+    )]
+    GitNotInstalled,
+
     // #[from] auto-converts std::io::Error → ShadeError
     #[error(transparent)]
     Io(#[from] std::io::Error),
@@ -86,5 +279,45 @@ pub enum ShadeError {
     Other(#[from] anyhow::Error),
 }
 
+impl ShadeError {
+    /// Process exit code, so scripts can tell failure classes apart
+    ///
+    /// 1 is anything without a class of its own. clap also exits with 2 on
+    /// invalid arguments.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShadeError::NotGitRepo { .. } => 2,
+            ShadeError::NotInitialized { .. }
+            | ShadeError::ProjectNotRegistered(_)
+            | ShadeError::ProjectPathMissing { .. } => 3,
+            ShadeError::ShadeRepoNotFound | ShadeError::ShadeDirNotRepo { .. } => 4,
+            ShadeError::ConflictDetected { .. }
+            | ShadeError::ShadeMergeConflict { .. }
+            | ShadeError::ShadeRepoBusy { .. }
+            | ShadeError::PullNotFastForward
+            | ShadeError::ShadeCopyDiffers { .. } => 5,
+            ShadeError::GitError(_)
+            | ShadeError::GitNotInstalled
+            | ShadeError::SigningFailed(_) => 6,
+            ShadeError::RemoteUnreachable(_) => 7,
+            ShadeError::PushRejected => 8,
+            ShadeError::AlreadyInitialized(_)
+            | ShadeError::FileNotFound(_)
+            | ShadeError::PathOutsideProject(_)
+            | ShadeError::ArchiveProjectMismatch { .. }
+            | ShadeError::NotTracked(_)
+            | ShadeError::DestinationExists(_)
+            | ShadeError::ConfirmationRequired(_)
+            | ShadeError::AddLimitExceeded { .. }
+            | ShadeError::NoFilesTracked
+            | ShadeError::DoctorFailed(_)
+            | ShadeError::VerifyFailed(_)
+            | ShadeError::HookFailed { .. }
+            | ShadeError::Io(_)
+            | ShadeError::Other(_) => 1,
+        }
+    }
+}
+
 // Type alias for cleaner return types
 pub type Result<T> = std::result::Result<T, ShadeError>;
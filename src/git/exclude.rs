@@ -1,67 +1,422 @@
+use crate::core::{to_slash, KEEP_FILE};
 use anyhow::{Context, Result};
-use std::fs::{self, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
-/// Add patterns to .git/info/exclude without creating duplicates
-pub fn add_to_exclude(project_path: &Path, patterns: &[String]) -> Result<()> {
-    let exclude_file = project_path.join(".git/info/exclude");
+/// A tracked entry in .git/info/exclude, relative to the project root
+///
+/// Directories are written as `secrets/` (or `secrets/**` with `--dir-as-glob`)
+/// and both forms parse back to the same `Pattern::Dir`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    File(PathBuf),
+    Dir(PathBuf),
+}
+
+impl Pattern {
+    /// Parse a line from .git/info/exclude
+    ///
+    /// Equivalent spellings (see `canonical_line`) parse to the same pattern.
+    pub fn parse(line: &str) -> Self {
+        let line = canonical_line(line);
+        let line = line.trim_start_matches('/');
+
+        if let Some(dir) = line.strip_suffix("/**") {
+            return Pattern::Dir(PathBuf::from(dir.trim_end_matches('/')));
+        }
+
+        if line.ends_with('/') {
+            return Pattern::Dir(PathBuf::from(line.trim_end_matches('/')));
+        }
+
+        Pattern::File(PathBuf::from(line))
+    }
+
+    /// Relative path of the file or directory, without any trailing slash
+    pub fn path(&self) -> &Path {
+        match self {
+            Pattern::File(path) | Pattern::Dir(path) => path,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Pattern::Dir(_))
+    }
+
+    /// Render the line written to .git/info/exclude (always with `/` separators)
+    pub fn to_exclude_line(&self, dir_as_glob: bool) -> String {
+        match self {
+            Pattern::File(path) => to_slash(path),
+            Pattern::Dir(path) if dir_as_glob => format!("{}/**", to_slash(path)),
+            Pattern::Dir(path) => format!("{}/", to_slash(path)),
+        }
+    }
+
+    /// Whether a relative file path is tracked by this pattern
+    ///
+    /// A plain `secrets` line also matches everything inside a `secrets` directory.
+    pub fn covers(&self, rel_path: &Path) -> bool {
+        rel_path.starts_with(self.path())
+    }
+
+    /// List the files this pattern refers to under `base`, relative to `base`
+    ///
+    /// Directories are expanded recursively (a plain `secrets` line matches a
+    /// directory too, as it does for git), skipping empty-dir markers. Missing
+    /// paths yield nothing.
+    pub fn files_in(&self, base: &Path) -> Result<Vec<PathBuf>> {
+        let full_path = base.join(self.path());
+        let mut files = Vec::new();
+
+        if full_path.is_file() {
+            files.push(self.path().to_path_buf());
+        } else if full_path.is_dir() {
+            for entry in WalkDir::new(&full_path) {
+                let entry = entry?;
+                if entry.file_type().is_file() && entry.file_name() != KEEP_FILE {
+                    if let Ok(rel) = entry.path().strip_prefix(base) {
+                        files.push(rel.to_path_buf());
+                    }
+                }
+            }
+            files.sort();
+        }
 
-    // Ensure .git/info directory exists
-    if let Some(parent) = exclude_file.parent() {
-        fs::create_dir_all(parent).context("Failed to create .git/info directory")?;
+        Ok(files)
     }
+}
 
-    // Read existing patterns
-    let existing_patterns = if exclude_file.exists() {
-        let file = fs::File::open(&exclude_file)?;
-        BufReader::new(file)
-            .lines()
-            .filter_map(|line| line.ok())
-            .collect::<Vec<String>>()
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_exclude_line(false))
+    }
+}
+
+/// `line` spelled the way git-shade writes it
+///
+/// Empty and `.` segments are dropped, and a directory keeps its `/` or `/**`
+/// suffix. A leading `/` (or `./`) stays on a single name: git matches
+/// `config.local` at any depth but `/config.local` only at the root. A path
+/// with a `/` inside is matched from the root either way, so it goes there.
+pub fn canonical_line(line: &str) -> String {
+    let line = line.trim();
+    let (body, suffix) = match line.strip_suffix("/**") {
+        Some(body) => (body, "/**"),
+        None => match line.strip_suffix('/') {
+            Some(body) => (body, "/"),
+            None => (line, ""),
+        },
+    };
+
+    let segments: Vec<&str> = body
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    let rooted = body.starts_with('/') || body.starts_with("./");
+    let anchor = if rooted && segments.len() == 1 && suffix != "/**" {
+        "/"
     } else {
-        Vec::new()
+        ""
     };
+    format!("{}{}{}", anchor, segments.join("/"), suffix)
+}
+
+/// Whether git only matches canonical `line` from the project root
+fn is_anchored(line: &str) -> bool {
+    line.starts_with('/') || line.trim_end_matches('/').contains('/')
+}
+
+/// For each pattern among `lines`, the index of its first spelling and of the
+/// one to keep
+///
+/// That's the first spelling, unless a later one matches at any depth where
+/// it's anchored: dropping the wider one would stop git excluding nested copies.
+fn kept_spellings(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut seen: Vec<Pattern> = Vec::new();
+    let mut kept: Vec<(usize, usize)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let pattern = Pattern::parse(line);
+        match seen.iter().position(|other| *other == pattern) {
+            Some(at) => {
+                let chosen = &mut kept[at].1;
+                if is_anchored(&canonical_line(lines[*chosen]))
+                    && !is_anchored(&canonical_line(line))
+                {
+                    *chosen = index;
+                }
+            }
+            None => {
+                seen.push(pattern);
+                kept.push((index, index));
+            }
+        }
+    }
+    kept
+}
+
+/// First line of the block git-shade manages in .git/info/exclude
+pub const BLOCK_START: &str = "# >>> git-shade";
+/// Last line of the block git-shade manages in .git/info/exclude
+pub const BLOCK_END: &str = "# <<< git-shade";
+
+/// .git/info/exclude split around git-shade's managed block
+///
+/// Only lines inside the block are git-shade's; everything else belongs to the
+/// user and is written back untouched.
+struct ExcludeFile {
+    path: PathBuf,
+    before: Vec<String>,
+    managed: Vec<String>,
+    after: Vec<String>,
+}
+
+impl ExcludeFile {
+    /// Load the exclude file, migrating a file without markers in memory
+    ///
+    /// Files written before the markers existed have git-shade's entries mixed
+    /// with the user's. Literal paths (what `add` writes) are taken as
+    /// git-shade's; globs like `*.log` and comments stay outside the block.
+    fn load(project_path: &Path) -> Result<Self> {
+        let path = project_path.join(".git/info/exclude");
+        let contents = if path.exists() {
+            fs::read_to_string(&path).context("Failed to read exclude file")?
+        } else {
+            String::new()
+        };
+        let lines: Vec<String> = contents.lines().map(str::to_string).collect();
 
-    // Filter out patterns that already exist
-    let new_patterns: Vec<&String> = patterns
+        let Some(start) = lines.iter().position(|line| line.trim() == BLOCK_START) else {
+            let (managed, before): (Vec<String>, Vec<String>) =
+                lines.into_iter().partition(|line| looks_managed(line));
+            return Ok(Self {
+                path,
+                before,
+                managed,
+                after: Vec::new(),
+            });
+        };
+
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.trim() == BLOCK_END)
+            .map_or(lines.len(), |offset| start + offset);
+
+        Ok(Self {
+            path,
+            before: lines[..start].to_vec(),
+            managed: lines[start + 1..end].to_vec(),
+            after: lines.get(end + 1..).unwrap_or_default().to_vec(),
+        })
+    }
+
+    /// Entries in the managed block, without blanks and comments
+    fn patterns(&self) -> Vec<String> {
+        self.managed
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut lines = self.before_trimmed();
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(BLOCK_START.to_string());
+        lines.extend(self.managed.iter().cloned());
+        lines.push(BLOCK_END.to_string());
+        lines.extend(self.after.iter().cloned());
+
+        self.write(&lines)
+    }
+
+    /// Write only the user's lines, dropping the block and its markers
+    fn save_without_block(&self) -> Result<()> {
+        let mut lines = self.before_trimmed();
+        lines.extend(self.after.iter().cloned());
+        self.write(&lines)
+    }
+
+    fn before_trimmed(&self) -> Vec<String> {
+        let mut before = self.before.clone();
+        while before.last().is_some_and(|line| line.trim().is_empty()) {
+            before.pop();
+        }
+        before
+    }
+
+    fn write(&self, lines: &[String]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create .git/info directory")?;
+        }
+
+        let mut contents = lines.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents).context("Failed to write exclude file")?;
+        Ok(())
+    }
+}
+
+/// Whether an unmarked exclude line is one git-shade would have written
+fn looks_managed(line: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+        return false;
+    }
+
+    let path = line.strip_suffix("/**").unwrap_or(line);
+    !path.contains(['*', '?', '[', '\\'])
+}
+
+/// Add patterns to git-shade's block in .git/info/exclude without creating duplicates
+pub fn add_to_exclude(project_path: &Path, patterns: &[String]) -> Result<()> {
+    let mut exclude = ExcludeFile::load(project_path)?;
+
+    // `secrets/` and `secrets/**` count as the same entry
+    let mut seen: Vec<Pattern> = exclude
+        .patterns()
         .iter()
-        .filter(|pattern| !existing_patterns.contains(pattern))
+        .map(|line| Pattern::parse(line))
         .collect();
 
-    if new_patterns.is_empty() {
-        return Ok(());
+    let mut added = false;
+    for pattern in patterns {
+        let parsed = Pattern::parse(pattern);
+        if !seen.contains(&parsed) {
+            seen.push(parsed);
+            exclude.managed.push(pattern.clone());
+            added = true;
+        }
     }
 
-    // Append new patterns
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&exclude_file)?;
-
-    for pattern in new_patterns {
-        writeln!(file, "{}", pattern)?;
+    if added {
+        exclude.save()?;
     }
 
     Ok(())
 }
 
-/// Read all patterns from .git/info/exclude
-pub fn read_exclude(project_path: &Path) -> Result<Vec<String>> {
-    let exclude_file = project_path.join(".git/info/exclude");
+/// Remove every line of git-shade's block that refers to `pattern`
+///
+/// Comments and lines outside the block are kept as they are. Returns the
+/// removed lines.
+pub fn remove_from_exclude(project_path: &Path, pattern: &Pattern) -> Result<Vec<String>> {
+    let mut exclude = ExcludeFile::load(project_path)?;
+
+    let (removed, kept): (Vec<String>, Vec<String>) = exclude.managed.drain(..).partition(|line| {
+        let trimmed = line.trim();
+        !trimmed.is_empty() && !trimmed.starts_with('#') && Pattern::parse(trimmed) == *pattern
+    });
+    exclude.managed = kept;
 
-    if !exclude_file.exists() {
+    if removed.is_empty() {
         return Ok(Vec::new());
     }
 
-    let file = fs::File::open(&exclude_file)?;
-    let patterns = BufReader::new(file)
-        .lines()
-        .filter_map(|line| line.ok())
-        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+    exclude.save()?;
+
+    Ok(removed
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+/// Remove git-shade's whole block from .git/info/exclude
+///
+/// Lines outside the block are kept. Returns the patterns that were in it.
+pub fn remove_exclude_block(project_path: &Path) -> Result<Vec<String>> {
+    let exclude = ExcludeFile::load(project_path)?;
+    let removed = exclude.patterns();
+
+    if exclude.path.exists() {
+        exclude.save_without_block()?;
+    }
+
+    Ok(removed)
+}
+
+/// Read the patterns git-shade manages in .git/info/exclude
+///
+/// Only lines between `BLOCK_START` and `BLOCK_END` count; in a file without
+/// markers, only literal paths do (see `ExcludeFile::load`). Lines come back
+/// canonical, each pattern once, however many spellings of it the file has.
+pub fn read_exclude(project_path: &Path) -> Result<Vec<String>> {
+    let patterns = ExcludeFile::load(project_path)?.patterns();
+    let lines: Vec<&str> = patterns.iter().map(String::as_str).collect();
+    Ok(kept_spellings(&lines)
+        .into_iter()
+        .map(|(_, chosen)| canonical_line(lines[chosen]))
+        .collect())
+}
+
+/// Lines of git-shade's block that repeat another spelling of their pattern
+/// or aren't canonical
+pub fn redundant_excludes(project_path: &Path) -> Result<Vec<String>> {
+    let patterns = ExcludeFile::load(project_path)?.patterns();
+    let lines: Vec<&str> = patterns.iter().map(String::as_str).collect();
+    let kept = kept_spellings(&lines);
+    Ok(lines
+        .iter()
+        .enumerate()
+        .filter(|(index, line)| {
+            !kept.iter().any(|(_, chosen)| chosen == index) || canonical_line(line) != **line
+        })
+        .map(|(_, line)| line.to_string())
+        .collect())
+}
+
+/// Rewrite git-shade's block with every pattern once, in canonical form
+///
+/// A pattern stays where its first spelling was, written the way
+/// `kept_spellings` picks; comments and lines outside the block are kept.
+/// Returns whether the file changed.
+pub fn normalize_exclude(project_path: &Path) -> Result<bool> {
+    let mut exclude = ExcludeFile::load(project_path)?;
+
+    let is_pattern = |line: &str| {
+        let trimmed = line.trim();
+        !trimmed.is_empty() && !trimmed.starts_with('#')
+    };
+    let lines: Vec<&str> = exclude
+        .managed
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| is_pattern(line))
         .collect();
+    let kept = kept_spellings(&lines);
 
-    Ok(patterns)
+    let mut managed = Vec::new();
+    let mut index = 0;
+    for line in &exclude.managed {
+        if !is_pattern(line) {
+            managed.push(line.clone());
+            continue;
+        }
+        if let Some((_, chosen)) = kept.iter().find(|(first, _)| *first == index) {
+            managed.push(canonical_line(lines[*chosen]));
+        }
+        index += 1;
+    }
+
+    if managed == exclude.managed {
+        return Ok(false);
+    }
+    exclude.managed = managed;
+    exclude.save()?;
+    Ok(true)
+}
+
+/// Read all patterns from .git/info/exclude as parsed `Pattern`s
+pub fn read_patterns(project_path: &Path) -> Result<Vec<Pattern>> {
+    Ok(read_exclude(project_path)?
+        .iter()
+        .map(|line| Pattern::parse(line))
+        .collect())
 }
 
 #[cfg(test)]
@@ -91,5 +446,209 @@ mod tests {
         add_to_exclude(project_path, &patterns).unwrap();
         let result = read_exclude(project_path).unwrap();
         assert_eq!(result.len(), 2);
+
+        // Glob form of an existing directory is not a new entry
+        add_to_exclude(project_path, &["secrets/**".to_string()]).unwrap();
+        let result = read_exclude(project_path).unwrap();
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_from_exclude_keeps_other_lines() {
+        let temp = TempDir::new().unwrap();
+        let project_path = temp.path();
+        fs::create_dir_all(project_path.join(".git/info")).unwrap();
+        fs::write(
+            project_path.join(".git/info/exclude"),
+            "# comment\nconfig.local\nsecrets/**\n",
+        )
+        .unwrap();
+
+        let removed =
+            remove_from_exclude(project_path, &Pattern::Dir(PathBuf::from("secrets"))).unwrap();
+        assert_eq!(removed, vec!["secrets/**".to_string()]);
+
+        let contents = fs::read_to_string(project_path.join(".git/info/exclude")).unwrap();
+        assert_eq!(
+            contents,
+            "# comment\n\n# >>> git-shade\nconfig.local\n# <<< git-shade\n"
+        );
+
+        // Nothing to remove
+        assert!(
+            remove_from_exclude(project_path, &Pattern::File(PathBuf::from("other")))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_exclude_only_returns_managed_block() {
+        let temp = TempDir::new().unwrap();
+        let project_path = temp.path();
+        fs::create_dir_all(project_path.join(".git/info")).unwrap();
+        fs::write(
+            project_path.join(".git/info/exclude"),
+            "# mine\nbuild/\n\n# >>> git-shade\nconfig.local\n# <<< git-shade\n*.log\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_exclude(project_path).unwrap(),
+            vec!["config.local".to_string()]
+        );
+
+        add_to_exclude(project_path, &["secrets/".to_string()]).unwrap();
+        let contents = fs::read_to_string(project_path.join(".git/info/exclude")).unwrap();
+        assert_eq!(
+            contents,
+            "# mine\nbuild/\n\n# >>> git-shade\nconfig.local\nsecrets/\n# <<< git-shade\n*.log\n"
+        );
+    }
+
+    #[test]
+    fn test_unmarked_exclude_is_migrated_on_write() {
+        let temp = TempDir::new().unwrap();
+        let project_path = temp.path();
+        fs::create_dir_all(project_path.join(".git/info")).unwrap();
+        fs::write(
+            project_path.join(".git/info/exclude"),
+            "# git ls-files --others --exclude-from=.git/info/exclude\n*.log\nconfig.local\nsecrets/**\n",
+        )
+        .unwrap();
+
+        // Before any write, globs are already left out
+        assert_eq!(
+            read_exclude(project_path).unwrap(),
+            vec!["config.local".to_string(), "secrets/**".to_string()]
+        );
+
+        add_to_exclude(project_path, &[".env.local".to_string()]).unwrap();
+        let contents = fs::read_to_string(project_path.join(".git/info/exclude")).unwrap();
+        assert_eq!(
+            contents,
+            "# git ls-files --others --exclude-from=.git/info/exclude\n*.log\n\n\
+             # >>> git-shade\nconfig.local\nsecrets/**\n.env.local\n# <<< git-shade\n"
+        );
+    }
+
+    #[test]
+    fn test_pattern_parse() {
+        assert_eq!(
+            Pattern::parse("config.local"),
+            Pattern::File(PathBuf::from("config.local"))
+        );
+        assert_eq!(
+            Pattern::parse("secrets/"),
+            Pattern::Dir(PathBuf::from("secrets"))
+        );
+        assert_eq!(
+            Pattern::parse("secrets/**"),
+            Pattern::Dir(PathBuf::from("secrets"))
+        );
+
+        let dir = Pattern::Dir(PathBuf::from("config/keys"));
+        assert_eq!(dir.to_exclude_line(false), "config/keys/");
+        assert_eq!(dir.to_exclude_line(true), "config/keys/**");
+        assert!(dir.covers(Path::new("config/keys/prod/api.key")));
+        assert!(!dir.covers(Path::new("config/other.yml")));
+    }
+
+    #[test]
+    fn test_equivalent_spellings_collapse_to_one_pattern() {
+        for spelling in ["config.local", " config.local "] {
+            assert_eq!(canonical_line(spelling), "config.local", "{}", spelling);
+        }
+        // git only matches these at the root
+        for spelling in ["/config.local", "./config.local", "././config.local"] {
+            assert_eq!(canonical_line(spelling), "/config.local", "{}", spelling);
+        }
+        assert_eq!(canonical_line("/secrets//keys/"), "secrets/keys/");
+        assert_eq!(canonical_line("./secrets/**"), "secrets/**");
+        assert_eq!(canonical_line("./secrets/"), "/secrets/");
+        assert_eq!(
+            Pattern::parse("/config.local"),
+            Pattern::File(PathBuf::from("config.local"))
+        );
+        assert_eq!(
+            Pattern::parse("/secrets/./keys/**"),
+            Pattern::Dir(PathBuf::from("secrets/keys"))
+        );
+
+        let temp = TempDir::new().unwrap();
+        let project_path = temp.path();
+        fs::create_dir_all(project_path.join(".git/info")).unwrap();
+        fs::write(
+            project_path.join(".git/info/exclude"),
+            "*.log\n\n# >>> git-shade\n/config.local\n./config.local\nconfig.local\n\
+             # keys\n./secrets/\nsecrets/**\n# <<< git-shade\n",
+        )
+        .unwrap();
+
+        // The spelling that matches at any depth wins over the anchored ones
+        assert_eq!(
+            read_exclude(project_path).unwrap(),
+            vec!["config.local", "/secrets/"]
+        );
+        assert_eq!(
+            redundant_excludes(project_path).unwrap(),
+            vec![
+                "/config.local",
+                "./config.local",
+                "./secrets/",
+                "secrets/**"
+            ]
+        );
+
+        // Adding another spelling doesn't bring a duplicate back
+        add_to_exclude(project_path, &["secrets/**".to_string()]).unwrap();
+
+        assert!(normalize_exclude(project_path).unwrap());
+        assert_eq!(
+            fs::read_to_string(project_path.join(".git/info/exclude")).unwrap(),
+            "*.log\n\n# >>> git-shade\nconfig.local\n# keys\n/secrets/\n# <<< git-shade\n"
+        );
+        assert!(redundant_excludes(project_path).unwrap().is_empty());
+        assert!(!normalize_exclude(project_path).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_exclude_lines_use_forward_slashes() {
+        let file = Pattern::File(PathBuf::from(r"config\app.local"));
+        assert_eq!(file.to_exclude_line(false), "config/app.local");
+
+        let dir = Pattern::Dir(PathBuf::from(r"config\keys"));
+        assert_eq!(dir.to_exclude_line(false), "config/keys/");
+        assert_eq!(dir.to_exclude_line(true), "config/keys/**");
+        assert_eq!(Pattern::parse("config/keys/"), dir);
+    }
+
+    #[test]
+    fn test_pattern_files_in_nested_dir() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path();
+        fs::create_dir_all(base.join("secrets/prod/db")).unwrap();
+        fs::write(base.join("secrets/api.key"), "a").unwrap();
+        fs::write(base.join("secrets/prod/db/password"), "b").unwrap();
+
+        let files = Pattern::parse("secrets/").files_in(base).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("secrets/api.key"),
+                PathBuf::from("secrets/prod/db/password"),
+            ]
+        );
+
+        // Missing paths yield nothing
+        assert!(Pattern::parse("missing/")
+            .files_in(base)
+            .unwrap()
+            .is_empty());
+        assert!(Pattern::parse("missing.txt")
+            .files_in(base)
+            .unwrap()
+            .is_empty());
     }
 }
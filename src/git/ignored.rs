@@ -0,0 +1,150 @@
+use super::exclude::{BLOCK_END, BLOCK_START};
+use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Existing files and directories the project's ignore rules match
+///
+/// Reads every `.gitignore` in the tree plus the user's part of
+/// `.git/info/exclude` (git-shade's own block is skipped). An ignored
+/// directory is returned as a whole and not descended into. Results are
+/// relative to `project_path` and sorted.
+pub fn find_ignored(project_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut matchers = vec![exclude_matcher(project_path)?];
+    if let Some(matcher) = gitignore_matcher(project_path)? {
+        matchers.push(matcher);
+    }
+
+    let mut ignored = Vec::new();
+    let mut walker = WalkDir::new(project_path)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        let is_dir = entry.file_type().is_dir();
+
+        if entry.file_name() == ".git" {
+            if is_dir {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+
+        let Ok(rel) = entry.path().strip_prefix(project_path) else {
+            continue;
+        };
+
+        if is_ignored(&matchers, entry.path(), is_dir) {
+            ignored.push(rel.to_path_buf());
+            if is_dir {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+
+        // A directory comes before its contents, so its .gitignore is loaded
+        // before anything it applies to
+        if is_dir {
+            if let Some(matcher) = gitignore_matcher(entry.path())? {
+                matchers.push(matcher);
+            }
+        }
+    }
+
+    ignored.sort();
+    Ok(ignored)
+}
+
+/// The deepest matcher with an opinion wins, like nested .gitignore files
+fn is_ignored(matchers: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    matchers
+        .iter()
+        .rev()
+        .filter(|matcher| path.starts_with(matcher.path()))
+        .map(|matcher| matcher.matched_path_or_any_parents(path, is_dir))
+        .find(|matched| !matched.is_none())
+        .is_some_and(|matched| matched.is_ignore())
+}
+
+fn gitignore_matcher(dir: &Path) -> Result<Option<Gitignore>> {
+    let file = dir.join(".gitignore");
+    if !file.is_file() {
+        return Ok(None);
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(err) = builder.add(&file) {
+        return Err(anyhow::anyhow!(
+            "Failed to read {}: {}",
+            file.display(),
+            err
+        ));
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Matcher for the lines of .git/info/exclude above git-shade's block
+fn exclude_matcher(project_path: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(project_path);
+    let exclude_file = project_path.join(".git/info/exclude");
+
+    if exclude_file.exists() {
+        let contents = std::fs::read_to_string(&exclude_file)?;
+        let mut in_block = false;
+        for line in contents.lines() {
+            if line.trim() == BLOCK_START {
+                in_block = true;
+            } else if in_block {
+                in_block = line.trim() != BLOCK_END;
+            } else {
+                builder.add_line(None, line)?;
+            }
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_ignored_reads_nested_gitignores() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path();
+        fs::create_dir_all(base.join(".git/info")).unwrap();
+        fs::create_dir_all(base.join("config")).unwrap();
+        fs::create_dir_all(base.join("tmp/cache")).unwrap();
+        fs::write(base.join(".gitignore"), "*.local\ntmp/\n").unwrap();
+        fs::write(base.join("config/.gitignore"), "*.key\n!public.key\n").unwrap();
+        fs::write(
+            base.join(".git/info/exclude"),
+            "notes.txt\n\n# >>> git-shade\nshaded.yml\n# <<< git-shade\n",
+        )
+        .unwrap();
+
+        fs::write(base.join("app.local"), "").unwrap();
+        fs::write(base.join("notes.txt"), "").unwrap();
+        fs::write(base.join("shaded.yml"), "").unwrap();
+        fs::write(base.join("README.md"), "").unwrap();
+        fs::write(base.join("tmp/cache/x"), "").unwrap();
+        fs::write(base.join("config/server.key"), "").unwrap();
+        fs::write(base.join("config/public.key"), "").unwrap();
+
+        assert_eq!(
+            find_ignored(base).unwrap(),
+            vec![
+                PathBuf::from("app.local"),
+                PathBuf::from("config/server.key"),
+                PathBuf::from("notes.txt"),
+                PathBuf::from("tmp"),
+            ]
+        );
+    }
+}
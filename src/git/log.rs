@@ -0,0 +1,164 @@
+use super::repo::run_git;
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use filetime::FileTime;
+use std::path::Path;
+
+/// Separates commits in `git log` output (ASCII record separator)
+const RECORD_SEP: char = '\x1e';
+/// Separates fields of a commit header (ASCII unit separator)
+const FIELD_SEP: char = '\x1f';
+
+/// Commit trailer `push` records the machine name in
+pub const MACHINE_TRAILER: &str = "Shade-Machine";
+
+/// Commit trailer `push` records the project in, for `push --amend`
+pub const PROJECT_TRAILER: &str = "Shade-Project";
+
+/// One commit that touched a file in the shade repo
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub hash: String,
+    pub date: DateTime<FixedOffset>,
+    pub subject: String,
+    /// Machine it was pushed from: the `Shade-Machine` trailer, or for older
+    /// commits what the subject says (`... from <host> ...`)
+    pub host: Option<String>,
+    /// Diff of the file in this commit, when asked for
+    pub patch: Option<String>,
+}
+
+/// Commits that touched `path` (relative to the repo root), newest first
+///
+/// Renames are followed for files; `--follow` doesn't work on directories.
+pub fn file_log(repo: &Path, path: &str, patch: bool) -> Result<Vec<LogEntry>> {
+    let format = format!(
+        "--format={}%H{}%cI{}%(trailers:key={},valueonly,separator=%x2C){}%s",
+        RECORD_SEP, FIELD_SEP, FIELD_SEP, MACHINE_TRAILER, FIELD_SEP
+    );
+    let mut args = vec!["log", format.as_str()];
+    if patch {
+        args.push("--patch");
+    }
+    if !repo.join(path).is_dir() {
+        args.push("--follow");
+    }
+    args.extend(["--", path]);
+
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        // No commits yet is an empty history, not an error
+        if String::from_utf8_lossy(&output.stderr).contains("does not have any commits") {
+            return Ok(Vec::new());
+        }
+        anyhow::bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_log(&String::from_utf8_lossy(&output.stdout), patch))
+}
+
+/// Time of the last commit that touched `path`, as an mtime (`None` if never committed)
+///
+/// Git doesn't store mtimes, so this is the one timestamp every clone agrees on.
+pub fn last_commit_mtime(repo: &Path, path: &str) -> Result<Option<FileTime>> {
+    let output = run_git(repo, &["log", "-1", "--format=%cI", "--", path])?;
+    if !output.status.success() {
+        if String::from_utf8_lossy(&output.stderr).contains("does not have any commits") {
+            return Ok(None);
+        }
+        anyhow::bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let date = stdout.trim();
+    if date.is_empty() {
+        return Ok(None);
+    }
+    let date = DateTime::parse_from_rfc3339(date)?;
+    Ok(Some(FileTime::from_unix_time(date.timestamp(), 0)))
+}
+
+fn parse_log(output: &str, patch: bool) -> Vec<LogEntry> {
+    output
+        .split(RECORD_SEP)
+        .filter_map(|record| {
+            let (header, rest) = record.split_once('\n').unwrap_or((record, ""));
+            let mut fields = header.split(FIELD_SEP);
+            let hash = fields.next()?.to_string();
+            let date = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+            let machine = fields.next().unwrap_or("").trim();
+            let subject = fields.next().unwrap_or("").to_string();
+            let host = match machine.split(',').next_back() {
+                Some(machine) if !machine.is_empty() => Some(machine.to_string()),
+                _ => host_from_subject(&subject),
+            };
+
+            Some(LogEntry {
+                hash,
+                date,
+                host,
+                subject,
+                patch: patch.then(|| rest.trim_matches('\n').to_string()),
+            })
+        })
+        .collect()
+}
+
+/// `laptop` from the default push message `[app] Update from laptop - 2024-...`
+///
+/// Custom commit templates only yield a host if they keep `from {hostname}`.
+pub fn host_from_subject(subject: &str) -> Option<String> {
+    let (_, rest) = subject.split_once(" from ")?;
+    let host = rest.split(" - ").next()?.trim();
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_from_subject() {
+        assert_eq!(
+            host_from_subject("[app] Update from laptop.local - 2024-05-01 10:00:00"),
+            Some("laptop.local".to_string())
+        );
+        assert_eq!(
+            host_from_subject("app: 3 file(s) from ci-runner"),
+            Some("ci-runner".to_string())
+        );
+        assert_eq!(host_from_subject("[app] Rotate keys"), None);
+    }
+
+    #[test]
+    fn test_parse_log_with_patches() {
+        let output = "\x1eabc123\x1f2024-05-02T09:00:00+02:00\x1f\x1f[app] Update from desk - x\n\
+                      \n\
+                      diff --git a/app/.env b/app/.env\n\
+                      -A=1\n\
+                      +A=2\n\
+                      \x1edef456\x1f2024-05-01T09:00:00+00:00\x1f\x1f[app] Rotate keys\n\
+                      \x1e789abc\x1f2024-04-30T09:00:00+00:00\x1fwork-vm\x1f[app] Update from ci - y\n";
+
+        let entries = parse_log(output, true);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].hash, "abc123");
+        assert_eq!(entries[0].host.as_deref(), Some("desk"));
+        assert_eq!(
+            entries[0].patch.as_deref(),
+            Some("diff --git a/app/.env b/app/.env\n-A=1\n+A=2")
+        );
+        assert_eq!(entries[1].subject, "[app] Rotate keys");
+        assert_eq!(entries[1].host, None);
+        assert_eq!(entries[1].patch.as_deref(), Some(""));
+        // The trailer wins over the hostname in the subject
+        assert_eq!(entries[2].subject, "[app] Update from ci - y");
+        assert_eq!(entries[2].host.as_deref(), Some("work-vm"));
+    }
+}
@@ -1,3 +1,21 @@
 pub mod exclude;
+pub mod ignored;
+pub mod log;
+pub mod remote;
+pub mod repo;
 
-pub use exclude::{add_to_exclude, read_exclude};
+pub use exclude::{
+    add_to_exclude, normalize_exclude, read_exclude, read_patterns, redundant_excludes,
+    remove_exclude_block, remove_from_exclude, Pattern,
+};
+pub use ignored::find_ignored;
+pub use log::{file_log, last_commit_mtime, LogEntry, MACHINE_TRAILER, PROJECT_TRAILER};
+pub use remote::{
+    fetch, show_blob, upstream_changes, validate_remote_url, RemoteCheck, RemoteKind, UpstreamFile,
+};
+pub use repo::{
+    classify_pull_failure, classify_push_failure, config_bool, config_value, conflicted_files,
+    git_installed, git_version, init_shade_repo, is_signing_failure, object_store_size,
+    operation_in_progress, parse_push_transfer, run_git, set_verbose, unpushed_files,
+    upstream_counts, InProgress, PullFailure, PushFailure, PushTransfer,
+};
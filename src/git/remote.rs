@@ -0,0 +1,333 @@
+use super::repo::run_git;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// A file as it is on the upstream branch, not merged into the working tree yet
+#[derive(Debug, Clone)]
+pub struct UpstreamFile {
+    /// Path relative to the project's shade dir
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+    /// Commit time of the last upstream commit that touched it
+    pub modified: DateTime<Utc>,
+}
+
+/// `git fetch` the shade repo without merging
+pub fn fetch(repo: &Path) -> Result<()> {
+    let output = run_git(repo, &["fetch", "--quiet"])?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Files under `dir/` that the upstream branch changed since it diverged from HEAD
+///
+/// Uses the remote-tracking branch as of the last fetch. Files deleted
+/// upstream are left out, and there are none without an upstream branch.
+pub fn upstream_changes(repo: &Path, dir: &str) -> Result<Vec<UpstreamFile>> {
+    let prefix = format!("{}/", dir);
+    let output = run_git(
+        repo,
+        &[
+            "diff",
+            "--name-only",
+            "--no-renames",
+            "--diff-filter=d",
+            "HEAD...@{u}",
+            "--",
+            &prefix,
+        ],
+    )?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for repo_path in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some(rel) = repo_path.strip_prefix(&prefix) else {
+            continue;
+        };
+        let Some(contents) = show_blob(repo, "@{u}", repo_path)? else {
+            continue;
+        };
+
+        files.push(UpstreamFile {
+            path: PathBuf::from(rel),
+            contents,
+            modified: last_commit_time(repo, "@{u}", repo_path)?.unwrap_or_else(Utc::now),
+        });
+    }
+
+    Ok(files)
+}
+
+/// Contents of `path` (relative to the repo root) at `rev`, `None` if it isn't there
+pub fn show_blob(repo: &Path, rev: &str, path: &str) -> Result<Option<Vec<u8>>> {
+    let output = run_git(repo, &["show", &format!("{}:{}", rev, path)])?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(output.stdout))
+}
+
+fn last_commit_time(repo: &Path, rev: &str, path: &str) -> Result<Option<DateTime<Utc>>> {
+    let output = run_git(repo, &["log", "-1", "--format=%cI", rev, "--", path])?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(
+        DateTime::parse_from_rfc3339(String::from_utf8_lossy(&output.stdout).trim())
+            .ok()
+            .map(|time| time.with_timezone(&Utc)),
+    )
+}
+
+/// How git reaches a remote, judged from its URL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoteKind {
+    /// `git@host:path` or `ssh://...`
+    Ssh,
+    Https,
+    /// Plain `http://`, unencrypted
+    Http,
+    /// A path or `file://` URL on this machine (or a mounted drive)
+    Local,
+    /// Anything else (`git://`, helpers like `s3://`); git may still handle it
+    Other,
+}
+
+impl std::fmt::Display for RemoteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RemoteKind::Ssh => "SSH",
+            RemoteKind::Https => "HTTPS",
+            RemoteKind::Http => "HTTP",
+            RemoteKind::Local => "local path",
+            RemoteKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A remote URL's kind, plus anything about it that looks like a mistake
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteCheck {
+    pub kind: RemoteKind,
+    pub warnings: Vec<String>,
+}
+
+/// Hosts whose repo URLs are `<host>/<owner>/<repo>`
+const KNOWN_HOSTS: &[&str] = &["github.com", "gitlab.com", "bitbucket.org", "codeberg.org"];
+
+/// Classify a remote URL and warn about common mistakes
+///
+/// Never rejects a URL: git accepts more shapes than are checked here, so
+/// anything unusual only gets a warning.
+pub fn validate_remote_url(url: &str) -> RemoteCheck {
+    let url = url.trim();
+    let mut warnings = Vec::new();
+
+    if url.contains(char::is_whitespace) {
+        warnings.push("The URL contains spaces; check it was pasted whole".to_string());
+    }
+
+    let (kind, host, path) = if let Some(rest) = url.strip_prefix("https://") {
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let has_credentials = authority.contains('@');
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        if KNOWN_HOSTS.contains(&host) && !has_credentials {
+            warnings.push(format!(
+                "HTTPS to {} needs a credential helper or token for private repos; \
+                 SSH (git@{}:{}) avoids password prompts",
+                host,
+                host,
+                with_git_suffix(path)
+            ));
+        }
+        (RemoteKind::Https, Some(host), path)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        warnings.push(format!(
+            "http:// is unencrypted; use https://{}/{} if the server supports it",
+            host, path
+        ));
+        (RemoteKind::Http, Some(host), path)
+    } else if let Some(rest) = url.strip_prefix("ssh://") {
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        // ssh://host:port/path; a non-numeric "port" is an scp-style path
+        if let Some((host, port)) = host.split_once(':') {
+            if !port.chars().all(|c| c.is_ascii_digit()) {
+                warnings.push(format!(
+                    "ssh:// URLs separate the path with '/', not ':'; \
+                     did you mean git@{}:{}?",
+                    host,
+                    [port, path].join("/").trim_end_matches('/')
+                ));
+            }
+        }
+        let host = host.split(':').next().unwrap_or(host);
+        (RemoteKind::Ssh, Some(host), path)
+    } else if let Some(path) = url.strip_prefix("file://") {
+        check_local_path(Path::new(path), &mut warnings);
+        (RemoteKind::Local, None, "")
+    } else if url.contains("://") {
+        (RemoteKind::Other, None, "")
+    } else if is_local_path(url) {
+        check_local_path(&expand_home(url), &mut warnings);
+        (RemoteKind::Local, None, "")
+    } else if let Some((authority, path)) = url.split_once(':') {
+        // scp-like: [user@]host:path
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        (RemoteKind::Ssh, Some(host), path)
+    } else if let Some((authority, path)) = url.split_once('/').filter(|(a, _)| a.contains('@')) {
+        // git@github.com/owner/repo: the ':' was typed as '/'
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        warnings.push(format!(
+            "SSH URLs need ':' after the host; did you mean {}:{}?",
+            authority, path
+        ));
+        (RemoteKind::Ssh, Some(host), path)
+    } else {
+        // A bare word is a relative path to git
+        check_local_path(Path::new(url), &mut warnings);
+        (RemoteKind::Local, None, "")
+    };
+
+    if let Some(host) = host.filter(|host| KNOWN_HOSTS.contains(host)) {
+        let segments: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        if segments.len() > 2 {
+            warnings.push(format!(
+                "This looks like a web page on {}, not a repo; repo URLs end in <owner>/<repo>.git",
+                host
+            ));
+        } else if segments.len() < 2 {
+            warnings.push(format!(
+                "{} repo URLs need both <owner>/<repo>; this one has {}",
+                host,
+                if segments.is_empty() {
+                    "neither".to_string()
+                } else {
+                    format!("only '{}'", segments[0])
+                }
+            ));
+        } else if !path.ends_with(".git") {
+            warnings.push(format!(
+                "The URL doesn't end in .git; {} accepts that, but check it's the repo URL",
+                host
+            ));
+        }
+    }
+
+    RemoteCheck { kind, warnings }
+}
+
+fn with_git_suffix(path: &str) -> String {
+    let path = path.trim_end_matches('/');
+    if path.ends_with(".git") {
+        path.to_string()
+    } else {
+        format!("{}.git", path)
+    }
+}
+
+fn is_local_path(url: &str) -> bool {
+    url.starts_with('/')
+        || url.starts_with("./")
+        || url.starts_with("../")
+        || url.starts_with('~')
+        // C:\... or C:/... on Windows, which would otherwise look scp-like
+        || url.as_bytes().get(1) == Some(&b':') && url.as_bytes()[0].is_ascii_alphabetic()
+}
+
+fn expand_home(url: &str) -> PathBuf {
+    match (url.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(url),
+    }
+}
+
+fn check_local_path(path: &Path, warnings: &mut Vec<String>) {
+    if !path.exists() {
+        warnings.push(format!(
+            "{} doesn't exist (create it with: git init --bare {})",
+            path.display(),
+            path.display()
+        ));
+    } else if !path.join("HEAD").exists() && !path.join(".git").exists() {
+        warnings.push(format!("{} isn't a git repository", path.display()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_remote_url_classifies_shapes() {
+        let check = validate_remote_url("git@github.com:me/dotfiles.git");
+        assert_eq!(check.kind, RemoteKind::Ssh);
+        assert!(check.warnings.is_empty(), "{:?}", check.warnings);
+
+        let check = validate_remote_url("ssh://git@git.example.com:2222/srv/shade.git");
+        assert_eq!(check.kind, RemoteKind::Ssh);
+        assert!(check.warnings.is_empty(), "{:?}", check.warnings);
+
+        let check = validate_remote_url("https://token@github.com/me/shade.git");
+        assert_eq!(check.kind, RemoteKind::Https);
+        assert!(check.warnings.is_empty(), "{:?}", check.warnings);
+
+        // Unusual but valid: no warnings, no rejection
+        let check = validate_remote_url("s3://bucket/shade");
+        assert_eq!(check.kind, RemoteKind::Other);
+        assert!(check.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_validate_remote_url_warns_about_common_mistakes() {
+        let check = validate_remote_url("https://github.com/me/shade");
+        assert_eq!(check.kind, RemoteKind::Https);
+        assert!(check.warnings[0].contains("git@github.com:me/shade.git"));
+        assert!(check.warnings[1].contains("doesn't end in .git"));
+
+        let check = validate_remote_url("http://git.example.com/shade.git");
+        assert_eq!(check.kind, RemoteKind::Http);
+        assert!(check.warnings[0].contains("https://git.example.com/shade.git"));
+
+        let check = validate_remote_url("git@github.com/me/shade.git");
+        assert_eq!(check.kind, RemoteKind::Ssh);
+        assert!(check.warnings[0].contains("git@github.com:me/shade.git"));
+
+        let check = validate_remote_url("ssh://git@github.com:me/shade.git");
+        assert!(check.warnings[0].contains("git@github.com:me/shade.git"));
+
+        let check = validate_remote_url("git@github.com:me/shade/tree/main");
+        assert!(check.warnings[0].contains("web page"));
+    }
+
+    #[test]
+    fn test_validate_remote_url_checks_local_paths() {
+        let temp = TempDir::new().unwrap();
+        let missing = temp.path().join("missing.git");
+        let check = validate_remote_url(missing.to_str().unwrap());
+        assert_eq!(check.kind, RemoteKind::Local);
+        assert!(check.warnings[0].contains("doesn't exist"));
+
+        let check = validate_remote_url(&format!("file://{}", temp.path().display()));
+        assert_eq!(check.kind, RemoteKind::Local);
+        assert!(check.warnings[0].contains("isn't a git repository"));
+
+        std::fs::write(temp.path().join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        let check = validate_remote_url(temp.path().to_str().unwrap());
+        assert!(check.warnings.is_empty());
+    }
+}
@@ -0,0 +1,562 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Log every git invocation made through `run_git` (the global `--verbose`)
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Run `git <args>` in `dir` and capture its output
+///
+/// With `--verbose` the command, its working directory and everything it
+/// printed are echoed to stderr, so stdout stays parseable.
+pub fn run_git<S: AsRef<OsStr>>(dir: &Path, args: &[S]) -> std::io::Result<Output> {
+    let verbose = VERBOSE.load(Ordering::Relaxed);
+
+    if verbose {
+        let command_line: Vec<String> = args
+            .iter()
+            .map(|arg| arg.as_ref().to_string_lossy().to_string())
+            .collect();
+        eprintln!(
+            "{} git {}  {}",
+            "$".dimmed(),
+            command_line.join(" "),
+            format!("(in {})", dir.display()).dimmed()
+        );
+    }
+
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+
+    tracing::debug!(
+        dir = %dir.display(),
+        args = ?args.iter().map(|arg| arg.as_ref().to_string_lossy()).collect::<Vec<_>>(),
+        status = %output.status,
+        "git"
+    );
+    if !output.status.success() {
+        tracing::debug!(stderr = %String::from_utf8_lossy(&output.stderr).trim(), "git failed");
+    }
+
+    if verbose {
+        for line in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+        {
+            eprintln!("  {} {}", "|".dimmed(), line);
+        }
+        if !output.status.success() {
+            eprintln!("  {} {}", "|".dimmed(), output.status);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Whether a `git` executable can be run at all
+///
+/// A missing git otherwise surfaces as a bare "No such file or directory"
+/// from whichever command runs it first.
+pub fn git_installed() -> bool {
+    git_version().is_some()
+}
+
+/// `git --version` output, e.g. `git version 2.43.0`, `None` without git
+pub fn git_version() -> Option<String> {
+    let output = Command::new("git").arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Why a `git pull` in the shade repo failed
+#[derive(Debug, PartialEq)]
+pub enum PullFailure {
+    /// The merge stopped with conflicts in the shade repo
+    MergeConflict,
+    /// The remote couldn't be reached or refused our credentials
+    Remote,
+    /// `--ff-only` (or `pull.ff = only`) and both sides have new commits
+    NotFastForward,
+    Other,
+}
+
+/// Classify a failed `git pull` from its combined stdout/stderr
+pub fn classify_pull_failure(output: &str) -> PullFailure {
+    if output.contains("CONFLICT") || output.contains("Automatic merge failed") {
+        return PullFailure::MergeConflict;
+    }
+
+    if output.contains("Not possible to fast-forward") {
+        return PullFailure::NotFastForward;
+    }
+
+    if is_remote_error(output) {
+        return PullFailure::Remote;
+    }
+
+    PullFailure::Other
+}
+
+/// Why a `git push` from the shade repo failed
+#[derive(Debug, PartialEq)]
+pub enum PushFailure {
+    /// The remote has commits we don't (or `--force-with-lease` found it moved)
+    Rejected,
+    /// The remote couldn't be reached or refused our credentials
+    Remote,
+    /// The remote won't take a push from a shallow clone
+    Shallow,
+    Other,
+}
+
+/// Classify a failed `git push` from its stderr
+pub fn classify_push_failure(output: &str) -> PushFailure {
+    const REJECTIONS: &[&str] = &["non-fast-forward", "fetch first", "stale info"];
+    if output.contains("[rejected]") && REJECTIONS.iter().any(|e| output.contains(e)) {
+        return PushFailure::Rejected;
+    }
+
+    if is_remote_error(output) {
+        return PushFailure::Remote;
+    }
+
+    if output.contains("shallow update not allowed") {
+        return PushFailure::Shallow;
+    }
+
+    PushFailure::Other
+}
+
+/// What a `git push --progress` sent to the remote
+#[derive(Debug, PartialEq)]
+pub struct PushTransfer {
+    pub objects: u64,
+    /// Objects sent as deltas against ones the remote already has
+    pub deltas: u64,
+    pub bytes: u64,
+}
+
+/// Read the pack size from `git push --progress` stderr
+///
+/// `None` when nothing was written (the remote already had every object).
+pub fn parse_push_transfer(output: &str) -> Option<PushTransfer> {
+    // Progress updates are separated by \r; the last "Writing objects" one is final
+    let writing = output
+        .split(['\r', '\n'])
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("Writing objects:"))?;
+
+    // " 100% (3/3), 1.20 KiB | 1.20 MiB/s, done."
+    let (_, counts) = writing.split_once('(')?;
+    let (counts, rest) = counts.split_once(')')?;
+    let objects = counts.split('/').nth(1)?.trim().parse().ok()?;
+
+    let size = rest.trim_start_matches(',').split('|').next()?.trim();
+    let (amount, unit) = size.split_once(' ')?;
+    let amount: f64 = amount.parse().ok()?;
+    let scale = match unit.trim() {
+        "bytes" | "byte" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+
+    // "Total 3 (delta 1), reused 0 (delta 0), pack-reused 0"
+    let deltas = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Total "))
+        .and_then(|total| total.split_once("(delta ")?.1.split_once(')'))
+        .and_then(|(deltas, _)| deltas.trim().parse().ok())
+        .unwrap_or(0);
+
+    Some(PushTransfer {
+        objects,
+        deltas,
+        bytes: (amount * scale).round() as u64,
+    })
+}
+
+/// Whether a failed `git commit` couldn't be signed (no key, wrong passphrase,
+/// missing gpg), as opposed to failing for another reason
+pub fn is_signing_failure(output: &str) -> bool {
+    const SIGNING_ERRORS: &[&str] = &[
+        "gpg failed to sign the data",
+        "cannot run gpg",
+        "failed to sign the data",
+        "Couldn't load public key",
+        "No secret key",
+    ];
+
+    SIGNING_ERRORS.iter().any(|e| output.contains(e))
+}
+
+/// Value of a boolean git config key as seen from `repo`, `false` when unset
+pub fn config_bool(repo: &Path, key: &str) -> Result<bool> {
+    let output = run_git(repo, &["config", "--bool", key])?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "true")
+}
+
+/// Value of a git config key as seen from `repo`, `None` when unset
+pub fn config_value(repo: &Path, key: &str) -> Result<Option<String>> {
+    let output = run_git(repo, &["config", "--get", key])?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !value.is_empty()).then_some(value))
+}
+
+/// Network and authentication failures, as git reports them for any remote command
+fn is_remote_error(output: &str) -> bool {
+    const REMOTE_ERRORS: &[&str] = &[
+        "Could not resolve host",
+        "Could not read from remote repository",
+        "Permission denied",
+        "Authentication failed",
+        "Connection refused",
+        "Connection timed out",
+        "does not appear to be a git repository",
+        "unable to access",
+    ];
+
+    REMOTE_ERRORS.iter().any(|e| output.contains(e))
+}
+
+/// An operation git stopped in the middle of (usually on conflicts)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InProgress {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+
+impl InProgress {
+    /// The git command that continues or aborts it
+    pub fn command(self) -> &'static str {
+        match self {
+            InProgress::Merge => "merge",
+            InProgress::Rebase => "rebase",
+            InProgress::CherryPick => "cherry-pick",
+            InProgress::Revert => "revert",
+        }
+    }
+}
+
+/// Merge, rebase, cherry-pick or revert left unfinished in `repo`, if any
+///
+/// Read from the state files git keeps in its directory (`MERGE_HEAD`,
+/// `rebase-merge/`, ...), so it works without conflicted files too.
+pub fn operation_in_progress(repo: &Path) -> Result<Option<InProgress>> {
+    let output = run_git(repo, &["rev-parse", "--absolute-git-dir"])?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git rev-parse failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let git_dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+
+    let markers = [
+        ("MERGE_HEAD", InProgress::Merge),
+        ("rebase-merge", InProgress::Rebase),
+        ("rebase-apply", InProgress::Rebase),
+        ("CHERRY_PICK_HEAD", InProgress::CherryPick),
+        ("REVERT_HEAD", InProgress::Revert),
+    ];
+    Ok(markers
+        .into_iter()
+        .find(|(marker, _)| git_dir.join(marker).exists())
+        .map(|(_, operation)| operation))
+}
+
+/// Paths (relative to the shade repo) with unresolved merge conflicts
+pub fn conflicted_files(repo: &Path) -> Result<Vec<String>> {
+    let output = run_git(repo, &["diff", "--name-only", "--diff-filter=U"])?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// Commits HEAD is behind and ahead of its upstream, `None` without an upstream
+///
+/// Compares against the remote-tracking branch as of the last fetch.
+pub fn upstream_counts(repo: &Path) -> Result<Option<(usize, usize)>> {
+    let output = run_git(
+        repo,
+        &["rev-list", "--left-right", "--count", "@{u}...HEAD"],
+    )?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(parse_left_right_counts(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Bytes the repo's object store takes on disk, loose objects and packs
+/// (`git count-objects -v`)
+pub fn object_store_size(repo: &Path) -> Result<u64> {
+    let output = run_git(repo, &["count-objects", "-v"])?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git count-objects failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_count_objects(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Sum of the `size`, `size-pack` and `size-garbage` lines (in KiB)
+fn parse_count_objects(output: &str) -> u64 {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter(|(key, _)| matches!(*key, "size" | "size-pack" | "size-garbage"))
+        .filter_map(|(_, kib)| kib.trim().parse::<u64>().ok())
+        .sum::<u64>()
+        * 1024
+}
+
+/// Files under `dir` (relative to the repo root) that the remote doesn't have yet
+///
+/// Uncommitted changes, plus files changed in commits ahead of the upstream
+/// as of the last fetch (only uncommitted ones without an upstream).
+pub fn unpushed_files(repo: &Path, dir: &str) -> Result<Vec<String>> {
+    let mut files = BTreeSet::new();
+
+    let status = run_git(
+        repo,
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            dir,
+        ],
+    )?;
+    let status = String::from_utf8_lossy(&status.stdout);
+    let mut entries = status.split('\0').filter(|entry| entry.len() > 3);
+    while let Some(entry) = entries.next() {
+        let (code, path) = entry.split_at(3);
+        files.insert(path.to_string());
+        // Renames and copies are followed by their source path
+        if code.starts_with(['R', 'C']) {
+            entries.next();
+        }
+    }
+
+    let committed = run_git(
+        repo,
+        &["diff", "--name-only", "-z", "@{u}...HEAD", "--", dir],
+    )?;
+    if committed.status.success() {
+        files.extend(
+            String::from_utf8_lossy(&committed.stdout)
+                .split('\0')
+                .filter(|path| !path.is_empty())
+                .map(|path| path.to_string()),
+        );
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+/// Create the shade repo at `dir`, optionally with an `origin` remote
+///
+/// If the remote already has commits, its default branch is checked out, as a
+/// clone would. Otherwise the current branch is set to track `origin` so the
+/// first `push` creates it there.
+pub fn init_shade_repo(dir: &Path, remote: Option<&str>) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    git_checked(dir, &["init", "--quiet"])?;
+
+    let Some(url) = remote else {
+        return Ok(());
+    };
+    git_checked(dir, &["remote", "add", "origin", url])?;
+
+    let fetched = run_git(dir, &["fetch", "--quiet", "origin"])?
+        .status
+        .success();
+    if fetched
+        && run_git(dir, &["remote", "set-head", "origin", "--auto"])?
+            .status
+            .success()
+    {
+        let head = run_git(dir, &["rev-parse", "--abbrev-ref", "origin/HEAD"])?;
+        let head = String::from_utf8_lossy(&head.stdout).trim().to_string();
+        if let Some(branch) = head.strip_prefix("origin/") {
+            return git_checked(
+                dir,
+                &["checkout", "--quiet", "-B", branch, "--track", &head],
+            );
+        }
+    }
+
+    let branch = run_git(dir, &["symbolic-ref", "--short", "HEAD"])?;
+    let branch = String::from_utf8_lossy(&branch.stdout).trim().to_string();
+    git_checked(
+        dir,
+        &["config", &format!("branch.{}.remote", branch), "origin"],
+    )?;
+    git_checked(
+        dir,
+        &[
+            "config",
+            &format!("branch.{}.merge", branch),
+            &format!("refs/heads/{}", branch),
+        ],
+    )
+}
+
+/// Run git and turn a non-zero exit into an error with its stderr
+fn git_checked(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = run_git(dir, args)?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Parse `git rev-list --left-right --count` output ("<left>\t<right>")
+fn parse_left_right_counts(output: &str) -> Option<(usize, usize)> {
+    let mut counts = output.split_whitespace().map(|count| count.parse().ok());
+    Some((counts.next()??, counts.next()??))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_left_right_counts() {
+        assert_eq!(parse_left_right_counts("3\t1\n"), Some((3, 1)));
+        assert_eq!(parse_left_right_counts("0\t0"), Some((0, 0)));
+        assert_eq!(parse_left_right_counts(""), None);
+    }
+
+    #[test]
+    fn test_parse_count_objects() {
+        let output = "count: 12\nsize: 48\nin-pack: 30\npacks: 1\n\
+                      size-pack: 100\nprune-packable: 0\ngarbage: 0\nsize-garbage: 0\n";
+        assert_eq!(parse_count_objects(output), 148 * 1024);
+        assert_eq!(parse_count_objects(""), 0);
+    }
+
+    #[test]
+    fn test_classify_pull_failure() {
+        assert_eq!(
+            classify_pull_failure(
+                "CONFLICT (content): Merge conflict in myapp/config.local\n\
+                 Automatic merge failed; fix conflicts and then commit the result."
+            ),
+            PullFailure::MergeConflict
+        );
+        assert_eq!(
+            classify_pull_failure(
+                "ssh: Could not resolve host: github.com\n\
+                 fatal: Could not read from remote repository."
+            ),
+            PullFailure::Remote
+        );
+        assert_eq!(
+            classify_pull_failure("fatal: Not possible to fast-forward, aborting."),
+            PullFailure::NotFastForward
+        );
+        assert_eq!(
+            classify_pull_failure("fatal: refusing to merge unrelated histories"),
+            PullFailure::Other
+        );
+    }
+
+    #[test]
+    fn test_is_signing_failure() {
+        assert!(is_signing_failure(
+            "error: gpg failed to sign the data
+fatal: failed to write commit object"
+        ));
+        assert!(is_signing_failure(
+            "error: cannot run gpg: No such file or directory"
+        ));
+        assert!(!is_signing_failure(
+            "Author identity unknown
+fatal: unable to auto-detect email address"
+        ));
+    }
+
+    #[test]
+    fn test_classify_push_failure() {
+        assert_eq!(
+            classify_push_failure(
+                "To ../remote.git\n \
+                 ! [rejected]        master -> master (fetch first)\n\
+                 error: failed to push some refs to '../remote.git'\n\
+                 hint: Updates were rejected because the remote contains work that you do not"
+            ),
+            PushFailure::Rejected
+        );
+        assert_eq!(
+            classify_push_failure(" ! [rejected]        master -> master (stale info)"),
+            PushFailure::Rejected
+        );
+        assert_eq!(
+            classify_push_failure(
+                "git@github.com: Permission denied (publickey).\n\
+                 fatal: Could not read from remote repository."
+            ),
+            PushFailure::Remote
+        );
+        assert_eq!(
+            classify_push_failure(
+                " ! [remote rejected] master -> master (pre-receive hook declined)"
+            ),
+            PushFailure::Other
+        );
+        assert_eq!(
+            classify_push_failure(
+                " ! [remote rejected] master -> master (shallow update not allowed)"
+            ),
+            PushFailure::Shallow
+        );
+    }
+
+    #[test]
+    fn test_parse_push_transfer() {
+        let output = "Enumerating objects: 5, done.\n\
+                      Writing objects:  50% (1/2)\rWriting objects: 100% (2/2), 1.50 KiB | 1.50 MiB/s, done.\n\
+                      Total 2 (delta 1), reused 0 (delta 0), pack-reused 0\n\
+                      To ../remote.git\n";
+        assert_eq!(
+            parse_push_transfer(output),
+            Some(PushTransfer {
+                objects: 2,
+                deltas: 1,
+                bytes: 1536,
+            })
+        );
+
+        let output = "Writing objects: 100% (3/3), 179 bytes | 89.00 KiB/s, done.\n";
+        assert_eq!(parse_push_transfer(output).map(|t| t.bytes), Some(179));
+
+        assert_eq!(parse_push_transfer("Everything up-to-date\n"), None);
+    }
+}
@@ -1,5 +1,8 @@
+pub mod api;
 pub mod commands;
 pub mod core;
 pub mod error;
 pub mod git;
 pub mod utils;
+
+pub use api::{Context, GitShade};
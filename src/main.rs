@@ -1,33 +1,267 @@
 mod cli;
-mod commands;
-mod core;
-mod error;
-mod git;
-mod utils;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use cli::{Cli, Commands};
-use error::Result;
+use git_shade::commands;
+use git_shade::commands::add::{AddOptions, StdinPaths};
+use git_shade::commands::init::InitOptions;
+use git_shade::commands::pull::PullOptions;
+use git_shade::commands::push::PushOptions;
+use git_shade::core::PullStrategy;
+use git_shade::error::{Result, ShadeError};
+use tracing_subscriber::EnvFilter;
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    match run() {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
     }
 }
 
-fn run() -> Result<()> {
+/// Run the command, returning the exit code when it succeeds
+fn run() -> Result<i32> {
     let cli = Cli::parse();
+    let verbose = cli.verbose;
+    colored::control::set_override(cli.color.enabled());
+    git_shade::git::set_verbose(verbose);
+    init_logging(cli.color.enabled());
+
+    // Everything but the guide, completions, config and env shells out to git
+    let needs_git = !matches!(
+        cli.command,
+        Commands::Guide | Commands::Completions { .. } | Commands::Config { .. } | Commands::Env
+    );
+    if needs_git && !git_shade::git::git_installed() {
+        return Err(ShadeError::GitNotInstalled);
+    }
 
     match cli.command {
-        Commands::Init { name } => commands::init::run(name),
-        Commands::Add { files } => commands::add::run(files),
-        Commands::Push { message } => commands::push::run(message),
-        Commands::Pull { force, dry_run } => commands::pull::run(force, dry_run),
-        Commands::Status => commands::status::run(),
+        Commands::Init {
+            name,
+            no_pull,
+            yes,
+            create_shade,
+            remote,
+            dry_run,
+            import_from,
+        } => commands::init::run(InitOptions {
+            name,
+            no_pull,
+            yes,
+            create_shade,
+            remote,
+            dry_run,
+            import_from,
+        }),
+        Commands::Clone {
+            url,
+            depth,
+            single_branch,
+        } => commands::clone::run(url, depth, single_branch),
+        Commands::Uninit {
+            name,
+            keep_exclude,
+            keep_shade,
+            yes,
+        } => commands::uninit::run(name, keep_exclude, keep_shade, yes),
+        Commands::Add {
+            files,
+            dir_as_glob,
+            force,
+            exclude,
+            from_gitignore,
+            name,
+            symlink,
+            stdin,
+            stdin0,
+            as_path,
+            link_existing,
+            template,
+            no_copy,
+            chmod,
+            stage,
+            per_os,
+        } => commands::add::run(
+            files,
+            AddOptions {
+                dir_as_glob,
+                force,
+                exclude,
+                from_gitignore,
+                name,
+                symlink,
+                stdin: if stdin0 {
+                    Some(StdinPaths::Nul)
+                } else if stdin {
+                    Some(StdinPaths::Lines)
+                } else {
+                    None
+                },
+                as_path,
+                link_existing,
+                template,
+                no_copy,
+                chmod,
+                stage,
+                per_os,
+            },
+        ),
+        Commands::Mv { from, to, name } => commands::mv::run(from, to, name),
+        Commands::Push {
+            message,
+            amend,
+            force_with_lease,
+            stat,
+            no_sign,
+            only_changed,
+            machine,
+            tag,
+            project,
+            name,
+        } => commands::push::run(
+            PushOptions {
+                message,
+                verbose,
+                amend,
+                force_with_lease,
+                stat,
+                no_sign,
+                only_changed,
+                machine,
+                tag,
+            },
+            project,
+            name,
+        ),
+        Commands::Pull {
+            force,
+            dry_run,
+            no_fetch,
+            strategy,
+            merge_markers,
+            rebase,
+            ff_only,
+            merge,
+            set_exclude_only,
+            project,
+            name,
+            output,
+            only,
+            except,
+            backup_all,
+            push_after,
+            prune,
+            yes,
+        } => commands::pull::run(
+            PullOptions {
+                force,
+                dry_run,
+                verbose,
+                skip_git_pull: no_fetch,
+                strategy,
+                output,
+                only,
+                except,
+                backup_all,
+                push_after,
+                prune,
+                yes,
+                merge_markers,
+                pull_strategy: pull_strategy(rebase, ff_only, merge),
+                exclude_only: set_exclude_only,
+            },
+            project,
+            name,
+        ),
+        Commands::Watch {
+            debounce,
+            project,
+            name,
+        } => commands::watch::run(debounce, verbose, project, name),
+        Commands::Sync {
+            force,
+            message,
+            project,
+        } => commands::sync::run(force, message, verbose, project),
+        Commands::Status {
+            project,
+            name,
+            fetch,
+            compact,
+            plan,
+            exit_code,
+        } => return commands::status::run(project, name, fetch, compact, plan, exit_code, verbose),
+        Commands::Export { out, project } => commands::export::run(out, project),
+        Commands::Import { archive, project } => commands::import::run(archive, project, verbose),
+        Commands::Config { action } => commands::config::run(action),
+        Commands::Env => commands::env::run(),
+        Commands::Doctor { repair } => commands::doctor::run(repair),
+        Commands::Gc { expire } => commands::gc::run(expire),
+        Commands::Verify { fix, yes, project } => commands::verify::run(project, fix, yes, verbose),
+        Commands::Rollback { yes, project, name } => commands::rollback::run(project, name, yes),
+        Commands::Pause {
+            files,
+            project,
+            name,
+        } => commands::pause::run(files, true, project, name),
+        Commands::Resume {
+            files,
+            project,
+            name,
+        } => commands::pause::run(files, false, project, name),
+        Commands::Stash {
+            action,
+            list,
+            project,
+            name,
+        } => commands::stash::run(action, list, project, name),
+        Commands::History {
+            file,
+            patch,
+            project,
+            name,
+        } => commands::history::run(file, patch, project, name),
+        Commands::List { remote } => commands::list::run(remote),
         Commands::Guide => {
             commands::guide::run();
             Ok(())
         }
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            clap_complete::generate(shell, &mut command, "git-shade", &mut std::io::stdout());
+            Ok(())
+        }
+    }?;
+
+    Ok(0)
+}
+
+/// `pull --rebase`/`--ff-only`/`--merge`, `None` to use `pull_strategy`
+fn pull_strategy(rebase: bool, ff_only: bool, merge: bool) -> Option<PullStrategy> {
+    if rebase {
+        Some(PullStrategy::Rebase)
+    } else if ff_only {
+        Some(PullStrategy::FfOnly)
+    } else if merge {
+        Some(PullStrategy::Merge)
+    } else {
+        None
     }
 }
+
+/// Send `tracing` logs to stderr, filtered by `RUST_LOG` (nothing when it's unset)
+fn init_logging(ansi: bool) {
+    let Ok(filter) = EnvFilter::try_from_default_env() else {
+        return;
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(ansi)
+        .init();
+}
@@ -1,4 +1,8 @@
+use crate::core::{is_excluded, same_contents, Storage};
 use anyhow::{Context, Result};
+use filetime::FileTime;
+use humansize::{FormatSizeOptions, WINDOWS};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -20,6 +24,17 @@ pub fn copy_file_preserve_structure(
 
     // Build destination path
     let dest = dest_base.join(rel_path);
+    copy_file_to(src, &dest)?;
+    Ok(dest)
+}
+
+/// Copy a file to an exact destination, creating parent directories and
+/// keeping the source mtime
+pub fn copy_file_to(src: &Path, dest: &Path) -> Result<()> {
+    // A file added with --symlink already is its shade copy
+    if links_to(dest, src) {
+        return Ok(());
+    }
 
     // Create parent directories if needed
     if let Some(parent) = dest.parent() {
@@ -27,30 +42,330 @@ pub fn copy_file_preserve_structure(
     }
 
     // Copy the file
-    fs::copy(src, &dest)
+    tracing::debug!(src = %src.display(), dest = %dest.display(), "copy");
+    fs::copy(src, dest)
         .with_context(|| format!("Failed to copy {} to {}", src.display(), dest.display()))?;
 
+    // Keep the source mtime: sync state detection compares timestamps
+    let modified = FileTime::from_last_modification_time(&fs::metadata(src)?);
+    filetime::set_file_mtime(dest, modified)
+        .with_context(|| format!("Failed to set mtime on {}", dest.display()))?;
+
+    Ok(())
+}
+
+/// Set Unix permission bits on a file (no-op on other platforms)
+#[cfg(unix)]
+pub fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Human-readable byte count, e.g. `1.5 MB`
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let options = FormatSizeOptions::from(WINDOWS)
+        .decimal_places(1)
+        .decimal_zeroes(1);
+    humansize::format_size(bytes, options)
+}
+
+/// Set a file's mtime (following symlinks)
+pub fn set_mtime(path: &Path, modified: FileTime) -> Result<()> {
+    filetime::set_file_mtime(path, modified)
+        .with_context(|| format!("Failed to set mtime on {}", path.display()))
+}
+
+/// Whether `link` is a symlink that resolves to `target`
+pub fn links_to(link: &Path, target: &Path) -> bool {
+    if !link.is_symlink() {
+        return false;
+    }
+    match (fs::canonicalize(link), fs::canonicalize(target)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Move a file into the shade dir and leave a symlink to it in its place
+///
+/// The shade copy is always a plain copy: with hardlink storage, edits made
+/// through the symlink would otherwise reach every project sharing the file.
+pub fn move_to_shade(src: &Path, src_base: &Path, dest_base: &Path) -> Result<PathBuf> {
+    let rel_path = src
+        .strip_prefix(src_base)
+        .context("Failed to calculate relative path")?;
+    let dest = dest_base.join(rel_path);
+
+    if dest.exists() {
+        fs::remove_file(&dest).with_context(|| format!("Failed to replace {}", dest.display()))?;
+    }
+    let dest = copy_file_preserve_structure(src, src_base, dest_base)?;
+
+    fs::remove_file(src).with_context(|| format!("Failed to remove {}", src.display()))?;
+    symlink_file(&dest, src)?;
     Ok(dest)
 }
 
-/// Copy entire directory recursively, preserving structure
-pub fn copy_dir_preserve_structure(
-    src_dir: &Path,
-    src_base: &Path,
-    dest_base: &Path,
-) -> Result<Vec<PathBuf>> {
-    let mut copied_files = Vec::new();
+/// Turn a symlink back into a regular copy of the file it points to
+pub fn replace_link_with_copy(link: &Path) -> Result<()> {
+    let target =
+        fs::canonicalize(link).with_context(|| format!("Failed to resolve {}", link.display()))?;
+    fs::remove_file(link).with_context(|| format!("Failed to remove {}", link.display()))?;
+    fs::copy(&target, link)
+        .with_context(|| format!("Failed to copy {} to {}", target.display(), link.display()))?;
 
-    for entry in walkdir::WalkDir::new(src_dir) {
-        let entry = entry?;
+    let modified = FileTime::from_last_modification_time(&fs::metadata(&target)?);
+    filetime::set_file_mtime(link, modified)
+        .with_context(|| format!("Failed to set mtime on {}", link.display()))?;
+    Ok(())
+}
+
+/// Give a hardlinked file its own copy, so a later mode or mtime change only touches it
+pub fn unshare_file(path: &Path) -> Result<()> {
+    if !is_shared(path) {
+        return Ok(());
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = path.with_file_name(format!(".{}.unshare", name));
+    copy_file_to(path, &copy)?;
+    fs::rename(&copy, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Create a symlink at `link` pointing to `target`
+#[cfg(unix)]
+pub fn symlink_file(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+        .with_context(|| format!("Failed to link {} to {}", link.display(), target.display()))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn symlink_file(_target: &Path, _link: &Path) -> Result<()> {
+    anyhow::bail!("Symlinking into shade is only supported on Unix")
+}
+
+/// Writes files into the shade repo, honouring the `storage` setting
+///
+/// With `Storage::Hardlink` it indexes every file under `root` (the shade
+/// repo, `.git` excluded) by size, and links a new file to an identical one
+/// instead of copying it.
+pub struct ShadeStore {
+    blobs: Option<HashMap<u64, Vec<PathBuf>>>,
+}
+
+impl ShadeStore {
+    pub fn new(storage: Storage, root: &Path) -> Result<Self> {
+        let blobs = match storage {
+            Storage::Copy => None,
+            Storage::Hardlink => Some(index_blobs(root)?),
+        };
+        Ok(Self { blobs })
+    }
+
+    /// Like `copy_file_preserve_structure`, linking to an identical file when
+    /// hardlink storage is on
+    ///
+    /// A linked file keeps the mtime of the file it shares: contents are
+    /// identical, so sync state doesn't depend on it, and touching it would
+    /// make the other project's copy look freshly changed.
+    pub fn store_file(&mut self, src: &Path, src_base: &Path, dest_base: &Path) -> Result<PathBuf> {
+        let rel_path = src
+            .strip_prefix(src_base)
+            .context("Failed to calculate relative path")?;
+        let dest = dest_base.join(rel_path);
+        self.store_file_to(src, &dest)?;
+        Ok(dest)
+    }
+
+    /// Like `store_file`, to an exact destination (files from outside the project)
+    pub fn store_file_to(&mut self, src: &Path, dest: &Path) -> Result<()> {
+        // Added with --symlink: the shade copy is the live file already
+        if links_to(src, dest) {
+            return Ok(());
+        }
+
+        // Writing through a link would change every project sharing the file
+        if is_shared(dest) {
+            fs::remove_file(dest)
+                .with_context(|| format!("Failed to unlink {}", dest.display()))?;
+        }
+
+        let Some(blobs) = self.blobs.as_mut() else {
+            return copy_file_to(src, dest);
+        };
 
+        let size = fs::metadata(src)?.len();
+        if let Some(existing) = find_identical(blobs, src, size, dest) {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent).context("Failed to create parent directories")?;
+            }
+            if dest.exists() {
+                fs::remove_file(dest)?;
+            }
+            // Different filesystems (or no link support) fall through to a copy
+            if same_filesystem(&existing, dest) && fs::hard_link(&existing, dest).is_ok() {
+                tracing::debug!(existing = %existing.display(), dest = %dest.display(), "hardlink");
+                blobs.entry(size).or_default().push(dest.to_path_buf());
+                return Ok(());
+            }
+        }
+
+        copy_file_to(src, dest)?;
+        let candidates = blobs.entry(size).or_default();
+        if !candidates.iter().any(|candidate| candidate == dest) {
+            candidates.push(dest.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Like `store_file_to`, then pins the copy's mode (`add --chmod`)
+    ///
+    /// The mode is this project's alone, and a hardlinked file shares its mode
+    /// with every link, so a file with a pinned mode is always a copy of its own.
+    pub fn store_file_with_mode(
+        &mut self,
+        src: &Path,
+        dest: &Path,
+        mode: Option<u32>,
+    ) -> Result<()> {
+        let Some(mode) = mode else {
+            return self.store_file_to(src, dest);
+        };
+
+        if !links_to(src, dest) {
+            if is_shared(dest) {
+                fs::remove_file(dest)
+                    .with_context(|| format!("Failed to unlink {}", dest.display()))?;
+            }
+            copy_file_to(src, dest)?;
+        }
+        set_file_mode(dest, mode)
+    }
+
+    /// Store an entire directory recursively, preserving structure
+    ///
+    /// Paths matching `exclude` (relative to `src_dir`, see `is_excluded`) are skipped.
+    pub fn store_dir(
+        &mut self,
+        src_dir: &Path,
+        src_base: &Path,
+        dest_base: &Path,
+        exclude: &[String],
+    ) -> Result<Vec<PathBuf>> {
+        let mut copied_files = Vec::new();
+
+        let walker = walkdir::WalkDir::new(src_dir)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(src_dir)
+                    .map_or(true, |rel| !is_excluded(rel, exclude))
+            });
+
+        for entry in walker {
+            let entry = entry?;
+
+            if entry.file_type().is_file() {
+                let copied = self.store_file(entry.path(), src_base, dest_base)?;
+                copied_files.push(copied);
+            }
+        }
+
+        Ok(copied_files)
+    }
+}
+
+fn index_blobs(root: &Path) -> Result<HashMap<u64, Vec<PathBuf>>> {
+    let mut blobs: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    if !root.exists() {
+        return Ok(blobs);
+    }
+
+    let walker = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+
+    for entry in walker {
+        let entry = entry?;
         if entry.file_type().is_file() {
-            let copied = copy_file_preserve_structure(entry.path(), src_base, dest_base)?;
-            copied_files.push(copied);
+            let size = entry.metadata()?.len();
+            blobs
+                .entry(size)
+                .or_default()
+                .push(entry.path().to_path_buf());
         }
     }
 
-    Ok(copied_files)
+    Ok(blobs)
+}
+
+fn find_identical(
+    blobs: &HashMap<u64, Vec<PathBuf>>,
+    src: &Path,
+    size: u64,
+    dest: &Path,
+) -> Option<PathBuf> {
+    blobs
+        .get(&size)?
+        .iter()
+        .filter(|candidate| candidate.as_path() != dest && same_mode(src, candidate))
+        .find(|candidate| matches!(same_contents(src, candidate), Ok(true)))
+        .cloned()
+}
+
+/// Whether a link to `candidate` would give `src`'s copy the mode a copy would have
+#[cfg(unix)]
+fn same_mode(src: &Path, candidate: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = |path: &Path| fs::metadata(path).map(|m| m.permissions().mode() & 0o7777);
+    matches!((mode(src), mode(candidate)), (Ok(a), Ok(b)) if a == b)
+}
+
+#[cfg(not(unix))]
+fn same_mode(_src: &Path, _candidate: &Path) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn is_shared(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.nlink() > 1)
+}
+
+#[cfg(not(unix))]
+fn is_shared(path: &Path) -> bool {
+    // No stable link count here; unlinking first is always safe for shade files
+    path.is_file()
+}
+
+#[cfg(unix)]
+fn same_filesystem(existing: &Path, dest: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let dest_dir = dest.parent().unwrap_or(dest);
+    match (fs::metadata(existing), fs::metadata(dest_dir)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_filesystem(_existing: &Path, _dest: &Path) -> bool {
+    true
 }
 
 #[cfg(test)]
@@ -79,7 +394,25 @@ mod tests {
     }
 
     #[test]
-    fn test_copy_dir_preserve_structure() {
+    fn test_copy_file_preserves_mtime() {
+        let temp = TempDir::new().unwrap();
+        let src_base = temp.path().join("src");
+        let dest_base = temp.path().join("dest");
+        let src_file = src_base.join("config.local");
+        fs::create_dir_all(&src_base).unwrap();
+        fs::write(&src_file, "x").unwrap();
+
+        let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+        filetime::set_file_mtime(&src_file, an_hour_ago).unwrap();
+
+        let dest_file = copy_file_preserve_structure(&src_file, &src_base, &dest_base).unwrap();
+
+        let dest_mtime = FileTime::from_last_modification_time(&fs::metadata(dest_file).unwrap());
+        assert_eq!(dest_mtime, an_hour_ago);
+    }
+
+    #[test]
+    fn test_store_dir_preserve_structure() {
         let temp = TempDir::new().unwrap();
         let src_base = temp.path().join("src");
         let dest_base = temp.path().join("dest");
@@ -89,13 +422,90 @@ mod tests {
         fs::create_dir_all(&secrets_dir).unwrap();
         fs::write(secrets_dir.join("api.key"), "secret1").unwrap();
         fs::write(secrets_dir.join("oauth.json"), "secret2").unwrap();
+        fs::create_dir_all(secrets_dir.join("tmp")).unwrap();
+        fs::write(secrets_dir.join("tmp/scratch.key"), "scratch").unwrap();
 
         // Copy directory
-        let copied = copy_dir_preserve_structure(&secrets_dir, &src_base, &dest_base).unwrap();
+        let mut store = ShadeStore::new(Storage::Copy, &dest_base).unwrap();
+        let copied = store
+            .store_dir(&secrets_dir, &src_base, &dest_base, &["tmp/".to_string()])
+            .unwrap();
 
         // Verify
         assert_eq!(copied.len(), 2);
         assert!(dest_base.join("secrets/api.key").exists());
         assert!(dest_base.join("secrets/oauth.json").exists());
+        assert!(!dest_base.join("secrets/tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlink_storage_shares_identical_files() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = TempDir::new().unwrap();
+        let shade = temp.path().join("projects");
+        let app = temp.path().join("app");
+        let api = temp.path().join("api");
+        fs::create_dir_all(shade.join("app")).unwrap();
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&api).unwrap();
+        fs::write(app.join("fixture.bin"), "big fixture").unwrap();
+        fs::write(api.join("fixture.bin"), "big fixture").unwrap();
+
+        let mut store = ShadeStore::new(Storage::Hardlink, &shade).unwrap();
+        let first = store
+            .store_file(&app.join("fixture.bin"), &app, &shade.join("app"))
+            .unwrap();
+        let second = store
+            .store_file(&api.join("fixture.bin"), &api, &shade.join("api"))
+            .unwrap();
+
+        let inode = |path: &Path| fs::metadata(path).unwrap().ino();
+        assert_eq!(inode(&first), inode(&second));
+
+        // Changing one project's file must not leak into the other
+        fs::write(api.join("fixture.bin"), "changed").unwrap();
+        let mut store = ShadeStore::new(Storage::Hardlink, &shade).unwrap();
+        store
+            .store_file(&api.join("fixture.bin"), &api, &shade.join("api"))
+            .unwrap();
+        assert_eq!(fs::read_to_string(&first).unwrap(), "big fixture");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "changed");
+        assert_ne!(inode(&first), inode(&second));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_move_to_shade_leaves_symlink() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("app");
+        let shade = temp.path().join("projects/app");
+        fs::create_dir_all(project.join("config")).unwrap();
+        let local = project.join("config/app.local");
+        fs::write(&local, "v1").unwrap();
+
+        let dest = move_to_shade(&local, &project, &shade).unwrap();
+        assert!(links_to(&local, &dest));
+
+        // Storing it again (e.g. on push) must not copy the file onto itself
+        let mut store = ShadeStore::new(Storage::Copy, &shade).unwrap();
+        store.store_file(&local, &project, &shade).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "v1");
+
+        fs::write(&local, "v2").unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "v2");
+
+        replace_link_with_copy(&local).unwrap();
+        assert!(!local.is_symlink());
+        assert_eq!(fs::read_to_string(&local).unwrap(), "v2");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 kB");
+        assert_eq!(format_size(50 * 1024 * 1024), "50.0 MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GB");
     }
 }
@@ -0,0 +1,57 @@
+use crate::error::{Result, ShadeError};
+use crate::utils::output::outln;
+use colored::Colorize;
+use std::path::Path;
+use std::process::Command;
+
+/// Run a configured hook command, if any, from the project root
+///
+/// The command runs through the shell with these variables set:
+/// `GIT_SHADE_HOOK`, `GIT_SHADE_PROJECT`, `GIT_SHADE_PROJECT_DIR` and
+/// `GIT_SHADE_SHADE_DIR`. A non-zero exit becomes `ShadeError::HookFailed`.
+pub fn run_hook(
+    hook: &str,
+    command: Option<&str>,
+    project_name: &str,
+    project_path: &Path,
+    shade_dir: &Path,
+) -> Result<()> {
+    let Some(command) = command else {
+        return Ok(());
+    };
+
+    outln!("{} Running {} hook: {}", "→".blue(), hook, command);
+
+    let status = shell(command)
+        .current_dir(project_path)
+        .env("GIT_SHADE_HOOK", hook)
+        .env("GIT_SHADE_PROJECT", project_name)
+        .env("GIT_SHADE_PROJECT_DIR", project_path)
+        .env("GIT_SHADE_SHADE_DIR", shade_dir)
+        .status()?;
+
+    if !status.success() {
+        return Err(ShadeError::HookFailed {
+            hook: hook.to_string(),
+            command: command.to_string(),
+            status: status.to_string(),
+        });
+    }
+
+    outln!();
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
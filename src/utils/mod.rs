@@ -1,5 +1,18 @@
 pub mod fs;
+pub mod hooks;
+pub mod output;
+pub mod progress;
 pub mod project;
+pub mod time;
 
-pub use fs::{copy_dir_preserve_structure, copy_file_preserve_structure};
-pub use project::{detect_project_name, verify_git_repo};
+pub use fs::{
+    copy_file_preserve_structure, copy_file_to, format_size, links_to, move_to_shade,
+    replace_link_with_copy, set_file_mode, set_mtime, symlink_file, unshare_file, ShadeStore,
+};
+pub use hooks::run_hook;
+pub use progress::CopyProgress;
+pub use project::{
+    contained_path, detect_project_name, load_tracker, migrate_tracker, project_name_in,
+    relative_to_project, resolve_project, tracker_path, verify_git_repo,
+};
+pub use time::{format_relative, format_timestamp};
@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// A writer for command output, shared between a `Context` and the commands it runs
+pub type Sink = Arc<Mutex<dyn Write + Send>>;
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+/// Write to the current sink, stdout unless `redirect` says otherwise
+///
+/// What `out!` and `outln!` expand to. A failing sink is ignored: output
+/// is only ever progress, and the command's result doesn't depend on it.
+pub fn write_fmt(args: fmt::Arguments) {
+    match SINK.with(|sink| sink.borrow().clone()) {
+        Some(sink) => {
+            if let Ok(mut writer) = sink.lock() {
+                let _ = writer.write_fmt(args);
+            }
+        }
+        None => print!("{}", args),
+    }
+}
+
+/// Send everything `f` prints with `out!`/`outln!` to `sink` (stdout for `None`)
+pub fn redirect<T>(sink: Option<&Sink>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Sink>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SINK.with(|sink| *sink.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(SINK.with(|current| current.replace(sink.cloned())));
+    f()
+}
+
+/// `print!` to the current sink
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::utils::output::write_fmt(format_args!($($arg)*))
+    };
+}
+
+/// `println!` to the current sink
+macro_rules! outln {
+    () => {
+        $crate::utils::output::write_fmt(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::utils::output::write_fmt(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+pub(crate) use {out, outln};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_captures_output_and_restores_stdout() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let sink: Sink = buffer.clone();
+
+        let result = redirect(Some(&sink), || {
+            out!("a");
+            outln!(" {}", 1);
+            outln!();
+            42
+        });
+
+        assert_eq!(result, 42);
+        assert_eq!(
+            String::from_utf8(buffer.lock().unwrap().clone()).unwrap(),
+            "a 1\n\n"
+        );
+        assert!(SINK.with(|sink| sink.borrow().is_none()));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::utils::output::outln;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+
+/// Progress reporting for the file-copy phase of push/pull
+///
+/// Shows a progress bar on a terminal. With `verbose` (or when stdout is not a
+/// terminal) no bar is drawn and per-file lines are printed instead when verbose.
+pub struct CopyProgress {
+    bar: Option<ProgressBar>,
+    verbose: bool,
+}
+
+impl CopyProgress {
+    pub fn new(total: usize, verbose: bool) -> Self {
+        let bar = if !verbose && std::io::stdout().is_terminal() {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("  [{bar:30}] {pos}/{len} {wide_msg}")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar())
+                    .progress_chars("=> "),
+            );
+            Some(bar)
+        } else {
+            None
+        };
+
+        Self { bar, verbose }
+    }
+
+    /// Mark `name` as the file currently being copied
+    pub fn start(&self, name: &str) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(name.to_string());
+        }
+    }
+
+    pub fn inc(&self) {
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    /// Per-file line, only shown with --verbose
+    pub fn verbose(&self, line: &str) {
+        if self.verbose {
+            outln!("{}", line);
+        }
+    }
+
+    /// Line that must always be shown (warnings), printed above the bar
+    pub fn println(&self, line: &str) {
+        match &self.bar {
+            Some(bar) => bar.println(line),
+            None => outln!("{}", line),
+        }
+    }
+
+    pub fn finish(self) {
+        if let Some(bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
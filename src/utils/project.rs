@@ -1,16 +1,30 @@
+use crate::core::{Config, ProjectConfig, ShadePaths, Tracker, SYNC_FILE};
 use crate::error::{Result, ShadeError};
+use crate::utils::output::outln;
+use colored::Colorize;
+use dialoguer::Confirm;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
+/// Project name of the current directory (see `project_name_in`)
 pub fn detect_project_name(name_override: Option<String>) -> Result<String> {
+    project_name_in(&env::current_dir()?, name_override)
+}
+
+/// Project name: `name_override`, else `name` from `.git-shade.toml`, else the directory name
+pub fn project_name_in(project_path: &Path, name_override: Option<String>) -> Result<String> {
     if let Some(name) = name_override {
         return Ok(name);
     }
 
-    let current_dir = env::current_dir()?;
+    if let Some(name) = ProjectConfig::load(project_path)?.and_then(|config| config.name) {
+        return Ok(name);
+    }
 
     // Get directory name
-    let name = current_dir
+    let name = project_path
         .file_name()
         .and_then(|n| n.to_str())
         .map(|s| s.to_string())
@@ -29,3 +43,171 @@ pub fn verify_git_repo() -> Result<PathBuf> {
 
     Ok(current_dir)
 }
+
+/// Find the project to operate on and return its root and name
+///
+/// With `project_override`, the project is looked up in `config` and its stored
+/// `local_path` is used, so this works from any directory. Otherwise the current
+/// directory must be the git repository of an initialized project, named
+/// `name_override` if given (as with `init --name`).
+pub fn resolve_project(
+    config: &Config,
+    project_override: Option<String>,
+    name_override: Option<String>,
+) -> Result<(PathBuf, String)> {
+    if let Some(project_name) = project_override {
+        let project = config
+            .find_project(&project_name)
+            .ok_or_else(|| ShadeError::ProjectNotRegistered(project_name.clone()))?;
+
+        if !project.local_path.exists() {
+            return Err(ShadeError::ProjectPathMissing {
+                project_name,
+                path: project.local_path.clone(),
+            });
+        }
+
+        return Ok((project.local_path.clone(), project_name));
+    }
+
+    let project_path = verify_git_repo()?;
+    let project_name = detect_project_name(name_override)?;
+
+    if config.find_project(&project_name).is_none() {
+        return Err(ShadeError::NotInitialized { project_name });
+    }
+
+    Ok((project_path, project_name))
+}
+
+/// `path` relative to the project root (relative paths are taken as relative to it already)
+pub fn relative_to_project(project_path: &Path, path: &Path) -> Result<PathBuf> {
+    let full_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        project_path.join(path)
+    };
+
+    let rel_path = full_path
+        .strip_prefix(project_path)
+        .map_err(|_| anyhow::anyhow!("Path is not inside project directory: {}", path.display()))?;
+
+    Ok(rel_path.to_path_buf())
+}
+
+/// `full_path` relative to the project root, refusing anything that resolves outside it
+///
+/// `..` and symlinked directories are resolved first, so neither can get a path
+/// outside the project into `.git/info/exclude` (pull writes to those paths). A
+/// symlinked file may point inside the project or into `shade_dir`, where
+/// `add --symlink` leaves its links. `full_path` must exist.
+pub fn contained_path(project_path: &Path, full_path: &Path, shade_dir: &Path) -> Result<PathBuf> {
+    let outside = || ShadeError::PathOutsideProject(full_path.to_path_buf());
+    let root = fs::canonicalize(project_path)?;
+
+    let resolved = match (
+        full_path.is_symlink(),
+        full_path.parent(),
+        full_path.file_name(),
+    ) {
+        (true, Some(parent), Some(name)) => {
+            let target = fs::canonicalize(full_path)?;
+            let in_shade =
+                fs::canonicalize(shade_dir).is_ok_and(|shade_dir| target.starts_with(shade_dir));
+            if !target.starts_with(&root) && !in_shade {
+                return Err(outside());
+            }
+            fs::canonicalize(parent)?.join(name)
+        }
+        _ => fs::canonicalize(full_path)?,
+    };
+
+    resolved
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .map_err(|_| outside())
+}
+
+/// The project's sync tracker, or a fresh one (with a warning) if it fails validation
+pub fn load_tracker(path: &Path) -> Tracker {
+    Tracker::load(path).unwrap_or_else(|e| {
+        eprintln!("{} Ignoring sync tracker: {:#}", "⚠".yellow(), e);
+        eprintln!(
+            "  Treating the project as never pulled. Reset it with: {}",
+            "git-shade doctor --repair".bold()
+        );
+        Tracker::new()
+    })
+}
+
+/// Where the project's sync tracker lives (see `metadata_in_repo`)
+///
+/// In the shade repo it's committed as `.shade-sync.<machine>`, one per
+/// machine so their sync times never clash. A plain `.shade-sync` left there
+/// by older versions is renamed to this machine's.
+pub fn tracker_path(paths: &ShadePaths, config: &Config, project_name: &str) -> Result<PathBuf> {
+    let file = paths.tracker_file(config, project_name);
+    if config.metadata_in_repo {
+        let legacy = paths.project_shade_dir(project_name).join(SYNC_FILE);
+        if legacy.is_file() && !file.exists() {
+            fs::rename(&legacy, &file)?;
+        }
+    }
+    Ok(file)
+}
+
+/// Offer to move a `.shade-sync` left in the other location by a change of `metadata_in_repo`
+///
+/// Without a terminal it only says so, since `status` must not block scripts.
+pub fn migrate_tracker(paths: &ShadePaths, config: &Config, project_name: &str) -> Result<()> {
+    let (from, to) = if config.metadata_in_repo {
+        (
+            paths.shade_sync_file(project_name),
+            paths.repo_sync_file(project_name, &config.machine_name()),
+        )
+    } else {
+        (
+            paths.repo_sync_file(project_name, &config.machine_name()),
+            paths.shade_sync_file(project_name),
+        )
+    };
+    if !from.is_file() || to.exists() {
+        return Ok(());
+    }
+
+    outln!(
+        "{} Sync state found at {} (metadata_in_repo = {})",
+        "⚠".yellow(),
+        from.display(),
+        config.metadata_in_repo
+    );
+
+    let confirmed = if !std::io::stdin().is_terminal() {
+        outln!(
+            "  {} No terminal detected, leaving it. Until it's moved to {}, the project counts as never pulled.",
+            "→".blue(),
+            to.display()
+        );
+        false
+    } else {
+        Confirm::new()
+            .with_prompt(format!("Move it to {}?", to.display()))
+            .default(true)
+            .interact()
+            .map_err(|e| anyhow::anyhow!("Dialog error: {}", e))?
+    };
+
+    if confirmed {
+        let to = tracker_path(paths, config, project_name)?;
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Metadata and shade dirs may be on different filesystems
+        fs::copy(&from, &to)?;
+        fs::remove_file(&from)?;
+        outln!("  {} Moved to {}", "✓".green(), to.display());
+    }
+    outln!();
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+/// How long before `now` `time` was, e.g. `3 hours ago`
+///
+/// Rounds down to the largest whole unit. Anything under a minute is
+/// `just now`, and a time after `now` (clock skew between machines) is
+/// `in the future`.
+pub fn format_relative<Tz: TimeZone>(time: &DateTime<Tz>, now: DateTime<Utc>) -> String {
+    let seconds = now.signed_duration_since(time).num_seconds();
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const UNITS: [(i64, &str); 6] = [
+        (365 * DAY, "year"),
+        (30 * DAY, "month"),
+        (7 * DAY, "week"),
+        (DAY, "day"),
+        (HOUR, "hour"),
+        (MINUTE, "minute"),
+    ];
+
+    UNITS
+        .iter()
+        .find(|(size, _)| seconds >= *size)
+        .map(|(size, unit)| {
+            let count = seconds / size;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{} {}{} ago", count, unit, plural)
+        })
+        .unwrap_or_else(|| "just now".to_string())
+}
+
+/// A `status` timestamp: relative, with the absolute UTC time in front when verbose
+///
+/// `2025-10-18 13:55:00 (3 hours ago)` verbose, `3 hours ago` otherwise.
+pub fn format_timestamp(time: DateTime<Utc>, verbose: bool) -> String {
+    let relative = format_relative(&time, Utc::now());
+    if verbose {
+        format!("{} ({})", time.format("%Y-%m-%d %H:%M:%S"), relative)
+    } else {
+        relative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, FixedOffset};
+
+    #[test]
+    fn test_format_relative_at_several_durations() {
+        let now = Utc.with_ymd_and_hms(2025, 10, 18, 14, 0, 0).unwrap();
+        let ago = |elapsed: Duration| format_relative(&(now - elapsed), now);
+
+        assert_eq!(ago(Duration::seconds(0)), "just now");
+        assert_eq!(ago(Duration::seconds(59)), "just now");
+        assert_eq!(ago(Duration::seconds(60)), "1 minute ago");
+        assert_eq!(ago(Duration::minutes(59)), "59 minutes ago");
+        assert_eq!(ago(Duration::minutes(61)), "1 hour ago");
+        assert_eq!(ago(Duration::hours(3)), "3 hours ago");
+        assert_eq!(ago(Duration::hours(47)), "1 day ago");
+        assert_eq!(ago(Duration::days(6)), "6 days ago");
+        assert_eq!(ago(Duration::days(14)), "2 weeks ago");
+        assert_eq!(ago(Duration::days(45)), "1 month ago");
+        assert_eq!(ago(Duration::days(800)), "2 years ago");
+        assert_eq!(ago(Duration::seconds(-30)), "in the future");
+    }
+
+    #[test]
+    fn test_format_relative_ignores_the_time_zone() {
+        let now = Utc.with_ymd_and_hms(2025, 10, 18, 14, 0, 0).unwrap();
+        let madrid = FixedOffset::east_opt(2 * 3600).unwrap();
+        let local = madrid.with_ymd_and_hms(2025, 10, 18, 13, 0, 0).unwrap();
+
+        assert_eq!(format_relative(&local, now), "3 hours ago");
+    }
+
+    #[test]
+    fn test_format_timestamp_keeps_absolute_time_when_verbose() {
+        let time = Utc::now() - Duration::hours(3);
+        let absolute = time.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        assert_eq!(format_timestamp(time, false), "3 hours ago");
+        assert_eq!(
+            format_timestamp(time, true),
+            format!("{} (3 hours ago)", absolute)
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 pub fn setup_test_repo() -> (TempDir, PathBuf) {
@@ -6,10 +8,100 @@ pub fn setup_test_repo() -> (TempDir, PathBuf) {
     let path = temp.path().to_path_buf();
 
     std::process::Command::new("git")
-        .args(&["init"])
+        .args(["init"])
         .current_dir(&path)
         .output()
         .unwrap();
 
     (temp, path)
 }
+
+/// Isolated HOME with a shade repo and a git project named `myapp`
+pub struct ShadeEnv {
+    pub home: TempDir,
+    pub project: PathBuf,
+}
+
+impl ShadeEnv {
+    pub fn new() -> Self {
+        let home = TempDir::new().unwrap();
+
+        let projects = home.path().join(".local/git-shade/projects");
+        std::fs::create_dir_all(&projects).unwrap();
+        git(&projects, &["init"]);
+        git(&projects, &["config", "user.name", "test"]);
+        git(&projects, &["config", "user.email", "test@example.com"]);
+
+        let project = home.path().join("myapp");
+        std::fs::create_dir_all(&project).unwrap();
+        git(&project, &["init"]);
+
+        Self { home, project }
+    }
+
+    /// Same as `new`, plus a bare `origin` with one commit so `git pull` works
+    pub fn with_remote() -> Self {
+        let env = Self::new();
+        let remote = env.home.path().join("remote.git");
+        git(
+            env.home.path(),
+            &["init", "--bare", remote.to_str().unwrap()],
+        );
+
+        let projects = env.projects();
+        git(
+            &projects,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        git(&projects, &["commit", "--allow-empty", "-m", "initial"]);
+        git(&projects, &["push", "-u", "origin", "HEAD"]);
+
+        env
+    }
+
+    pub fn projects(&self) -> PathBuf {
+        self.home.path().join(".local/git-shade/projects")
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.home.path().join(".local/git-shade/config.toml")
+    }
+
+    pub fn shade_dir(&self) -> PathBuf {
+        self.projects().join("myapp")
+    }
+
+    /// `git-shade` command running inside the project with HOME pointed at the temp dir
+    pub fn cmd(&self) -> assert_cmd::Command {
+        let mut cmd = assert_cmd::Command::cargo_bin("git-shade").unwrap();
+        cmd.current_dir(&self.project)
+            .env("HOME", self.home.path())
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .env_remove("RUST_LOG")
+            .env_remove("GIT_SHADE_XDG")
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME");
+        cmd
+    }
+}
+
+/// Run git and return trimmed stdout
+pub fn git_stdout(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+pub fn git(dir: &Path, args: &[&str]) {
+    std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+}
@@ -1,5 +1,16 @@
+mod common;
+
 use assert_cmd::Command;
+use common::{git_stdout, ShadeEnv};
+use filetime::FileTime;
+use git_shade::commands::add::AddOptions;
+use git_shade::commands::init::InitOptions;
+use git_shade::commands::push::PushOptions;
+use git_shade::commands::status::FileStatus;
+use git_shade::core::{detect_sync_state, FileMetadata, SyncPolicy, SyncState};
+use git_shade::{Context, GitShade};
 use predicates::prelude::*;
+use std::fs;
 
 #[test]
 fn test_help_works() {
@@ -10,3 +21,4444 @@ fn test_help_works() {
         .success()
         .stdout(predicate::str::contains("git-shade"));
 }
+
+#[test]
+fn test_missing_git_is_reported_clearly() {
+    let env = ShadeEnv::new();
+    let empty_path = env.home.path().join("no-git");
+    fs::create_dir_all(&empty_path).unwrap();
+
+    env.cmd()
+        .arg("status")
+        .env("PATH", &empty_path)
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains("git is not installed"))
+        .stderr(predicate::str::contains("os error").not());
+
+    // Commands that don't run git still work
+    env.cmd()
+        .arg("guide")
+        .env("PATH", &empty_path)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_library_api_runs_commands_with_explicit_context() {
+    let env = ShadeEnv::new();
+    let root = env.home.path().join(".local/git-shade");
+    // Collects what the commands print
+    #[derive(Clone, Default)]
+    struct Output(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+    impl std::io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let output = Output::default();
+
+    // Neither the current directory nor HOME point at the project here
+    let context = Context::new(&root, &env.project, None).unwrap();
+    let shade = GitShade::new(context.with_output(output.clone()));
+    assert_eq!(shade.context().project_name, "myapp");
+
+    assert!(shade.status().is_err());
+    shade
+        .init(InitOptions {
+            no_pull: true,
+            ..InitOptions::default()
+        })
+        .unwrap();
+
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+    shade
+        .add(vec!["config.local".into()], AddOptions::default())
+        .unwrap();
+    assert!(shade
+        .push(&PushOptions {
+            tag: Some("first".into()),
+            ..PushOptions::default()
+        })
+        .unwrap());
+    assert_eq!(
+        git_stdout(&root.join("projects"), &["tag", "--list"]),
+        "myapp/first"
+    );
+    let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert!(printed.contains("Initialized git-shade for project: myapp"));
+    assert!(printed.contains("Committed: [myapp]"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "secret"
+    );
+
+    let statuses = shade.status().unwrap();
+    assert_eq!(
+        statuses,
+        vec![("config.local".into(), FileStatus::State(SyncState::InSync))]
+    );
+}
+
+#[test]
+fn test_xdg_layout_moves_existing_files_once() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    let config_home = env.home.path().join("xdg-config");
+    let data_home = env.home.path().join("xdg-data");
+    let xdg_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.env("XDG_CONFIG_HOME", &config_home)
+            .env("XDG_DATA_HOME", &data_home);
+        cmd
+    };
+
+    // Without the toggle the XDG directories aren't touched
+    xdg_cmd().arg("status").assert().success();
+    assert!(!config_home.exists());
+
+    xdg_cmd()
+        .env("GIT_SHADE_XDG", "1")
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Moved"))
+        .stdout(predicate::str::contains("config.local"));
+    assert!(!env.home.path().join(".local/git-shade").exists());
+    assert!(config_home.join("git-shade/config.toml").is_file());
+    assert!(data_home
+        .join("git-shade/projects/myapp/config.local")
+        .is_file());
+
+    // Once set up, the layout is found without the toggle
+    xdg_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Moved").not())
+        .stdout(predicate::str::contains("config.local"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_xdg_move_repoints_symlinked_files_and_hints_name_new_paths() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--symlink", "config.local"])
+        .assert()
+        .success();
+
+    let config_home = env.home.path().join("xdg-config");
+    let data_home = env.home.path().join("xdg-data");
+    let xdg_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.env("XDG_CONFIG_HOME", &config_home)
+            .env("XDG_DATA_HOME", &data_home)
+            .env("GIT_SHADE_XDG", "1");
+        cmd
+    };
+
+    xdg_cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Re-pointed 1 file(s) added with --symlink",
+        ));
+    let link = env.project.join("config.local");
+    assert_eq!(
+        fs::read_link(&link).unwrap(),
+        data_home.join("git-shade/projects/myapp/config.local")
+    );
+    assert_eq!(fs::read_to_string(&link).unwrap(), "secret");
+
+    // Hints name the XDG paths
+    xdg_cmd()
+        .args(["status", "--project", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "listed in ~/xdg-config/git-shade/config.toml",
+        ))
+        .stderr(predicate::str::contains(".local/git-shade").not());
+}
+
+#[test]
+fn test_color_flag_and_no_color() {
+    let guide = |args: &[&str], no_color: bool| {
+        let mut cmd = Command::cargo_bin("git-shade").unwrap();
+        cmd.arg("guide").args(args);
+        if no_color {
+            cmd.env("NO_COLOR", "1");
+        } else {
+            cmd.env_remove("NO_COLOR");
+        }
+        String::from_utf8(cmd.output().unwrap().stdout).unwrap()
+    };
+
+    // Output is piped here, so auto means no color
+    assert!(!guide(&[], false).contains('\x1b'));
+    assert!(guide(&["--color", "always"], false).contains('\x1b'));
+    assert!(guide(&["--color", "always"], true).contains('\x1b'));
+    assert!(!guide(&["--color", "never"], false).contains('\x1b'));
+}
+
+#[test]
+fn test_nested_directory_add_push_status() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets/prod/db")).unwrap();
+    fs::write(env.project.join("secrets/api.key"), "key").unwrap();
+    fs::write(env.project.join("secrets/prod/db/password"), "pw").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "secrets"]).assert().success();
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.lines().any(|l| l == "secrets/"));
+
+    env.cmd()
+        .args(["push", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ secrets/"));
+    assert!(env.shade_dir().join("secrets/prod/db/password").exists());
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("secrets/api.key  "))
+        .stdout(predicate::str::contains("secrets/prod/db/password  "))
+        .stdout(predicate::str::contains("local only, not in shade").not());
+}
+
+#[test]
+fn test_add_dir_as_glob() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets/nested")).unwrap();
+    fs::write(env.project.join("secrets/nested/token"), "t").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--dir-as-glob", "secrets"])
+        .assert()
+        .success();
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.lines().any(|l| l == "secrets/**"));
+
+    env.cmd().arg("push").assert().success();
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("secrets/nested/token  "));
+}
+
+#[test]
+fn test_add_exclude_skips_subpath_on_add_and_push() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("uploads/tmp")).unwrap();
+    fs::write(env.project.join("uploads/logo.png"), "png").unwrap();
+    fs::write(env.project.join("uploads/tmp/partial.bin"), "tmp").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "uploads/", "--exclude", "tmp/"])
+        .assert()
+        .success();
+
+    assert!(env.shade_dir().join("uploads/logo.png").exists());
+    assert!(!env.shade_dir().join("uploads/tmp").exists());
+
+    // Files created later in the excluded subpath are skipped by push too
+    fs::write(env.project.join("uploads/tmp/new.bin"), "new").unwrap();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s) copied"));
+    assert!(!env.shade_dir().join("uploads/tmp").exists());
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("uploads/tmp").not());
+}
+
+#[test]
+fn test_junk_files_in_tracked_directory_are_not_synced() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/api.key"), "key").unwrap();
+    fs::write(env.project.join("secrets/.DS_Store"), "finder").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "secrets/"]).assert().success();
+    assert!(env.shade_dir().join("secrets/api.key").exists());
+    assert!(!env.shade_dir().join("secrets/.DS_Store").exists());
+
+    // Junk created later is skipped by push, and status doesn't list it
+    fs::write(env.project.join("secrets/.api.key.swp"), "vim").unwrap();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s) copied"));
+    assert!(!env.shade_dir().join("secrets/.api.key.swp").exists());
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".DS_Store").not())
+        .stdout(predicate::str::contains(".swp").not());
+
+    // With the list emptied, everything in the directory is synced
+    env.cmd()
+        .args(["config", "set", "junk_patterns", ""])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    assert!(env.shade_dir().join("secrets/.DS_Store").exists());
+}
+
+#[test]
+fn test_per_os_project_keeps_variants_per_os_and_falls_back_to_shared() {
+    let env = ShadeEnv::with_remote();
+    let os = std::env::consts::OS;
+    let other = if os == "macos" { "linux" } else { "macos" };
+    fs::write(env.project.join("paths.local"), "this os").unwrap();
+    fs::write(env.project.join("config.local"), "shared").unwrap();
+
+    env.cmd().arg("init").assert().success();
+
+    // Only files added with --per-os go to this OS's directory
+    env.cmd()
+        .args(["add", "--per-os", "paths.local"])
+        .assert()
+        .success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    let shade = env.shade_dir();
+    assert!(shade.join(os).join("paths.local").exists());
+    assert!(!shade.join("paths.local").exists());
+    assert!(shade.join("config.local").exists());
+    env.cmd().arg("push").assert().success();
+
+    // Edits to a shared file reach the shared copy
+    fs::write(env.project.join("config.local"), "shared v2").unwrap();
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(shade.join("config.local")).unwrap(),
+        "shared v2"
+    );
+    assert!(!shade.join(os).join("config.local").exists());
+
+    // The layout is recorded in the shade repo, not in this machine's config
+    assert!(!fs::read_to_string(env.config_file())
+        .unwrap()
+        .contains("per_os"));
+    assert_eq!(
+        git_stdout(&env.projects(), &["ls-files", "myapp/.shade-per-os"]).trim(),
+        "myapp/.shade-per-os"
+    );
+
+    // Another machine has its own paths.local
+    fs::create_dir_all(shade.join(other)).unwrap();
+    fs::write(shade.join(other).join("paths.local"), "other os").unwrap();
+    common::git(&env.projects(), &["add", "-A"]);
+    common::git(&env.projects(), &["commit", "-m", "other machine"]);
+    common::git(&env.projects(), &["push"]);
+
+    fs::remove_file(env.project.join("paths.local")).unwrap();
+    fs::remove_file(env.project.join("config.local")).unwrap();
+    env.cmd()
+        .args(["pull", "--strategy", "remote"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("paths.local")).unwrap(),
+        "this os"
+    );
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "shared v2"
+    );
+    assert!(!env.project.join(other).exists());
+    assert!(!env.project.join(".shade-per-os").exists());
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(other).not());
+    env.cmd().arg("verify").assert().success();
+}
+
+#[test]
+fn test_per_os_config_records_layout_on_push() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "shared").unwrap();
+    env.cmd().arg("init").assert().success();
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    let config = config.replace("[[projects]]\n", "[[projects]]\nper_os = true\n");
+    fs::write(env.config_file(), config).unwrap();
+
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Recorded the per-OS layout in the shade repo",
+        ));
+    assert!(env.shade_dir().join(".shade-per-os").exists());
+    assert!(env.shade_dir().join("config.local").exists());
+}
+
+#[test]
+fn test_add_from_gitignore_imports_existing_ignored_files() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join(".gitignore"), "*.local\n/build/\n.env\n").unwrap();
+    fs::write(env.project.join("config.local"), "c").unwrap();
+    fs::write(env.project.join("README.md"), "r").unwrap();
+    common::git(&env.project, &["add", ".gitignore", "README.md"]);
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--from-gitignore"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Importing from ignore rules:"))
+        .stdout(predicate::str::contains("  - config.local"))
+        .stdout(predicate::str::contains(".env").not());
+
+    assert!(env.shade_dir().join("config.local").exists());
+    assert!(!env.shade_dir().join("README.md").exists());
+
+    // Nothing left to import
+    env.cmd()
+        .args(["add", "--from-gitignore"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No existing files matched"));
+}
+
+#[test]
+fn test_add_reads_paths_from_stdin() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("a.local"), "a").unwrap();
+    fs::write(env.project.join("b.local"), "b").unwrap();
+    fs::write(env.project.join("my file.local"), "spaces").unwrap();
+    fs::write(env.project.join("odd\nname.local"), "newline").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "a.local"]).assert().success();
+
+    env.cmd()
+        .args(["add", "--stdin"])
+        .write_stdin("a.local\nb.local\r\n\nb.local\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Skipping 1 path(s) from stdin that are already tracked",
+        ));
+    assert!(env.shade_dir().join("b.local").exists());
+
+    env.cmd()
+        .args(["add", "--stdin0"])
+        .write_stdin("my file.local\0")
+        .assert()
+        .success();
+    assert!(env.shade_dir().join("my file.local").exists());
+
+    // Read fine, but there's no way to write it to .git/info/exclude
+    env.cmd()
+        .args(["add", "--stdin0"])
+        .write_stdin("odd\nname.local\0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path with a newline"));
+    assert!(!env.shade_dir().join("odd\nname.local").exists());
+
+    env.cmd()
+        .args(["add", "--stdin"])
+        .write_stdin("a.local\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to add"));
+}
+
+#[test]
+fn test_add_template_adds_existing_files() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join(".env.local"), "A=1").unwrap();
+    fs::write(env.project.join(".npmrc"), "//registry/:_authToken=x").unwrap();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+
+    env.cmd()
+        .args(["add", "--template", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ .env.local"))
+        .stdout(predicate::str::contains("+ .npmrc"))
+        .stdout(predicate::str::contains(
+            ".env.production.local (not found, skipped)",
+        ));
+    assert!(env.shade_dir().join(".npmrc").exists());
+    assert!(!env.shade_dir().join(".env.production.local").exists());
+
+    // Templates from config.toml, next to the built-in ones
+    let mut config = fs::read_to_string(env.config_file()).unwrap();
+    config.push_str("\n[templates]\nmine = [\"config.local\", \".npmrc\"]\n");
+    fs::write(env.config_file(), config).unwrap();
+
+    env.cmd()
+        .args(["add", "--template", "mine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".npmrc (already tracked)"));
+    assert!(env.shade_dir().join("config.local").exists());
+
+    env.cmd()
+        .args(["add", "--template", "java"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Available templates: docker, mine, node, python, rails",
+        ));
+}
+
+#[test]
+fn test_add_and_pull_warn_about_paths_differing_only_by_case() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::write(env.project.join("config.local"), "lower").unwrap();
+    fs::write(env.project.join("Config.local"), "upper").unwrap();
+    if fs::read_dir(&env.project).unwrap().count() < 3 {
+        // Case-insensitive filesystem: there is only one file to begin with
+        return;
+    }
+
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd()
+        .args(["add", "Config.local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 tracked path(s) differ only by case",
+        ))
+        .stdout(predicate::str::contains("Config.local / config.local"));
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("differ only by case"));
+}
+
+#[test]
+fn test_add_no_copy_tracks_paths_before_they_exist() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    env.cmd()
+        .args(["add", "--no-copy", ".env.local", "secrets/"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("doesn't exist yet"))
+        .stdout(predicate::str::contains("Nothing was copied to shade"));
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.contains(".env.local"));
+    assert!(exclude.contains("secrets/"));
+    assert!(!env.shade_dir().join(".env.local").exists());
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"\.env\.local +pending").unwrap())
+        .stdout(predicate::str::is_match(r"secrets +pending").unwrap());
+
+    // Once the files show up, push picks them up like any tracked file
+    fs::write(env.project.join(".env.local"), "TOKEN=1").unwrap();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/api.key"), "key").unwrap();
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("secrets/api.key")).unwrap(),
+        "key"
+    );
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pending").not());
+
+    env.cmd()
+        .args(["add", "--no-copy", "../elsewhere.env"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("outside the project"));
+}
+
+#[test]
+fn test_add_does_not_clobber_differing_shade_copy() {
+    let env = ShadeEnv::new();
+    env.cmd().arg("init").assert().success();
+
+    // Another machine already added these files with other contents
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "theirs").unwrap();
+    fs::write(env.shade_dir().join(".env.local"), "THEIRS=1").unwrap();
+    fs::write(env.project.join("config.local"), "mine").unwrap();
+    fs::write(env.project.join(".env.local"), "MINE=1").unwrap();
+
+    // No terminal to ask on: nothing is copied
+    env.cmd()
+        .args(["add", "config.local"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "1 file(s) already in shade have different contents: config.local",
+        ))
+        .stderr(predicate::str::contains("--link-existing"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "theirs"
+    );
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("config.local"));
+
+    // --link-existing adopts the shade version
+    env.cmd()
+        .args(["add", "--link-existing", "config.local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kept the shade version of:"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "theirs"
+    );
+
+    // --force overwrites it, as add used to
+    env.cmd()
+        .args(["add", "--force", ".env.local"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join(".env.local")).unwrap(),
+        "MINE=1"
+    );
+}
+
+#[test]
+fn test_add_compares_against_this_os_variant() {
+    let env = ShadeEnv::new();
+    let os = std::env::consts::OS;
+    fs::write(env.project.join("a.local"), "a").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--per-os", "a.local"])
+        .assert()
+        .success();
+
+    // Another machine on this OS already added its own variant
+    let variant = env.shade_dir().join(os).join("paths.local");
+    fs::write(&variant, "theirs").unwrap();
+    fs::write(env.project.join("paths.local"), "mine").unwrap();
+
+    env.cmd()
+        .args(["add", "paths.local"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "1 file(s) already in shade have different contents: paths.local",
+        ));
+
+    env.cmd()
+        .args(["add", "--link-existing", "paths.local"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("paths.local")).unwrap(),
+        "theirs"
+    );
+    assert_eq!(fs::read_to_string(&variant).unwrap(), "theirs");
+}
+
+#[test]
+fn test_add_keep_both_stays_a_conflict_until_pull_settles_it() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    // A teammate pushed config.local; add kept both versions ("Keep both" in
+    // the prompt, which needs a terminal), so the manifest records the conflict
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "theirs").unwrap();
+    common::git(&env.projects(), &["add", "-A"]);
+    common::git(&env.projects(), &["commit", "-m", "[myapp] teammate"]);
+    common::git(&env.projects(), &["push"]);
+    fs::write(env.project.join("config.local"), "mine").unwrap();
+    env.cmd()
+        .args(["add", "--no-copy", "config.local"])
+        .assert()
+        .success();
+    let manifest = env
+        .home
+        .path()
+        .join(".local/git-shade/metadata/myapp/manifest.toml");
+    let contents = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        format!("conflicts = [\"config.local\"]\n{}", contents),
+    )
+    .unwrap();
+
+    // The local file is newer, but it's still a conflict
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"⚠ config\.local +conflict").unwrap());
+
+    // Push leaves the teammate's version alone
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s) in conflict skipped"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "theirs"
+    );
+
+    env.cmd().arg("pull").assert().code(5);
+    env.cmd()
+        .args(["pull", "--strategy", "local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("conflict, local wins"));
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "mine"
+    );
+    assert!(!fs::read_to_string(&manifest).unwrap().contains("conflicts"));
+}
+
+#[test]
+fn test_add_as_round_trips_file_outside_project() {
+    let env = ShadeEnv::with_remote();
+    let source = env.home.path().join(".config/app/settings");
+    fs::create_dir_all(source.parent().unwrap()).unwrap();
+    fs::write(&source, "theme=dark").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--as", "external/settings"])
+        .arg(&source)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("as external/settings"));
+
+    let shade_file = env.shade_dir().join("external/settings");
+    assert_eq!(fs::read_to_string(&shade_file).unwrap(), "theme=dark");
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("external"));
+
+    // Push reads the source, wherever it lives
+    fs::write(&source, "theme=light").unwrap();
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("external/settings  "));
+    env.cmd().arg("push").assert().success();
+    assert_eq!(fs::read_to_string(&shade_file).unwrap(), "theme=light");
+
+    // Pull writes it back to the source, not into the project
+    fs::remove_file(&source).unwrap();
+    env.cmd().arg("pull").assert().success();
+    assert_eq!(fs::read_to_string(&source).unwrap(), "theme=light");
+    assert!(!env.project.join("external").exists());
+
+    env.cmd().args(["verify"]).assert().success();
+
+    // A machine without the file maps it first, then pulls it
+    fs::remove_file(&source).unwrap();
+    fs::remove_file(
+        env.home
+            .path()
+            .join(".local/git-shade/metadata/myapp/manifest.toml"),
+    )
+    .unwrap();
+    env.cmd()
+        .args(["add", "--as", "external/settings"])
+        .arg(&source)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Not there yet"));
+    env.cmd().arg("pull").assert().success();
+    assert_eq!(fs::read_to_string(&source).unwrap(), "theme=light");
+
+    env.cmd()
+        .args(["add", "--as", "../escape"])
+        .arg(&source)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--as needs a path inside"));
+}
+
+#[test]
+fn test_add_over_file_limit_needs_force() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("cache")).unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(env.project.join("cache").join(name), name).unwrap();
+    }
+
+    env.cmd().arg("init").assert().success();
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    fs::write(
+        env.config_file(),
+        config.replace("max_add_files = 1000", "max_add_files = 2"),
+    )
+    .unwrap();
+
+    env.cmd()
+        .args(["add", "cache"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to add 3 file(s)"));
+    assert!(!env.shade_dir().join("cache/a").exists());
+
+    env.cmd()
+        .args(["add", "--force", "cache"])
+        .assert()
+        .success();
+    assert!(env.shade_dir().join("cache/c").exists());
+}
+
+#[test]
+fn test_add_rejects_paths_outside_project() {
+    let env = ShadeEnv::new();
+    let outside = env.home.path().join("other");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("secret"), "s").unwrap();
+    fs::create_dir_all(env.project.join("config")).unwrap();
+    fs::write(env.project.join("config/app.local"), "a").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "../other/secret"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Path is outside the project"));
+    env.cmd()
+        .args(["add", "config/../../other/secret"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Path is outside the project"));
+    env.cmd()
+        .arg("add")
+        .arg(outside.join("secret"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Path is outside the project"));
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("secret"));
+    assert!(!env.projects().join("other").exists());
+
+    // `..` that stays inside the project resolves to a clean pattern
+    env.cmd()
+        .args(["add", "config/../config/app.local"])
+        .assert()
+        .success();
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.lines().any(|line| line == "config/app.local"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_add_rejects_symlinks_that_escape_project() {
+    let env = ShadeEnv::new();
+    let outside = env.home.path().join("other");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("secret"), "s").unwrap();
+    std::os::unix::fs::symlink(&outside, env.project.join("linked")).unwrap();
+    std::os::unix::fs::symlink(outside.join("secret"), env.project.join("secret.local")).unwrap();
+    fs::write(env.project.join("real.local"), "r").unwrap();
+    std::os::unix::fs::symlink("real.local", env.project.join("alias.local")).unwrap();
+
+    env.cmd().arg("init").assert().success();
+    for path in ["linked/secret", "linked", "secret.local"] {
+        env.cmd()
+            .args(["add", path])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Path is outside the project"));
+    }
+
+    // A symlink to a file inside the project is fine
+    env.cmd().args(["add", "alias.local"]).assert().success();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_add_symlink_moves_file_into_shade() {
+    let env = ShadeEnv::with_remote();
+    let local = env.project.join("notes.local");
+    fs::write(&local, "v1").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--symlink", "notes.local"])
+        .assert()
+        .success();
+
+    let shade_file = env.shade_dir().join("notes.local");
+    assert!(local.is_symlink());
+    assert_eq!(fs::read_link(&local).unwrap(), shade_file);
+
+    // Editing either side is the same file; push and pull leave the link alone
+    fs::write(&local, "v2").unwrap();
+    env.cmd().arg("push").assert().success();
+    env.cmd().arg("pull").assert().success();
+    assert!(local.is_symlink());
+    assert_eq!(fs::read_to_string(&shade_file).unwrap(), "v2");
+
+    env.cmd()
+        .args(["add", "--symlink", "notes.local"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already a symlink"));
+
+    env.cmd()
+        .args(["mv", "notes.local", "notes2.local"])
+        .assert()
+        .success();
+    let moved = env.project.join("notes2.local");
+    assert_eq!(
+        fs::read_link(&moved).unwrap(),
+        env.shade_dir().join("notes2.local")
+    );
+    env.cmd().arg("push").assert().success();
+
+    env.cmd().args(["uninit", "--yes"]).assert().success();
+    assert!(!moved.is_symlink());
+    assert_eq!(fs::read_to_string(&moved).unwrap(), "v2");
+}
+
+#[test]
+fn test_uninit_removes_project_everywhere() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    fs::write(env.project.join(".git/info/exclude"), "# mine\n*.log\n").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Needs confirmation without a terminal
+    env.cmd()
+        .arg("uninit")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs confirmation"));
+
+    env.cmd().args(["uninit", "--yes"]).assert().success();
+
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    assert!(!config.contains("myapp"));
+    assert!(!env
+        .home
+        .path()
+        .join(".local/git-shade/metadata/myapp")
+        .exists());
+    assert!(!env.shade_dir().exists());
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert_eq!(exclude, "# mine\n*.log\n");
+    assert!(env.project.join("config.local").exists());
+    let log = git_stdout(&env.projects(), &["log", "-1", "--format=%s"]);
+    assert_eq!(log, "[myapp] Remove project");
+}
+
+#[test]
+fn test_uninit_keep_flags() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    env.cmd()
+        .args(["uninit", "--yes", "--keep-exclude", "--keep-shade"])
+        .assert()
+        .success();
+
+    assert!(env.shade_dir().join("config.local").exists());
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.lines().any(|l| l == "config.local"));
+
+    // Can be initialized again
+    env.cmd().args(["init", "--no-pull"]).assert().success();
+}
+
+#[test]
+fn test_mv_renames_tracked_file_everywhere() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "dev settings").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["mv", "config.local", "config.dev"])
+        .assert()
+        .success();
+
+    assert!(!env.project.join("config.local").exists());
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.dev")).unwrap(),
+        "dev settings"
+    );
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.lines().any(|l| l == "config.dev"));
+    assert!(!exclude.lines().any(|l| l == "config.local"));
+    assert!(env.shade_dir().join("config.dev").exists());
+    assert!(!env.shade_dir().join("config.local").exists());
+
+    // Staged as a rename so history follows the file
+    let staged = git_stdout(&env.projects(), &["diff", "--cached", "--name-status"]);
+    assert!(
+        staged.starts_with('R'),
+        "expected a rename, got: {}",
+        staged
+    );
+
+    env.cmd()
+        .args(["mv", "config.local", "other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not tracked: config.local"));
+}
+
+#[test]
+fn test_mv_moves_this_os_variant_too() {
+    let env = ShadeEnv::new();
+    let os = std::env::consts::OS;
+    fs::write(env.project.join("paths.local"), "this os").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--per-os", "paths.local"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["mv", "paths.local", "paths.dev"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Moved in shade: myapp/{}/paths.local → myapp/{}/paths.dev",
+            os, os
+        )));
+
+    let shade = env.shade_dir().join(os);
+    assert!(!shade.join("paths.local").exists());
+    assert_eq!(
+        fs::read_to_string(shade.join("paths.dev")).unwrap(),
+        "this os"
+    );
+    assert!(!env.shade_dir().join("paths.dev").exists());
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"paths\.dev +in sync").unwrap());
+}
+
+#[test]
+fn test_history_lists_file_changes() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1\n").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    fs::write(env.project.join("config.local"), "A=2\n").unwrap();
+    env.cmd()
+        .args(["push", "-m", "Rotate A"])
+        .assert()
+        .success();
+
+    let host = hostname::get().unwrap().into_string().unwrap();
+    env.cmd()
+        .args(["history", "config.local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[myapp] Rotate A"))
+        .stdout(predicate::str::contains(format!(
+            "[myapp] Update from {}",
+            host
+        )))
+        // A custom message still records the machine, in a trailer
+        .stdout(predicate::str::contains("unknown host").not())
+        .stdout(predicate::str::contains("2 commit(s)"))
+        .stdout(predicate::str::contains("+A=2").not());
+
+    env.cmd()
+        .args(["history", "--patch", "config.local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-A=1"))
+        .stdout(predicate::str::contains("+A=2"));
+
+    env.cmd()
+        .args(["history", "other.local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No shade history for other.local"));
+}
+
+#[test]
+fn test_machine_name_labels_pushes_in_history_and_status() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1\n").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd()
+        .args(["config", "set", "machine_name", "work-vm"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+
+    fs::write(env.project.join("config.local"), "A=2\n").unwrap();
+    env.cmd()
+        .args(["push", "--machine", "ci-box", "-m", "Rotate A"])
+        .assert()
+        .success();
+
+    let log = git_stdout(&env.projects(), &["log", "--format=%B"]);
+    assert!(log.contains("[myapp] Update from work-vm"));
+    assert!(log.contains("Shade-Machine: work-vm"));
+    assert!(log.contains("Shade-Machine: ci-box"));
+
+    let history = env
+        .cmd()
+        .args(["history", "config.local"])
+        .output()
+        .unwrap();
+    let history = String::from_utf8(history.stdout).unwrap();
+    let ci_box = history.find("ci-box").unwrap();
+    let work_vm = history.find("work-vm").unwrap();
+    assert!(ci_box < work_vm, "newest first:\n{}", history);
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Machine: work-vm"));
+}
+
+#[test]
+fn test_equivalent_exclude_lines_count_once_and_verify_fix_collapses_them() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    // Other spellings of the same path, added by hand over time
+    let exclude = env.project.join(".git/info/exclude");
+    let contents = fs::read_to_string(&exclude).unwrap();
+    fs::write(
+        &exclude,
+        contents.replace(
+            "config.local\n",
+            "config.local\n/config.local\n./config.local\n",
+        ),
+    )
+    .unwrap();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pushed 1 file(s)"));
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.local").count(1));
+
+    env.cmd()
+        .arg("verify")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Exclude lines to normalize"))
+        .stdout(predicate::str::contains("- /config.local"))
+        .stdout(predicate::str::contains("- ./config.local"))
+        .stderr(predicate::str::contains("Verify found 2 problem(s)"));
+
+    env.cmd()
+        .args(["verify", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Normalized 2 exclude line(s)"));
+    let contents = fs::read_to_string(&exclude).unwrap();
+    assert_eq!(contents.matches("config.local").count(), 1, "{}", contents);
+    env.cmd().arg("verify").assert().success();
+}
+
+#[test]
+fn test_verify_reports_and_fixes_drift() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "a").unwrap();
+    fs::write(env.project.join("notes.local"), "b").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", "notes.local"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    env.cmd().arg("verify").assert().success();
+
+    // Exclude entry lost by hand, a shade file whose local copy is gone,
+    // and a tracked file that was never pushed
+    let exclude = env.project.join(".git/info/exclude");
+    let contents = fs::read_to_string(&exclude).unwrap();
+    fs::write(&exclude, contents.replace("notes.local\n", "")).unwrap();
+    fs::write(env.shade_dir().join("old.key"), "stale").unwrap();
+    fs::write(env.project.join("new.local"), "c").unwrap();
+    env.cmd().args(["add", "new.local"]).assert().success();
+    fs::remove_file(env.shade_dir().join("new.local")).unwrap();
+
+    env.cmd()
+        .arg("verify")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "In shade but not excluded (exists locally)",
+        ))
+        .stdout(predicate::str::contains("notes.local"))
+        .stdout(predicate::str::contains("old.key"))
+        .stdout(predicate::str::contains("Local files never pushed"))
+        .stderr(predicate::str::contains("Verify found 4 problem(s)"));
+
+    // Deleting needs confirmation, and nothing is fixed without it
+    env.cmd()
+        .args(["verify", "--fix"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no terminal to ask on"));
+    assert!(!fs::read_to_string(&exclude)
+        .unwrap()
+        .contains("notes.local"));
+
+    env.cmd()
+        .args(["verify", "--fix", "--yes"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&exclude)
+        .unwrap()
+        .contains("notes.local"));
+    assert!(!env.shade_dir().join("old.key").exists());
+    assert!(env.shade_dir().join("new.local").exists());
+    env.cmd().arg("verify").assert().success();
+}
+
+#[test]
+fn test_clone_depth_makes_shallow_shade_repo_that_can_push() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    env.cmd().arg("push").assert().success();
+
+    // A new machine: no shade repo yet
+    fs::remove_dir_all(env.projects()).unwrap();
+    let url = format!("file://{}", env.home.path().join("remote.git").display());
+
+    env.cmd()
+        .args(["clone", &url, "--depth", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cloned shade repo"))
+        .stdout(predicate::str::contains("Shallow clone"));
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-parse", "--is-shallow-repository"]),
+        "true"
+    );
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "v2"
+    );
+
+    fs::write(env.project.join("config.local"), "v3").unwrap();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["clone", &url])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
+
+#[test]
+fn test_list_remote_shows_projects_not_initialized_here() {
+    let env = ShadeEnv::new();
+    env.cmd().arg("init").assert().success();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::create_dir_all(env.projects().join("other-app/config")).unwrap();
+    fs::write(env.projects().join("other-app/config/db.local"), "db").unwrap();
+    fs::write(env.projects().join("other-app/.env"), "env").unwrap();
+    fs::write(env.projects().join("README.md"), "not a project").unwrap();
+
+    env.cmd()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("myapp"))
+        .stdout(predicate::str::contains("other-app").not());
+
+    env.cmd()
+        .args(["list", "--remote"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("other-app (2 file(s))"))
+        .stdout(predicate::str::contains("git-shade init --name other-app"))
+        .stdout(predicate::str::contains("myapp").not())
+        .stdout(predicate::str::contains("README").not());
+}
+
+#[test]
+fn test_gc_compacts_shade_repo() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1\n").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+    fs::write(env.project.join("config.local"), "A=22\n").unwrap();
+    env.cmd().args(["push", "--amend"]).assert().success();
+
+    env.cmd()
+        .args(["gc", "--expire", "now"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Expired reflog entries older than now",
+        ))
+        .stdout(predicate::str::contains("Repacked objects"))
+        .stdout(predicate::str::is_match(r"Shade repo: .+ → .+ \(.+ freed\)").unwrap());
+
+    let objects = git_stdout(&env.projects(), &["count-objects", "-v"]);
+    assert!(objects.contains("count: 0"), "{}", objects);
+    assert!(objects.contains("packs: 1"), "{}", objects);
+}
+
+#[test]
+fn test_env_reports_version_paths_and_remote_outside_a_repo() {
+    let env = ShadeEnv::with_remote();
+
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .arg("env")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "git-shade {}",
+            env!("CARGO_PKG_VERSION")
+        )))
+        .stdout(predicate::str::contains("git version"))
+        .stdout(predicate::str::contains(
+            env.config_file().display().to_string(),
+        ))
+        .stdout(predicate::str::contains("Found"))
+        .stdout(predicate::str::is_match(r"Remote: origin .*remote\.git").unwrap());
+}
+
+#[test]
+fn test_doctor_reports_healthy_setup() {
+    let env = ShadeEnv::new();
+    env.cmd().arg("init").assert().success();
+
+    env.cmd()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Config parses"))
+        .stdout(predicate::str::contains("No remote configured"));
+}
+
+#[test]
+fn test_doctor_warns_when_shade_repo_is_inside_a_project() {
+    let env = ShadeEnv::new();
+    // A project at HOME (e.g. a dotfiles repo) contains ~/.local/git-shade
+    common::git(env.home.path(), &["init"]);
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .args(["init", "--name", "dotfiles"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Shade repo is inside the project (.local/git-shade/projects/)",
+        ))
+        .stdout(predicate::str::contains(
+            "echo '/.local/git-shade/projects/' >>",
+        ));
+
+    let exclude = env.home.path().join(".git/info/exclude");
+    let mut contents = fs::read_to_string(&exclude).unwrap();
+    contents.push_str("/.local/git-shade/projects/\n");
+    fs::write(&exclude, contents).unwrap();
+
+    env.cmd()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Shade repo is inside the project (.local/git-shade/projects/), ignored",
+        ));
+}
+
+#[test]
+fn test_doctor_fails_when_local_path_missing() {
+    let env = ShadeEnv::new();
+    env.cmd().arg("init").assert().success();
+    fs::remove_dir_all(&env.project).unwrap();
+
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Local path missing"))
+        .stderr(predicate::str::contains("critical problem"));
+}
+
+#[test]
+fn test_tampered_tracker_is_ignored_until_repaired() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // A hand edit pushes last_pull far into the future
+    let tracker = env
+        .home
+        .path()
+        .join(".local/git-shade/metadata/myapp/.shade-sync");
+    let contents = fs::read_to_string(&tracker).unwrap();
+    fs::write(
+        &tracker,
+        format!("last_pull = \"2999-01-01T00:00:00Z\"\n{}", contents),
+    )
+    .unwrap();
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Ignoring sync tracker"))
+        .stdout(predicate::str::contains("Last pull: never"));
+
+    env.cmd()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sync tracker is invalid"));
+
+    env.cmd()
+        .args(["doctor", "--repair"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sync tracker reset"));
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Ignoring sync tracker").not());
+}
+
+#[test]
+fn test_pull_reports_local_only_files() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::write(env.project.join("notes.local"), "only here").unwrap();
+    fs::write(env.project.join(".git/info/exclude"), "notes.local\n").unwrap();
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s) exist only locally"))
+        .stdout(predicate::str::contains("notes.local"));
+}
+
+#[test]
+fn test_pull_never_pulled_both_exist_is_conflict() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    fs::write(env.shade_dir().join("config.local"), "remote version").unwrap();
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("never pulled on this machine"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "local"
+    );
+}
+
+#[test]
+fn test_exit_codes_per_failure_class() {
+    let env = ShadeEnv::with_remote();
+
+    env.cmd()
+        .arg("status")
+        .current_dir(env.home.path())
+        .assert()
+        .code(2);
+    env.cmd().arg("status").assert().code(3);
+
+    env.cmd().arg("init").assert().success();
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    fs::write(env.shade_dir().join("config.local"), "remote version").unwrap();
+    env.cmd().arg("pull").assert().code(5);
+}
+
+#[test]
+fn test_pull_strategy_resolves_conflicts() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(env.shade_dir().join("config.local"), "remote version").unwrap();
+
+    env.cmd()
+        .args(["pull", "--strategy", "local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "config.local (conflict, local wins - kept)",
+        ));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "local"
+    );
+
+    // Both sides change again after that pull; the shade copy is newer
+    fs::write(env.project.join("config.local"), "local again").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(env.shade_dir().join("config.local"), "remote again").unwrap();
+
+    env.cmd()
+        .args(["pull", "--strategy", "newest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "config.local (conflict, remote wins)",
+        ));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "remote again"
+    );
+}
+
+#[test]
+fn test_pull_merge_markers_writes_both_versions_into_local_file() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::write(
+        env.project.join("app.conf"),
+        "host = a\nport = 3000\nuser = me\n",
+    )
+    .unwrap();
+    fs::write(env.project.join("logo.bin"), b"\x00local").unwrap();
+    env.cmd()
+        .args(["add", "app.conf", "logo.bin"])
+        .assert()
+        .success();
+    fs::write(
+        env.shade_dir().join("app.conf"),
+        "host = a\nport = 8080\nuser = you\n",
+    )
+    .unwrap();
+    fs::write(env.shade_dir().join("logo.bin"), b"\x00remote").unwrap();
+
+    // A binary conflict can't take markers: nothing is touched
+    env.cmd()
+        .args(["pull", "--merge-markers"])
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains("logo.bin is binary"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("app.conf")).unwrap(),
+        "host = a\nport = 3000\nuser = me\n"
+    );
+
+    // Marked files are still unresolved: no in-sync summary, conflict exit code
+    env.cmd()
+        .args(["pull", "--merge-markers", "--except", "*.bin"])
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains(
+            "app.conf (conflict, merge markers written)",
+        ))
+        .stdout(predicate::str::contains(
+            "1 file(s) with merge markers to resolve",
+        ))
+        .stdout(predicate::str::contains("No conflicts detected").not())
+        .stdout(predicate::str::contains("All files are in sync").not());
+    assert_eq!(
+        fs::read_to_string(env.project.join("app.conf")).unwrap(),
+        "host = a\n\
+         <<<<<<< local\n\
+         port = 3000\n\
+         user = me\n\
+         =======\n\
+         port = 8080\n\
+         user = you\n\
+         >>>>>>> shade\n"
+    );
+
+    // Push leaves the markers out of the shade repo until they're resolved
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 file(s) with unresolved merge markers skipped",
+        ));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("app.conf")).unwrap(),
+        "host = a\nport = 8080\nuser = you\n"
+    );
+
+    fs::write(
+        env.project.join("app.conf"),
+        "host = a\nport = 8080\nuser = me\n",
+    )
+    .unwrap();
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("app.conf")).unwrap(),
+        "host = a\nport = 8080\nuser = me\n"
+    );
+}
+
+#[test]
+fn test_pull_output_writes_to_other_dir() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    fs::create_dir_all(env.shade_dir().join("config")).unwrap();
+    fs::write(env.shade_dir().join("config/db.conf"), "remote").unwrap();
+    let preview = env.home.path().join("preview");
+
+    env.cmd()
+        .args(["pull", "--output", preview.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pulled 1 file(s)"));
+
+    assert_eq!(
+        fs::read_to_string(preview.join("config/db.conf")).unwrap(),
+        "remote"
+    );
+    assert!(!env.project.join("config/db.conf").exists());
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("db.conf"));
+
+    // The project was left alone, so a normal pull still brings the file in
+    env.cmd().arg("pull").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("config/db.conf")).unwrap(),
+        "remote"
+    );
+}
+
+#[test]
+fn test_pull_no_fetch_syncs_without_reaching_remote() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    // As if the shade repo had been pulled by hand, and the remote is now gone
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+    let missing = env.home.path().join("missing.git");
+    common::git(
+        &env.projects(),
+        &["remote", "set-url", "origin", missing.to_str().unwrap()],
+    );
+
+    env.cmd().arg("pull").assert().failure();
+    assert!(!env.project.join("config.local").exists());
+
+    env.cmd()
+        .args(["pull", "--no-fetch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped git pull"))
+        .stdout(predicate::str::contains("Git pull successful").not())
+        .stdout(predicate::str::contains("Pulled 1 file(s)"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "from shade"
+    );
+
+    env.cmd()
+        .args(["pull", "--offline", "--push-after"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_pull_only_and_except_filter_files() {
+    let env = ShadeEnv::with_remote();
+    env.cmd().arg("init").assert().success();
+
+    // A conflict outside the filter must not block the pull
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    fs::write(env.shade_dir().join("config.local"), "remote version").unwrap();
+
+    fs::write(env.shade_dir().join(".env.local"), "A=1").unwrap();
+    fs::write(env.shade_dir().join(".env.test"), "B=2").unwrap();
+    fs::write(env.shade_dir().join("db.conf"), "remote").unwrap();
+
+    env.cmd()
+        .args(["pull", "--only", ".env.*", "--except", "*.test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pulled 1 file(s)"));
+
+    assert!(env.project.join(".env.local").exists());
+    assert!(!env.project.join(".env.test").exists());
+    assert!(!env.project.join("db.conf").exists());
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "local"
+    );
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.contains(".env.local"));
+    assert!(!exclude.contains(".env.test"));
+    assert!(!exclude.contains("db.conf"));
+}
+
+#[test]
+fn test_push_without_tty_prints_summary() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/a.key"), "a").unwrap();
+    fs::write(env.project.join("secrets/b.key"), "b").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "secrets"]).assert().success();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 file(s) copied"))
+        .stdout(predicate::str::contains("✓ secrets/").not());
+}
+
+#[test]
+fn test_push_only_changed_skips_files_in_sync() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/a.key"), "a").unwrap();
+    fs::write(env.project.join("secrets/b.key"), "b").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "secrets"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["push", "--only-changed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 file(s) copied"))
+        .stdout(predicate::str::contains("2 unchanged file(s) skipped"))
+        .stdout(predicate::str::contains("No changes to commit"));
+
+    fs::write(env.project.join("secrets/b.key"), "b changed").unwrap();
+    fs::write(env.project.join("secrets/c.key"), "c").unwrap();
+    env.cmd()
+        .args(["push", "--only-changed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 file(s) copied"))
+        .stdout(predicate::str::contains("1 unchanged file(s) skipped"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("secrets/b.key")).unwrap(),
+        "b changed"
+    );
+    assert!(env.shade_dir().join("secrets/c.key").exists());
+}
+
+#[test]
+fn test_pushed_file_is_in_sync_with_its_shade_copy() {
+    let env = ShadeEnv::new();
+    let local = env.project.join("config.local");
+    fs::write(&local, "settings").unwrap();
+
+    // Edited a while ago, so a fresh copy time would be far off
+    let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+    filetime::set_file_mtime(&local, an_hour_ago).unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    let local_meta = FileMetadata::from_path(&local).unwrap();
+    let shade_meta = FileMetadata::from_path(&env.shade_dir().join("config.local")).unwrap();
+    assert_eq!(local_meta.modified, shade_meta.modified);
+    assert_eq!(
+        detect_sync_state(
+            Some(&local_meta),
+            Some(&shade_meta),
+            None,
+            &SyncPolicy::default()
+        ),
+        SyncState::InSync
+    );
+}
+
+#[test]
+fn test_push_and_pull_print_totals() {
+    let env = ShadeEnv::with_remote();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/a.key"), "a").unwrap();
+    fs::write(env.project.join("secrets/b.key"), "bb").unwrap();
+    fs::write(env.project.join("config.local"), "c").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "secrets", "config.local"])
+        .assert()
+        .success();
+    fs::remove_file(env.project.join("config.local")).unwrap();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Pushed 2 file(s) (3 B) in project myapp, 1 missing (skipped)",
+        ));
+
+    // `add` already copied config.local to shade, so pull brings both back
+    fs::remove_file(env.project.join("secrets/b.key")).unwrap();
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Pulled 2 file(s) (3 B) into project myapp",
+        ));
+}
+
+#[test]
+fn test_verbose_logs_git_commands() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    env.cmd()
+        .args(["push", "-v"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ config.local"))
+        .stderr(predicate::str::contains("git add myapp/"))
+        .stderr(predicate::str::contains("git commit -m [myapp]"));
+
+    // Global flag works before the subcommand too
+    env.cmd()
+        .args(["--verbose", "status"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("git status --porcelain"));
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("git status").not());
+}
+
+#[test]
+fn test_rust_log_traces_sync_decisions() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    env.cmd()
+        .arg("status")
+        .env("RUST_LOG", "git_shade=debug")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.local"))
+        .stderr(predicate::str::contains("file=config.local state=InSync"))
+        .stderr(predicate::str::contains(
+            r#"args=["status", "--porcelain"]"#,
+        ));
+
+    // Nothing is logged without RUST_LOG
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_push_amend_folds_into_last_project_commit() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    let count_before = git_stdout(&env.projects(), &["rev-list", "--count", "HEAD"]);
+
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    env.cmd()
+        .args(["push", "--amend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Amended: [myapp]"))
+        .stdout(predicate::str::contains("already pushed"));
+
+    let count_after = git_stdout(&env.projects(), &["rev-list", "--count", "HEAD"]);
+    assert_eq!(count_before, count_after);
+
+    // Force-pushed: the remote branch points at the amended commit
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-parse", "HEAD"]),
+        git_stdout(&env.projects(), &["rev-parse", "@{u}"])
+    );
+}
+
+#[test]
+fn test_push_amend_works_with_custom_commit_template() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd()
+        .args([
+            "config",
+            "set",
+            "commit_template",
+            "Sync {project} from {hostname}",
+        ])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        git_stdout(
+            &env.projects(),
+            &[
+                "log",
+                "-1",
+                "--format=%(trailers:key=Shade-Project,valueonly)"
+            ]
+        ),
+        "myapp"
+    );
+
+    let count_before = git_stdout(&env.projects(), &["rev-list", "--count", "HEAD"]);
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    env.cmd()
+        .args(["push", "--amend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Amended: Sync myapp from"));
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-list", "--count", "HEAD"]),
+        count_before
+    );
+}
+
+#[test]
+fn test_push_amend_skips_other_projects_commit() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    // Last commit is the "initial" commit, not one for myapp
+    env.cmd()
+        .args(["push", "--amend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Last commit is not for myapp"))
+        .stdout(predicate::str::contains("Committed: [myapp]"));
+
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-list", "--count", "HEAD"]),
+        "2"
+    );
+}
+
+#[test]
+fn test_rollback_undoes_last_push_for_this_project_only() {
+    let env = ShadeEnv::with_remote();
+    let local = env.project.join("config.local");
+    fs::write(&local, "good").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    fs::write(&local, "bad").unwrap();
+    fs::write(env.project.join("new.local"), "new").unwrap();
+    env.cmd().args(["add", "new.local"]).assert().success();
+    env.cmd()
+        .args(["push", "-m", "Bad push"])
+        .assert()
+        .success();
+
+    // Another project's commit lands after it
+    fs::create_dir_all(env.projects().join("other")).unwrap();
+    fs::write(env.projects().join("other/app.env"), "other").unwrap();
+    common::git(&env.projects(), &["add", "other"]);
+    common::git(&env.projects(), &["commit", "-q", "-m", "[other] Update"]);
+
+    // Without a terminal it needs --yes
+    env.cmd()
+        .arg("rollback")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Rolling back"))
+        .stdout(predicate::str::contains("config.local (reverted)"))
+        .stdout(predicate::str::contains("new.local (removed)"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "bad"
+    );
+
+    env.cmd()
+        .args(["rollback", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Bad push)"))
+        .stdout(predicate::str::contains("Pushed"));
+
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "good"
+    );
+    assert!(!env.shade_dir().join("new.local").exists());
+    assert!(env.projects().join("other/app.env").exists());
+    assert_eq!(git_stdout(&env.projects(), &["status", "--porcelain"]), "");
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-parse", "HEAD"]),
+        git_stdout(&env.projects(), &["rev-parse", "@{u}"])
+    );
+
+    env.cmd()
+        .args(["pull", "--strategy", "remote"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&local).unwrap(), "good");
+}
+
+#[test]
+fn test_push_uses_commit_template() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    fs::write(env.project.join("api.key"), "k1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", "api.key"])
+        .assert()
+        .success();
+
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    fs::write(
+        env.config_file(),
+        config.replacen(
+            "version = \"1.0\"",
+            "version = \"1.0\"\ncommit_template = \"sync({project}): {count} file(s)\"",
+            1,
+        ),
+    )
+    .unwrap();
+
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        git_stdout(&env.projects(), &["log", "-1", "--format=%s"]),
+        "sync(myapp): 2 file(s)"
+    );
+
+    // The environment variable wins over config
+    fs::write(env.project.join("api.key"), "k2").unwrap();
+    env.cmd()
+        .arg("push")
+        .env(
+            "GIT_SHADE_COMMIT_TEMPLATE",
+            "[{project}] ci: {count} changed",
+        )
+        .assert()
+        .success();
+    assert_eq!(
+        git_stdout(&env.projects(), &["log", "-1", "--format=%s"]),
+        "[myapp] ci: 1 changed"
+    );
+}
+
+#[test]
+fn test_init_dry_run_prints_plan_without_writing() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from another machine").unwrap();
+    fs::write(env.project.join(".env.local"), "KEY=1").unwrap();
+
+    env.cmd()
+        .args(["init", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Create metadata dir:"))
+        .stdout(predicate::str::contains("[[projects]]"))
+        .stdout(predicate::str::contains("name = \"myapp\""))
+        .stdout(predicate::str::contains(
+            "Would offer to pull 1 file(s) from shade:",
+        ))
+        .stdout(predicate::str::contains("↓ config.local"))
+        .stdout(predicate::str::contains("  - .env.local"))
+        .stdout(predicate::str::contains(
+            "Dry-run completed (no changes made)",
+        ));
+
+    assert!(!env.config_file().exists());
+    assert!(!env
+        .home
+        .path()
+        .join(".local/git-shade/metadata/myapp")
+        .exists());
+    assert!(!env.project.join("config.local").exists());
+
+    // Nothing was registered, so a real init still works
+    env.cmd().args(["init", "--no-pull"]).assert().success();
+}
+
+#[test]
+fn test_init_creates_missing_shade_repo() {
+    let env = ShadeEnv::new();
+    fs::remove_dir_all(env.projects()).unwrap();
+
+    // Without a terminal or flag it still fails the old way
+    env.cmd()
+        .arg("init")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("Shade repository not found"));
+
+    env.cmd()
+        .args(["init", "--create-shade"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created shade repo"));
+    assert!(env.projects().join(".git").exists());
+    assert!(env.config_file().exists());
+}
+
+#[test]
+fn test_init_turns_existing_shade_dir_into_repo() {
+    let env = ShadeEnv::new();
+    fs::remove_dir_all(env.projects().join(".git")).unwrap();
+    fs::write(env.projects().join("notes.txt"), "mine").unwrap();
+
+    env.cmd()
+        .arg("init")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains(
+            "Shade directory is not a git repository",
+        ))
+        .stderr(predicate::str::contains("git-shade init --create-shade"));
+    env.cmd()
+        .arg("doctor")
+        .assert()
+        .stdout(predicate::str::contains("isn't a git repository"));
+
+    env.cmd()
+        .args(["init", "--create-shade"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Initialized shade repo"));
+    assert!(env.projects().join(".git").exists());
+    assert_eq!(
+        fs::read_to_string(env.projects().join("notes.txt")).unwrap(),
+        "mine"
+    );
+}
+
+#[test]
+fn test_init_remote_checks_out_existing_shade_repo() {
+    let env = ShadeEnv::with_remote();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+    common::git(&env.projects(), &["add", "-A"]);
+    common::git(&env.projects(), &["commit", "-m", "shade files"]);
+    common::git(&env.projects(), &["push"]);
+
+    // A new machine: no shade repo, only the remote
+    fs::remove_dir_all(env.projects()).unwrap();
+    let remote = env.home.path().join("remote.git");
+
+    env.cmd()
+        .args(["init", "--remote", remote.to_str().unwrap(), "--yes"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "from shade"
+    );
+
+    fs::write(env.project.join("config.local"), "changed").unwrap();
+    env.cmd().arg("push").assert().success();
+    assert!(git_stdout(&remote, &["log", "--oneline"]).contains("[myapp]"));
+}
+
+#[test]
+fn test_init_without_tty_does_not_prompt() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+
+    env.cmd()
+        .arg("init")
+        .write_stdin("")
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No terminal detected"));
+    assert!(!env.project.join("config.local").exists());
+}
+
+#[test]
+fn test_init_yes_pulls_existing_files() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+
+    env.cmd()
+        .args(["init", "--yes"])
+        .write_stdin("")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "from shade"
+    );
+}
+
+#[test]
+fn test_init_import_from_seeds_shade_from_a_folder() {
+    let env = ShadeEnv::new();
+    let vault = env.home.path().join("vault");
+    fs::create_dir_all(vault.join("secrets")).unwrap();
+    fs::write(vault.join(".env.local"), "TOKEN=1").unwrap();
+    fs::write(vault.join("secrets/api.key"), "key").unwrap();
+    fs::write(vault.join("secrets/.DS_Store"), "finder").unwrap();
+
+    env.cmd()
+        .args(["init", "--yes", "--import-from", vault.to_str().unwrap()])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 file(s)"));
+
+    assert!(env.shade_dir().join("secrets/api.key").exists());
+    assert!(!env.shade_dir().join("secrets/.DS_Store").exists());
+    assert_eq!(
+        fs::read_to_string(env.project.join(".env.local")).unwrap(),
+        "TOKEN=1"
+    );
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert_eq!(exclude.matches("secrets/api.key").count(), 1);
+    assert!(exclude.contains(".env.local"));
+
+    // The import only lands in the shade working tree; push commits it
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Committed:"));
+}
+
+#[test]
+fn test_init_no_pull_skips_pulling() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.shade_dir()).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+
+    env.cmd().args(["init", "--no-pull"]).assert().success();
+    assert!(!env.project.join("config.local").exists());
+}
+
+#[test]
+fn test_init_suggests_secret_files_without_tty() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join(".env.local"), "TOKEN=1").unwrap();
+    fs::write(env.project.join("deploy.key"), "committed").unwrap();
+    common::git(&env.project, &["add", "deploy.key"]);
+
+    env.cmd()
+        .arg("init")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("usually belong in shade"))
+        .stdout(predicate::str::contains("  - .env.local"))
+        .stdout(predicate::str::contains("deploy.key").not());
+
+    // Nothing is added without a prompt
+    assert!(!env.shade_dir().join(".env.local").exists());
+}
+
+#[test]
+fn test_init_uses_default_patterns_from_config() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join(".env.local"), "TOKEN=1").unwrap();
+    fs::write(env.project.join("db.secret"), "pw").unwrap();
+    fs::write(
+        env.config_file(),
+        "version = \"1.0\"\ndefault_patterns = [\"*.secret\"]\n",
+    )
+    .unwrap();
+
+    env.cmd()
+        .arg("init")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  - db.secret"))
+        .stdout(predicate::str::contains(".env.local").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_reads_project_config_file() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/api.key"), "k").unwrap();
+    fs::write(env.project.join("config.local"), "c").unwrap();
+    fs::write(
+        env.project.join(".git-shade.toml"),
+        "name = \"billing\"\nfiles = [\"config.local\", \"secrets/\", \".env.prod\"]\n",
+    )
+    .unwrap();
+
+    env.cmd()
+        .arg("init")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("project: billing"))
+        .stdout(predicate::str::contains(".env.prod (not found, skipped)"));
+
+    let shade_dir = env.projects().join("billing");
+    assert!(shade_dir.join("config.local").exists());
+    assert!(shade_dir.join("secrets/api.key").exists());
+
+    // Other commands pick up the name too
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Committed: [billing]"));
+}
+
+#[test]
+fn test_executable_bit_survives_push_and_pull() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = ShadeEnv::with_remote();
+    let script = env.project.join("scripts/deploy.sh");
+    fs::create_dir_all(script.parent().unwrap()).unwrap();
+    fs::write(&script, "#!/bin/sh\necho deploy\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "scripts"]).assert().success();
+    env.cmd().arg("push").assert().success();
+    assert!(env.shade_dir().join(".shade-modes.toml").exists());
+
+    // Simulate a checkout that dropped the executable bit
+    let shade_script = env.shade_dir().join("scripts/deploy.sh");
+    fs::set_permissions(&shade_script, fs::Permissions::from_mode(0o644)).unwrap();
+    fs::remove_file(&script).unwrap();
+
+    env.cmd().arg("pull").assert().success();
+
+    let mode = fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode, 0o755);
+    assert!(!env.project.join(".shade-modes.toml").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_add_stage_stages_copies_in_shade_repo() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1").unwrap();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/api.key"), "key").unwrap();
+    fs::write(env.project.join("notes.local"), "notes").unwrap();
+    env.cmd().arg("init").assert().success();
+
+    env.cmd()
+        .args(["add", "config.local", "secrets", "--stage"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Staged 2 file(s) in the shade repo",
+        ));
+    env.cmd().args(["add", "notes.local"]).assert().success();
+
+    let staged = git_stdout(&env.projects(), &["diff", "--cached", "--name-only"]);
+    assert_eq!(staged, "myapp/config.local\nmyapp/secrets/api.key");
+    let untracked = git_stdout(
+        &env.projects(),
+        &["ls-files", "--others", "--exclude-standard"],
+    );
+    assert_eq!(untracked, "myapp/notes.local");
+
+    // Staged, not committed: push still makes the commit
+    assert!(!git_stdout(&env.projects(), &["log", "--oneline"]).contains("myapp"));
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        git_stdout(&env.projects(), &["diff", "--cached", "--name-only"]),
+        ""
+    );
+}
+
+#[test]
+fn test_push_leaves_other_projects_staged_files_alone() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "A=1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    let other = env.home.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    common::git(&other, &["init"]);
+    fs::write(other.join("other.local"), "B=1").unwrap();
+    let other_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.current_dir(&other);
+        cmd
+    };
+    other_cmd().arg("init").assert().success();
+    other_cmd()
+        .args(["add", "other.local", "--stage"])
+        .assert()
+        .success();
+
+    // myapp's push and amend leave other's staged file out of its commit
+    fs::write(env.project.join("config.local"), "A=2").unwrap();
+    env.cmd().arg("push").assert().success();
+    fs::write(env.project.join("config.local"), "A=3").unwrap();
+    env.cmd().args(["push", "--amend"]).assert().success();
+    let files = git_stdout(
+        &env.projects(),
+        &["show", "--name-only", "--format=", "HEAD"],
+    );
+    assert_eq!(files, "myapp/config.local");
+    assert_eq!(
+        git_stdout(&env.projects(), &["diff", "--cached", "--name-only"]),
+        "other/other.local"
+    );
+
+    other_cmd().arg("push").assert().success();
+    let files = git_stdout(
+        &env.projects(),
+        &["show", "--name-only", "--format=", "HEAD"],
+    );
+    assert!(files.contains("other/other.local"));
+    assert!(!files.contains("myapp/"));
+}
+
+#[test]
+fn test_add_chmod_applies_mode_on_push_and_pull() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode_of = |path: &std::path::Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    let env = ShadeEnv::with_remote();
+    let key = env.project.join("secrets/api.key");
+    fs::create_dir_all(key.parent().unwrap()).unwrap();
+    fs::write(&key, "key").unwrap();
+    fs::set_permissions(&key, fs::Permissions::from_mode(0o644)).unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "--chmod", "600", "secrets/api.key"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mode 0600"));
+    let shade_key = env.shade_dir().join("secrets/api.key");
+    assert_eq!(mode_of(&key), 0o600);
+    assert_eq!(mode_of(&shade_key), 0o600);
+
+    // A later edit loosens the local mode; the shade copy and the recorded mode stay 600
+    fs::write(&key, "new key").unwrap();
+    fs::set_permissions(&key, fs::Permissions::from_mode(0o644)).unwrap();
+    env.cmd().arg("push").assert().success();
+    assert_eq!(mode_of(&shade_key), 0o600);
+    let modes = fs::read_to_string(env.shade_dir().join(".shade-modes.toml")).unwrap();
+    assert!(modes.contains(&format!("\"secrets/api.key\" = {}", 0o600)));
+
+    // A checkout under a loose umask still lands restrictive
+    fs::set_permissions(&shade_key, fs::Permissions::from_mode(0o644)).unwrap();
+    fs::remove_file(&key).unwrap();
+    env.cmd().arg("pull").assert().success();
+    assert_eq!(mode_of(&key), 0o600);
+
+    env.cmd()
+        .args(["add", "--chmod", "999", "secrets/api.key"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't an octal mode"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_chmod_leaves_hardlinked_copies_of_other_projects_alone() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    let meta = |path: &std::path::Path| fs::metadata(path).unwrap();
+
+    let env = ShadeEnv::new();
+    env.cmd()
+        .args(["config", "set", "storage", "hardlink"])
+        .assert()
+        .success();
+
+    let other = env.home.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    common::git(&other, &["init"]);
+    let other_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.current_dir(&other);
+        cmd
+    };
+
+    for dir in [&env.project, &other] {
+        let key = dir.join("secrets/api.key");
+        fs::create_dir_all(key.parent().unwrap()).unwrap();
+        fs::write(&key, "key").unwrap();
+        fs::set_permissions(&key, fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "secrets/api.key"])
+        .assert()
+        .success();
+    other_cmd().arg("init").assert().success();
+    other_cmd()
+        .args(["add", "secrets/api.key"])
+        .assert()
+        .success();
+
+    let shade_key = env.shade_dir().join("secrets/api.key");
+    let other_key = env.projects().join("other/secrets/api.key");
+    assert_eq!(meta(&shade_key).ino(), meta(&other_key).ino());
+
+    // Only myapp pins 0600; other's copy keeps its own mode through both pushes
+    env.cmd()
+        .args(["add", "--chmod", "600", "secrets/api.key"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    other_cmd().arg("push").assert().success();
+
+    assert_eq!(meta(&shade_key).permissions().mode() & 0o777, 0o600);
+    assert_eq!(meta(&other_key).permissions().mode() & 0o777, 0o644);
+    assert_ne!(meta(&shade_key).ino(), meta(&other_key).ino());
+    assert_eq!(fs::read_to_string(&other_key).unwrap(), "key");
+}
+
+#[test]
+fn test_manifest_tracks_files_after_exclude_edit() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    let manifest_path = env
+        .home
+        .path()
+        .join(".local/git-shade/metadata/myapp/manifest.toml");
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    assert!(manifest.contains("config.local"));
+
+    // Hand-edited exclude no longer lists the file
+    fs::write(env.project.join(".git/info/exclude"), "").unwrap();
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.local  "))
+        .stdout(predicate::str::contains(
+            "Tracked but missing from .git/info/exclude",
+        ));
+}
+
+#[test]
+fn test_project_flag_works_outside_project() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .args(["push", "--project", "myapp"])
+        .assert()
+        .success();
+
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .args(["status", "--project", "myapp"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Project: myapp"));
+
+    let mut cmd = env.cmd();
+    cmd.current_dir(env.home.path())
+        .args(["pull", "--project", "unknown"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Project not registered: unknown"));
+}
+
+#[test]
+fn test_name_flag_follows_init_name() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "secret").unwrap();
+    env.cmd()
+        .args(["init", "--name", "billing"])
+        .assert()
+        .success();
+
+    env.cmd().args(["add", "config.local"]).assert().code(3);
+    env.cmd()
+        .args(["add", "config.local", "--name", "billing"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["push", "--name", "billing"])
+        .assert()
+        .success();
+    assert!(env.projects().join("billing/config.local").exists());
+
+    env.cmd()
+        .args(["status", "--name", "billing"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Project: billing"));
+    env.cmd()
+        .args(["pull", "--name", "billing"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_sync_pulls_then_pushes() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    env.cmd()
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("==> Pull"))
+        .stdout(predicate::str::contains("Committed: [myapp]"))
+        .stdout(predicate::str::contains("changes pushed"));
+
+    env.cmd()
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Already up to date"));
+}
+
+#[test]
+fn test_status_fetch_reports_commits_behind_remote() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["status", "--fetch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Remote: Up to date"));
+
+    // Another machine pushes a change
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    fs::write(other.join("myapp/config.local"), "from other machine").unwrap();
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-am",
+            "other",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    // Without --fetch the remote isn't checked
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("behind remote").not());
+
+    env.cmd()
+        .args(["status", "--fetch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 commit(s) behind remote - run git-shade pull",
+        ));
+
+    // Nothing was merged into the shade working tree
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "original"
+    );
+}
+
+#[test]
+fn test_status_compact_lists_only_files_out_of_sync() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "same").unwrap();
+    fs::write(env.project.join("secrets.env"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", "secrets.env"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    fs::write(env.project.join("secrets.env"), "changed").unwrap();
+
+    env.cmd()
+        .args(["status", "--compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Conflict (1):\n  secrets.env"))
+        .stdout(predicate::str::contains("1 of 2 file(s) in sync"))
+        .stdout(predicate::str::contains("config.local").not())
+        .stdout(predicate::str::contains("Legend").not());
+
+    // The full view is still the default
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"config\.local +in sync").unwrap())
+        .stdout(predicate::str::contains("Legend"));
+}
+
+#[test]
+fn test_sync_hash_treats_identical_contents_as_in_sync() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "same").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    // Restored by a tool that doesn't keep mtimes
+    let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+    filetime::set_file_mtime(env.project.join("config.local"), an_hour_ago).unwrap();
+
+    env.cmd()
+        .args(["status", "--compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Conflict (1):\n  config.local"));
+
+    env.cmd()
+        .args(["config", "set", "sync.hash", "true"])
+        .assert()
+        .success();
+    env.cmd()
+        .args(["status", "--compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 of 1 file(s) in sync"));
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All files are in sync"));
+}
+
+#[test]
+fn test_status_aligns_file_columns() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.project.join("config/credentials")).unwrap();
+    fs::write(env.project.join(".env"), "A=1").unwrap();
+    fs::write(env.project.join("config/credentials/dev.key"), "key").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", ".env", "config/credentials/dev.key"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    fs::write(env.project.join(".env"), "A=22").unwrap();
+
+    let output = env.cmd().arg("status").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // Characters before the description on a file's line (the symbols are multi-byte)
+    let description_column = |path: &str| {
+        let line = stdout
+            .lines()
+            .find(|line| line.contains(path))
+            .expect(&stdout);
+        let (before, after) = line.split_once(path).unwrap();
+        before.chars().count() + path.chars().count() + after.len() - after.trim_start().len()
+    };
+    assert_eq!(
+        description_column(" .env "),
+        description_column(" config/credentials/dev.key ")
+    );
+    assert!(stdout.contains("config/credentials/dev.key  in sync"));
+}
+
+#[test]
+fn test_status_shows_relative_times_and_absolute_when_verbose() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join(".env"), "A=1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", ".env"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Last push: just now"))
+        .stdout(predicate::str::contains("Last pull: never"));
+
+    env.cmd()
+        .args(["status", "--verbose"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"Last push: \d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} \(just now\)",
+            )
+            .unwrap(),
+        );
+
+    env.cmd()
+        .args(["history", ".env"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(just now)"));
+}
+
+#[test]
+fn test_status_exit_code_reports_what_needs_action() {
+    let env = ShadeEnv::new();
+    let local = env.project.join("config.local");
+    fs::write(&local, "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd().args(["status", "--exit-code"]).assert().code(0);
+
+    // Deleted locally: needs a pull
+    fs::remove_file(&local).unwrap();
+    env.cmd().args(["status", "--exit-code"]).assert().code(1);
+    env.cmd().arg("status").assert().success();
+
+    // Different on both sides (and never pulled): a conflict
+    fs::write(&local, "changed").unwrap();
+    env.cmd()
+        .args(["status", "--exit-code"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("conflict"));
+}
+
+#[test]
+fn test_status_plan_says_what_push_and_pull_would_do() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "same").unwrap();
+    fs::write(env.project.join("secrets.env"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", "secrets.env"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+    fs::remove_file(env.project.join("secrets.env")).unwrap();
+
+    env.cmd()
+        .args(["status", "--plan"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "config.local (in sync)\n      push would change nothing\n      pull would change nothing",
+        ))
+        .stdout(predicate::str::contains(
+            "secrets.env (remote only)\n      push would leave the shade copy alone\n      pull would restore it from shade",
+        ))
+        .stdout(predicate::str::contains("Legend").not());
+}
+
+#[test]
+fn test_pull_backup_all_snapshots_overwritten_files() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Another machine pushes a change
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    fs::write(other.join("myapp/config.local"), "from other machine").unwrap();
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-am",
+            "other",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    env.cmd()
+        .args(["pull", "--backup-all", "--strategy", "remote"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backed up 1 file(s) to"));
+
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "from other machine"
+    );
+    let backups = env.home.path().join(".local/git-shade/backups/myapp");
+    let snapshot = fs::read_dir(&backups).unwrap().next().unwrap().unwrap();
+    assert_eq!(
+        fs::read_to_string(snapshot.path().join("config.local")).unwrap(),
+        "original"
+    );
+}
+
+#[test]
+fn test_pull_aligns_mtimes_of_identical_files() {
+    let env = ShadeEnv::with_remote();
+    let local = env.project.join("config.local");
+    fs::write(&local, "settings").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // The local file is old, and a checkout just rewrote the shade copy
+    let mtime = |path: &std::path::Path| {
+        FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+    };
+    let shade = env.shade_dir().join("config.local");
+    let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+    filetime::set_file_mtime(&local, an_hour_ago).unwrap();
+    filetime::set_file_mtime(&shade, FileTime::now()).unwrap();
+
+    env.cmd()
+        .args(["pull", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matched mtimes of 1 identical file(s)",
+        ))
+        .stdout(predicate::str::contains("(copied)").not());
+
+    assert_eq!(mtime(&shade), an_hour_ago);
+    assert_eq!(mtime(&local), an_hour_ago);
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"config\.local +in sync").unwrap());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pull_aligns_mtimes_without_touching_hardlinked_copies() {
+    let env = ShadeEnv::with_remote();
+    env.cmd()
+        .args(["config", "set", "storage", "hardlink"])
+        .assert()
+        .success();
+
+    let other = env.home.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    common::git(&other, &["init"]);
+    let other_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.current_dir(&other);
+        cmd
+    };
+    for dir in [&other, &env.project] {
+        fs::write(dir.join("config.local"), "settings").unwrap();
+    }
+    other_cmd().arg("init").assert().success();
+    other_cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    let mtime = |path: &std::path::Path| {
+        FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+    };
+    let other_shade = env.projects().join("other/config.local");
+    let other_mtime = mtime(&other_shade);
+    let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+    filetime::set_file_mtime(env.project.join("config.local"), an_hour_ago).unwrap();
+
+    env.cmd()
+        .args(["pull", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matched mtimes of 1 identical file(s)",
+        ));
+
+    assert_eq!(mtime(&env.shade_dir().join("config.local")), an_hour_ago);
+    assert_eq!(mtime(&other_shade), other_mtime);
+}
+
+#[test]
+fn test_normalize_mtime_gives_both_machines_the_commit_time() {
+    let laptop = ShadeEnv::with_remote();
+    let local = laptop.project.join("config.local");
+    fs::write(&local, "settings").unwrap();
+    let an_hour_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 3600, 0);
+    filetime::set_file_mtime(&local, an_hour_ago).unwrap();
+
+    laptop
+        .cmd()
+        .args(["config", "set", "normalize_mtime", "true"])
+        .assert()
+        .success();
+    laptop.cmd().arg("init").assert().success();
+    laptop
+        .cmd()
+        .args(["add", "config.local"])
+        .assert()
+        .success();
+    laptop
+        .cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Set mtimes to commit time"));
+
+    let commit_time: i64 = git_stdout(&laptop.projects(), &["log", "-1", "--format=%ct"])
+        .parse()
+        .unwrap();
+    let mtime = |path: &std::path::Path| {
+        FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+    };
+    assert_eq!(mtime(&local).unix_seconds(), commit_time);
+    assert_eq!(
+        mtime(&laptop.shade_dir().join("config.local")).unix_seconds(),
+        commit_time
+    );
+
+    // A second machine clones the shade repo a bit later, then pulls
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let desktop = ShadeEnv::new();
+    fs::remove_dir_all(desktop.projects()).unwrap();
+    let remote = laptop.home.path().join("remote.git");
+    common::git(
+        desktop.home.path(),
+        &[
+            "clone",
+            remote.to_str().unwrap(),
+            desktop.projects().to_str().unwrap(),
+        ],
+    );
+    desktop
+        .cmd()
+        .args(["config", "set", "normalize_mtime", "true"])
+        .assert()
+        .success();
+    desktop.cmd().arg("init").assert().success();
+    desktop.cmd().arg("pull").assert().success();
+
+    let pulled = desktop.project.join("config.local");
+    assert_eq!(fs::read_to_string(&pulled).unwrap(), "settings");
+    assert_eq!(mtime(&pulled), mtime(&local));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_normalize_mtime_leaves_hardlinked_copies_of_other_projects_alone() {
+    use std::os::unix::fs::MetadataExt;
+    let env = ShadeEnv::new();
+    for (key, value) in [("storage", "hardlink"), ("normalize_mtime", "true")] {
+        env.cmd()
+            .args(["config", "set", key, value])
+            .assert()
+            .success();
+    }
+
+    let other = env.home.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    common::git(&other, &["init"]);
+    fs::write(other.join("config.local"), "settings").unwrap();
+    let other_cmd = || {
+        let mut cmd = env.cmd();
+        cmd.current_dir(&other);
+        cmd
+    };
+    other_cmd().arg("init").assert().success();
+    other_cmd().args(["add", "config.local"]).assert().success();
+    other_cmd().arg("push").assert().success();
+
+    let other_shade = env.projects().join("other/config.local");
+    let mtime = |path: &std::path::Path| {
+        FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+    };
+    let other_mtime = mtime(&other_shade);
+
+    // myapp's identical file is linked to other's, then pushed a commit later
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(env.project.join("config.local"), "settings").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    let shade = env.shade_dir().join("config.local");
+    assert_eq!(
+        fs::metadata(&shade).unwrap().ino(),
+        fs::metadata(&other_shade).unwrap().ino()
+    );
+    env.cmd().arg("push").assert().success();
+
+    assert_eq!(mtime(&other_shade), other_mtime);
+    assert_ne!(mtime(&shade), other_mtime);
+}
+
+#[test]
+fn test_metadata_in_repo_commits_tracker_per_machine() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "local").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    for (key, value) in [("metadata_in_repo", "true"), ("machine_name", "laptop")] {
+        env.cmd()
+            .args(["config", "set", key, value])
+            .assert()
+            .success();
+    }
+
+    // The old tracker is found, but never moved without a terminal
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sync state found at"))
+        .stdout(predicate::str::contains("No terminal detected"));
+
+    fs::write(env.project.join("config.local"), "changed").unwrap();
+    env.cmd().arg("push").assert().success();
+
+    let tracker = env.shade_dir().join(".shade-sync.laptop");
+    assert!(fs::read_to_string(&tracker)
+        .unwrap()
+        .contains("last_push = "));
+    assert!(git_stdout(&env.projects(), &["ls-files"]).contains("myapp/.shade-sync.laptop"));
+    assert!(!env.project.join(".shade-sync.laptop").exists());
+
+    // A pull only touches the tracker, which never makes a commit on its own
+    env.cmd().arg("pull").assert().success();
+    let head = git_stdout(&env.projects(), &["rev-parse", "HEAD"]);
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to push"));
+    assert_eq!(git_stdout(&env.projects(), &["rev-parse", "HEAD"]), head);
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sync state found at").not())
+        .stdout(predicate::str::contains("Last push: never").not());
+
+    // The same machine setting up again from a clone keeps its sync state
+    let again = ShadeEnv::new();
+    fs::remove_dir_all(again.projects()).unwrap();
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        again.home.path(),
+        &[
+            "clone",
+            remote.to_str().unwrap(),
+            again.projects().to_str().unwrap(),
+        ],
+    );
+    for (key, value) in [("metadata_in_repo", "true"), ("machine_name", "laptop")] {
+        again
+            .cmd()
+            .args(["config", "set", key, value])
+            .assert()
+            .success();
+    }
+    again
+        .cmd()
+        .arg("init")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Keeping sync state found in the shade repo",
+        ));
+}
+
+#[test]
+fn test_push_reports_transfer_size_and_stat() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "a=1\n").unwrap();
+    fs::write(env.project.join(".env.local"), "KEY=1\n").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", ".env.local"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"Pushed to origin/main \(\d+ object\(s\), \d+ as deltas, \d+ B sent\)",
+            )
+            .unwrap(),
+        )
+        .stdout(predicate::str::contains("config.local |").not());
+
+    fs::write(env.project.join("config.local"), "a=1\nb=2\n").unwrap();
+    env.cmd()
+        .args(["push", "--stat"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.local | 1 +"))
+        .stdout(predicate::str::contains(".env.local").not())
+        .stdout(predicate::str::contains("1 file changed, 1 insertion(+)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_push_reports_signing_failure_and_no_sign_skips_signing() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "settings").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    // A gpg that can't sign anything
+    let gpg = env.home.path().join("broken-gpg");
+    fs::write(&gpg, "#!/bin/sh\nexit 1\n").unwrap();
+    fs::set_permissions(&gpg, fs::Permissions::from_mode(0o755)).unwrap();
+    common::git(
+        &env.projects(),
+        &["config", "gpg.program", gpg.to_str().unwrap()],
+    );
+    env.cmd()
+        .args(["config", "set", "sign_commits", "true"])
+        .assert()
+        .success();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains("Could not sign the shade commit"))
+        .stderr(predicate::str::contains("gpg failed to sign the data"));
+
+    env.cmd()
+        .args(["push", "--no-sign"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Committed:"));
+    assert_eq!(
+        git_stdout(&env.projects(), &["log", "-1", "--format=%G?"]),
+        "N"
+    );
+}
+
+#[test]
+fn test_pull_push_after_converges_files_added_on_two_machines() {
+    let machine_a = ShadeEnv::with_remote();
+    let remote = machine_a.home.path().join("remote.git");
+
+    // Machine B clones the same shade remote
+    let machine_b = ShadeEnv::new();
+    fs::remove_dir_all(machine_b.projects()).unwrap();
+    common::git(
+        machine_b.home.path(),
+        &[
+            "clone",
+            remote.to_str().unwrap(),
+            machine_b.projects().to_str().unwrap(),
+        ],
+    );
+
+    // Each machine adds its own file; A pushes first
+    fs::write(machine_a.project.join("a.env"), "A=1").unwrap();
+    machine_a.cmd().arg("init").assert().success();
+    machine_a.cmd().args(["add", "a.env"]).assert().success();
+    machine_a.cmd().arg("push").assert().success();
+
+    fs::write(machine_b.project.join("b.env"), "B=1").unwrap();
+    machine_b
+        .cmd()
+        .args(["init", "--no-pull"])
+        .assert()
+        .success();
+    machine_b.cmd().args(["add", "b.env"]).assert().success();
+
+    // B's pull gets a.env and points out that b.env hasn't been pushed
+    machine_b
+        .cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 file(s) changed on this machine aren't on the shade remote",
+        ))
+        .stdout(predicate::str::contains("  - b.env"));
+    assert_eq!(
+        fs::read_to_string(machine_b.project.join("a.env")).unwrap(),
+        "A=1"
+    );
+
+    machine_b
+        .cmd()
+        .args(["pull", "--push-after"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("==> Push"))
+        .stdout(predicate::str::contains("Pushed to origin/main"));
+
+    // A now gets b.env, and neither machine has anything left to push
+    machine_a
+        .cmd()
+        .args(["pull", "--push-after"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("==> Push").not())
+        .stdout(predicate::str::contains("aren't on the shade remote").not());
+    assert_eq!(
+        fs::read_to_string(machine_a.project.join("b.env")).unwrap(),
+        "B=1"
+    );
+    machine_b
+        .cmd()
+        .arg("pull")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("aren't on the shade remote").not());
+}
+
+#[test]
+fn test_pull_prune_deletes_files_removed_from_shade() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("a.env"), "A=1").unwrap();
+    fs::write(env.project.join("b.env"), "B=1").unwrap();
+    fs::write(env.project.join("c.env"), "C=1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "a.env", "b.env", "c.env"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+
+    // Another machine deletes a.env and b.env from shade
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    common::git(&other, &["rm", "-q", "myapp/a.env", "myapp/b.env"]);
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-m",
+            "remove",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    // b.env was edited here since the last sync, so it isn't pruned
+    fs::write(env.project.join("b.env"), "B=2").unwrap();
+
+    env.cmd()
+        .args(["pull", "--prune"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("  - a.env"))
+        .stdout(predicate::str::contains("  - b.env").not())
+        .stderr(predicate::str::contains("needs confirmation"));
+    assert!(env.project.join("a.env").exists());
+
+    env.cmd()
+        .args(["pull", "--prune", "--yes", "--strategy", "local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 file(s) (backed up to"))
+        .stdout(predicate::str::contains(
+            "b.env (deleted remotely, local edit kept)",
+        ));
+
+    assert!(!env.project.join("a.env").exists());
+    assert_eq!(
+        fs::read_to_string(env.project.join("b.env")).unwrap(),
+        "B=2"
+    );
+    assert_eq!(
+        fs::read_to_string(env.project.join("c.env")).unwrap(),
+        "C=1"
+    );
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("a.env"));
+    assert!(exclude.contains("b.env"));
+    let manifest = fs::read_to_string(
+        env.home
+            .path()
+            .join(".local/git-shade/metadata/myapp/manifest.toml"),
+    )
+    .unwrap();
+    assert!(!manifest.contains("\"a.env\""));
+
+    let backups = env.home.path().join(".local/git-shade/backups/myapp");
+    let backup = fs::read_dir(&backups)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert_eq!(fs::read_to_string(backup.join("a.env")).unwrap(), "A=1");
+}
+
+#[test]
+fn test_pull_reports_file_deleted_remotely_and_edited_locally() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("old.env"), "A=1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "old.env"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Deleted on another machine, edited here before pulling
+    let delete_in_shade = || {
+        common::git(&env.projects(), &["rm", "-q", "myapp/old.env"]);
+        common::git(&env.projects(), &["commit", "-m", "remove old.env"]);
+        common::git(&env.projects(), &["push"]);
+    };
+    delete_in_shade();
+    fs::write(env.project.join("old.env"), "A=2").unwrap();
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains(
+            "deleted remotely but modified locally",
+        ))
+        .stdout(predicate::str::contains("Remote: deleted"))
+        .stdout(predicate::str::contains("git-shade pull --strategy local"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("old.env")).unwrap(),
+        "A=2"
+    );
+
+    // Keeping the edit and pushing puts the file back in shade
+    env.cmd()
+        .args(["pull", "--strategy", "local"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("local edit kept"));
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("old.env")).unwrap(),
+        "A=2"
+    );
+
+    // Accepting the deletion backs the edit up first
+    delete_in_shade();
+    fs::write(env.project.join("old.env"), "A=3").unwrap();
+    env.cmd()
+        .args(["pull", "--strategy", "remote"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Deleted 1 file(s) removed from shade",
+        ));
+    assert!(!env.project.join("old.env").exists());
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(!exclude.contains("old.env"));
+
+    let backups = env.home.path().join(".local/git-shade/backups/myapp");
+    let backup = fs::read_dir(&backups)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert_eq!(fs::read_to_string(backup.join("old.env")).unwrap(), "A=3");
+}
+
+#[test]
+fn test_paused_file_is_skipped_by_push_and_pull() {
+    let env = ShadeEnv::with_remote();
+    fs::create_dir_all(env.project.join("secrets")).unwrap();
+    fs::write(env.project.join("secrets/dev.key"), "dev1").unwrap();
+    fs::write(env.project.join("secrets/prod.key"), "prod1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "secrets/"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .args(["pause", "notes.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not tracked"));
+    env.cmd()
+        .args(["pause", "secrets/dev.key"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Paused secrets/dev.key"));
+
+    // Local experiments with dev.key stay local; the rest of the directory syncs
+    fs::write(env.project.join("secrets/dev.key"), "experiment").unwrap();
+    fs::write(env.project.join("secrets/prod.key"), "prod2").unwrap();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 paused file(s) skipped"));
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("secrets/dev.key")).unwrap(),
+        "dev1"
+    );
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("secrets/prod.key")).unwrap(),
+        "prod2"
+    );
+
+    // Changes from elsewhere don't reach it either
+    fs::write(env.shade_dir().join("secrets/dev.key"), "dev2").unwrap();
+    common::git(&env.projects(), &["commit", "-am", "other machine"]);
+    common::git(&env.projects(), &["push"]);
+    env.cmd().arg("pull").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.project.join("secrets/dev.key")).unwrap(),
+        "experiment"
+    );
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"secrets/dev\.key +paused").unwrap());
+
+    env.cmd()
+        .args(["resume", "secrets/dev.key"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Resumed secrets/dev.key"));
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(paused)").not());
+}
+
+#[test]
+fn test_stash_sets_local_changes_aside_and_pop_restores_them() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    fs::write(env.project.join("other.local"), "o1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd()
+        .args(["add", "config.local", "other.local"])
+        .assert()
+        .success();
+    env.cmd().arg("push").assert().success();
+
+    env.cmd()
+        .arg("stash")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No local changes to stash"));
+
+    fs::write(env.project.join("config.local"), "mine").unwrap();
+    env.cmd()
+        .arg("stash")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stashed 1 file(s)"))
+        .stdout(predicate::str::contains("config.local"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "v1"
+    );
+    env.cmd()
+        .args(["stash", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file(s)"));
+
+    // With the edit out of the way, a pull has nothing to conflict with
+    env.cmd().arg("pull").assert().success();
+
+    env.cmd()
+        .args(["stash", "pop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 file(s)"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "mine"
+    );
+    assert_eq!(
+        fs::read_to_string(env.project.join("other.local")).unwrap(),
+        "o1"
+    );
+    env.cmd()
+        .args(["stash", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No stashes"));
+
+    // The restored edit is what push sends
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "mine"
+    );
+}
+
+#[test]
+fn test_push_tag_creates_and_pushes_project_tag() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    env.cmd()
+        .args(["push", "--tag", "before-migration"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Tagged myapp/before-migration and pushed it to origin",
+        ));
+
+    let remote = env.home.path().join("remote.git");
+    assert_eq!(
+        git_stdout(&remote, &["rev-parse", "myapp/before-migration^{commit}"]),
+        git_stdout(&env.projects(), &["rev-parse", "HEAD"])
+    );
+    assert_eq!(
+        git_stdout(
+            &env.projects(),
+            &["cat-file", "-t", "myapp/before-migration"]
+        ),
+        "tag"
+    );
+
+    // A taken name is refused before anything is copied or committed
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    let head = git_stdout(&env.projects(), &["rev-parse", "HEAD"]);
+    env.cmd()
+        .args(["push", "--tag", "before-migration"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Copying").not())
+        .stderr(predicate::str::contains(
+            "Tag myapp/before-migration already exists",
+        ));
+    assert_eq!(git_stdout(&env.projects(), &["rev-parse", "HEAD"]), head);
+
+    env.cmd()
+        .args(["push", "--tag", "bad..name"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't a valid tag name"));
+
+    // Taken on the remote only, e.g. by another machine
+    let remote = env.home.path().join("remote.git");
+    common::git(&remote, &["tag", "myapp/from-desktop", "HEAD"]);
+    env.cmd()
+        .args(["push", "--tag", "from-desktop"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Tag myapp/from-desktop already exists on origin",
+        ));
+    assert_eq!(git_stdout(&env.projects(), &["rev-parse", "HEAD"]), head);
+}
+
+#[test]
+fn test_push_retries_commit_left_unpushed_by_a_failed_push() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // The remote goes away after the commit is made
+    let remote = env.home.path().join("remote.git");
+    let offline = env.home.path().join("remote-offline.git");
+    fs::rename(&remote, &offline).unwrap();
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    env.cmd().arg("push").assert().code(7);
+
+    let state_file = env.home.path().join(".local/git-shade/.push-state");
+    assert!(state_file.exists());
+    let head = git_stdout(&env.projects(), &["rev-parse", "HEAD"]);
+
+    env.cmd()
+        .args(["status", "--exit-code"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(format!(
+            "Pending push: {} from",
+            &head[..7]
+        )));
+
+    // Back online, push sends the same commit instead of making another
+    fs::rename(&offline, &remote).unwrap();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Retrying push of {}",
+            &head[..7]
+        )))
+        .stdout(predicate::str::contains("No changes to commit"));
+    assert!(!state_file.exists());
+    assert_eq!(git_stdout(&env.projects(), &["rev-parse", "HEAD"]), head);
+    assert_eq!(
+        git_stdout(&remote, &["rev-parse", "HEAD"]),
+        head,
+        "remote has the commit"
+    );
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pending push").not());
+}
+
+#[test]
+fn test_push_rejected_by_unpulled_remote_changes() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Another machine pushes to a different project
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    let other_push = |contents: &str| {
+        fs::create_dir_all(other.join("otherapp")).unwrap();
+        fs::write(other.join("otherapp/.env"), contents).unwrap();
+        common::git(&other, &["add", "-A"]);
+        common::git(
+            &other,
+            &[
+                "-c",
+                "user.name=o",
+                "-c",
+                "user.email=o@x",
+                "commit",
+                "-m",
+                "other",
+            ],
+        );
+        common::git(&other, &["push"]);
+    };
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    other_push("A=1");
+
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .code(8)
+        .stderr(predicate::str::contains(
+            "The shade remote has changes you haven't pulled",
+        ));
+
+    // Pulling merges them, and the next push sends the waiting commit
+    env.cmd().arg("pull").assert().success();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pushed to"));
+    assert_eq!(
+        git_stdout(&env.projects(), &["rev-parse", "HEAD"]),
+        git_stdout(&env.projects(), &["rev-parse", "origin/master"])
+    );
+
+    // --force-with-lease replaces remote commits once they've been fetched
+    other_push("A=2");
+    fs::write(env.project.join("config.local"), "v3").unwrap();
+    env.cmd().args(["status", "--fetch"]).assert().success();
+    env.cmd()
+        .args(["push", "--force-with-lease"])
+        .assert()
+        .success();
+    assert_eq!(
+        git_stdout(
+            &env.projects(),
+            &["show", "origin/master:myapp/config.local"]
+        ),
+        "v3"
+    );
+
+    // A rebasing pull rewrites the waiting commit; it still goes out
+    common::git(&other, &["fetch"]);
+    common::git(&other, &["reset", "--hard", "@{u}"]);
+    other_push("A=3");
+    fs::write(env.project.join("config.local"), "v4").unwrap();
+    env.cmd().arg("push").assert().code(8);
+    env.cmd().args(["pull", "--rebase"]).assert().success();
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Retrying push of"));
+    assert_eq!(
+        git_stdout(
+            &env.projects(),
+            &["show", "origin/master:myapp/config.local"]
+        ),
+        "v4"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_watch_pushes_changes_until_interrupted() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    fs::write(env.project.join("untracked.txt"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("git-shade"))
+        .args(["watch", "--debounce", "200"])
+        .current_dir(&env.project)
+        .env("HOME", env.home.path())
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env_remove("RUST_LOG")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    fs::write(env.project.join("untracked.txt"), "y").unwrap();
+    fs::write(env.project.join("config.local"), "v2").unwrap();
+
+    let shade_file = env.shade_dir().join("config.local");
+    for _ in 0..50 {
+        if fs::read_to_string(&shade_file).unwrap() == "v2" {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Changed: config.local\n"), "{}", stdout);
+    assert!(stdout.contains("Stopped watching myapp"), "{}", stdout);
+    assert_eq!(fs::read_to_string(&shade_file).unwrap(), "v2");
+    assert!(git_stdout(&env.projects(), &["log", "-1", "--format=%s"]).starts_with("[myapp]"));
+}
+
+#[test]
+fn test_watch_skips_files_changed_in_shade_too() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "v1").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // A change from another machine reached the shade copy
+    let shade_file = env.shade_dir().join("config.local");
+    fs::write(&shade_file, "theirs").unwrap();
+    common::git(&env.projects(), &["commit", "-am", "[myapp] other machine"]);
+
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("git-shade"))
+        .args(["watch", "--debounce", "200"])
+        .current_dir(&env.project)
+        .env("HOME", env.home.path())
+        .env("GIT_AUTHOR_NAME", "test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env_remove("RUST_LOG")
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+    fs::write(env.project.join("config.local"), "mine").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("Also changed in shade, skipping: config.local"),
+        "{}",
+        stdout
+    );
+    assert_eq!(fs::read_to_string(&shade_file).unwrap(), "theirs");
+}
+
+#[test]
+fn test_pull_dry_run_checks_unpulled_remote_changes() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+    env.cmd().arg("pull").assert().success();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(env.project.join("config.local"), "changed here").unwrap();
+
+    // Another machine changes the same file and adds one
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    fs::write(other.join("myapp/config.local"), "from other machine").unwrap();
+    fs::write(other.join("myapp/db.conf"), "new").unwrap();
+    common::git(&other, &["add", "-A"]);
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-m",
+            "other",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    env.cmd()
+        .args(["pull", "--dry-run"])
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains("config.local"));
+
+    env.cmd()
+        .args(["pull", "--dry-run", "--only", "db.conf"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would pull 1 file(s) (3 B)"));
+
+    // Still nothing merged or copied
+    assert!(!env.shade_dir().join("db.conf").exists());
+    assert!(!env.project.join("db.conf").exists());
+}
+
+#[test]
+fn test_pull_reports_merge_conflict_in_shade_repo() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Another machine changes the same file and pushes
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    fs::write(other.join("myapp/config.local"), "from other machine").unwrap();
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-am",
+            "other",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    // This machine commits a different change
+    fs::write(env.project.join("config.local"), "from this machine").unwrap();
+    env.cmd().arg("push").assert().failure();
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("MERGE CONFLICT IN SHADE REPO"))
+        .stdout(predicate::str::contains("myapp/config.local"))
+        .stderr(predicate::str::contains("Merge conflict in shade repo"));
+
+    // Pushing now would commit on top of the unfinished merge
+    let head = git_stdout(&env.projects(), &["rev-parse", "HEAD"]);
+    env.cmd()
+        .arg("push")
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains("Copying files").not())
+        .stderr(predicate::str::contains(
+            "The shade repo is in the middle of a merge",
+        ))
+        .stderr(predicate::str::contains("git merge --abort"));
+    assert_eq!(git_stdout(&env.projects(), &["rev-parse", "HEAD"]), head);
+}
+
+#[test]
+fn test_pull_set_exclude_only_registers_patterns_without_copying() {
+    let env = ShadeEnv::new();
+    fs::create_dir_all(env.shade_dir().join("secrets")).unwrap();
+    fs::write(env.shade_dir().join("config.local"), "from shade").unwrap();
+    fs::write(env.shade_dir().join("secrets/api.key"), "key").unwrap();
+    fs::write(env.project.join("config.local"), "mine").unwrap();
+    env.cmd().args(["init", "--no-pull"]).assert().success();
+
+    env.cmd()
+        .args(["pull", "--set-exclude-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- config.local"))
+        .stdout(predicate::str::contains("- secrets/api.key"))
+        .stdout(predicate::str::contains("Nothing was copied"));
+
+    let exclude = fs::read_to_string(env.project.join(".git/info/exclude")).unwrap();
+    assert!(exclude.contains("config.local"));
+    assert!(exclude.contains("secrets/api.key"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("config.local")).unwrap(),
+        "mine"
+    );
+    assert!(!env.project.join("secrets/api.key").exists());
+    assert_eq!(git_stdout(&env.project, &["status", "--porcelain"]), "");
+
+    // Nothing claims the contents were pulled
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Last pull: never"));
+
+    env.cmd()
+        .args(["pull", "--set-exclude-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All 2 shade file(s) are already in .git/info/exclude",
+        ));
+}
+
+#[test]
+fn test_pull_strategy_ff_only_and_rebase_keep_history_linear() {
+    let env = ShadeEnv::with_remote();
+    fs::write(env.project.join("config.local"), "original").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    env.cmd().arg("push").assert().success();
+
+    // Another machine pushes a commit to another project
+    let other = env.home.path().join("other-machine");
+    let remote = env.home.path().join("remote.git");
+    common::git(
+        env.home.path(),
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    fs::create_dir_all(other.join("other-app")).unwrap();
+    fs::write(other.join("other-app/.env"), "env").unwrap();
+    common::git(&other, &["add", "-A"]);
+    common::git(
+        &other,
+        &[
+            "-c",
+            "user.name=o",
+            "-c",
+            "user.email=o@x",
+            "commit",
+            "-m",
+            "other",
+        ],
+    );
+    common::git(&other, &["push"]);
+
+    // This machine commits its own change, which the remote rejects
+    fs::write(env.project.join("config.local"), "from this machine").unwrap();
+    env.cmd().arg("push").assert().code(8);
+
+    env.cmd()
+        .args(["config", "set", "pull_strategy", "ff-only"])
+        .assert()
+        .success();
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "Can't fast-forward the shade repo",
+        ))
+        .stderr(predicate::str::contains("git-shade pull --rebase"));
+
+    env.cmd().args(["pull", "--rebase"]).assert().success();
+    assert_eq!(
+        git_stdout(&env.projects(), &["log", "--merges", "--oneline"]),
+        ""
+    );
+    assert!(env.projects().join("other-app/.env").exists());
+
+    env.cmd().arg("push").assert().success();
+    assert_eq!(
+        fs::read_to_string(env.shade_dir().join("config.local")).unwrap(),
+        "from this machine"
+    );
+}
+
+#[test]
+fn test_export_then_import_on_another_machine() {
+    let laptop = ShadeEnv::new();
+    fs::create_dir_all(laptop.project.join("secrets")).unwrap();
+    fs::write(laptop.project.join("config.local"), "from laptop").unwrap();
+    fs::write(laptop.project.join("secrets/api.key"), "key").unwrap();
+    laptop.cmd().arg("init").assert().success();
+    laptop
+        .cmd()
+        .args(["add", "config.local", "secrets"])
+        .assert()
+        .success();
+    laptop.cmd().arg("push").assert().success();
+
+    let archive = laptop.home.path().join("myapp-shade.tar.gz");
+    laptop
+        .cmd()
+        .args(["export", archive.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 file(s)"));
+
+    // Air-gapped machine: no remote, fresh project
+    let desktop = ShadeEnv::new();
+    desktop.cmd().arg("init").assert().success();
+    desktop
+        .cmd()
+        .args(["import", archive.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped git pull"));
+
+    assert_eq!(
+        fs::read_to_string(desktop.project.join("config.local")).unwrap(),
+        "from laptop"
+    );
+    assert!(desktop.project.join("secrets/api.key").exists());
+    let log = git_stdout(&desktop.projects(), &["log", "-1", "--format=%s"]);
+    assert!(log.starts_with("[myapp] Import from"));
+}
+
+#[test]
+fn test_import_rejects_archive_for_other_project() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+    let archive = env.home.path().join("myapp.tar.gz");
+    env.cmd()
+        .args(["export", archive.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let other = env.home.path().join("otherapp");
+    fs::create_dir_all(&other).unwrap();
+    common::git(&other, &["init"]);
+    env.cmd().current_dir(&other).arg("init").assert().success();
+
+    env.cmd()
+        .current_dir(&other)
+        .args(["import", archive.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Archive is for a different project: myapp",
+        ));
+
+    env.cmd()
+        .current_dir(&other)
+        .args(["import", archive.to_str().unwrap(), "--project", "otherapp"])
+        .assert()
+        .success();
+    assert!(other.join("config.local").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hooks_run_around_push_and_abort_pull() {
+    let env = ShadeEnv::new();
+    fs::write(env.project.join("config.local"), "x").unwrap();
+    env.cmd().arg("init").assert().success();
+    env.cmd().args(["add", "config.local"]).assert().success();
+
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    let config = config.replace(
+        "[hooks]\n",
+        "[hooks]\n\
+         pre_push = \"echo $GIT_SHADE_PROJECT > pre_push.out\"\n\
+         post_push = \"test -f $GIT_SHADE_SHADE_DIR/config.local && touch post_push.out\"\n\
+         pre_pull = \"exit 3\"\n",
+    );
+    fs::write(env.config_file(), config).unwrap();
+
+    env.cmd()
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Running pre_push hook"));
+    assert_eq!(
+        fs::read_to_string(env.project.join("pre_push.out")).unwrap(),
+        "myapp\n"
+    );
+    assert!(env.project.join("post_push.out").exists());
+
+    env.cmd()
+        .arg("pull")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Pulling from shade repo").not())
+        .stderr(predicate::str::contains("pre_pull hook failed"));
+}
+
+#[test]
+fn test_preserve_empty_dirs_survive_push_and_pull() {
+    let env = ShadeEnv::with_remote();
+    fs::create_dir_all(env.project.join("data/logs")).unwrap();
+    fs::create_dir_all(env.project.join("data/cache/tmp")).unwrap();
+    fs::write(env.project.join("data/seed.sql"), "seed").unwrap();
+
+    env.cmd().arg("init").assert().success();
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    fs::write(
+        env.config_file(),
+        config.replace("preserve_empty_dirs = false", "preserve_empty_dirs = true"),
+    )
+    .unwrap();
+
+    env.cmd().args(["add", "data"]).assert().success();
+    env.cmd().arg("push").assert().success();
+    assert!(env.shade_dir().join("data/logs/.shade-keep").exists());
+
+    env.cmd()
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".shade-keep").not());
+
+    // Fresh target: the directory is gone locally
+    fs::remove_dir_all(env.project.join("data")).unwrap();
+    env.cmd().arg("pull").assert().success();
+
+    assert!(env.project.join("data/seed.sql").exists());
+    assert!(env.project.join("data/logs").is_dir());
+    assert!(env.project.join("data/cache/tmp").is_dir());
+    assert!(!env.project.join("data/logs/.shade-keep").exists());
+}
+
+#[test]
+fn test_config_get_set_list() {
+    let env = ShadeEnv::new();
+
+    env.cmd()
+        .args(["config", "set", "max_add_files", "5"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("max_add_files = 5"));
+    env.cmd()
+        .args(["config", "get", "max_add_files"])
+        .assert()
+        .success()
+        .stdout("5\n");
+
+    env.cmd()
+        .args(["config", "set", "preserve_empty_dirs", "maybe"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be true or false"));
+    env.cmd()
+        .args(["config", "get", "no_such_key"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key"));
+
+    env.cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("max_add_files = 5"))
+        .stdout(predicate::str::contains("hooks.pre_push = (not set)"));
+
+    let config = fs::read_to_string(env.config_file()).unwrap();
+    assert!(config.contains("max_add_files = 5"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_edit_keeps_only_edits_that_parse() {
+    let env = ShadeEnv::new();
+
+    // No config yet: the editor gets the defaults to change
+    env.cmd()
+        .args(["config", "edit"])
+        .env_remove("VISUAL")
+        .env(
+            "EDITOR",
+            "sed -i.bak 's/^max_add_files = .*/max_add_files = 7/'",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved"));
+    env.cmd()
+        .args(["config", "get", "max_add_files"])
+        .assert()
+        .success()
+        .stdout("7\n");
+
+    let before = fs::read_to_string(env.config_file()).unwrap();
+    env.cmd()
+        .args(["config", "edit"])
+        .env_remove("VISUAL")
+        .env("EDITOR", "echo 'max_add_files = [' >>")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the edit was discarded"));
+    assert_eq!(fs::read_to_string(env.config_file()).unwrap(), before);
+
+    // An editor that fails leaves the file alone too
+    env.cmd()
+        .args(["config", "edit"])
+        .env("VISUAL", "echo 'broken' >> \"$1\"; false")
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(env.config_file()).unwrap(), before);
+}
+
+#[test]
+fn test_completions_generates_script() {
+    Command::cargo_bin("git-shade")
+        .unwrap()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_git-shade"))
+        .stdout(predicate::str::contains("push"));
+}